@@ -3,11 +3,98 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Default maximum stored error message length in bytes
+ */
+#define DEFAULT_MAX_ERROR_MSG_LEN (8 * 1024)
+
+/**
+ * Logging init FFI result: the global subscriber was installed
+ */
+#define EASYTIER_LOGGING_INITIALIZED 0
+
+/**
+ * Logging init FFI result: logging was already initialized and the level was updated
+ */
+#define EASYTIER_LOGGING_LEVEL_UPDATED 1
+
+/**
+ * RFC 3339 timestamp pattern with microseconds, for `set_log_time_format`
+ */
+#define RFC3339_TIME_FORMAT "%Y-%m-%dT%H:%M:%S%.6f%:z"
+
+/**
+ * Log level passed to the log callback for `ERROR` records
+ */
+#define CORTEX_LOG_LEVEL_ERROR 1
+
+/**
+ * Log level passed to the log callback for `WARN` records
+ */
+#define CORTEX_LOG_LEVEL_WARN 2
+
+/**
+ * Log level passed to the log callback for `INFO` records
+ */
+#define CORTEX_LOG_LEVEL_INFO 3
+
+/**
+ * Log level passed to the log callback for `DEBUG` records
+ */
+#define CORTEX_LOG_LEVEL_DEBUG 4
+
+/**
+ * Log level passed to the log callback for `TRACE` records
+ */
+#define CORTEX_LOG_LEVEL_TRACE 5
+
+/**
+ * Error category of the last failed FFI call, see `cortex_get_last_error_code`
+ */
+typedef enum CortexErrorCode {
+  OK = 0,
+  NULL_POINTER = 1,
+  INVALID_UTF8 = 2,
+  INVALID_URL = 3,
+  NOT_FOUND = 4,
+  DB_ERROR = 5,
+  ALREADY_EXISTS = 6,
+  INVALID_ARGUMENT = 7,
+  /**
+   * The database is reachable but its schema migration failed
+   */
+  MIGRATION_ERROR = 8,
+  /**
+   * No pooled database connection became free within the acquire timeout
+   */
+  POOL_EXHAUSTED = 9,
+  /**
+   * A database operation ran longer than the configured statement timeout
+   */
+  STATEMENT_TIMEOUT = 10,
+  INTERNAL = 99,
+} CortexErrorCode;
+
+/**
+ * Log record sink registered from the host application
+ */
+typedef void (*LogCallback)(int level, const char *msg);
+
 /**
  * Get last error message
  */
 const char *easytier_common_get_error_msg(void);
 
+/**
+ * Get the `CortexErrorCode` of the most recent error, `OK` (0) if none was reported
+ */
+int cortex_get_last_error_code(void);
+
+/**
+ * Get the length of the last error message in bytes, excluding the null terminator
+ */
+uintptr_t cortex_get_error_msg_len(void);
+
 /**
  * Free a C string allocated by Rust
  */
@@ -23,9 +110,32 @@ void easytier_common_free_string(const char *s);
 void easytier_common_free_string_array(const char *const *arr,
                                        int32_t count);
 
+/**
+ * Gather metrics in Prometheus text format
+ * Returns 0 on success, -1 on error
+ *
+ * The returned string must be freed with `easytier_common_free_string`.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `out` is a valid mutable pointer.
+ */
+int cortex_metrics_gather(char **out);
+
+/**
+ * Release everything at process exit: stop gateway cores, destroy the config
+ * service, flush the log files and clear the error state
+ * Safe to call when nothing was initialized. Returns 0 on success, or -1 if a
+ * resource failed to shut down (the error message lists them).
+ */
+int cortex_shutdown_all(void);
+
 /**
  * FFI wrapper: Initialize console logging
  *
+ * Returns `EASYTIER_LOGGING_INITIALIZED` on the first call,
+ * `EASYTIER_LOGGING_LEVEL_UPDATED` when logging was already initialized, -1 on error.
+ *
  * # Safety
  *
  * The caller must ensure that `level` and `module_name` are valid C strings.
@@ -35,6 +145,8 @@ int easytier_common_init_console_logging(const char *level, const char *module_n
 /**
  * FFI wrapper: Initialize file logging
  *
+ * Returns the same codes as `easytier_common_init_console_logging`.
+ *
  * # Safety
  *
  * The caller must ensure that `level`, `module_name`, and `log_path` are valid C strings.
@@ -42,3 +154,64 @@ int easytier_common_init_console_logging(const char *level, const char *module_n
 int easytier_common_init_file_logging(const char *level,
                                       const char *module_name,
                                       const char *log_path);
+
+/**
+ * FFI wrapper: Initialize logging filtered by per-target directives
+ *
+ * `filter_spec` uses `EnvFilter` syntax, e.g. `easytier=warn,sqlx=error`.
+ * Logs go to the console only when `log_path` is null. Returns the same
+ * codes as `easytier_common_init_console_logging`.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `filter_spec` is a valid C string and that
+ * `log_path` is either null or a valid C string.
+ */
+int easytier_common_init_logging_filtered(const char *filter_spec, const char *log_path);
+
+/**
+ * FFI wrapper: Set the strftime pattern of log timestamps
+ *
+ * Timestamps are in UTC when `use_utc` is true and in the local timezone
+ * otherwise. A null `format` restores the default format.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `format` is either null or a valid C string.
+ */
+int easytier_common_set_log_time_format(const char *format, bool use_utc);
+
+/**
+ * Flush the log file written by file logging
+ *
+ * Blocks until the lines logged so far are written. Safe to call from a log
+ * callback. Returns 0 after flushing, 1 when file logging is not initialized.
+ */
+int cortex_core_flush_logs(void);
+
+/**
+ * Register a callback receiving each log record, passing null clears it
+ *
+ * `msg` is only valid for the duration of the call. The callback runs on the
+ * logging thread without internal locks held, so it may call back into the
+ * library. Logging must be initialized for records to be forwarded.
+ */
+void cortex_core_set_log_callback(LogCallback cb);
+
+/**
+ * Set the correlation id attached to the logs of the next FFI call on this thread
+ *
+ * The id is cleared when that call returns. Go callers must keep the
+ * goroutine on one OS thread (`runtime.LockOSThread`) between this call and
+ * the call it tags. A null `id` clears it.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `id` is either null or a valid C string.
+ */
+void cortex_set_request_id(const char *id);
+
+/**
+ * Clear the correlation id of the calling thread
+ */
+void cortex_clear_request_id(void);
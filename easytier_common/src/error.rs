@@ -1,7 +1,75 @@
 //! Common error types for EasyTier integration
 
+use std::ffi::c_int;
 use thiserror::Error;
 
+/// Error category of the last failed FFI call, see `cortex_get_last_error_code`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CortexErrorCode {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidUrl = 3,
+    NotFound = 4,
+    DbError = 5,
+    AlreadyExists = 6,
+    InvalidArgument = 7,
+    /// The database is reachable but its schema migration failed
+    MigrationError = 8,
+    /// No pooled database connection became free within the acquire timeout
+    PoolExhausted = 9,
+    /// A database operation ran longer than the configured statement timeout
+    StatementTimeout = 10,
+    Internal = 99,
+}
+
+impl CortexErrorCode {
+    /// Map an error returned by `c_str_to_string` or `parse_string_array`
+    pub fn from_c_str_error(err: &str) -> Self {
+        if err.starts_with("Null pointer") {
+            CortexErrorCode::NullPointer
+        } else if err.starts_with("Invalid UTF-8") {
+            CortexErrorCode::InvalidUtf8
+        } else {
+            CortexErrorCode::InvalidArgument
+        }
+    }
+
+    pub fn from_c_int(code: c_int) -> Option<Self> {
+        Some(match code {
+            0 => CortexErrorCode::Ok,
+            1 => CortexErrorCode::NullPointer,
+            2 => CortexErrorCode::InvalidUtf8,
+            3 => CortexErrorCode::InvalidUrl,
+            4 => CortexErrorCode::NotFound,
+            5 => CortexErrorCode::DbError,
+            6 => CortexErrorCode::AlreadyExists,
+            7 => CortexErrorCode::InvalidArgument,
+            8 => CortexErrorCode::MigrationError,
+            9 => CortexErrorCode::PoolExhausted,
+            10 => CortexErrorCode::StatementTimeout,
+            99 => CortexErrorCode::Internal,
+            _ => return None,
+        })
+    }
+}
+
+impl From<&EasyTierError> for CortexErrorCode {
+    fn from(err: &EasyTierError) -> Self {
+        match err {
+            EasyTierError::InvalidParameter(_) | EasyTierError::ConfigError(_) => {
+                CortexErrorCode::InvalidArgument
+            }
+            EasyTierError::DatabaseError(_) => CortexErrorCode::DbError,
+            EasyTierError::NotFound(_) => CortexErrorCode::NotFound,
+            EasyTierError::FfiError(_)
+            | EasyTierError::NetworkError(_)
+            | EasyTierError::Internal(_) => CortexErrorCode::Internal,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum EasyTierError {
     #[error("Invalid parameter: {0}")]
@@ -24,6 +24,16 @@ pub fn string_to_c_str(s: &str) -> Result<*mut c_char, &'static str> {
         .map_err(|_| "String contains null byte")
 }
 
+/// Convert Rust string to C string (caller must free), never failing
+///
+/// Interior null bytes are replaced with spaces so the rest of the message
+/// survives instead of being dropped.
+pub fn to_c_string_lossy(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', " "))
+        .expect("interior null bytes were replaced")
+        .into_raw()
+}
+
 /// Parse an array of C strings
 ///
 /// # Safety
@@ -99,4 +109,14 @@ mod tests {
             let _ = CString::from_raw(result);
         }
     }
+
+    #[test]
+    fn test_to_c_string_lossy_interior_null() {
+        let result = to_c_string_lossy("device\0 not found");
+        unsafe {
+            let back = CStr::from_ptr(result).to_str().unwrap();
+            assert_eq!(back, "device  not found");
+            let _ = CString::from_raw(result);
+        }
+    }
 }
@@ -5,24 +5,74 @@
 
 #[cfg(test)]
 use std::ffi::CStr;
-use std::ffi::{c_char, CString};
+use std::ffi::{c_char, c_int, CString};
 use std::ptr;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
 use std::sync::Mutex;
 
 mod error;
 mod ffi_utils;
 mod logging;
+mod metrics;
+mod shutdown;
 
 pub use error::*;
 pub use ffi_utils::*;
 pub use logging::*;
+pub use metrics::*;
+pub use shutdown::*;
 
 // Global error message storage for FFI
 static ERROR_MSG: once_cell::sync::Lazy<Mutex<Vec<u8>>> =
     once_cell::sync::Lazy::new(|| Mutex::new(Vec::new()));
 
+/// Default maximum stored error message length in bytes
+pub const DEFAULT_MAX_ERROR_MSG_LEN: usize = 8 * 1024;
+
+/// Suffix appended to error messages cut at the maximum length
+pub const ERROR_MSG_TRUNCATED_SUFFIX: &str = "…(truncated)";
+
+/// Held by tests that set or read the global error state or the last panic,
+/// so they do not observe each other's messages
+#[cfg(test)]
+pub(crate) static ERROR_STATE_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+static ERROR_CODE: AtomicI32 = AtomicI32::new(CortexErrorCode::Ok as i32);
+
+static MAX_ERROR_MSG_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_ERROR_MSG_LEN);
+
+/// Set the maximum stored error message length in bytes, excluding the null terminator
+pub fn set_max_error_msg_len(len: usize) {
+    MAX_ERROR_MSG_LEN.store(len.max(ERROR_MSG_TRUNCATED_SUFFIX.len()), Ordering::Relaxed);
+}
+
+/// Cut a message to at most `max_len` bytes, ending it with the truncation suffix
+fn bounded_error_msg(msg: &str, max_len: usize) -> std::borrow::Cow<'_, str> {
+    if msg.len() <= max_len {
+        return msg.into();
+    }
+
+    let mut end = max_len.saturating_sub(ERROR_MSG_TRUNCATED_SUFFIX.len());
+    while !msg.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &msg[..end], ERROR_MSG_TRUNCATED_SUFFIX).into()
+}
+
 /// Set error message for FFI error reporting
+///
+/// The error code is set to `CortexErrorCode::Internal`, use `set_error` for
+/// a specific category. Messages longer than the configured maximum are truncated.
 pub fn set_error_msg(msg: &str) {
+    set_error(CortexErrorCode::Internal, msg);
+}
+
+/// Set error code and message for FFI error reporting
+///
+/// Messages longer than the configured maximum are truncated.
+pub fn set_error(code: CortexErrorCode, msg: &str) {
+    ERROR_CODE.store(code as i32, Ordering::Relaxed);
+    let msg = bounded_error_msg(msg, MAX_ERROR_MSG_LEN.load(Ordering::Relaxed));
     if let Ok(mut error_msg) = ERROR_MSG.lock() {
         error_msg.clear();
         error_msg.extend_from_slice(msg.as_bytes());
@@ -30,6 +80,14 @@ pub fn set_error_msg(msg: &str) {
     }
 }
 
+/// Reset the error code to `Ok` and drop the stored error message
+pub fn clear_error() {
+    ERROR_CODE.store(CortexErrorCode::Ok as i32, Ordering::Relaxed);
+    if let Ok(mut error_msg) = ERROR_MSG.lock() {
+        error_msg.clear();
+    }
+}
+
 /// Get last error message
 #[no_mangle]
 pub extern "C" fn easytier_common_get_error_msg() -> *const c_char {
@@ -41,6 +99,21 @@ pub extern "C" fn easytier_common_get_error_msg() -> *const c_char {
     ptr::null()
 }
 
+/// Get the `CortexErrorCode` of the most recent error, `Ok` (0) if none was reported
+#[no_mangle]
+pub extern "C" fn cortex_get_last_error_code() -> c_int {
+    ERROR_CODE.load(Ordering::Relaxed)
+}
+
+/// Get the length of the last error message in bytes, excluding the null terminator
+#[no_mangle]
+pub extern "C" fn cortex_get_error_msg_len() -> usize {
+    if let Ok(error_msg) = ERROR_MSG.lock() {
+        return error_msg.len().saturating_sub(1);
+    }
+    0
+}
+
 /// Free a C string allocated by Rust
 #[no_mangle]
 pub extern "C" fn easytier_common_free_string(s: *const c_char) {
@@ -66,6 +139,9 @@ mod tests {
 
     #[test]
     fn test_error_msg() {
+        let _guard = ERROR_STATE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
         set_error_msg("test error");
         let msg = easytier_common_get_error_msg();
         assert!(!msg.is_null());
@@ -74,5 +150,76 @@ mod tests {
             let c_str = CStr::from_ptr(msg);
             assert_eq!(c_str.to_str().unwrap(), "test error");
         }
+        assert_eq!(cortex_get_error_msg_len(), "test error".len());
+    }
+
+    #[test]
+    fn test_c_str_error_codes() {
+        let err = unsafe { c_str_to_string(ptr::null()) }.unwrap_err();
+        assert_eq!(
+            CortexErrorCode::from_c_str_error(err),
+            CortexErrorCode::NullPointer
+        );
+
+        let invalid = [0xffu8, 0xfe, 0];
+        let err = unsafe { c_str_to_string(invalid.as_ptr() as *const c_char) }.unwrap_err();
+        assert_eq!(
+            CortexErrorCode::from_c_str_error(err),
+            CortexErrorCode::InvalidUtf8
+        );
+
+        let err = unsafe { parse_string_array(ptr::null(), 1) }.unwrap_err();
+        assert_eq!(
+            CortexErrorCode::from_c_str_error(err),
+            CortexErrorCode::NullPointer
+        );
+    }
+
+    #[test]
+    fn test_error_code_round_trip() {
+        for code in [
+            CortexErrorCode::Ok,
+            CortexErrorCode::NullPointer,
+            CortexErrorCode::InvalidUtf8,
+            CortexErrorCode::InvalidUrl,
+            CortexErrorCode::NotFound,
+            CortexErrorCode::DbError,
+            CortexErrorCode::AlreadyExists,
+            CortexErrorCode::InvalidArgument,
+            CortexErrorCode::MigrationError,
+            CortexErrorCode::PoolExhausted,
+            CortexErrorCode::StatementTimeout,
+            CortexErrorCode::Internal,
+        ] {
+            assert_eq!(CortexErrorCode::from_c_int(code as c_int), Some(code));
+        }
+        assert_eq!(CortexErrorCode::from_c_int(42), None);
+        assert_eq!(
+            CortexErrorCode::from(&EasyTierError::DatabaseError("down".into())),
+            CortexErrorCode::DbError
+        );
+    }
+
+    #[test]
+    fn test_short_error_msg_is_kept() {
+        let msg = bounded_error_msg("short error", DEFAULT_MAX_ERROR_MSG_LEN);
+        assert_eq!(msg, "short error");
+        assert_eq!(msg.len(), "short error".len());
+    }
+
+    #[test]
+    fn test_long_error_msg_is_truncated() {
+        let long = "x".repeat(DEFAULT_MAX_ERROR_MSG_LEN * 2);
+        let msg = bounded_error_msg(&long, DEFAULT_MAX_ERROR_MSG_LEN);
+        assert!(msg.ends_with(ERROR_MSG_TRUNCATED_SUFFIX));
+        assert_eq!(msg.len(), DEFAULT_MAX_ERROR_MSG_LEN);
+    }
+
+    #[test]
+    fn test_truncation_respects_char_boundaries() {
+        let long = "错".repeat(100);
+        let msg = bounded_error_msg(&long, 64);
+        assert!(msg.ends_with(ERROR_MSG_TRUNCATED_SUFFIX));
+        assert!(msg.len() <= 64);
     }
 }
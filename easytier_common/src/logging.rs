@@ -3,17 +3,37 @@
 //! This module provides logging initialization and panic recovery functionality
 //! shared across all EasyTier integration crates.
 
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, Utc};
+use std::cell::{Cell, RefCell};
+use std::ffi::CString;
+use std::ffi::OsString;
+use std::fmt::Write as _;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Once;
-use tracing::{debug, info};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use std::sync::RwLock;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, EnteredSpan, Id};
+use tracing::{debug, info, Event, Level, Subscriber};
+use tracing_appender::non_blocking::{NonBlocking, NonBlockingBuilder, WorkerGuard};
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::writer::{MakeWriter, OptionalWriter};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry,
+};
 
 /// Configuration for logging setup
 #[derive(Debug, Clone)]
 pub struct LoggingConfig {
     pub log_level: String,
     pub module_name: String,
+    /// Per-target directives (`EnvFilter` syntax) replacing the level/module filter
+    pub filter_spec: Option<String>,
 }
 
 impl LoggingConfig {
@@ -21,38 +41,177 @@ impl LoggingConfig {
         Self {
             log_level: level.to_string(),
             module_name: module_name.to_string(),
+            filter_spec: None,
+        }
+    }
+
+    /// Build a configuration from comma-separated per-target directives,
+    /// e.g. `easytier=warn,sqlx=error,my_module=debug`
+    pub fn from_filter_spec(filter_spec: &str) -> Result<Self, ParseError> {
+        parse_filter_spec(filter_spec)?;
+        Ok(Self {
+            log_level: filter_spec.to_string(),
+            module_name: "custom filter".to_string(),
+            filter_spec: Some(filter_spec.to_string()),
+        })
+    }
+}
+
+/// Parse comma-separated per-target directives into a filter
+pub fn parse_filter_spec(filter_spec: &str) -> Result<EnvFilter, ParseError> {
+    EnvFilter::try_new(filter_spec)
+}
+
+/// Outcome of a successful logging initializer call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoggingInitStatus {
+    /// The global subscriber was installed by this call
+    Initialized,
+    /// Logging was already initialized, only the filter was replaced
+    LevelUpdated,
+}
+
+impl LoggingInitStatus {
+    /// FFI return code, see `EASYTIER_LOGGING_INITIALIZED` and `EASYTIER_LOGGING_LEVEL_UPDATED`
+    pub fn as_c_int(self) -> c_int {
+        match self {
+            LoggingInitStatus::Initialized => EASYTIER_LOGGING_INITIALIZED,
+            LoggingInitStatus::LevelUpdated => EASYTIER_LOGGING_LEVEL_UPDATED,
         }
     }
 }
 
-// Static variables for ensuring single initialization
-static CONSOLE_INIT: Once = Once::new();
-static FILE_INIT: Once = Once::new();
+/// Logging init FFI result: the global subscriber was installed
+pub const EASYTIER_LOGGING_INITIALIZED: c_int = 0;
+/// Logging init FFI result: logging was already initialized and the level was updated
+pub const EASYTIER_LOGGING_LEVEL_UPDATED: c_int = 1;
+
+type FilterReloadHandle = reload::Handle<EnvFilter, Registry>;
+
+// Filter reload handle of the installed global subscriber, also serializing initialization
+static LOGGING_RELOAD: once_cell::sync::Lazy<std::sync::Mutex<Option<FilterReloadHandle>>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
 static PANIC_HOOK_INIT: Once = Once::new();
 
-// Guards for non-blocking writers
-static FILE_GUARD: once_cell::sync::Lazy<
-    std::sync::Mutex<Option<tracing_appender::non_blocking::WorkerGuard>>,
-> = once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
-static CONSOLE_GUARD: once_cell::sync::Lazy<
-    std::sync::Mutex<Option<tracing_appender::non_blocking::WorkerGuard>>,
-> = once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
+// Log file writer of the installed subscriber and guard for the console writer
+static FILE_WRITER: once_cell::sync::OnceCell<FileLogWriter> = once_cell::sync::OnceCell::new();
+static CONSOLE_GUARD: once_cell::sync::Lazy<std::sync::Mutex<Option<WorkerGuard>>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Non-blocking writer of the log file, replaced by a fresh one to flush it
+///
+/// Lives in a static for the whole program: dropping the worker guard stops
+/// file logging.
+struct FileLogWriter {
+    dir: PathBuf,
+    file_name: OsString,
+    current: RwLock<Option<(NonBlocking, WorkerGuard)>>,
+}
+
+impl FileLogWriter {
+    fn open(dir: &Path, file_name: &std::ffi::OsStr) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            file_name: file_name.to_os_string(),
+            current: RwLock::new(Some(Self::spawn(dir, file_name))),
+        }
+    }
+
+    fn spawn(dir: &Path, file_name: &std::ffi::OsStr) -> (NonBlocking, WorkerGuard) {
+        // Create file appender without rotation. The default writer is lossy
+        // and silently drops lines once its queue is full, so block instead.
+        NonBlockingBuilder::default()
+            .lossy(false)
+            .finish(tracing_appender::rolling::never(dir, file_name))
+    }
+
+    /// Swap in a new writer and drop the old guard, which waits until the old
+    /// worker has written every queued line
+    fn flush(&self) {
+        let fresh = Self::spawn(&self.dir, &self.file_name);
+        let previous = self
+            .current
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(fresh);
+        drop(previous);
+    }
+
+    /// Flush and stop writing to the file
+    fn close(&self) {
+        let previous = self
+            .current
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        drop(previous);
+    }
+}
+
+/// `MakeWriter` handing out the current log file writer
+struct FileLogMakeWriter;
+
+impl<'a> MakeWriter<'a> for FileLogMakeWriter {
+    type Writer = OptionalWriter<NonBlocking>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        let writer = FILE_WRITER.get().and_then(|file| {
+            file.current
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_ref()
+                .map(|(writer, _)| writer.clone())
+        });
+        writer.map_or_else(OptionalWriter::none, OptionalWriter::some)
+    }
+}
 
 // Last panic message storage
 static LAST_PANIC: once_cell::sync::Lazy<std::sync::Mutex<Option<String>>> =
     once_cell::sync::Lazy::new(|| std::sync::Mutex::new(None));
 
-/// Initialize console logging with environment variable support
-pub fn init_console_logging(config: &LoggingConfig) {
-    CONSOLE_INIT.call_once(|| {
-        let result = std::panic::catch_unwind(|| {
-            let env_filter = create_env_filter(config);
+/// Install the global subscriber built by `install` exactly once
+///
+/// Later calls replace the filter of the installed subscriber instead.
+fn install_or_reload(
+    config: &LoggingConfig,
+    install: impl FnOnce(reload::Layer<EnvFilter, Registry>) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<LoggingInitStatus, Box<dyn std::error::Error>> {
+    let mut reload_handle = LOGGING_RELOAD.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(handle) = reload_handle.as_ref() {
+        handle.reload(create_env_filter(config))?;
+        return Ok(LoggingInitStatus::LevelUpdated);
+    }
 
-            tracing_subscriber::registry()
-                .with(env_filter)
-                .with(fmt::layer().with_target(true).with_thread_ids(true))
-                .init();
+    let (filter, handle) = reload::Layer::new(create_env_filter(config));
+    install(filter)?;
+    *reload_handle = Some(handle);
+    Ok(LoggingInitStatus::Initialized)
+}
+
+/// Initialize console logging with environment variable support
+///
+/// Only the first initializer call installs the global subscriber, later calls
+/// update the level and return `LoggingInitStatus::LevelUpdated`.
+pub fn init_console_logging(
+    config: &LoggingConfig,
+) -> Result<LoggingInitStatus, Box<dyn std::error::Error>> {
+    let status = install_or_reload(config, |filter| {
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(
+                fmt::layer()
+                    .with_timer(LogTimer)
+                    .with_target(true)
+                    .with_thread_ids(true),
+            )
+            .with(LogCallbackLayer)
+            .try_init()?;
+        Ok(())
+    })?;
 
+    match status {
+        LoggingInitStatus::Initialized => {
             debug!(
                 "Console logging initialized for {} (level: {})",
                 config.module_name, config.log_level
@@ -62,101 +221,102 @@ pub fn init_console_logging(config: &LoggingConfig) {
                 "[RUST] Console logging initialized for module: {}",
                 config.module_name
             );
-        });
-
-        if result.is_err() {
-            eprintln!("[EASYTIER_COMMON] Failed to initialize console logging");
         }
+        LoggingInitStatus::LevelUpdated => {
+            info!(
+                "[RUST] Logging level updated for module: {} (level: {})",
+                config.module_name, config.log_level
+            );
+        }
+    }
 
-        // Always initialize panic hook
-        init_panic_recovery();
-    });
+    // Always initialize panic hook
+    init_panic_recovery();
+
+    Ok(status)
 }
 
 /// Initialize file logging with both file and console output
+///
+/// If logging is already initialized only the level is updated, the file
+/// output is not added.
 pub fn init_file_logging(
     config: &LoggingConfig,
     log_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut init_result = Ok(());
-
-    FILE_INIT.call_once(|| {
-        let result = std::panic::catch_unwind(|| -> Result<(), Box<dyn std::error::Error>> {
-            // Extract directory and filename from log_path
-            let path = Path::new(log_path);
-            let log_dir = path
-                .parent()
-                .ok_or("Invalid log path: no parent directory")?;
-            let log_filename = path.file_name().ok_or("Invalid log path: no filename")?;
-
-            // Create log directory if it doesn't exist
-            fs::create_dir_all(log_dir)?;
-
-            use tracing_appender::non_blocking;
-
-            let env_filter = create_env_filter(config);
-
-            // Create file appender without rotation
-            let file_appender = tracing_appender::rolling::never(log_dir, log_filename);
-            let (file_writer, file_guard) = non_blocking(file_appender);
-            *FILE_GUARD.lock().unwrap() = Some(file_guard);
-
-            // Create console writer
-            let (console_writer, console_guard) = non_blocking(std::io::stdout());
-            *CONSOLE_GUARD.lock().unwrap() = Some(console_guard);
-
-            // Initialize subscriber with both outputs
-            tracing_subscriber::registry()
-                .with(env_filter)
-                .with(
-                    fmt::layer()
-                        .with_writer(file_writer)
-                        .with_target(true)
-                        .with_thread_ids(true)
-                        .with_ansi(false),
-                )
-                .with(
-                    fmt::layer()
-                        .with_writer(console_writer)
-                        .with_target(true)
-                        .with_thread_ids(true)
-                        .with_ansi(true),
-                )
-                .init();
+) -> Result<LoggingInitStatus, Box<dyn std::error::Error>> {
+    let status = install_or_reload(config, |filter| {
+        // Extract directory and filename from log_path
+        let path = Path::new(log_path);
+        let log_dir = path
+            .parent()
+            .ok_or("Invalid log path: no parent directory")?;
+        let log_filename = path.file_name().ok_or("Invalid log path: no filename")?;
+
+        // Create log directory if it doesn't exist
+        fs::create_dir_all(log_dir)?;
+
+        let _ = FILE_WRITER.set(FileLogWriter::open(log_dir, log_filename));
+
+        // Create console writer
+        let (console_writer, console_guard) = tracing_appender::non_blocking(std::io::stdout());
+
+        // Initialize subscriber with both outputs
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(
+                fmt::layer()
+                    .with_writer(FileLogMakeWriter)
+                    .with_timer(LogTimer)
+                    .with_target(true)
+                    .with_thread_ids(true)
+                    .with_ansi(false),
+            )
+            .with(
+                fmt::layer()
+                    .with_writer(console_writer)
+                    .with_timer(LogTimer)
+                    .with_target(true)
+                    .with_thread_ids(true)
+                    .with_ansi(true),
+            )
+            .with(LogCallbackLayer)
+            .try_init()?;
+
+        *CONSOLE_GUARD.lock().unwrap() = Some(console_guard);
+        Ok(())
+    })?;
 
+    match status {
+        LoggingInitStatus::Initialized => {
             debug!(
-                "File logging initialized for {} in directory: {}",
-                config.module_name,
-                log_dir.display()
+                "File logging initialized for {} at: {}",
+                config.module_name, log_path
             );
 
             info!(
                 "[RUST] File logging initialized for module: {} (level: {})",
                 config.module_name, config.log_level
             );
-
-            Ok(())
-        });
-
-        match result {
-            Ok(Ok(())) => {}
-            Ok(Err(e)) => {
-                init_result = Err(e);
-            }
-            Err(e) => {
-                let error_msg = format!("Panic during file logging initialization: {:?}", e);
-                init_result = Err(error_msg.into());
-            }
         }
+        LoggingInitStatus::LevelUpdated => {
+            info!(
+                "[RUST] Logging already initialized, level updated for module: {} (level: {})",
+                config.module_name, config.log_level
+            );
+        }
+    }
 
-        init_panic_recovery();
-    });
+    init_panic_recovery();
 
-    init_result
+    Ok(status)
 }
 
 /// Create environment filter for logging
 fn create_env_filter(config: &LoggingConfig) -> EnvFilter {
+    if let Some(filter_spec) = &config.filter_spec {
+        return parse_filter_spec(filter_spec).unwrap_or_else(|_| EnvFilter::new("info"));
+    }
+
     let submodules = [
         "logging",
         "ffi_utils",
@@ -198,10 +358,253 @@ fn create_env_filter(config: &LoggingConfig) -> EnvFilter {
         })
 }
 
+/// RFC 3339 timestamp pattern with microseconds, for `set_log_time_format`
+pub const RFC3339_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6f%:z";
+
+#[derive(Debug, Clone)]
+struct LogTimeFormat {
+    format: String,
+    use_utc: bool,
+}
+
+static LOG_TIME_FORMAT: RwLock<Option<LogTimeFormat>> = RwLock::new(None);
+
+/// Set the strftime pattern of log timestamps, in UTC or the local timezone
+///
+/// Takes effect immediately, also for logging initialized earlier. Fails for
+/// an empty pattern or one with an invalid specifier.
+pub fn set_log_time_format(format: &str, use_utc: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if format.is_empty() {
+        return Err("Log time format must not be empty".into());
+    }
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid log time format: {}", format).into());
+    }
+
+    *LOG_TIME_FORMAT.write().unwrap_or_else(|e| e.into_inner()) = Some(LogTimeFormat {
+        format: format.to_string(),
+        use_utc,
+    });
+    Ok(())
+}
+
+/// Restore the default log timestamp format
+pub fn reset_log_time_format() {
+    *LOG_TIME_FORMAT.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Timer formatting log timestamps as configured by `set_log_time_format`
+pub struct LogTimer;
+
+impl FormatTime for LogTimer {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        let time_format = LOG_TIME_FORMAT
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        match time_format {
+            Some(LogTimeFormat {
+                format,
+                use_utc: true,
+            }) => write!(w, "{}", Utc::now().format(&format)),
+            Some(LogTimeFormat {
+                format,
+                use_utc: false,
+            }) => write!(w, "{}", Local::now().format(&format)),
+            None => SystemTime.format_time(w),
+        }
+    }
+}
+
+/// Log record sink registered from the host application
+pub type LogCallback = extern "C" fn(level: c_int, msg: *const c_char);
+
+/// Log level passed to the log callback for `ERROR` records
+pub const CORTEX_LOG_LEVEL_ERROR: c_int = 1;
+/// Log level passed to the log callback for `WARN` records
+pub const CORTEX_LOG_LEVEL_WARN: c_int = 2;
+/// Log level passed to the log callback for `INFO` records
+pub const CORTEX_LOG_LEVEL_INFO: c_int = 3;
+/// Log level passed to the log callback for `DEBUG` records
+pub const CORTEX_LOG_LEVEL_DEBUG: c_int = 4;
+/// Log level passed to the log callback for `TRACE` records
+pub const CORTEX_LOG_LEVEL_TRACE: c_int = 5;
+
+static LOG_CALLBACK: RwLock<Option<LogCallback>> = RwLock::new(None);
+
+thread_local! {
+    // Set while the callback runs so records it logs are not forwarded again
+    static IN_LOG_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Register the log callback, replacing any previous one, `None` clears it
+pub fn set_log_callback(callback: Option<LogCallback>) {
+    *LOG_CALLBACK.write().unwrap_or_else(|e| e.into_inner()) = callback;
+}
+
+fn log_level_to_c_int(level: &Level) -> c_int {
+    match *level {
+        Level::ERROR => CORTEX_LOG_LEVEL_ERROR,
+        Level::WARN => CORTEX_LOG_LEVEL_WARN,
+        Level::INFO => CORTEX_LOG_LEVEL_INFO,
+        Level::DEBUG => CORTEX_LOG_LEVEL_DEBUG,
+        Level::TRACE => CORTEX_LOG_LEVEL_TRACE,
+    }
+}
+
+/// Collects an event's message and fields into a single line
+#[derive(Default)]
+struct LogLineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LogLineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+thread_local! {
+    // Correlation id of the host request the current FFI call belongs to
+    static REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Set the correlation id attached to the logs of the next FFI call on this thread
+pub fn set_request_id(id: &str) {
+    REQUEST_ID.with(|request_id| *request_id.borrow_mut() = Some(id.to_string()));
+}
+
+/// Clear the correlation id of this thread
+pub fn clear_request_id() {
+    REQUEST_ID.with(|request_id| *request_id.borrow_mut() = None);
+}
+
+/// Correlation id currently set on this thread
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.with(|request_id| request_id.borrow().clone())
+}
+
+/// Span tagging the logs of one FFI call with the thread's correlation id
+///
+/// Dropping it leaves the span and clears the id, so an id never leaks into a
+/// later call reusing the thread.
+pub struct RequestScope {
+    _span: EnteredSpan,
+}
+
+/// Enter the request span of the FFI call `call`, a no-op span when no id is set
+pub fn enter_request_scope(call: &'static str) -> RequestScope {
+    let span = match current_request_id() {
+        Some(request_id) => tracing::error_span!("ffi_call", call, request_id = %request_id),
+        None => tracing::Span::none(),
+    };
+    RequestScope {
+        _span: span.entered(),
+    }
+}
+
+impl Drop for RequestScope {
+    fn drop(&mut self) {
+        clear_request_id();
+    }
+}
+
+/// Correlation id recorded on an `ffi_call` span
+struct SpanRequestId(String);
+
+/// Picks the `request_id` field out of a span's fields
+#[derive(Default)]
+struct RequestIdVisitor(Option<String>);
+
+impl Visit for RequestIdVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "request_id" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "request_id" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Tracing layer forwarding each record to the registered log callback
+///
+/// Included in the subscribers installed by the logging initializers. Records
+/// are dropped while no callback is registered.
+pub struct LogCallbackLayer;
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for LogCallbackLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = RequestIdVisitor::default();
+        attrs.record(&mut visitor);
+        let Some(request_id) = visitor.0 else {
+            return;
+        };
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanRequestId(request_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        // Copy the callback out so it runs without the lock held
+        let Some(callback) = *LOG_CALLBACK.read().unwrap_or_else(|e| e.into_inner()) else {
+            return;
+        };
+        if IN_LOG_CALLBACK.with(|flag| flag.replace(true)) {
+            return;
+        }
+
+        let metadata = event.metadata();
+        let mut visitor = LogLineVisitor::default();
+        event.record(&mut visitor);
+        let mut line = format!(
+            "{}: {}{}",
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        );
+        let request_id = ctx.event_scope(event).and_then(|scope| {
+            scope.from_root().find_map(|span| {
+                span.extensions()
+                    .get::<SpanRequestId>()
+                    .map(|id| id.0.clone())
+            })
+        });
+        if let Some(request_id) = request_id {
+            let _ = write!(line, " request_id={}", request_id);
+        }
+        // Interior null bytes cannot cross the C boundary
+        if let Ok(line) = CString::new(line.replace('\0', "")) {
+            callback(log_level_to_c_int(metadata.level()), line.as_ptr());
+        }
+
+        IN_LOG_CALLBACK.with(|flag| flag.set(false));
+    }
+}
+
 /// Set configuration and initialize console logging
-pub fn set_and_init_console_logging(level: &str, module_name: &str) {
+pub fn set_and_init_console_logging(
+    level: &str,
+    module_name: &str,
+) -> Result<LoggingInitStatus, Box<dyn std::error::Error>> {
     let config = LoggingConfig::new(level, module_name);
-    init_console_logging(&config);
+    init_console_logging(&config)
 }
 
 /// Set configuration and initialize file logging
@@ -209,16 +612,32 @@ pub fn set_and_init_file_logging(
     level: &str,
     module_name: &str,
     log_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<LoggingInitStatus, Box<dyn std::error::Error>> {
     let config = LoggingConfig::new(level, module_name);
     init_file_logging(&config, log_path)
 }
 
+/// Initialize console logging, or file and console logging when `log_path` is
+/// given, filtered by comma-separated per-target directives
+pub fn set_and_init_console_logging_filtered(
+    filter_spec: &str,
+    log_path: Option<&str>,
+) -> Result<LoggingInitStatus, Box<dyn std::error::Error>> {
+    let config = LoggingConfig::from_filter_spec(filter_spec)?;
+    match log_path {
+        Some(log_path) => init_file_logging(&config, log_path),
+        None => init_console_logging(&config),
+    }
+}
+
 // FFI exports for Go integration
 use std::ffi::{c_char, c_int, CStr};
 
 /// FFI wrapper: Initialize console logging
 ///
+/// Returns `EASYTIER_LOGGING_INITIALIZED` on the first call,
+/// `EASYTIER_LOGGING_LEVEL_UPDATED` when logging was already initialized, -1 on error.
+///
 /// # Safety
 ///
 /// The caller must ensure that `level` and `module_name` are valid C strings.
@@ -241,12 +660,16 @@ pub unsafe extern "C" fn easytier_common_init_console_logging(
         Err(_) => return -1,
     };
 
-    set_and_init_console_logging(level_str, module_str);
-    0
+    match set_and_init_console_logging(level_str, module_str) {
+        Ok(status) => status.as_c_int(),
+        Err(_) => -1,
+    }
 }
 
 /// FFI wrapper: Initialize file logging
 ///
+/// Returns the same codes as `easytier_common_init_console_logging`.
+///
 /// # Safety
 ///
 /// The caller must ensure that `level`, `module_name`, and `log_path` are valid C strings.
@@ -276,6 +699,121 @@ pub unsafe extern "C" fn easytier_common_init_file_logging(
     };
 
     match set_and_init_file_logging(level_str, module_str, path_str) {
+        Ok(status) => status.as_c_int(),
+        Err(_) => -1,
+    }
+}
+
+/// FFI wrapper: Initialize logging filtered by per-target directives
+///
+/// `filter_spec` uses `EnvFilter` syntax, e.g. `easytier=warn,sqlx=error`.
+/// Logs go to the console only when `log_path` is null. Returns the same
+/// codes as `easytier_common_init_console_logging`.
+///
+/// # Safety
+///
+/// The caller must ensure that `filter_spec` is a valid C string and that
+/// `log_path` is either null or a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn easytier_common_init_logging_filtered(
+    filter_spec: *const c_char,
+    log_path: *const c_char,
+) -> c_int {
+    if filter_spec.is_null() {
+        return -1;
+    }
+
+    let filter_str = match CStr::from_ptr(filter_spec).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let path_str = if log_path.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(log_path).to_str() {
+            Ok(s) => Some(s),
+            Err(_) => return -1,
+        }
+    };
+
+    match set_and_init_console_logging_filtered(filter_str, path_str) {
+        Ok(status) => status.as_c_int(),
+        Err(_) => -1,
+    }
+}
+
+/// Flush the log file written by file logging
+///
+/// Blocks until the lines logged so far are written. Safe to call from a log
+/// callback. Returns 0 after flushing, 1 when file logging is not initialized.
+#[no_mangle]
+pub extern "C" fn cortex_core_flush_logs() -> c_int {
+    if flush_file_logs() {
+        0
+    } else {
+        1
+    }
+}
+
+/// Register a callback receiving each log record, passing null clears it
+///
+/// `msg` is only valid for the duration of the call. The callback runs on the
+/// logging thread without internal locks held, so it may call back into the
+/// library. Logging must be initialized for records to be forwarded.
+#[no_mangle]
+pub extern "C" fn cortex_core_set_log_callback(cb: Option<LogCallback>) {
+    set_log_callback(cb);
+}
+
+/// Set the correlation id attached to the logs of the next FFI call on this thread
+///
+/// The id is cleared when that call returns. Go callers must keep the
+/// goroutine on one OS thread (`runtime.LockOSThread`) between this call and
+/// the call it tags. A null `id` clears it.
+///
+/// # Safety
+///
+/// The caller must ensure that `id` is either null or a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_set_request_id(id: *const c_char) {
+    if id.is_null() {
+        clear_request_id();
+        return;
+    }
+    set_request_id(&CStr::from_ptr(id).to_string_lossy());
+}
+
+/// Clear the correlation id of the calling thread
+#[no_mangle]
+pub extern "C" fn cortex_clear_request_id() {
+    clear_request_id();
+}
+
+/// FFI wrapper: Set the strftime pattern of log timestamps
+///
+/// Timestamps are in UTC when `use_utc` is true and in the local timezone
+/// otherwise. A null `format` restores the default format.
+///
+/// # Safety
+///
+/// The caller must ensure that `format` is either null or a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn easytier_common_set_log_time_format(
+    format: *const c_char,
+    use_utc: bool,
+) -> c_int {
+    if format.is_null() {
+        reset_log_time_format();
+        return 0;
+    }
+
+    let format_str = match CStr::from_ptr(format).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    match set_log_time_format(format_str, use_utc) {
         Ok(_) => 0,
         Err(_) => -1,
     }
@@ -325,6 +863,35 @@ pub fn clear_last_panic() {
     *LAST_PANIC.lock().unwrap() = None;
 }
 
+/// Write out the log file lines queued so far, logging continues afterwards
+///
+/// Returns false when file logging is not initialized. A line being written
+/// by another thread at the moment of the flush may be dropped.
+pub fn flush_file_logs() -> bool {
+    match FILE_WRITER.get() {
+        Some(file) => {
+            file.flush();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Flush the non-blocking log writers and stop their worker threads
+///
+/// Dropping the guards writes out everything still queued. Records logged to
+/// the file and console writers afterwards are lost, so only call this when
+/// the process is shutting down.
+pub fn flush_logging() {
+    if let Some(file) = FILE_WRITER.get() {
+        file.close();
+    }
+    CONSOLE_GUARD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+}
+
 // Logging macros
 #[macro_export]
 macro_rules! debug {
@@ -367,13 +934,183 @@ mod tests {
 
     #[test]
     fn test_console_logging_init() {
-        set_and_init_console_logging("debug", "test_module");
-        // Should not panic on second call
-        set_and_init_console_logging("info", "test_module");
+        assert!(set_and_init_console_logging("debug", "test_module").is_ok());
+        // Should not panic or install a second subscriber on the second call
+        assert_eq!(
+            set_and_init_console_logging("info", "test_module").unwrap(),
+            LoggingInitStatus::LevelUpdated
+        );
+
+        let level = CString::new("warn").unwrap();
+        let module = CString::new("test_module").unwrap();
+        let result =
+            unsafe { easytier_common_init_console_logging(level.as_ptr(), module.as_ptr()) };
+        assert_eq!(result, EASYTIER_LOGGING_LEVEL_UPDATED);
+    }
+
+    /// Writer collecting formatted log output in memory
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CapturedLogs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_filter_spec_suppresses_target() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(parse_filter_spec("sqlx=off,info").unwrap())
+            .with(
+                fmt::layer()
+                    .with_writer(move || writer.clone())
+                    .with_ansi(false),
+            );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "sqlx::query", "suppressed sqlx event");
+            tracing::info!(target: "cortex_bridge", "allowed event");
+        });
+
+        let output = logs.contents();
+        assert!(!output.contains("suppressed sqlx event"));
+        assert!(output.contains("allowed event"));
+    }
+
+    #[test]
+    fn test_invalid_filter_spec_is_rejected() {
+        assert!(LoggingConfig::from_filter_spec("sqlx=notalevel").is_err());
+        assert!(set_and_init_console_logging_filtered("easytier=[", None).is_err());
+        assert!(LoggingConfig::from_filter_spec("easytier=warn,sqlx=error").is_ok());
+    }
+
+    static RECEIVED_LOGS: std::sync::Mutex<Vec<(c_int, String)>> =
+        std::sync::Mutex::new(Vec::new());
+
+    extern "C" fn collect_log(level: c_int, msg: *const c_char) {
+        let msg = unsafe { CStr::from_ptr(msg) }
+            .to_string_lossy()
+            .into_owned();
+        RECEIVED_LOGS.lock().unwrap().push((level, msg));
+    }
+
+    fn received_log(needle: &str) -> Option<(c_int, String)> {
+        RECEIVED_LOGS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, msg)| msg.contains(needle))
+            .cloned()
+    }
+
+    #[test]
+    fn test_log_callback_receives_records() {
+        let subscriber = tracing_subscriber::registry().with(LogCallbackLayer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            cortex_core_set_log_callback(Some(collect_log));
+            tracing::warn!(target: "cortex_bridge", device = 7, "callback record");
+
+            cortex_core_set_log_callback(None);
+            tracing::warn!(target: "cortex_bridge", "record after clear");
+        });
+
+        let (level, msg) = received_log("callback record").expect("log line should arrive");
+        assert_eq!(level, CORTEX_LOG_LEVEL_WARN);
+        assert_eq!(msg, "cortex_bridge: callback record device=7");
+        assert!(received_log("record after clear").is_none());
+    }
+
+    #[test]
+    fn test_utc_rfc3339_log_timestamp() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .with_writer(move || writer.clone())
+                .with_timer(LogTimer)
+                .with_ansi(false),
+        );
+
+        set_log_time_format(RFC3339_TIME_FORMAT, true).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("timestamped event");
+        });
+        reset_log_time_format();
+
+        let output = logs.contents();
+        let line = output
+            .lines()
+            .find(|line| line.contains("timestamped event"))
+            .expect("log line should be emitted");
+        let timestamp = line.split_whitespace().next().unwrap();
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(),
+            "{} should be an RFC 3339 timestamp",
+            timestamp
+        );
+        assert!(timestamp.ends_with("+00:00"), "{} should be UTC", timestamp);
+    }
+
+    #[test]
+    fn test_invalid_log_time_format_is_rejected() {
+        assert!(set_log_time_format("", true).is_err());
+        assert!(set_log_time_format("%Y-%m-%d %Q", false).is_err());
+    }
+
+    #[test]
+    fn test_request_id_tags_records() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .with_writer(move || writer.clone())
+                .with_ansi(false),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let id = CString::new("req-42").unwrap();
+            unsafe { cortex_set_request_id(id.as_ptr()) };
+            {
+                let _scope = enter_request_scope("test_call");
+                tracing::info!("inside request");
+            }
+            assert_eq!(current_request_id(), None);
+            tracing::info!("after request");
+        });
+
+        let output = logs.contents();
+        let line = |needle: &str| {
+            output
+                .lines()
+                .find(|line| line.contains(needle))
+                .expect("log line should be emitted")
+                .to_string()
+        };
+        assert!(line("inside request").contains("request_id=req-42"));
+        assert!(!line("after request").contains("request_id"));
     }
 
     #[test]
     fn test_panic_recovery() {
+        // cortex_shutdown_all clears the last panic
+        let _guard = crate::ERROR_STATE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
         init_panic_recovery();
         clear_last_panic();
 
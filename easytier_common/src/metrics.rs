@@ -0,0 +1,185 @@
+//! Lightweight process-wide metrics exported in Prometheus text format
+//!
+//! Each metric is a plain atomic so instrumentation points stay cheap.
+//! Note that every cdylib links its own copy of these statics, so
+//! `cortex_metrics_gather` reports the counters of the library it is called on.
+
+use std::ffi::{c_char, c_int, CString};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use crate::set_error_msg;
+
+/// A value that can go up and down
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub const fn new() -> Self {
+        Gauge(AtomicI64::new(0))
+    }
+
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.sub(1);
+    }
+
+    pub fn sub(&self, value: i64) {
+        self.0.fetch_sub(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A monotonically increasing value
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub const fn new() -> Self {
+        Counter(AtomicU64::new(0))
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Running gateway core instances
+pub static ACTIVE_GATEWAY_INSTANCES: Gauge = Gauge::new();
+
+/// Sessions currently tracked by config server client managers
+pub static ACTIVE_CONFIG_SESSIONS: Gauge = Gauge::new();
+
+/// Heartbeats processed successfully by the config server
+pub static HEARTBEATS_PROCESSED_TOTAL: Counter = Counter::new();
+
+/// Heartbeats that failed to sync the device record to the database
+pub static HEARTBEAT_DB_ERRORS_TOTAL: Counter = Counter::new();
+
+fn write_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Render all metrics in Prometheus text exposition format
+pub fn gather_metrics() -> String {
+    let mut out = String::new();
+    write_metric(
+        &mut out,
+        "cortex_gateway_active_instances",
+        "gauge",
+        "Number of running gateway core instances",
+        ACTIVE_GATEWAY_INSTANCES.get(),
+    );
+    write_metric(
+        &mut out,
+        "cortex_config_server_active_sessions",
+        "gauge",
+        "Number of active config server sessions",
+        ACTIVE_CONFIG_SESSIONS.get(),
+    );
+    write_metric(
+        &mut out,
+        "cortex_config_server_heartbeats_total",
+        "counter",
+        "Total heartbeats processed by the config server",
+        HEARTBEATS_PROCESSED_TOTAL.get(),
+    );
+    write_metric(
+        &mut out,
+        "cortex_config_server_heartbeat_db_errors_total",
+        "counter",
+        "Total heartbeats that failed to sync the device record",
+        HEARTBEAT_DB_ERRORS_TOTAL.get(),
+    );
+    out
+}
+
+/// Gather metrics in Prometheus text format
+/// Returns 0 on success, -1 on error
+///
+/// The returned string must be freed with `easytier_common_free_string`.
+///
+/// # Safety
+///
+/// The caller must ensure that `out` is a valid mutable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_metrics_gather(out: *mut *mut c_char) -> c_int {
+    if out.is_null() {
+        set_error_msg("out is null");
+        return -1;
+    }
+
+    match CString::new(gather_metrics()) {
+        Ok(c_str) => {
+            *out = c_str.into_raw();
+            0
+        }
+        Err(e) => {
+            set_error_msg(&format!("failed to create C string: {}", e));
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::ptr;
+
+    fn metric_value(payload: &str, name: &str) -> Option<u64> {
+        payload
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{} ", name)))
+            .and_then(|value| value.parse().ok())
+    }
+
+    #[test]
+    fn test_gather_includes_incremented_counter() {
+        let before = HEARTBEATS_PROCESSED_TOTAL.get();
+        HEARTBEATS_PROCESSED_TOTAL.inc();
+
+        let mut out: *mut c_char = ptr::null_mut();
+        let result = unsafe { cortex_metrics_gather(&mut out) };
+        assert_eq!(result, 0);
+
+        let payload = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_string();
+        crate::easytier_common_free_string(out);
+
+        assert!(payload.contains("# TYPE cortex_config_server_heartbeats_total counter"));
+        let value = metric_value(&payload, "cortex_config_server_heartbeats_total").unwrap();
+        assert!(value > before, "counter should reflect the increment");
+        assert!(payload.contains("cortex_gateway_active_instances "));
+        assert!(payload.contains("cortex_config_server_heartbeat_db_errors_total "));
+    }
+
+    #[test]
+    fn test_gather_null_output() {
+        let _guard = crate::ERROR_STATE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        assert_eq!(unsafe { cortex_metrics_gather(ptr::null_mut()) }, -1);
+    }
+}
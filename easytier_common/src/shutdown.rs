@@ -0,0 +1,104 @@
+//! Process exit cleanup shared by all FFI crates
+//!
+//! Crates register a hook when they create a resource that must be released
+//! at exit (gateway cores, the config service singleton). `cortex_shutdown_all`
+//! runs the hooks once, flushes the log writers and clears the error state.
+//! Like the metrics, every cdylib links its own registry, so the call only
+//! reaches the hooks of the library it is called on.
+
+use std::ffi::c_int;
+use std::sync::Mutex;
+
+use crate::{clear_error, clear_last_panic, flush_logging, info, set_error, CortexErrorCode};
+
+/// Cleanup run by `cortex_shutdown_all`, returns a message on failure
+pub type ShutdownHook = fn() -> Result<(), String>;
+
+static SHUTDOWN_HOOKS: Mutex<Vec<(&'static str, ShutdownHook)>> = Mutex::new(Vec::new());
+
+/// Register `hook` under `name`, registering the same name again is a no-op
+pub fn register_shutdown_hook(name: &'static str, hook: ShutdownHook) {
+    let mut hooks = SHUTDOWN_HOOKS.lock().unwrap_or_else(|e| e.into_inner());
+    if !hooks.iter().any(|(registered, _)| *registered == name) {
+        hooks.push((name, hook));
+    }
+}
+
+/// Run and unregister every shutdown hook, returns the failure messages
+///
+/// Hooks run in reverse registration order; a panicking hook counts as a
+/// failure and does not stop the others.
+pub fn run_shutdown_hooks() -> Vec<String> {
+    let hooks = std::mem::take(&mut *SHUTDOWN_HOOKS.lock().unwrap_or_else(|e| e.into_inner()));
+
+    let mut failures = Vec::new();
+    for (name, hook) in hooks.into_iter().rev() {
+        match std::panic::catch_unwind(hook) {
+            Ok(Ok(())) => info!("Shutdown hook '{}' finished", name),
+            Ok(Err(e)) => failures.push(format!("{}: {}", name, e)),
+            Err(_) => failures.push(format!("{}: panicked", name)),
+        }
+    }
+    failures
+}
+
+/// Release everything at process exit: stop gateway cores, destroy the config
+/// service, flush the log files and clear the error state
+/// Safe to call when nothing was initialized. Returns 0 on success, or -1 if a
+/// resource failed to shut down (the error message lists them).
+#[no_mangle]
+pub extern "C" fn cortex_shutdown_all() -> c_int {
+    let failures = run_shutdown_hooks();
+    flush_logging();
+    clear_last_panic();
+    clear_error();
+
+    if failures.is_empty() {
+        0
+    } else {
+        set_error(
+            CortexErrorCode::Internal,
+            &format!("shutdown failed: {}", failures.join("; ")),
+        );
+        -1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_hook() -> Result<(), String> {
+        HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown_all_on_fresh_state() {
+        let _guard = crate::ERROR_STATE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        assert_eq!(cortex_shutdown_all(), 0);
+
+        // Calling it again is harmless
+        assert_eq!(cortex_shutdown_all(), 0);
+    }
+
+    #[test]
+    fn test_shutdown_hooks_run_once() {
+        let _guard = crate::ERROR_STATE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        register_shutdown_hook("test_counting", counting_hook);
+        register_shutdown_hook("test_counting", counting_hook);
+
+        assert_eq!(cortex_shutdown_all(), 0);
+        assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 1);
+
+        assert_eq!(cortex_shutdown_all(), 0);
+        assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,48 @@
+//! Error code reporting tests
+//!
+//! The last error is process-wide state, so these checks live in their own
+//! test binary and run sequentially inside a single test.
+
+use std::ffi::{c_int, CStr};
+
+use easytier_common::{
+    cortex_get_error_msg_len, cortex_get_last_error_code, easytier_common_get_error_msg, set_error,
+    set_error_msg, CortexErrorCode,
+};
+
+fn last_error() -> (c_int, String) {
+    let msg = easytier_common_get_error_msg();
+    assert!(!msg.is_null());
+    let msg = unsafe { CStr::from_ptr(msg) }
+        .to_string_lossy()
+        .into_owned();
+    (cortex_get_last_error_code(), msg)
+}
+
+#[test]
+fn test_error_code_follows_last_error() {
+    assert_eq!(cortex_get_last_error_code(), CortexErrorCode::Ok as c_int);
+
+    set_error(CortexErrorCode::NotFound, "instance 'x' not found");
+    assert_eq!(
+        last_error(),
+        (
+            CortexErrorCode::NotFound as c_int,
+            "instance 'x' not found".to_string()
+        )
+    );
+
+    set_error(CortexErrorCode::InvalidUrl, "bad url");
+    assert_eq!(last_error().0, CortexErrorCode::InvalidUrl as c_int);
+
+    // Plain messages are reported as internal errors
+    set_error_msg("generic failure");
+    assert_eq!(
+        last_error(),
+        (
+            CortexErrorCode::Internal as c_int,
+            "generic failure".to_string()
+        )
+    );
+    assert_eq!(cortex_get_error_msg_len(), "generic failure".len());
+}
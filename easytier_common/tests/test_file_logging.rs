@@ -0,0 +1,37 @@
+//! File logging tests
+//!
+//! The global subscriber can only be installed once per process, so file
+//! logging is tested in its own binary.
+
+use std::path::PathBuf;
+
+use easytier_common::{cortex_core_flush_logs, set_and_init_file_logging};
+
+fn log_path() -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("easytier-common-logs-{}", std::process::id()))
+        .join("test.log")
+}
+
+fn read_log(path: &PathBuf) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn test_flush_writes_logged_lines() {
+    let path = log_path();
+    // The filter enables records whose target is the module name
+    set_and_init_file_logging("info", "test_file_logging", path.to_str().unwrap())
+        .expect("Failed to initialize file logging");
+
+    tracing::info!("first flush marker");
+    assert_eq!(cortex_core_flush_logs(), 0);
+    assert!(read_log(&path).contains("first flush marker"));
+
+    // Logging keeps working after a flush
+    tracing::info!("second flush marker");
+    assert_eq!(cortex_core_flush_logs(), 0);
+    assert!(read_log(&path).contains("second flush marker"));
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
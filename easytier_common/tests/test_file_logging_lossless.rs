@@ -0,0 +1,36 @@
+//! File logging must not drop lines under load
+//!
+//! Bursts larger than the non-blocking writer's queue used to be dropped
+//! silently. Runs in its own binary since it installs the global subscriber.
+
+use std::time::Duration;
+
+use easytier_common::{cortex_core_flush_logs, set_and_init_file_logging};
+
+const BURSTS: usize = 3;
+const LINES_PER_BURST: usize = 200_000;
+
+#[test]
+fn test_all_lines_reach_the_file() {
+    let dir = std::env::temp_dir().join(format!("easytier-common-lossless-{}", std::process::id()));
+    let path = dir.join("lossless.log");
+    set_and_init_file_logging("info", "test_file_logging_lossless", path.to_str().unwrap())
+        .expect("Failed to initialize file logging");
+
+    for burst in 0..BURSTS {
+        for line in 0..LINES_PER_BURST {
+            tracing::info!("lossless line {} {}", burst, line);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(cortex_core_flush_logs(), 0);
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let written = content
+        .lines()
+        .filter(|line| line.contains("lossless line"))
+        .count();
+    assert_eq!(written, BURSTS * LINES_PER_BURST);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -6,6 +6,8 @@
 /**
  * 创建 NetworkConfigService 单例
  *
+ * 已弃用：请使用 `create_network_config_service_singleton_json`
+ *
  * # Safety
  *
  * 这个函数是不安全的，因为它接受原始指针作为参数
@@ -14,6 +16,20 @@ bool create_network_config_service_singleton(const char *db_url,
                                              const char *geoip_path,
                                              char **err_msg);
 
+/**
+ * 使用 JSON 配置创建 NetworkConfigService 单例
+ *
+ * config_json 示例：`{"db_url": "user:pass@tcp(host:3306)/db", "geoip_path": "...",
+ * "auto_approve_all_orgs": false, "auto_approve_orgs": ["org"], "default_device_type": "Edge",
+ * "reject_unapproved_devices": false, "reject_message": "..."}`，
+ * 其中 db_url 为 Go DSN 格式且必填，其余配置项可选，无法识别的配置项只记录警告
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool create_network_config_service_singleton_json(const char *config_json, char **err_msg);
+
 /**
  * 启动 NetworkConfigService 的监听器
  *
@@ -41,6 +57,17 @@ bool destroy_network_config_service_singleton(char **err_msg);
  */
 void free_c_char(char *s);
 
+/**
+ * 获取版本信息 JSON：`{bridge_version, easytier_version, rerun_version, build_profile}`
+ *
+ * 成功返回 0，失败返回 -1；返回的字符串需要用 `free_c_char` 释放
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+int cortex_get_version_info(char **out_json);
+
 /**
  * 收集单个网络实例信息
  *
@@ -54,6 +81,20 @@ bool network_config_service_collect_one_network_info(const char *org_id,
                                                      char **result_json_out,
                                                      char **err_msg);
 
+/**
+ * 获取设备当前会话的收发字节数
+ *
+ * 输出 `{"tx_bytes": ..., "rx_bytes": ...}`，隧道没有计数时对应字段为 null
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_get_device_throughput(const char *org_id,
+                                                  const char *device_id,
+                                                  char **out_json,
+                                                  char **err_msg);
+
 /**
  * 收集多个网络实例信息
  *
@@ -91,6 +132,28 @@ bool network_config_service_remove_network_instance(const char *org_id,
                                                     const char *inst_id,
                                                     char **err_msg);
 
+/**
+ * 删除设备记录，设备存在活动会话时先关闭会话
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_delete_device(const char *org_id,
+                                          const char *device_id,
+                                          char **err_msg);
+
+/**
+ * 从服务端断开设备的当前会话，设备没有连接时返回 NotFound 错误
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_disconnect_device(const char *org_id,
+                                              const char *device_id,
+                                              char **err_msg);
+
 /**
  * 列出设备
  *
@@ -102,6 +165,108 @@ bool network_config_service_list_devices(const char *org_id,
                                          char **result_json_out,
                                          char **err_msg);
 
+/**
+ * 获取组织内已连接设备之间的网络拓扑，返回 JSON
+ *
+ * 结果包含节点（设备 ID、主机名、peer ID、虚拟 IP）和边（直连的 peer 及延迟）；
+ * 单台设备采集失败时该节点的 `error` 字段记录原因，其它设备不受影响
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_get_org_topology(const char *org_id,
+                                             char **result_json_out,
+                                             char **err_msg);
+
+/**
+ * 按状态统计设备数量，返回 JSON 对象（状态 -> 数量）
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_count_devices_by_status(const char *org_id,
+                                                    char **result_json_out,
+                                                    char **err_msg);
+
+/**
+ * 按过滤条件查询设备记录，`filter_json` 可包含可选的 `status` 和 `name` 字段
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_query_devices(const char *org_id,
+                                          const char *filter_json,
+                                          char **result_json_out,
+                                          char **err_msg);
+
+/**
+ * 分页列出设备记录，返回当前页及设备总数
+ *
+ * 设备记录不含 network_config，避免泄露网络密钥
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_list_devices_paginated(const char *org_id,
+                                                   uint64_t offset,
+                                                   uint64_t limit,
+                                                   char **result_json_out,
+                                                   char **err_msg);
+
+/**
+ * 导出组织内所有设备为 RFC 4180 CSV
+ *
+ * 列为 id、name、serial_number、device_type、status、last_heartbeat、created_at
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_export_devices_csv(const char *org_id, char **out_csv, char **err_msg);
+
+/**
+ * 批量设置组织内设备的状态，`device_ids_json` 为设备 ID 的 JSON 数组，
+ * `status` 为设备状态，大小写不敏感（如 "Online"、"rejected"），与查询过滤和状态统计的写法一致
+ *
+ * 所有更新在同一事务中完成，任一设备不属于该组织时不做修改并返回 false，
+ * 更新的行数写入 `out_count`（可为空）
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_bulk_set_device_status(const char *org_id,
+                                                   const char *device_ids_json,
+                                                   const char *status,
+                                                   uint64_t *out_count,
+                                                   char **err_msg);
+
+/**
+ * 创建组织，组织已存在时直接返回成功
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_create_organization(const char *org_id,
+                                                const char *name,
+                                                char **err_msg);
+
+/**
+ * 检查组织是否存在，结果写入 `exists_out`
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_organization_exists(const char *org_id,
+                                                bool *exists_out,
+                                                char **err_msg);
+
 /**
  * 更新网络状态
  *
@@ -115,6 +280,92 @@ bool network_config_service_update_network_state(const char *org_id,
                                                  bool disabled,
                                                  char **err_msg);
 
+/**
+ * 列出已启动的监听器，返回包含 protocol、bind_addr、port、ip_version 的 JSON 数组
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_list_listeners(char **result_json_out, char **err_msg);
+
+/**
+ * 重新加载 GeoIP 数据库
+ *
+ * 新数据库加载成功后原子替换，之后的新连接使用新数据；加载失败时继续使用旧数据库。
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_reload_geoip(const char *path, char **err_msg);
+
+/**
+ * 列出所有组织的已连接客户端，返回包含 organization_id、client_url 的 JSON 数组
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_list_all_clients(char **result_json_out, char **err_msg);
+
+/**
+ * 一次性获取所有会话最近的心跳，返回以设备 ID 为键的 JSON 对象
+ *
+ * 每项包含 client_url、info（心跳内容）和 age_ms（距收到心跳的毫秒数）
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_snapshot_heartbeats(char **result_json_out, char **err_msg);
+
+/**
+ * 检查尚未应用的数据库迁移，返回迁移名称的 JSON 数组（空数组表示已是最新）
+ *
+ * 只读检查，不会修改数据库
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_check_migrations(char **result_json_out, char **err_msg);
+
+/**
+ * 回滚最近应用的 `steps` 个数据库迁移，用于发布失败后的运维恢复
+ *
+ * `steps` 必须大于 0
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_rollback_migration(int steps, char **err_msg);
+
+/**
+ * 取出待处理的设备状态变更事件，返回包含 device_id、organization_id、old_status、new_status 的 JSON 数组
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_poll_device_events(char **result_json_out, char **err_msg);
+
+/**
+ * 等待设备的下一次心跳，返回心跳的 JSON；超时返回 true 且输出 "null"
+ *
+ * 等待期间不持有服务锁和 runtime 管理器锁，不会阻塞其他 FFI 调用
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_wait_for_heartbeat(const char *org_id,
+                                               const char *device_id,
+                                               uint64_t timeout_ms,
+                                               char **result_json_out,
+                                               char **err_msg);
+
 /**
  * 验证网络配置
  *
@@ -127,6 +378,22 @@ bool network_config_service_validate_config(const char *org_id,
                                             const char *config_json,
                                             char **err_msg);
 
+/**
+ * 验证网络配置，以 JSON 返回逐字段的校验结果
+ *
+ * 校验完成即返回 true，配置是否有效见结果中的 `valid`，未通过的字段及原因见 `issues`；
+ * 参数错误、设备不在线等无法完成校验的情况返回 false
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_validate_config_detailed(const char *org_id,
+                                                     const char *device_id,
+                                                     const char *config_json,
+                                                     char **result_json_out,
+                                                     char **err_msg);
+
 /**
  * 运行网络实例
  *
@@ -139,3 +406,20 @@ bool network_config_service_run_network_instance(const char *org_id,
                                                  const char *config_json,
                                                  char **inst_id_out,
                                                  char **err_msg);
+
+/**
+ * 运行网络实例，携带幂等键
+ *
+ * 调用超时后使用相同的 `idempotency_key` 重试，若首次调用已成功则返回同一个实例 ID，
+ * 不会重复创建实例；`idempotency_key` 为空指针或空字符串时不做幂等处理
+ *
+ * # Safety
+ *
+ * 这个函数是不安全的，因为它接受原始指针作为参数
+ */
+bool network_config_service_run_network_instance_with_key(const char *org_id,
+                                                          const char *device_id,
+                                                          const char *config_json,
+                                                          const char *idempotency_key,
+                                                          char **inst_id_out,
+                                                          char **err_msg);
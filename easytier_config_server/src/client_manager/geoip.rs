@@ -0,0 +1,405 @@
+//! GeoIP lookups for connecting clients
+//!
+//! The database can be replaced while listeners are running: every lookup
+//! works on a snapshot, and the previous database keeps serving until the new
+//! one has been loaded.
+//!
+//! Resolved locations are cached per IP, so clients reconnecting frequently do
+//! not hit the databases on every connection. Reloading a database clears the
+//! cache.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use maxminddb::geoip2;
+
+use super::session::Location;
+
+type Reader = maxminddb::Reader<Vec<u8>>;
+
+fn load_geoip_db(geoip_db: Option<String>) -> Option<Reader> {
+    if let Some(path) = geoip_db {
+        crate::info!("[GEOIP] Attempting to load GeoIP2 database from: {}", path);
+        match maxminddb::Reader::open_readfile(&path) {
+            Ok(reader) => {
+                crate::info!("[GEOIP] Successfully loaded GeoIP2 database from: {}", path);
+                Some(reader)
+            }
+            Err(err) => {
+                crate::warn!(
+                    "[GEOIP] Failed to load GeoIP2 database from {}: {}",
+                    path,
+                    err
+                );
+                None
+            }
+        }
+    } else {
+        crate::info!("[GEOIP] No GeoIP2 database path provided, GeoIP lookup will be disabled");
+        None
+    }
+}
+
+fn load_asn_db(path: String) -> Option<Reader> {
+    crate::info!("[GEOIP] Attempting to load ASN database from: {}", path);
+    match Reader::open_readfile(&path) {
+        Ok(reader) => Some(reader),
+        Err(err) => {
+            crate::warn!("[GEOIP] Failed to load ASN database from {}: {}", path, err);
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    location: Location,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<IpAddr, CacheEntry>,
+    /// Bumped on every invalidation, so a lookup racing a reload cannot insert
+    /// a result from the previous database
+    generation: u64,
+    /// Use counter ordering the entries for LRU eviction
+    clock: u64,
+}
+
+/// Recently resolved locations keyed by client IP
+///
+/// Entries expire after `ttl`; once `capacity` entries are cached the least
+/// recently used one is evicted. A capacity of 0 disables caching.
+#[derive(Debug, Default)]
+struct LookupCache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+}
+
+impl LookupCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            ..Default::default()
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn generation(&self) -> u64 {
+        self.state().generation
+    }
+
+    fn get(&self, ip: IpAddr) -> Option<Location> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut state = self.state();
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(&ip)?;
+        if entry.inserted_at.elapsed() >= self.ttl {
+            state.entries.remove(&ip);
+            return None;
+        }
+        entry.last_used = clock;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.location.clone())
+    }
+
+    fn insert(&self, generation: u64, ip: IpAddr, location: Location) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state();
+        if state.generation != generation {
+            return;
+        }
+        if !state.entries.contains_key(&ip) && state.entries.len() >= self.capacity {
+            // Linear scans are fine for the small capacities this cache is meant for
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, entry| entry.inserted_at.elapsed() < ttl);
+            if state.entries.len() >= self.capacity {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(addr, _)| *addr);
+                if let Some(oldest) = oldest {
+                    state.entries.remove(&oldest);
+                }
+            }
+        }
+        state.clock += 1;
+        let last_used = state.clock;
+        state.entries.insert(
+            ip,
+            CacheEntry {
+                location,
+                inserted_at: Instant::now(),
+                last_used,
+            },
+        );
+    }
+
+    fn clear(&self) {
+        let mut state = self.state();
+        state.entries.clear();
+        state.generation += 1;
+    }
+}
+
+/// GeoIP databases shared by the listener tasks
+#[derive(Debug, Default)]
+pub struct GeoIpDb {
+    reader: RwLock<Arc<Option<Reader>>>,
+    /// Optional GeoLite2-ASN database for the ISP of a client
+    asn_reader: RwLock<Arc<Option<Reader>>>,
+    cache: LookupCache,
+    /// Lookups that missed the cache and read the databases
+    db_lookups: AtomicU64,
+}
+
+impl GeoIpDb {
+    /// Load the database at `path`; without one every lookup returns an unknown location
+    pub fn open(path: Option<String>) -> Self {
+        Self::open_with_asn(path, None)
+    }
+
+    /// Load the city database and an optional ASN database
+    ///
+    /// Without an ASN database `Location::asn` and `Location::asn_org` stay `None`.
+    pub fn open_with_asn(path: Option<String>, asn_path: Option<String>) -> Self {
+        Self {
+            reader: RwLock::new(Arc::new(load_geoip_db(path))),
+            asn_reader: RwLock::new(Arc::new(asn_path.and_then(load_asn_db))),
+            cache: LookupCache::new(
+                crate::config::get_geoip_cache_capacity(),
+                crate::config::get_geoip_cache_ttl(),
+            ),
+            db_lookups: AtomicU64::new(0),
+        }
+    }
+
+    /// Replace the lookup cache settings; a `capacity` of 0 disables caching
+    pub fn with_cache(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.cache = LookupCache::new(capacity, ttl);
+        self
+    }
+
+    /// Number of lookups served from the cache
+    pub fn cache_hits(&self) -> u64 {
+        self.cache.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups that read the databases
+    pub fn db_lookups(&self) -> u64 {
+        self.db_lookups.load(Ordering::Relaxed)
+    }
+
+    /// Whether a database is loaded
+    pub fn is_loaded(&self) -> bool {
+        self.current().is_some()
+    }
+
+    /// Load the database at `path` and swap it in atomically
+    ///
+    /// On error the current database stays in use.
+    pub fn reload(&self, path: &str) -> anyhow::Result<()> {
+        let reader = Reader::open_readfile(path)
+            .with_context(|| format!("failed to load GeoIP database from {}", path))?;
+        *self.reader.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(Some(reader));
+        self.cache.clear();
+        crate::info!("[GEOIP] Reloaded GeoIP2 database from: {}", path);
+        Ok(())
+    }
+
+    /// Load the ASN database at `path` and swap it in atomically
+    ///
+    /// On error the current ASN database stays in use.
+    pub fn reload_asn(&self, path: &str) -> anyhow::Result<()> {
+        let reader = Reader::open_readfile(path)
+            .with_context(|| format!("failed to load ASN database from {}", path))?;
+        *self.asn_reader.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(Some(reader));
+        self.cache.clear();
+        crate::info!("[GEOIP] Reloaded ASN database from: {}", path);
+        Ok(())
+    }
+
+    fn current(&self) -> Arc<Option<Reader>> {
+        self.reader
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Autonomous system number and organization of `ip`, if an ASN database is loaded
+    fn lookup_asn(&self, ip: IpAddr) -> Option<(Option<u32>, Option<String>)> {
+        let asn_db = self
+            .asn_reader
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let db = asn_db.as_ref().as_ref()?;
+        match db.lookup::<geoip2::Asn>(ip) {
+            Ok(Some(asn)) => Some((
+                asn.autonomous_system_number,
+                asn.autonomous_system_organization
+                    .map(|org| org.to_string()),
+            )),
+            Ok(None) => {
+                crate::debug!("[GEOIP] ASN lookup returned no data for {}", ip);
+                None
+            }
+            Err(err) => {
+                crate::debug!("[GEOIP] ASN lookup failed for {}: {}", ip, err);
+                None
+            }
+        }
+    }
+
+    /// Lookup geographic location for client IP
+    pub fn lookup_location(&self, client_url: &url::Url) -> Option<Location> {
+        let host = client_url.host_str()?;
+        crate::trace!("[GEOIP] Looking up location for host: {}", host);
+
+        let ip: std::net::IpAddr = if let Ok(ip) = host.parse() {
+            ip
+        } else {
+            crate::debug!("[GEOIP] Failed to parse host as IP address: {}", host);
+            return None;
+        };
+
+        // Skip lookup for private/special IPs
+        let is_private = match ip {
+            std::net::IpAddr::V4(ipv4) => {
+                ipv4.is_private() || ipv4.is_loopback() || ipv4.is_unspecified()
+            }
+            std::net::IpAddr::V6(ipv6) => ipv6.is_loopback() || ipv6.is_unspecified(),
+        };
+
+        if is_private {
+            crate::debug!(
+                "[GEOIP] Skipping GeoIP lookup for private/special IP: {}",
+                ip
+            );
+            let location = Location {
+                country: "本地网络".to_string(),
+                city: None,
+                region: None,
+                asn: None,
+                asn_org: None,
+            };
+            return Some(location);
+        }
+
+        if let Some(location) = self.cache.get(ip) {
+            crate::trace!("[GEOIP] Serving cached location for IP: {}", ip);
+            return Some(location);
+        }
+
+        let generation = self.cache.generation();
+        let location = self.lookup_uncached(ip);
+        self.cache.insert(generation, ip, location.clone());
+        Some(location)
+    }
+
+    /// Resolve a public IP against the loaded databases
+    fn lookup_uncached(&self, ip: IpAddr) -> Location {
+        self.db_lookups.fetch_add(1, Ordering::Relaxed);
+        let geoip_db = self.current();
+        let mut location = if let Some(db) = &*geoip_db {
+            crate::trace!("[GEOIP] Performing GeoIP lookup for IP: {}", ip);
+            match db.lookup::<geoip2::City>(ip) {
+                Ok(Some(city)) => {
+                    let country = city
+                        .country
+                        .and_then(|c| c.names)
+                        .and_then(|n| {
+                            n.get("zh-CN")
+                                .or_else(|| n.get("en"))
+                                .map(|s| s.to_string())
+                        })
+                        .unwrap_or_else(|| "海外".to_string());
+
+                    let city_name = city.city.and_then(|c| c.names).and_then(|n| {
+                        n.get("zh-CN")
+                            .or_else(|| n.get("en"))
+                            .map(|s| s.to_string())
+                    });
+
+                    let region = city
+                        .subdivisions
+                        .and_then(|mut subdivisions| subdivisions.pop())
+                        .and_then(|subdivision| subdivision.names)
+                        .and_then(|n| {
+                            n.get("zh-CN")
+                                .or_else(|| n.get("en"))
+                                .map(|s| s.to_string())
+                        });
+
+                    let location = Location {
+                        country: country.clone(),
+                        city: city_name.clone(),
+                        region: region.clone(),
+                        asn: None,
+                        asn_org: None,
+                    };
+
+                    crate::debug!("[GEOIP] Successfully resolved location for {}: country={}, city={:?}, region={:?}", 
+                                  ip, country, city_name, region);
+                    location
+                }
+                Ok(None) => {
+                    crate::debug!("[GEOIP] GeoIP lookup returned no data for {}", ip);
+                    Location {
+                        country: "未知".to_string(),
+                        city: None,
+                        region: None,
+                        asn: None,
+                        asn_org: None,
+                    }
+                }
+                Err(err) => {
+                    crate::debug!("[GEOIP] GeoIP lookup failed for {}: {}", ip, err);
+                    Location {
+                        country: "未知".to_string(),
+                        city: None,
+                        region: None,
+                        asn: None,
+                        asn_org: None,
+                    }
+                }
+            }
+        } else {
+            crate::trace!("[GEOIP] No GeoIP database available, returning unknown location");
+            Location {
+                country: "未知".to_string(),
+                city: None,
+                region: None,
+                asn: None,
+                asn_org: None,
+            }
+        };
+
+        if let Some((asn, asn_org)) = self.lookup_asn(ip) {
+            location.asn = asn;
+            location.asn_org = asn_org;
+        }
+
+        location
+    }
+}
@@ -0,0 +1,72 @@
+//! Idempotency keys for operations the host application may retry
+//!
+//! A caller that timed out cannot tell whether its request was carried out.
+//! Retrying with the same key returns the id created by the first successful
+//! call instead of running the operation again.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::OnceCell;
+
+/// Id created under a key with its creation time, empty while the operation
+/// is still running
+type Entry = Arc<OnceCell<(uuid::Uuid, Instant)>>;
+
+/// Ids created under an idempotency key, remembered for `ttl`
+#[derive(Debug)]
+pub struct IdempotencyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the id recorded for `key`, or run `operation` and record its id
+    ///
+    /// Concurrent calls with the same key wait for the one running `operation`
+    /// instead of running it again, while calls with other keys proceed. Failed
+    /// operations are not recorded and can be retried under the same key.
+    pub async fn get_or_run<F, Fut>(&self, key: &str, operation: F) -> anyhow::Result<uuid::Uuid>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<uuid::Uuid>>,
+    {
+        let entry = {
+            let mut entries = self.entries.lock().unwrap();
+            let ttl = self.ttl;
+            // Keep entries still in use by a caller, even if not filled yet
+            entries.retain(|_, entry| match entry.get() {
+                Some((_, created_at)) => created_at.elapsed() < ttl,
+                None => Arc::strong_count(entry) > 1,
+            });
+            entries.entry(key.to_string()).or_default().clone()
+        };
+
+        let mut ran = false;
+        let (id, _) = *entry
+            .get_or_try_init(|| async {
+                ran = true;
+                let id = operation().await?;
+                Ok::<_, anyhow::Error>((id, Instant::now()))
+            })
+            .await?;
+
+        if !ran {
+            crate::info!(
+                "[IDEMPOTENCY] Key {} already created {}, skipping operation",
+                key,
+                id
+            );
+        }
+        Ok(id)
+    }
+}
@@ -3,8 +3,10 @@
 //! This module provides client management functionality compatible with easytier-web,
 //! but using MySQL instead of SQLite for data persistence.
 
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::{
-    atomic::{AtomicU32, Ordering},
+    atomic::{AtomicBool, AtomicU32, Ordering},
     Arc,
 };
 
@@ -17,15 +19,26 @@ use easytier::{
         tcp::TcpTunnelListener, udp::UdpTunnelListener, websocket::WSTunnelListener, TunnelListener,
     },
 };
-use maxminddb::geoip2;
+use easytier_common::ACTIVE_CONFIG_SESSIONS;
 use tokio::task::JoinSet;
 
-use crate::db::Database;
+use crate::db::{Database, PoolOptions};
 
+pub mod geoip;
+pub mod idempotency;
+pub mod rate_limit;
 pub mod session;
 pub mod storage;
-
-use session::{Location, Session};
+#[cfg(unix)]
+pub mod unix_listener;
+pub mod wss_listener;
+
+use geoip::GeoIpDb;
+use rate_limit::{ConnectionRateLimit, ConnectionRateLimiter};
+use session::{
+    Location, Session, SessionThroughput, DEFAULT_HEARTBEAT_CHANNEL_CAPACITY,
+    DEFAULT_SESSION_RX_TIMEOUT,
+};
 use storage::{Storage, StorageToken};
 
 pub type OrgIdInDb = i32;
@@ -35,9 +48,25 @@ pub enum Error {
     InvalidUrl(String),
     ListenerError(anyhow::Error),
     DatabaseError(anyhow::Error),
+    /// The database is reachable but its schema could not be migrated
+    MigrationError(anyhow::Error),
     NetworkError(anyhow::Error),
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidUrl(url) => write!(f, "Invalid url: {}", url),
+            Error::ListenerError(e) => write!(f, "Listener error: {}", e),
+            Error::DatabaseError(e) => write!(f, "Database error: {}", e),
+            Error::MigrationError(e) => write!(f, "Migration error: {}", e),
+            Error::NetworkError(e) => write!(f, "Network error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl From<anyhow::Error> for Error {
     fn from(err: anyhow::Error) -> Self {
         Error::ListenerError(err)
@@ -50,18 +79,186 @@ impl From<std::io::Error> for Error {
     }
 }
 
+/// Why a listener failed to bind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum BindErrorKind {
+    AddrInUse,
+    PermissionDenied,
+    Other,
+}
+
+impl BindErrorKind {
+    /// Classify a listener error by the first I/O error in its chain
+    fn classify(err: &anyhow::Error) -> Self {
+        let io_kind = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<std::io::Error>())
+            .map(|io| io.kind());
+        match io_kind {
+            Some(std::io::ErrorKind::AddrInUse) => BindErrorKind::AddrInUse,
+            Some(std::io::ErrorKind::PermissionDenied) => BindErrorKind::PermissionDenied,
+            Some(_) => BindErrorKind::Other,
+            // Some listeners flatten the I/O error into a message
+            None => {
+                let msg = format!("{:?}", err);
+                if msg.contains("Address already in use") || msg.contains("AddrInUse") {
+                    BindErrorKind::AddrInUse
+                } else if msg.contains("Permission denied") || msg.contains("PermissionDenied") {
+                    BindErrorKind::PermissionDenied
+                } else {
+                    BindErrorKind::Other
+                }
+            }
+        }
+    }
+}
+
+/// A listener that `ClientManager::start` could not bind
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ListenerBindFailure {
+    pub url: url::Url,
+    pub kind: BindErrorKind,
+    pub message: String,
+}
+
+/// Listeners bound by `ClientManager::start`
+///
+/// A non-empty `failed` list with at least one bound listener means the
+/// server is only partially reachable (e.g. IPv6 bound, IPv4 port in use).
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct StartReport {
+    pub bound: Vec<url::Url>,
+    pub failed: Vec<ListenerBindFailure>,
+}
+
+impl StartReport {
+    /// Whether some but not all listeners were bound
+    pub fn is_partial(&self) -> bool {
+        !self.bound.is_empty() && !self.failed.is_empty()
+    }
+}
+
+/// Returned by `ClientManager::start` when no listener could be bound
+#[derive(Debug, Clone)]
+pub struct StartError {
+    pub failed: Vec<ListenerBindFailure>,
+}
+
+impl std::fmt::Display for StartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to listen on both IPv4 and IPv6")?;
+        for failure in &self.failed {
+            write!(
+                f,
+                "; {} ({:?}): {}",
+                failure.url, failure.kind, failure.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for StartError {}
+
 /// Create a TunnelListener from URL
+///
+/// `wss` URLs terminate TLS with the certificate and key named by the `cert`
+/// and `key` query parameters or the configuration, see `wss_listener`.
+/// `unix` URLs bind a UNIX domain socket at the URL path.
 pub fn get_listener_by_url(l: &url::Url) -> Result<Box<dyn TunnelListener>, Error> {
     Ok(match l.scheme() {
         "tcp" => Box::new(TcpTunnelListener::new(l.clone())),
         "udp" => Box::new(UdpTunnelListener::new(l.clone())),
         "ws" => Box::new(WSTunnelListener::new(l.clone())),
+        "wss" => Box::new(
+            wss_listener::WssTunnelListener::new(l.clone())
+                .map_err(|e| Error::InvalidUrl(format!("{}: {}", l, e)))?,
+        ),
+        #[cfg(unix)]
+        "unix" => Box::new(unix_listener::UnixTunnelListener::new(l.clone())),
         _ => {
             return Err(Error::InvalidUrl(l.to_string()));
         }
     })
 }
 
+/// Check whether a protocol binds IPv4 and IPv6 separately
+///
+/// Plain websocket listeners (`ws`) only bind the IPv4 wildcard address.
+pub fn is_dual_stack_protocol(protocol: &str) -> bool {
+    matches!(
+        protocol.trim().to_lowercase().as_str(),
+        "tcp" | "udp" | "wss"
+    )
+}
+
+/// IP stacks to create listeners for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub enum StackPreference {
+    /// IPv6 and IPv4, skipping a stack that is unavailable
+    #[default]
+    DualStack,
+    V4Only,
+    V6Only,
+}
+
+/// Create the listeners of the requested IP stacks for a given protocol and port
+///
+/// Returns `(v6_listener, v4_listener)`. `V4Only` and `V6Only` fail when the
+/// requested stack is unavailable instead of omitting it.
+pub async fn get_stack_listener(
+    protocol: &str,
+    port: u16,
+    stack: StackPreference,
+) -> Result<
+    (
+        Option<Box<dyn TunnelListener>>,
+        Option<Box<dyn TunnelListener>>,
+    ),
+    Error,
+> {
+    let v6_url: url::Url = format!("{protocol}://[::0]:{port}")
+        .parse()
+        .map_err(|_| Error::InvalidUrl(format!("{protocol}://[::0]:{port}")))?;
+    let v4_url: url::Url = format!("{protocol}://0.0.0.0:{port}")
+        .parse()
+        .map_err(|_| Error::InvalidUrl(format!("{protocol}://0.0.0.0:{port}")))?;
+
+    match stack {
+        StackPreference::DualStack => {
+            let v6_listener = if is_dual_stack_protocol(protocol) && local_ipv6().await.is_ok() {
+                get_listener_by_url(&v6_url).ok()
+            } else {
+                None
+            };
+            let v4_listener = if local_ipv4().await.is_ok() {
+                get_listener_by_url(&v4_url).ok()
+            } else {
+                None
+            };
+            Ok((v6_listener, v4_listener))
+        }
+        StackPreference::V4Only => {
+            local_ipv4().await.map_err(|e| {
+                Error::NetworkError(anyhow::anyhow!("IPv4 is not available: {}", e))
+            })?;
+            Ok((None, Some(get_listener_by_url(&v4_url)?)))
+        }
+        StackPreference::V6Only => {
+            if !is_dual_stack_protocol(protocol) {
+                return Err(Error::ListenerError(anyhow::anyhow!(
+                    "{} listeners cannot bind IPv6 separately",
+                    protocol
+                )));
+            }
+            local_ipv6().await.map_err(|e| {
+                Error::NetworkError(anyhow::anyhow!("IPv6 is not available: {}", e))
+            })?;
+            Ok((Some(get_listener_by_url(&v6_url)?), None))
+        }
+    }
+}
+
 /// Create dual-stack listeners (IPv4 and IPv6) for a given protocol and port
 pub async fn get_dual_stack_listener(
     protocol: &str,
@@ -73,51 +270,124 @@ pub async fn get_dual_stack_listener(
     ),
     Error,
 > {
-    let is_protocol_support_dual_stack =
-        protocol.trim().to_lowercase() == "tcp" || protocol.trim().to_lowercase() == "udp";
-    let v6_listener = if is_protocol_support_dual_stack && local_ipv6().await.is_ok() {
-        get_listener_by_url(&format!("{protocol}://[::0]:{port}").parse().unwrap()).ok()
-    } else {
-        None
-    };
-    let v4_listener = if local_ipv4().await.is_ok() {
-        get_listener_by_url(&format!("{protocol}://0.0.0.0:{port}").parse().unwrap()).ok()
-    } else {
-        None
-    };
-    Ok((v6_listener, v4_listener))
+    get_stack_listener(protocol, port, StackPreference::DualStack).await
 }
 
-fn load_geoip_db(geoip_db: Option<String>) -> Option<maxminddb::Reader<Vec<u8>>> {
-    if let Some(path) = geoip_db {
-        crate::info!("[GEOIP] Attempting to load GeoIP2 database from: {}", path);
-        match maxminddb::Reader::open_readfile(&path) {
-            Ok(reader) => {
-                crate::info!("[GEOIP] Successfully loaded GeoIP2 database from: {}", path);
-                Some(reader)
-            }
-            Err(err) => {
-                crate::warn!(
-                    "[GEOIP] Failed to load GeoIP2 database from {}: {}",
-                    path,
-                    err
-                );
-                None
-            }
+/// Metadata of a started tunnel listener
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ListenerInfo {
+    pub protocol: String,
+    pub bind_addr: String,
+    pub port: u16,
+    pub ip_version: String,
+}
+
+impl ListenerInfo {
+    fn from_url(url: &url::Url) -> Self {
+        if url.scheme() == "unix" {
+            return ListenerInfo {
+                protocol: url.scheme().to_string(),
+                bind_addr: url.path().to_string(),
+                port: 0,
+                ip_version: "unix".to_string(),
+            };
+        }
+
+        let bind_addr = url.host_str().unwrap_or_default().to_string();
+        let ip_version = if matches!(url.host(), Some(url::Host::Ipv6(_))) {
+            "ipv6"
+        } else {
+            "ipv4"
+        };
+        ListenerInfo {
+            protocol: url.scheme().to_string(),
+            bind_addr,
+            port: url.port().unwrap_or_default(),
+            ip_version: ip_version.to_string(),
         }
-    } else {
-        crate::info!("[GEOIP] No GeoIP2 database path provided, GeoIP lookup will be disabled");
-        None
     }
 }
 
 #[derive(Debug)]
 pub struct ClientManager {
     tasks: JoinSet<()>,
+    tasks_healthy: TaskHealth,
     listeners_cnt: Arc<AtomicU32>,
+    listeners: Arc<DashMap<url::Url, ListenerInfo>>,
     client_sessions: Arc<DashMap<url::Url, Arc<Session>>>,
     storage: Storage,
-    geoip_db: Arc<Option<maxminddb::Reader<Vec<u8>>>>,
+    geoip_db: Arc<GeoIpDb>,
+    session_rx_timeout: std::time::Duration,
+    heartbeat_channel_capacity: usize,
+    connection_limiter: Arc<ConnectionRateLimiter>,
+    /// Sessions report here when they must be closed, see `Session::with_evictions`
+    session_evictions: tokio::sync::mpsc::UnboundedSender<url::Url>,
+}
+
+/// Delay before restarting a background task that stopped
+const BACKGROUND_TASK_RESTART_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Aborts the wrapped task when dropped, so a cancelled supervisor does not leak it
+struct AbortOnDrop(tokio::task::AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Health flags of the supervised background tasks, one per task
+#[derive(Debug, Default)]
+struct TaskHealth(Vec<Arc<AtomicBool>>);
+
+impl TaskHealth {
+    /// Add the flag of a new task, it starts out healthy
+    fn register(&mut self) -> Arc<AtomicBool> {
+        let healthy = Arc::new(AtomicBool::new(true));
+        self.0.push(healthy.clone());
+        healthy
+    }
+
+    fn all_healthy(&self) -> bool {
+        self.0.iter().all(|healthy| healthy.load(Ordering::Acquire))
+    }
+}
+
+/// Spawn a background loop that is restarted whenever it panics or returns
+///
+/// Either case is logged and clears `healthy`, the flag of this task.
+fn spawn_supervised<F, Fut>(
+    tasks: &mut JoinSet<()>,
+    healthy: Arc<AtomicBool>,
+    name: &'static str,
+    task: F,
+) where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tasks.spawn(async move {
+        loop {
+            let handle = tokio::spawn(task());
+            let _abort = AbortOnDrop(handle.abort_handle());
+            match handle.await {
+                Err(e) if e.is_panic() => {
+                    crate::error!(
+                        "[CLIENT_MANAGER] Background task '{}' panicked, restarting: {:?}",
+                        name,
+                        e
+                    );
+                }
+                _ => {
+                    crate::error!(
+                        "[CLIENT_MANAGER] Background task '{}' stopped unexpectedly, restarting",
+                        name
+                    );
+                }
+            }
+            healthy.store(false, Ordering::Release);
+            tokio::time::sleep(BACKGROUND_TASK_RESTART_DELAY).await;
+        }
+    });
 }
 
 /// Run database migrations to create required tables
@@ -141,7 +411,11 @@ pub async fn run_migrations(conn: &sea_orm::DatabaseConnection) -> Result<(), St
 /// Open a database connection and run migrations
 async fn open(database_url: &str) -> Result<Database, Error> {
     crate::debug!("Connecting to database: {}", database_url);
-    let database = match Database::new(database_url).await {
+    let pool_options = PoolOptions {
+        statement_timeout: crate::config::get_db_statement_timeout(),
+        ..Default::default()
+    };
+    let database = match Database::new_with_pool_options(database_url, &pool_options).await {
         Ok(db) => db,
         Err(e) => {
             crate::error!("Database connection failed: {}", e);
@@ -158,7 +432,7 @@ async fn open(database_url: &str) -> Result<Database, Error> {
     if let Err(e) = run_migrations(conn).await {
         crate::error!("Failed to run migrations: {}", e);
         crate::error!("Required database tables do not exist and migrations failed. ClientManager initialization aborted.");
-        return Err(Error::DatabaseError(anyhow::anyhow!(
+        return Err(Error::MigrationError(anyhow::anyhow!(
             "Failed to run migrations: {}",
             e
         )));
@@ -177,86 +451,272 @@ impl ClientManager {
     /// # Returns
     /// * `Result<Self, Error>` - New ClientManager instance or error
     pub async fn new(db_url: &str, geoip_db: Option<String>) -> Result<Self, Error> {
+        Self::new_with_org_session_limit(db_url, geoip_db, None).await
+    }
+
+    /// Create a new ClientManager that limits concurrent sessions per organization
+    ///
+    /// # Arguments
+    /// * `db_url` - Database connection URL
+    /// * `geoip_db` - Optional path to GeoIP database
+    /// * `max_sessions_per_org` - Maximum active sessions per organization, None means unlimited
+    pub async fn new_with_org_session_limit(
+        db_url: &str,
+        geoip_db: Option<String>,
+        max_sessions_per_org: Option<usize>,
+    ) -> Result<Self, Error> {
         crate::info!("[CLIENT_MANAGER] Initializing ClientManager with MySQL database");
 
         // Initialize database connection and run migrations
         let database = open(db_url).await?;
+        let storage = Storage::new(database);
+        storage.set_org_session_limit(max_sessions_per_org);
 
         let client_sessions = Arc::new(DashMap::new());
         let sessions: Arc<DashMap<url::Url, Arc<Session>>> = client_sessions.clone();
         let mut tasks = JoinSet::new();
+        let mut tasks_healthy = TaskHealth::default();
 
         // Cleanup task for inactive sessions
         crate::debug!("[CLIENT_MANAGER] Starting cleanup task for inactive sessions");
-        tasks.spawn(async move {
-            loop {
-                tokio::time::sleep(std::time::Duration::from_secs(15)).await;
-                let initial_count = sessions.len();
-                sessions.retain(|_, session| session.is_running());
-                let final_count = sessions.len();
-                if initial_count != final_count {
-                    crate::debug!(
-                        "[CLIENT_MANAGER] Cleaned up {} inactive sessions (from {} to {})",
-                        initial_count - final_count,
-                        initial_count,
-                        final_count
-                    );
+        spawn_supervised(
+            &mut tasks,
+            tasks_healthy.register(),
+            "session cleanup",
+            move || {
+                let sessions = sessions.clone();
+                async move {
+                    loop {
+                        tokio::time::sleep(std::time::Duration::from_secs(15)).await;
+                        Self::cleanup_sessions(&sessions, None).await;
+                    }
                 }
-            }
-        });
+            },
+        );
+
+        // Eviction task - close sessions that were refused, e.g. over the organization's session limit
+        let (session_evictions, evictions_rx) = tokio::sync::mpsc::unbounded_channel();
+        let evictions_rx = Arc::new(tokio::sync::Mutex::new(evictions_rx));
+        let sessions = client_sessions.clone();
+        spawn_supervised(
+            &mut tasks,
+            tasks_healthy.register(),
+            "session eviction",
+            move || {
+                let evictions_rx = evictions_rx.clone();
+                let sessions = sessions.clone();
+                async move {
+                    let mut evictions_rx = evictions_rx.lock().await;
+                    while let Some(client_url) = evictions_rx.recv().await {
+                        Self::evict_session(&sessions, &client_url).await;
+                    }
+                }
+            },
+        );
 
         // Device timeout task - mark devices as offline if no heartbeat for 60 seconds
-        let storage_weak = Storage::new(database.clone()).weak_ref();
-        tasks.spawn(async move {
-            loop {
-                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
-
-                if let Ok(storage) = Storage::try_from(storage_weak.clone()) {
-                    if let Err(e) = Self::mark_offline_devices(&storage).await {
-                        crate::error!("[CLIENT_MANAGER] Failed to mark offline devices: {:?}", e);
+        let storage_weak = storage.weak_ref();
+        let offline_check_interval = crate::config::get_offline_check_interval();
+        let healthy = tasks_healthy.register();
+        spawn_supervised(&mut tasks, healthy.clone(), "offline marking", move || {
+            let storage_weak = storage_weak.clone();
+            let healthy = healthy.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(offline_check_interval).await;
+
+                    if let Ok(storage) = Storage::try_from(storage_weak.clone()) {
+                        match Self::mark_offline_devices(&storage).await {
+                            // A pass that works again means this task recovered
+                            Ok(_) => healthy.store(true, Ordering::Release),
+                            Err(e) => {
+                                crate::error!(
+                                    "[CLIENT_MANAGER] Failed to mark offline devices: {:?}",
+                                    e
+                                );
+                                healthy.store(false, Ordering::Release);
+                            }
+                        }
                     }
                 }
             }
         });
 
+        // Optional retention task - delete devices that stayed offline past the retention period
+        if let Some(retention) = crate::config::get_device_retention_period() {
+            crate::info!(
+                "[CLIENT_MANAGER] Device retention enabled, offline devices are removed after {} hours",
+                retention.num_hours()
+            );
+            let retention_check_interval = crate::config::get_device_retention_check_interval();
+            // Weak reference to the manager's own storage, so the task does not keep it alive
+            let storage_weak = storage.weak_ref();
+            spawn_supervised(
+                &mut tasks,
+                tasks_healthy.register(),
+                "device retention",
+                move || {
+                    let storage_weak = storage_weak.clone();
+                    async move {
+                        loop {
+                            tokio::time::sleep(retention_check_interval).await;
+
+                            if let Ok(storage) = Storage::try_from(storage_weak.clone()) {
+                                if let Err(e) =
+                                    Self::delete_stale_devices(&storage, retention).await
+                                {
+                                    crate::error!(
+                                        "[CLIENT_MANAGER] Failed to delete stale devices: {:?}",
+                                        e
+                                    );
+                                }
+                            }
+                        }
+                    }
+                },
+            );
+        }
+
         // Use provided path or auto-detect from configuration
         let geoip_path = geoip_db.or_else(crate::config::get_geoip_db_path);
 
         let manager = ClientManager {
             tasks,
+            tasks_healthy,
             listeners_cnt: Arc::new(AtomicU32::new(0)),
+            listeners: Arc::new(DashMap::new()),
             client_sessions,
-            storage: Storage::new(database),
-            geoip_db: Arc::new(load_geoip_db(geoip_path)),
+            storage,
+            geoip_db: Arc::new(GeoIpDb::open_with_asn(
+                geoip_path,
+                crate::config::get_geoip_asn_db_path(),
+            )),
+            session_rx_timeout: DEFAULT_SESSION_RX_TIMEOUT,
+            heartbeat_channel_capacity: DEFAULT_HEARTBEAT_CHANNEL_CAPACITY,
+            connection_limiter: Arc::new(
+                ConnectionRateLimiter::new(ConnectionRateLimit::default()),
+            ),
+            session_evictions,
         };
 
+        if let Some(limit) = max_sessions_per_org {
+            crate::info!(
+                "[CLIENT_MANAGER] Limiting each organization to {} active sessions",
+                limit
+            );
+        }
+
         crate::info!("[CLIENT_MANAGER] ClientManager initialized successfully");
         Ok(manager)
     }
 
-    pub async fn start(&mut self, protocol: &str, port: u16) -> Result<(), anyhow::Error> {
-        // Get dual-stack listeners
-        let (v6_listener, v4_listener) = get_dual_stack_listener(protocol, port)
+    /// Use a custom RPC receive timeout for accepted sessions instead of the 30s default
+    pub fn with_session_rx_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.session_rx_timeout = timeout;
+        self
+    }
+
+    /// Buffer up to `capacity` heartbeats per subscriber of new sessions instead of 16
+    pub fn with_heartbeat_channel_capacity(mut self, capacity: usize) -> Self {
+        self.heartbeat_channel_capacity = capacity.max(1);
+        self
+    }
+
+    /// Limit how fast each client host may open connections to listeners started afterwards
+    pub fn with_connection_rate_limit(mut self, limit: ConnectionRateLimit) -> Self {
+        self.connection_limiter = Arc::new(ConnectionRateLimiter::new(limit));
+        self
+    }
+
+    /// Approve devices seen for the first time according to `policy` instead of leaving them pending
+    pub fn with_auto_approval(self, policy: storage::AutoApprovalPolicy) -> Self {
+        self.storage.set_auto_approval(policy);
+        self
+    }
+
+    /// Append `message` to every heartbeat rejection sent to devices, None sends the reason only
+    pub fn with_heartbeat_reject_message(self, message: Option<String>) -> Self {
+        self.storage.set_reject_message(message);
+        self
+    }
+
+    /// Give devices seen for the first time `device_type` instead of `Robot`
+    pub fn with_default_device_type(
+        self,
+        device_type: crate::db::entities::devices::DeviceType,
+    ) -> Self {
+        self.storage.set_default_device_type(device_type);
+        self
+    }
+
+    /// RPC receive timeout applied to new sessions
+    pub fn session_rx_timeout(&self) -> std::time::Duration {
+        self.session_rx_timeout
+    }
+
+    /// Heartbeat channel capacity applied to new sessions
+    pub fn heartbeat_channel_capacity(&self) -> usize {
+        self.heartbeat_channel_capacity
+    }
+
+    /// Bind the IPv6 and IPv4 listeners for a protocol and port
+    ///
+    /// Fails with a `StartError` when no listener could be bound. A partial
+    /// bind still succeeds; the failed listener is reported in `StartReport::failed`.
+    pub async fn start(&mut self, protocol: &str, port: u16) -> Result<StartReport, anyhow::Error> {
+        self.start_with_stack(protocol, port, StackPreference::DualStack)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to get dual stack listener: {:?}", e))?;
+    }
+
+    /// Bind the listeners of the requested IP stacks for a protocol and port
+    ///
+    /// With `V4Only` or `V6Only` the requested stack failing to bind is an error.
+    pub async fn start_with_stack(
+        &mut self,
+        protocol: &str,
+        port: u16,
+        stack: StackPreference,
+    ) -> Result<StartReport, anyhow::Error> {
+        let (v6_listener, v4_listener) = get_stack_listener(protocol, port, stack)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get {:?} listener: {}", stack, e))?;
 
         // Check if at least one listener is available
         if v4_listener.is_none() && v6_listener.is_none() {
-            return Err(anyhow::anyhow!("Failed to listen on both IPv4 and IPv6"));
+            return Err(StartError { failed: vec![] }.into());
         }
 
-        // Add IPv6 listener
-        if let Some(listener) = v6_listener {
-            self.add_listener(listener).await?;
+        let mut report = StartReport::default();
+        for listener in [v6_listener, v4_listener].into_iter().flatten() {
+            let url = listener.local_url();
+            match self.add_listener(listener).await {
+                Ok(()) => report.bound.push(url),
+                Err(e) => report.failed.push(ListenerBindFailure {
+                    url,
+                    kind: BindErrorKind::classify(&e),
+                    message: format!("{:#}", e),
+                }),
+            }
         }
 
-        // Add IPv4 listener
-        if let Some(listener) = v4_listener {
-            self.add_listener(listener).await?;
+        if report.bound.is_empty() {
+            return Err(StartError {
+                failed: report.failed,
+            }
+            .into());
+        }
+        for failure in &report.failed {
+            crate::warn!(
+                "[CLIENT_MANAGER] Listening partially, {} failed to bind ({:?}): {}",
+                failure.url,
+                failure.kind,
+                failure.message
+            );
         }
 
-        Ok(())
+        Ok(report)
     }
+
     /// Add a tunnel listener
     pub async fn add_listener<L: TunnelListener + 'static>(
         &mut self,
@@ -270,15 +730,26 @@ impl ClientManager {
         })?;
 
         let listener_id = self.listeners_cnt.fetch_add(1, Ordering::Relaxed) + 1;
+        let local_url = listener.local_url();
+        let listener_info = ListenerInfo::from_url(&local_url);
         crate::info!(
-            "[CLIENT_MANAGER] Tunnel listener {} started successfully",
-            listener_id
+            "[CLIENT_MANAGER] Tunnel listener {} started successfully on {}://{}:{}",
+            listener_id,
+            listener_info.protocol,
+            listener_info.bind_addr,
+            listener_info.port
         );
+        self.listeners.insert(local_url.clone(), listener_info);
 
         let sessions = self.client_sessions.clone();
         let storage = self.storage.weak_ref();
         let listeners_cnt = self.listeners_cnt.clone();
+        let listeners = self.listeners.clone();
         let geoip_db = self.geoip_db.clone();
+        let session_rx_timeout = self.session_rx_timeout;
+        let heartbeat_channel_capacity = self.heartbeat_channel_capacity;
+        let connection_limiter = self.connection_limiter.clone();
+        let session_evictions = self.session_evictions.clone();
 
         self.tasks.spawn(async move {
             crate::debug!(
@@ -287,19 +758,59 @@ impl ClientManager {
             );
 
             while let Ok(tunnel) = listener.accept().await {
-                let info = tunnel.info().unwrap();
-                let client_url: url::Url = info.remote_addr.unwrap().into();
-                let location = Self::lookup_location(&client_url, geoip_db.clone());
+                // A tunnel without a remote address cannot be keyed as a session,
+                // drop it instead of taking the whole listener down
+                let Some(remote_addr) = tunnel.info().and_then(|info| info.remote_addr) else {
+                    crate::warn!(
+                        "[CLIENT_MANAGER] Listener {} accepted a tunnel without remote address, dropping it",
+                        listener_id
+                    );
+                    continue;
+                };
+                let client_url: url::Url = remote_addr.into();
+
+                // Unix socket peers have no host and are local processes, they are not throttled
+                let client_host = client_url.host_str().filter(|host| !host.is_empty());
+                if !client_host.map_or(true, |host| connection_limiter.try_acquire(host)) {
+                    crate::warn!(
+                        event = "client_throttled",
+                        client_url = %client_url,
+                        listener_id,
+                        "[CLIENT_MANAGER] Dropping connection from {}: host exceeds {:?}",
+                        client_url,
+                        connection_limiter.limit()
+                    );
+                    continue;
+                }
+
+                let location = geoip_db.lookup_location(&client_url);
 
                 crate::info!(
+                    event = "client_connected",
+                    client_url = %client_url,
+                    listener_id,
                     "[CLIENT_MANAGER] New client connected from {} (listener {})",
                     client_url,
                     listener_id
                 );
 
-                let mut session = Session::new(storage.clone(), client_url.clone(), location);
+                let mut session = Session::new_with_heartbeat_capacity(
+                    storage.clone(),
+                    client_url.clone(),
+                    location,
+                    session_rx_timeout,
+                    heartbeat_channel_capacity,
+                )
+                .with_listener_id(listener_id)
+                .with_evictions(session_evictions.clone());
+
                 session.serve(tunnel).await;
-                sessions.insert(client_url.clone(), Arc::new(session));
+                if sessions
+                    .insert(client_url.clone(), Arc::new(session))
+                    .is_none()
+                {
+                    ACTIVE_CONFIG_SESSIONS.inc();
+                }
 
                 crate::trace!(
                     "[CLIENT_MANAGER] Session {} added to active sessions (total: {})",
@@ -309,17 +820,155 @@ impl ClientManager {
             }
 
             listeners_cnt.fetch_sub(1, Ordering::Relaxed);
+            listeners.remove(&local_url);
             crate::info!("[CLIENT_MANAGER] Listener {} task terminated", listener_id);
         });
 
         Ok(())
     }
 
+    /// Run the session cleanup immediately
+    ///
+    /// Removes sessions that stopped running and, with `idle_threshold`, also
+    /// running sessions without a heartbeat for longer than the threshold.
+    /// Returns the number of removed sessions.
+    pub async fn cleanup_sessions_now(&self, idle_threshold: Option<std::time::Duration>) -> usize {
+        Self::cleanup_sessions(&self.client_sessions, idle_threshold).await
+    }
+
+    async fn is_stale_session(
+        session: &Session,
+        idle_threshold: Option<std::time::Duration>,
+    ) -> bool {
+        if !session.is_running() {
+            return true;
+        }
+        match idle_threshold {
+            Some(threshold) => session.data().read().await.idle_duration() > threshold,
+            None => false,
+        }
+    }
+
+    /// Remove stale sessions, shared by the periodic cleanup task and `cleanup_sessions_now`
+    async fn cleanup_sessions(
+        sessions: &DashMap<url::Url, Arc<Session>>,
+        idle_threshold: Option<std::time::Duration>,
+    ) -> usize {
+        let initial_count = sessions.len();
+        let candidates = sessions
+            .iter()
+            .map(|item| (item.key().clone(), item.value().clone()))
+            .collect::<Vec<_>>();
+
+        let mut removed = 0;
+        for (client_url, candidate) in candidates {
+            if !Self::is_stale_session(&candidate, idle_threshold).await {
+                continue;
+            }
+            // Only remove the entry we inspected, a reconnect may have replaced it
+            let entry =
+                sessions.remove_if(&client_url, |_, current| Arc::ptr_eq(current, &candidate));
+            drop(candidate);
+            if let Some((_, session)) = entry {
+                removed += 1;
+                ACTIVE_CONFIG_SESSIONS.dec();
+                Self::log_session_disconnected(&client_url, &session).await;
+                if let Ok(mut session) = Arc::try_unwrap(session) {
+                    session.shutdown().await;
+                }
+            }
+        }
+
+        if removed > 0 {
+            crate::debug!(
+                "[CLIENT_MANAGER] Cleaned up {} inactive sessions (from {} to {})",
+                removed,
+                initial_count,
+                sessions.len()
+            );
+        }
+        removed
+    }
+
+    /// Close and remove a session that asked to be evicted
+    async fn evict_session(sessions: &DashMap<url::Url, Arc<Session>>, client_url: &url::Url) {
+        let Some((_, session)) = sessions.remove(client_url) else {
+            return;
+        };
+        ACTIVE_CONFIG_SESSIONS.dec();
+        Self::log_session_disconnected(client_url, &session).await;
+        if let Ok(mut session) = Arc::try_unwrap(session) {
+            session.shutdown().await;
+        }
+        crate::info!("[CLIENT_MANAGER] Evicted session {}", client_url);
+    }
+
+    /// Emit the structured disconnection event for a session removed from the active set
+    async fn log_session_disconnected(client_url: &url::Url, session: &Session) {
+        let token = session.get_token().await;
+        crate::info!(
+            event = "client_disconnected",
+            client_url = %client_url,
+            listener_id = session.listener_id(),
+            organization_id = token.as_ref().map(|t| t.organization_id.as_str()),
+            device_id = token.as_ref().map(|t| tracing::field::display(t.device_id)),
+            "[CLIENT_MANAGER] Client {} disconnected",
+            client_url
+        );
+    }
+
     /// Check if the client manager is running
     pub fn is_running(&self) -> bool {
         self.listeners_cnt.load(Ordering::Relaxed) > 0
     }
 
+    /// Whether the background maintenance tasks are doing their job
+    ///
+    /// Each background task has its own flag, which is cleared when the task
+    /// panicked or stopped (it is restarted). The offline-marking task also
+    /// clears its flag on a failed pass and sets it again after the next
+    /// successful one. Healthy only while every flag is set.
+    pub fn is_healthy(&self) -> bool {
+        self.tasks_healthy.all_healthy()
+    }
+
+    /// Number of sessions currently tracked by the manager
+    pub fn session_count(&self) -> usize {
+        self.client_sessions.len()
+    }
+
+    /// Number of tracked sessions grouped by the ID of the listener that accepted them
+    pub fn sessions_per_listener(&self) -> HashMap<u32, usize> {
+        let mut counts = HashMap::new();
+        for item in self.client_sessions.iter() {
+            if let Some(listener_id) = item.value().listener_id() {
+                *counts.entry(listener_id).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Load the GeoIP database at `path` and use it for new connections
+    ///
+    /// The current database keeps serving until the new one is loaded, and
+    /// stays in use if loading fails.
+    pub fn reload_geoip(&self, path: &str) -> anyhow::Result<()> {
+        self.geoip_db.reload(path)
+    }
+
+    /// List the listeners that are currently accepting connections
+    pub fn list_listeners(&self) -> Vec<ListenerInfo> {
+        let mut ret = self
+            .listeners
+            .iter()
+            .map(|item| item.value().clone())
+            .collect::<Vec<_>>();
+        ret.sort_by(|a, b| {
+            (&a.protocol, a.port, &a.bind_addr).cmp(&(&b.protocol, b.port, &b.bind_addr))
+        });
+        ret
+    }
+
     /// List all active sessions
     pub async fn list_sessions(&self) -> Vec<StorageToken> {
         crate::debug!("[CLIENT_MANAGER] Listing all active sessions");
@@ -341,6 +990,78 @@ impl ClientManager {
         ret
     }
 
+    /// Latest heartbeat of every active session and when it was received
+    ///
+    /// Sessions that have not sent a heartbeat yet are left out.
+    pub async fn snapshot_all_heartbeats(
+        &self,
+    ) -> Vec<(url::Url, HeartbeatRequest, std::time::Instant)> {
+        let sessions = self
+            .client_sessions
+            .iter()
+            .map(|item| (item.key().clone(), item.value().clone()))
+            .collect::<Vec<_>>();
+
+        let mut ret = vec![];
+        for (client_url, s) in sessions {
+            let data = s.data().read().await;
+            if let (Some(req), Some(received_at)) = (data.req(), data.req_received_at()) {
+                ret.push((client_url, req, received_at));
+            }
+        }
+
+        crate::trace!(
+            "[CLIENT_MANAGER] Snapshot {} heartbeats from {} sessions",
+            ret.len(),
+            self.client_sessions.len()
+        );
+        ret
+    }
+
+    /// Snapshot the byte counters of the connected devices of an organization
+    pub async fn device_throughputs(
+        &self,
+        organization_id: &str,
+    ) -> HashMap<uuid::Uuid, SessionThroughput> {
+        let sessions = self
+            .client_sessions
+            .iter()
+            .map(|item| item.value().clone())
+            .collect::<Vec<_>>();
+
+        let mut ret = HashMap::new();
+        for s in sessions {
+            if let Some(token) = s.get_token().await {
+                if token.organization_id == organization_id {
+                    ret.insert(token.device_id, s.throughput());
+                }
+            }
+        }
+        ret
+    }
+
+    /// Sessions of the connected devices of an organization, with their tokens
+    pub async fn organization_sessions(
+        &self,
+        organization_id: &str,
+    ) -> Vec<(StorageToken, Arc<Session>)> {
+        let sessions = self
+            .client_sessions
+            .iter()
+            .map(|item| item.value().clone())
+            .collect::<Vec<_>>();
+
+        let mut ret = vec![];
+        for s in sessions {
+            if let Some(token) = s.get_token().await {
+                if token.organization_id == organization_id {
+                    ret.push((token, s));
+                }
+            }
+        }
+        ret
+    }
+
     /// Get session by device ID
     pub async fn get_session_by_device_id(
         &self,
@@ -378,7 +1099,47 @@ impl ClientManager {
         session
     }
 
+    /// Close and remove every active session of a device
+    ///
+    /// A dual-stacked device may hold one session per address family; all of
+    /// them are closed. Returns whether a session was found.
+    pub async fn close_device_session(
+        &self,
+        organization_id: &str,
+        device_id: &uuid::Uuid,
+    ) -> bool {
+        let client_urls = self
+            .storage
+            .get_client_urls_by_device_id(&organization_id.to_string(), device_id);
+
+        let mut closed = false;
+        for client_url in client_urls {
+            let Some((_, session)) = self.client_sessions.remove(&client_url) else {
+                continue;
+            };
+            ACTIVE_CONFIG_SESSIONS.dec();
+            Self::log_session_disconnected(&client_url, &session).await;
+
+            if let Some(token) = session.get_token().await {
+                self.storage.remove_client(&token);
+            }
+            if let Ok(mut session) = Arc::try_unwrap(session) {
+                session.shutdown().await;
+            }
+
+            crate::info!(
+                "[CLIENT_MANAGER] Closed session {} for device_id: {}",
+                client_url,
+                device_id
+            );
+            closed = true;
+        }
+        closed
+    }
+
     /// List devices by organization ID
+    ///
+    /// Returns one client URL per device, also for devices connected over both IPv4 and IPv6.
     pub async fn list_devices_by_organization_id(&self, organization_id: &str) -> Vec<url::Url> {
         crate::debug!(
             "[CLIENT_MANAGER] Listing devices for organization_id: {}",
@@ -396,15 +1157,32 @@ impl ClientManager {
         urls
     }
 
+    /// List connected client URLs of all organizations
+    pub fn list_all_clients(&self) -> Vec<(crate::db::OrgIdInDb, url::Url)> {
+        let clients = self.storage.list_all_clients();
+        crate::debug!(
+            "[CLIENT_MANAGER] Found {} clients across all organizations",
+            clients.len()
+        );
+        clients
+    }
+
     /// Get heartbeat requests for a client
-    pub async fn get_heartbeat_requests(&self, client_url: &url::Url) -> Option<HeartbeatRequest> {
+    ///
+    /// With `max_age`, a heartbeat received longer ago than that is treated as
+    /// missing; None returns the latest heartbeat however old it is.
+    pub async fn get_heartbeat_requests(
+        &self,
+        client_url: &url::Url,
+        max_age: Option<std::time::Duration>,
+    ) -> Option<HeartbeatRequest> {
         crate::trace!(
             "[CLIENT_MANAGER] Getting heartbeat request for client: {}",
             client_url
         );
 
         let session = self.client_sessions.get(client_url)?.value().clone();
-        let heartbeat = session.data().read().await.req();
+        let heartbeat = session.data().read().await.req_within(max_age);
 
         if heartbeat.is_some() {
             crate::trace!(
@@ -460,7 +1238,7 @@ impl ClientManager {
     /// Mark devices as offline if they haven't sent heartbeat for more than 60 seconds
     async fn mark_offline_devices(storage: &Storage) -> Result<(), anyhow::Error> {
         use crate::db::entities::devices;
-        use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 
         let cutoff_time = chrono::Utc::now() - chrono::Duration::seconds(60);
 
@@ -503,26 +1281,79 @@ impl ClientManager {
             );
         }
 
-        // Mark each device as offline
+        // Mark each device as offline, remembering its status for the reconnect
         for device in offline_devices {
-            let mut active: devices::ActiveModel = device.clone().into();
-            active.status = Set(devices::DeviceStatus::Offline);
-            active.updated_at = Set(chrono::Utc::now().into());
-
-            active
-                .update(storage.db().orm())
+            let marked = storage
+                .transition_device_status(
+                    &device.id,
+                    &device.status,
+                    &devices::DeviceStatus::Offline,
+                )
                 .await
                 .with_context(|| format!("Failed to mark device {} as offline", device.id))?;
 
-            crate::debug!(
-                "[CLIENT_MANAGER] Marked device {} as offline due to timeout",
-                device.id
-            );
+            if marked {
+                crate::debug!(
+                    "[CLIENT_MANAGER] Marked device {} as offline due to timeout",
+                    device.id
+                );
+            }
         }
 
         Ok(())
     }
 
+    /// Run a single retention pass, deleting devices offline for longer than `retention`
+    ///
+    /// Returns the number of removed device records.
+    pub async fn run_device_retention_pass(
+        &self,
+        retention: chrono::Duration,
+    ) -> Result<u64, anyhow::Error> {
+        Self::delete_stale_devices(&self.storage, retention).await
+    }
+
+    /// Delete offline devices whose last heartbeat is older than the retention period
+    async fn delete_stale_devices(
+        storage: &Storage,
+        retention: chrono::Duration,
+    ) -> Result<u64, anyhow::Error> {
+        use crate::db::entities::devices;
+        use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter};
+
+        let cutoff_time = chrono::Utc::now() - retention;
+
+        // Only offline devices are eligible; devices that never sent a heartbeat
+        // fall back to their last update time. The filters are checked by the
+        // delete itself, so a device that reconnects during the pass is kept.
+        let result = devices::Entity::delete_many()
+            .filter(devices::Column::Status.eq(devices::DeviceStatus::Offline))
+            .filter(
+                Condition::any()
+                    .add(devices::Column::LastHeartbeat.lt(cutoff_time))
+                    .add(
+                        Condition::all()
+                            .add(devices::Column::LastHeartbeat.is_null())
+                            .add(devices::Column::UpdatedAt.lt(cutoff_time)),
+                    ),
+            )
+            .exec(storage.db().orm())
+            .await
+            .with_context(|| "Failed to delete stale devices")?;
+
+        if result.rows_affected == 0 {
+            crate::debug!("[CLIENT_MANAGER] No stale devices to remove");
+        } else {
+            crate::info!(
+                "[CLIENT_MANAGER] Removed {} stale devices offline since before {:?}",
+                result.rows_affected,
+                cutoff_time
+            );
+        }
+
+        Ok(result.rows_affected)
+    }
+
     /// Shutdown the client manager and cleanup resources
     pub async fn shutdown(&mut self) {
         crate::info!("[CLIENT_MANAGER] Shutting down ClientManager...");
@@ -537,112 +1368,10 @@ impl ClientManager {
         );
 
         self.tasks.shutdown().await;
+        self.listeners.clear();
+        ACTIVE_CONFIG_SESSIONS.sub(self.client_sessions.len() as i64);
+        self.client_sessions.clear();
 
         crate::info!("[CLIENT_MANAGER] ClientManager shutdown completed");
     }
-
-    /// Lookup geographic location for client IP
-    fn lookup_location(
-        client_url: &url::Url,
-        geoip_db: Arc<Option<maxminddb::Reader<Vec<u8>>>>,
-    ) -> Option<Location> {
-        let host = client_url.host_str()?;
-        crate::trace!("[GEOIP] Looking up location for host: {}", host);
-
-        let ip: std::net::IpAddr = if let Ok(ip) = host.parse() {
-            ip
-        } else {
-            crate::debug!("[GEOIP] Failed to parse host as IP address: {}", host);
-            return None;
-        };
-
-        // Skip lookup for private/special IPs
-        let is_private = match ip {
-            std::net::IpAddr::V4(ipv4) => {
-                ipv4.is_private() || ipv4.is_loopback() || ipv4.is_unspecified()
-            }
-            std::net::IpAddr::V6(ipv6) => ipv6.is_loopback() || ipv6.is_unspecified(),
-        };
-
-        if is_private {
-            crate::debug!(
-                "[GEOIP] Skipping GeoIP lookup for private/special IP: {}",
-                ip
-            );
-            let location = Location {
-                country: "本地网络".to_string(),
-                city: None,
-                region: None,
-            };
-            return Some(location);
-        }
-
-        let location = if let Some(db) = &*geoip_db {
-            crate::trace!("[GEOIP] Performing GeoIP lookup for IP: {}", ip);
-            match db.lookup::<geoip2::City>(ip) {
-                Ok(Some(city)) => {
-                    let country = city
-                        .country
-                        .and_then(|c| c.names)
-                        .and_then(|n| {
-                            n.get("zh-CN")
-                                .or_else(|| n.get("en"))
-                                .map(|s| s.to_string())
-                        })
-                        .unwrap_or_else(|| "海外".to_string());
-
-                    let city_name = city.city.and_then(|c| c.names).and_then(|n| {
-                        n.get("zh-CN")
-                            .or_else(|| n.get("en"))
-                            .map(|s| s.to_string())
-                    });
-
-                    let region = city
-                        .subdivisions
-                        .and_then(|mut subdivisions| subdivisions.pop())
-                        .and_then(|subdivision| subdivision.names)
-                        .and_then(|n| {
-                            n.get("zh-CN")
-                                .or_else(|| n.get("en"))
-                                .map(|s| s.to_string())
-                        });
-
-                    let location = Location {
-                        country: country.clone(),
-                        city: city_name.clone(),
-                        region: region.clone(),
-                    };
-
-                    crate::debug!("[GEOIP] Successfully resolved location for {}: country={}, city={:?}, region={:?}", 
-                                  ip, country, city_name, region);
-                    location
-                }
-                Ok(None) => {
-                    crate::debug!("[GEOIP] GeoIP lookup returned no data for {}", ip);
-                    Location {
-                        country: "未知".to_string(),
-                        city: None,
-                        region: None,
-                    }
-                }
-                Err(err) => {
-                    crate::debug!("[GEOIP] GeoIP lookup failed for {}: {}", ip, err);
-                    Location {
-                        country: "未知".to_string(),
-                        city: None,
-                        region: None,
-                    }
-                }
-            }
-        } else {
-            crate::trace!("[GEOIP] No GeoIP database available, returning unknown location");
-            Location {
-                country: "未知".to_string(),
-                city: None,
-                region: None,
-            }
-        };
-
-        Some(location)
-    }
 }
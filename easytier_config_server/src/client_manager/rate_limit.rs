@@ -0,0 +1,106 @@
+//! Per-host connection rate limiting for the listener accept loops
+//!
+//! A device stuck in a crash loop reconnects as fast as it can, creating and
+//! tearing down sessions. Each client host gets a token bucket; connections
+//! arriving with an empty bucket are dropped before a session is created.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Most client hosts tracked at once
+///
+/// At the cap, buckets that refilled completely are pruned first; if every
+/// tracked host is still throttled, the one idle the longest is forgotten.
+pub const MAX_TRACKED_HOSTS: usize = 4096;
+
+/// Token bucket parameters applied to every client host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConnectionRateLimit {
+    /// Connections a host may open back to back
+    pub burst: u32,
+    /// Connections per second a host regains
+    pub per_second: f64,
+}
+
+impl Default for ConnectionRateLimit {
+    fn default() -> Self {
+        ConnectionRateLimit {
+            burst: 30,
+            per_second: 5.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets of the client hosts seen by the listeners
+#[derive(Debug)]
+pub struct ConnectionRateLimiter {
+    limit: ConnectionRateLimit,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl ConnectionRateLimiter {
+    pub fn new(limit: ConnectionRateLimit) -> Self {
+        ConnectionRateLimiter {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn limit(&self) -> ConnectionRateLimit {
+        self.limit
+    }
+
+    /// Number of client hosts with a bucket
+    pub fn tracked_hosts(&self) -> usize {
+        self.buckets.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Take a token for a new connection from `host`, false if it must be dropped
+    pub fn try_acquire(&self, host: &str) -> bool {
+        self.try_acquire_at(host, Instant::now())
+    }
+
+    /// `try_acquire` at a given time
+    pub fn try_acquire_at(&self, host: &str, now: Instant) -> bool {
+        let burst = f64::from(self.limit.burst);
+        let refill = |bucket: &Bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            (bucket.tokens + elapsed * self.limit.per_second).min(burst)
+        };
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_HOSTS && !buckets.contains_key(host) {
+            buckets.retain(|_, bucket| refill(bucket) < burst);
+            if buckets.len() >= MAX_TRACKED_HOSTS {
+                let idlest = buckets
+                    .iter()
+                    .min_by_key(|(_, bucket)| bucket.updated)
+                    .map(|(host, _)| host.clone());
+                if let Some(idlest) = idlest {
+                    buckets.remove(&idlest);
+                }
+            }
+        }
+
+        let bucket = buckets.entry(host.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
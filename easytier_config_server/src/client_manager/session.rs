@@ -1,6 +1,12 @@
 //! Session management for EasyTier clients with MySQL storage
 
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use anyhow::Context;
 use easytier::{
@@ -14,11 +20,18 @@ use easytier::{
             WebServerServiceServer,
         },
     },
-    tunnel::Tunnel,
+    tunnel::{
+        common::TunnelWrapper,
+        filter::{StatsRecorderTunnelFilter, TunnelFilter, TunnelWithFilter},
+        stats::Throughput,
+        Tunnel,
+    },
 };
-use tokio::sync::{broadcast, RwLock};
+use easytier_common::{HEARTBEATS_PROCESSED_TOTAL, HEARTBEAT_DB_ERRORS_TOTAL};
+use futures::StreamExt;
+use tokio::sync::{broadcast, mpsc, RwLock};
 
-use super::storage::{Storage, StorageToken, WeakRefStorage};
+use super::storage::{DeviceStatusEvent, Storage, StorageToken, WeakRefStorage};
 
 /// Location information for geographic positioning
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -26,6 +39,19 @@ pub struct Location {
     pub country: String,
     pub city: Option<String>,
     pub region: Option<String>,
+    /// Autonomous system number, only filled when an ASN database is configured
+    #[serde(default)]
+    pub asn: Option<u32>,
+    /// Organization (ISP) owning the autonomous system
+    #[serde(default)]
+    pub asn_org: Option<String>,
+}
+
+/// Bytes transferred over a session's tunnel, `None` while no tunnel is served
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct SessionThroughput {
+    pub tx_bytes: Option<u64>,
+    pub rx_bytes: Option<u64>,
 }
 
 /// Session data structure
@@ -35,13 +61,25 @@ pub struct SessionData {
     client_url: url::Url,
     storage_token: Option<StorageToken>,
     notifier: broadcast::Sender<HeartbeatRequest>,
-    req: Option<HeartbeatRequest>,
+    /// Latest accepted heartbeat and when it was received
+    req: Option<(HeartbeatRequest, std::time::Instant)>,
     location: Option<Location>,
+    /// Session creation or last accepted heartbeat, whichever is later
+    last_activity: std::time::Instant,
+    /// Cleared once the tunnel served by the session stops delivering packets
+    tunnel_alive: Arc<AtomicBool>,
+    /// Where to ask for the session to be closed after its heartbeat was refused
+    evictions: Option<mpsc::UnboundedSender<url::Url>>,
 }
 
 impl SessionData {
-    fn new(storage: WeakRefStorage, client_url: url::Url, location: Option<Location>) -> Self {
-        let (tx, _rx1) = broadcast::channel(2);
+    fn new(
+        storage: WeakRefStorage,
+        client_url: url::Url,
+        location: Option<Location>,
+        heartbeat_capacity: usize,
+    ) -> Self {
+        let (tx, _rx1) = broadcast::channel(heartbeat_capacity.max(1));
 
         SessionData {
             storage,
@@ -50,11 +88,33 @@ impl SessionData {
             notifier: tx,
             req: None,
             location,
+            last_activity: std::time::Instant::now(),
+            tunnel_alive: Arc::new(AtomicBool::new(true)),
+            evictions: None,
         }
     }
 
     pub fn req(&self) -> Option<HeartbeatRequest> {
-        self.req.clone()
+        self.req_within(None)
+    }
+
+    /// Latest heartbeat, or None if it was received more than `max_age` ago
+    ///
+    /// A device that went quiet keeps its session until cleanup, so callers that
+    /// need current data pass a `max_age`; None returns the heartbeat at any age.
+    pub fn req_within(&self, max_age: Option<std::time::Duration>) -> Option<HeartbeatRequest> {
+        self.req
+            .as_ref()
+            .filter(|(_, received_at)| match max_age {
+                Some(max_age) => received_at.elapsed() <= max_age,
+                None => true,
+            })
+            .map(|(req, _)| req.clone())
+    }
+
+    /// When the latest heartbeat was received
+    pub fn req_received_at(&self) -> Option<std::time::Instant> {
+        self.req.as_ref().map(|(_, received_at)| *received_at)
     }
 
     pub fn heartbeat_waiter(&self) -> broadcast::Receiver<HeartbeatRequest> {
@@ -64,6 +124,47 @@ impl SessionData {
     pub fn location(&self) -> Option<&Location> {
         self.location.as_ref()
     }
+
+    /// Time since the session was created or last sent a heartbeat
+    pub fn idle_duration(&self) -> std::time::Duration {
+        self.last_activity.elapsed()
+    }
+
+    /// Ask the session's manager to close the session once the device had time
+    /// to receive the rejection
+    fn request_eviction(&self) {
+        let Some(evictions) = self.evictions.clone() else {
+            return;
+        };
+        let client_url = self.client_url.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(REJECTED_SESSION_LINGER).await;
+            let _ = evictions.send(client_url);
+        });
+    }
+}
+
+/// Clear `alive` once the tunnel's stream ends or fails
+fn track_tunnel_liveness(tunnel: Box<dyn Tunnel>, alive: Arc<AtomicBool>) -> Box<dyn Tunnel> {
+    let info = tunnel.info();
+    let (stream, sink) = tunnel.split();
+    let ended = alive.clone();
+    let stream = stream
+        .inspect(move |packet| {
+            if packet.is_err() {
+                alive.store(false, Ordering::Release);
+            }
+        })
+        .chain(futures::stream::poll_fn(move |_| {
+            ended.store(false, Ordering::Release);
+            std::task::Poll::Ready(None)
+        }))
+        // Keep the original tunnel alive as long as its stream
+        .map(move |packet| {
+            let _ = &tunnel;
+            packet
+        });
+    Box::new(TunnelWrapper::new(stream, sink, info))
 }
 
 impl Drop for SessionData {
@@ -78,6 +179,93 @@ impl Drop for SessionData {
 
 pub type SharedSessionData = Arc<RwLock<SessionData>>;
 
+/// Machine readable reason for a rejected heartbeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatRejectCode {
+    InvalidDeviceId,
+    OrganizationNotFound,
+    /// The organization already has as many sessions as it is allowed
+    OverQuota,
+    /// The device waits for an admin to approve it
+    AwaitingApproval,
+    /// An admin rejected the device
+    Rejected,
+}
+
+impl HeartbeatRejectCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeartbeatRejectCode::InvalidDeviceId => "INVALID_DEVICE_ID",
+            HeartbeatRejectCode::OrganizationNotFound => "ORGANIZATION_NOT_FOUND",
+            HeartbeatRejectCode::OverQuota => "OVER_QUOTA",
+            HeartbeatRejectCode::AwaitingApproval => "AWAITING_APPROVAL",
+            HeartbeatRejectCode::Rejected => "REJECTED",
+        }
+    }
+}
+
+/// Heartbeat rejection sent back to the device as the RPC error payload
+///
+/// Formatted as `[CODE] reason` so the device can show the reason to users,
+/// followed by the configured rejection message in parentheses if there is one.
+#[derive(Debug, Clone)]
+pub struct HeartbeatRejection {
+    pub code: HeartbeatRejectCode,
+    pub reason: String,
+    pub message: Option<String>,
+}
+
+impl HeartbeatRejection {
+    fn new(code: HeartbeatRejectCode, reason: impl Into<String>) -> Self {
+        Self {
+            code,
+            reason: reason.into(),
+            message: None,
+        }
+    }
+
+    fn with_message(mut self, message: Option<String>) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+impl std::fmt::Display for HeartbeatRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code.as_str(), self.reason)?;
+        if let Some(message) = &self.message {
+            write!(f, " ({})", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HeartbeatRejection {}
+
+impl From<HeartbeatRejection> for rpc_types::error::Error {
+    fn from(rejection: HeartbeatRejection) -> Self {
+        anyhow::Error::new(rejection).into()
+    }
+}
+
+/// Length in characters of the device name and serial number columns
+const DEVICE_NAME_MAX_CHARS: usize = 100;
+
+/// Make a reported hostname fit the device name column
+///
+/// Control characters are dropped and names longer than the column are cut,
+/// ending with an ellipsis. The heartbeat itself keeps the original hostname.
+pub fn sanitize_hostname(hostname: &str) -> String {
+    let cleaned: String = hostname.chars().filter(|c| !c.is_control()).collect();
+    if cleaned.chars().count() <= DEVICE_NAME_MAX_CHARS {
+        return cleaned;
+    }
+
+    let mut truncated: String = cleaned.chars().take(DEVICE_NAME_MAX_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
 /// RPC service for handling session requests
 #[derive(Clone)]
 pub struct SessionRpcService {
@@ -103,9 +291,13 @@ impl SessionRpcService {
         let device_id: uuid::Uuid = req
             .machine_id
             .map(Into::into)
-            .ok_or(anyhow::anyhow!(
-                "Device id is not set correctly, expect uuid but got: {:?}",
-                req.machine_id
+            .ok_or(Self::reject(
+                &storage,
+                HeartbeatRejectCode::InvalidDeviceId,
+                format!(
+                    "Device id is not set correctly, expect uuid but got: {:?}",
+                    req.machine_id
+                ),
             ))
             .map_err(|e| {
                 crate::error!("[SESSION_RPC] Failed to parse device_id: {:?}", e);
@@ -144,7 +336,12 @@ impl SessionRpcService {
 
         if !organization_exists {
             crate::warn!("[SESSION_RPC] Organization not found: {}", organization_id);
-            return Err(anyhow::anyhow!("Organization not found: {}", organization_id).into());
+            return Err(Self::reject(
+                &storage,
+                HeartbeatRejectCode::OrganizationNotFound,
+                format!("Organization not found: {}", organization_id),
+            )
+            .into());
         }
 
         let organization_id = organization_id.clone();
@@ -163,23 +360,55 @@ impl SessionRpcService {
             organization_id: organization_id.clone(),
         };
 
-        // Always update client info in memory on each heartbeat (for session freshness)
-        if let Ok(storage) = Storage::try_from(data.storage.clone()) {
-            let report_time = chrono::Utc::now().timestamp();
-            storage.update_client(storage_token.clone(), report_time);
+        // Always update client info in memory on each heartbeat (for session freshness),
+        // a new session over the organization's limit is rejected before anything is stored
+        let report_time = chrono::Utc::now().timestamp();
+        if !storage.try_register_client(
+            storage_token.clone(),
+            report_time,
+            data.tunnel_alive.clone(),
+        ) {
+            crate::warn!(
+                "[SESSION_RPC] Rejecting session {}: organization {} reached its session limit",
+                data.client_url,
+                organization_id
+            );
+            data.request_eviction();
+            return Err(Self::reject(
+                &storage,
+                HeartbeatRejectCode::OverQuota,
+                format!(
+                    "Organization {} reached its active session limit",
+                    organization_id
+                ),
+            )
+            .into());
         }
 
-        // Sync device record in database on every heartbeat
-        let device_status = Self::sync_device_record(&storage, &req, &organization_id, device_id)
+        // Sync device record in database on every heartbeat, a slow database fails
+        // the heartbeat at the statement timeout instead of stalling the session
+        let device_status = storage
+            .db()
+            .with_statement_timeout(Self::sync_device_record(
+                &storage,
+                &req,
+                &organization_id,
+                device_id,
+            ))
             .await
             .with_context(|| format!("Failed to sync device record for device_id: {}", device_id))
             .map_err(|e| {
+                HEARTBEAT_DB_ERRORS_TOTAL.inc();
                 crate::error!("[SESSION_RPC] Failed to sync device record: {:?}", e);
                 e
             })?;
 
         // Update session data
-        if data.req.replace(req.clone()).is_none() {
+        if data
+            .req
+            .replace((req.clone(), std::time::Instant::now()))
+            .is_none()
+        {
             // First heartbeat - initialize storage token
             assert!(data.storage_token.is_none());
             data.storage_token = Some(storage_token);
@@ -190,10 +419,54 @@ impl SessionRpcService {
 
         crate::trace!("[SESSION_RPC] Successfully processed heartbeat for organization_id: {}, device_id: {}, status: {:?}", organization_id, device_id, device_status);
 
+        HEARTBEATS_PROCESSED_TOTAL.inc();
+        data.last_activity = std::time::Instant::now();
         let _ = data.notifier.send(req);
+
+        // The heartbeat is recorded either way, so the device shows up for approval
+        if storage.rejects_unapproved_devices() {
+            use crate::db::entities::devices::DeviceStatus;
+            match device_status {
+                DeviceStatus::Pending => {
+                    return Err(Self::reject(
+                        &storage,
+                        HeartbeatRejectCode::AwaitingApproval,
+                        format!("Device {} is awaiting approval", device_id),
+                    )
+                    .into());
+                }
+                DeviceStatus::Rejected => {
+                    return Err(Self::reject(
+                        &storage,
+                        HeartbeatRejectCode::Rejected,
+                        format!("Device {} was rejected", device_id),
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+        }
         Ok(HeartbeatResponse {})
     }
 
+    /// Rejection carrying the configured rejection message
+    fn reject(
+        storage: &Storage,
+        code: HeartbeatRejectCode,
+        reason: impl Into<String>,
+    ) -> HeartbeatRejection {
+        HeartbeatRejection::new(code, reason).with_message(storage.reject_message())
+    }
+
+    /// Serial number recorded for a device first seen through this heartbeat
+    ///
+    /// EasyTier 2.4 heartbeats carry no serial number of their own, so the machine id
+    /// stands in until an admin sets a real one. Unlike the hostname it is stable and
+    /// unique, so devices sharing a hostname are never taken for one another.
+    fn heartbeat_serial_number(device_id: uuid::Uuid) -> String {
+        device_id.to_string()
+    }
+
     /// Sync device record in database, creating if not exists
     async fn sync_device_record(
         storage: &super::storage::Storage,
@@ -202,7 +475,10 @@ impl SessionRpcService {
         device_id: uuid::Uuid,
     ) -> anyhow::Result<crate::db::entities::devices::DeviceStatus> {
         use crate::db::entities::devices;
-        use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+        use sea_orm::sea_query::Expr;
+        use sea_orm::{
+            ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+        };
 
         let device_id_str = device_id.to_string();
 
@@ -216,61 +492,109 @@ impl SessionRpcService {
 
         match existing {
             Some(device) => {
-                // Update existing device heartbeat
-                let mut active: devices::ActiveModel = device.clone().into();
-                active.last_heartbeat = Set(Some(chrono::Utc::now().into()));
-                active.updated_at = Set(chrono::Utc::now().into());
+                // Update existing device heartbeat, name and serial_number may have been
+                // edited by an admin and are left untouched. Only the heartbeat columns are
+                // written so a concurrent admin status change is never overwritten.
+                let now: sea_orm::prelude::DateTimeWithTimeZone = chrono::Utc::now().into();
+                devices::Entity::update_many()
+                    .col_expr(devices::Column::LastHeartbeat, Expr::value(now))
+                    .col_expr(devices::Column::UpdatedAt, Expr::value(now))
+                    .filter(devices::Column::Id.eq(&device_id_str))
+                    .exec(storage.db().orm())
+                    .await
+                    .with_context(|| {
+                        format!("Failed to update device heartbeat: {}", device_id_str)
+                    })?;
 
                 // Handle status transitions based on current status
-                let new_status = match device.status {
+                let old_status = device.status.clone();
+                let target_status = match device.status {
+                    // Rejected devices stay rejected while unapproved devices are turned away
+                    devices::DeviceStatus::Rejected if storage.rejects_unapproved_devices() => None,
                     // If device is rejected, change status back to pending when it reconnects
                     // This gives the device another chance to be approved by admin
                     devices::DeviceStatus::Rejected => {
                         crate::info!("[SESSION_RPC] Rejected device {} reconnected, changing status to pending", device_id_str);
-                        active.status = Set(devices::DeviceStatus::Pending);
-                        devices::DeviceStatus::Pending
+                        Some(devices::DeviceStatus::Pending)
                     }
-                    // If device is offline, restore it to online status when it reconnects
-                    // Note: Only approved devices (online/offline/busy/maintenance) are marked as offline on timeout
+                    // If device is offline, restore the status it had before going offline
+                    // Records without one (marked offline before it was tracked) fall back to online
                     devices::DeviceStatus::Offline => {
-                        crate::info!("[SESSION_RPC] Offline device {} reconnected, restoring to online status", device_id_str);
-                        active.status = Set(devices::DeviceStatus::Online);
-                        devices::DeviceStatus::Online
+                        let restored = device
+                            .previous_status
+                            .clone()
+                            .filter(|status| *status != devices::DeviceStatus::Offline)
+                            .unwrap_or(devices::DeviceStatus::Online);
+                        crate::info!(
+                            "[SESSION_RPC] Offline device {} reconnected, restoring status {:?}",
+                            device_id_str,
+                            restored
+                        );
+                        Some(restored)
                     }
                     // For other statuses, keep the existing status
-                    _ => {
-                        // Preserve current status (pending waits for admin, online/busy/maintenance preserved)
-                        if device.status.is_pending() {
-                            // Keep pending status - admin needs to explicitly approve
-                            device.status
+                    // (pending waits for admin, online/busy/maintenance preserved)
+                    _ => None,
+                };
+
+                let (new_status, transitioned) = match target_status {
+                    Some(target_status) => {
+                        if storage
+                            .transition_device_status(&device_id_str, &old_status, &target_status)
+                            .await
+                            .with_context(|| {
+                                format!("Failed to update device status: {}", device_id_str)
+                            })?
+                        {
+                            (target_status, true)
                         } else {
-                            // For other statuses, preserve them when heartbeat comes in
-                            device.status
+                            // The status changed since it was read, keep the newer value
+                            let current = devices::Entity::find_by_id(device_id_str.clone())
+                                .one(storage.db().orm())
+                                .await
+                                .with_context(|| {
+                                    format!("Failed to query device: {}", device_id_str)
+                                })?
+                                .map(|device| device.status)
+                                .unwrap_or(old_status.clone());
+                            crate::info!(
+                                "[SESSION_RPC] Status of device {} changed concurrently to {:?}, keeping it",
+                                device_id_str,
+                                current
+                            );
+                            (current, false)
                         }
                     }
+                    None => (old_status.clone(), false),
                 };
 
-                active.update(storage.db().orm()).await.with_context(|| {
-                    format!("Failed to update device heartbeat: {}", device_id_str)
-                })?;
-
                 crate::trace!(
                     "[SESSION_RPC] Updated heartbeat for existing device: {}, status: {:?}",
                     device_id_str,
                     new_status
                 );
+
+                if transitioned {
+                    storage.publish_device_event(DeviceStatusEvent {
+                        device_id,
+                        organization_id: organization_id.to_string(),
+                        old_status,
+                        new_status: new_status.clone(),
+                    });
+                }
                 Ok(new_status)
             }
             None => {
                 // Device not found by device_id, check if a device with same serial_number exists
                 // This handles the case where device was rejected/deleted and is rejoining
+                let serial_number = Self::heartbeat_serial_number(device_id);
                 let existing_by_serial = devices::Entity::find()
-                    .filter(devices::Column::SerialNumber.eq(&req.hostname))
+                    .filter(devices::Column::SerialNumber.eq(&serial_number))
                     .filter(devices::Column::OrganizationId.eq(organization_id))
                     .one(storage.db().orm())
                     .await
                     .with_context(|| {
-                        format!("Failed to query device by serial_number: {}", req.hostname)
+                        format!("Failed to query device by serial_number: {}", serial_number)
                     })?;
 
                 match existing_by_serial {
@@ -279,14 +603,23 @@ impl SessionRpcService {
                         // Delete old record and create new one with updated device_id
                         crate::info!(
                             "[SESSION_RPC] Found existing device with serial_number: {}, replacing device_id from {} to {}",
-                            req.hostname,
+                            serial_number,
                             old_device.id,
                             device_id_str
                         );
 
+                        // Replace the record in one transaction, a heartbeat failing or
+                        // timing out in between rolls the delete back
+                        let txn = storage
+                            .db()
+                            .orm()
+                            .begin()
+                            .await
+                            .context("Failed to start device record replacement")?;
+
                         // Delete the old device record
                         devices::Entity::delete_by_id(old_device.id.clone())
-                            .exec(storage.db().orm())
+                            .exec(&txn)
                             .await
                             .with_context(|| {
                                 format!("Failed to delete old device record: {}", old_device.id)
@@ -295,8 +628,8 @@ impl SessionRpcService {
                         // Create new device record with new device_id
                         let new_device = devices::ActiveModel {
                             id: Set(device_id_str.clone()),
-                            name: Set(req.hostname.clone()),
-                            serial_number: Set(req.hostname.clone()),
+                            name: Set(sanitize_hostname(&req.hostname)),
+                            serial_number: Set(serial_number),
                             device_type: Set(old_device.device_type),
                             organization_id: Set(Some(organization_id.to_string())),
                             status: Set(devices::DeviceStatus::Pending),
@@ -306,15 +639,15 @@ impl SessionRpcService {
                             ..Default::default()
                         };
 
-                        new_device
-                            .insert(storage.db().orm())
-                            .await
-                            .with_context(|| {
-                                format!(
-                                    "Failed to create device record with new device_id: {}",
-                                    device_id_str
-                                )
-                            })?;
+                        new_device.insert(&txn).await.with_context(|| {
+                            format!(
+                                "Failed to create device record with new device_id: {}",
+                                device_id_str
+                            )
+                        })?;
+                        txn.commit().await.with_context(|| {
+                            format!("Failed to replace device record: {}", old_device.id)
+                        })?;
 
                         crate::info!(
                             "[SESSION_RPC] Replaced device record with new device_id: {}, status: pending",
@@ -324,13 +657,19 @@ impl SessionRpcService {
                     }
                     None => {
                         // No existing device with this serial_number, create new one
+                        // Trusted organizations skip the manual approval step
+                        let status = if storage.auto_approves(organization_id) {
+                            devices::DeviceStatus::Online
+                        } else {
+                            devices::DeviceStatus::Pending
+                        };
                         let new_device = devices::ActiveModel {
                             id: Set(device_id_str.clone()),
-                            name: Set(req.hostname.clone()),
-                            serial_number: Set(req.hostname.clone()), // Use hostname as serial for now
-                            device_type: Set(devices::DeviceType::Robot), // Default to robot
+                            name: Set(sanitize_hostname(&req.hostname)),
+                            serial_number: Set(serial_number),
+                            device_type: Set(storage.default_device_type()),
                             organization_id: Set(Some(organization_id.to_string())),
-                            status: Set(devices::DeviceStatus::Pending),
+                            status: Set(status.clone()),
                             last_heartbeat: Set(Some(chrono::Utc::now().into())),
                             created_at: Set(chrono::Utc::now().into()),
                             updated_at: Set(chrono::Utc::now().into()),
@@ -345,10 +684,11 @@ impl SessionRpcService {
                             })?;
 
                         crate::info!(
-                            "[SESSION_RPC] Created new device record: {}, status: pending",
-                            device_id_str
+                            "[SESSION_RPC] Created new device record: {}, status: {:?}",
+                            device_id_str,
+                            status
                         );
-                        Ok(devices::DeviceStatus::Pending)
+                        Ok(status)
                     }
                 }
             }
@@ -382,6 +722,12 @@ pub struct Session {
     run_network_on_start_task: Option<ScopedTask<()>>,
     // 添加一个关闭通知通道
     shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    // 接受该会话的监听器 ID
+    listener_id: Option<u32>,
+    // RPC 接收超时
+    rx_timeout: std::time::Duration,
+    // 隧道收发字节计数
+    throughput: Option<Arc<Throughput>>,
 }
 
 impl Debug for Session {
@@ -392,17 +738,54 @@ impl Debug for Session {
 
 type SessionRpcClient = Box<dyn WebClientService<Controller = BaseController> + Send>;
 
+/// Time a session refused over quota is kept open, so the device receives the reason
+const REJECTED_SESSION_LINGER: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Default RPC receive timeout of a session
+pub const DEFAULT_SESSION_RX_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Default number of heartbeats buffered for each heartbeat subscriber
+pub const DEFAULT_HEARTBEAT_CHANNEL_CAPACITY: usize = 16;
+
 impl Session {
     pub fn new(storage: WeakRefStorage, client_url: url::Url, location: Option<Location>) -> Self {
+        Self::new_with_rx_timeout(storage, client_url, location, DEFAULT_SESSION_RX_TIMEOUT)
+    }
+
+    /// Create a session whose RPC manager uses the given receive timeout
+    pub fn new_with_rx_timeout(
+        storage: WeakRefStorage,
+        client_url: url::Url,
+        location: Option<Location>,
+        rx_timeout: std::time::Duration,
+    ) -> Self {
+        Self::new_with_heartbeat_capacity(
+            storage,
+            client_url,
+            location,
+            rx_timeout,
+            DEFAULT_HEARTBEAT_CHANNEL_CAPACITY,
+        )
+    }
+
+    /// Create a session buffering up to `heartbeat_capacity` heartbeats per subscriber
+    ///
+    /// Subscribers falling further behind skip the oldest heartbeats.
+    pub fn new_with_heartbeat_capacity(
+        storage: WeakRefStorage,
+        client_url: url::Url,
+        location: Option<Location>,
+        rx_timeout: std::time::Duration,
+        heartbeat_capacity: usize,
+    ) -> Self {
         crate::debug!(
             "[SESSION] Creating new session for client_url: {}",
             client_url
         );
-        let session_data = SessionData::new(storage, client_url, location);
+        let session_data = SessionData::new(storage, client_url, location, heartbeat_capacity);
         let data = Arc::new(RwLock::new(session_data));
 
-        let rpc_mgr =
-            BidirectRpcManager::new().set_rx_timeout(Some(std::time::Duration::from_secs(30)));
+        let rpc_mgr = BidirectRpcManager::new().set_rx_timeout(Some(rx_timeout));
 
         rpc_mgr.rpc_server().registry().register(
             WebServerServiceServer::new(SessionRpcService { data: data.clone() }),
@@ -414,13 +797,64 @@ impl Session {
             data,
             run_network_on_start_task: None,
             shutdown_tx: None,
+            listener_id: None,
+            rx_timeout,
+            throughput: None,
+        }
+    }
+
+    /// RPC receive timeout used by this session
+    pub fn rx_timeout(&self) -> std::time::Duration {
+        self.rx_timeout
+    }
+
+    /// Record the ID of the listener that accepted this session
+    pub fn with_listener_id(mut self, listener_id: u32) -> Self {
+        self.listener_id = Some(listener_id);
+        self
+    }
+
+    /// Report the session's URL to `evictions` when it must be closed, e.g.
+    /// after its organization's session limit refused it
+    pub fn with_evictions(self, evictions: mpsc::UnboundedSender<url::Url>) -> Self {
+        self.data
+            .try_write()
+            .expect("session data is not shared before the session is served")
+            .evictions = Some(evictions);
+        self
+    }
+
+    /// ID of the listener that accepted this session, if known
+    pub fn listener_id(&self) -> Option<u32> {
+        self.listener_id
+    }
+
+    /// Bytes sent to the client, `None` if the session has no tunnel counters
+    pub fn tx_bytes(&self) -> Option<u64> {
+        self.throughput.as_ref().map(|t| t.tx_bytes())
+    }
+
+    /// Bytes received from the client, `None` if the session has no tunnel counters
+    pub fn rx_bytes(&self) -> Option<u64> {
+        self.throughput.as_ref().map(|t| t.rx_bytes())
+    }
+
+    /// Snapshot of the session's byte counters
+    pub fn throughput(&self) -> SessionThroughput {
+        SessionThroughput {
+            tx_bytes: self.tx_bytes(),
+            rx_bytes: self.rx_bytes(),
         }
     }
 
     /// Serve the session with a tunnel
     pub async fn serve(&mut self, tunnel: Box<dyn Tunnel>) {
         crate::info!("[SESSION] Starting to serve session with tunnel");
-        self.rpc_mgr.run_with_tunnel(tunnel);
+        let tunnel = track_tunnel_liveness(tunnel, self.data.read().await.tunnel_alive.clone());
+        let stats_filter = StatsRecorderTunnelFilter::new();
+        self.throughput = Some(stats_filter.filter_output());
+        self.rpc_mgr
+            .run_with_tunnel(Box::new(TunnelWithFilter::new(tunnel, stats_filter)));
 
         // 创建关闭通知通道
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
@@ -455,16 +889,20 @@ impl Session {
             crate::debug!("[run_network_on_start] Entering loop iteration");
             heartbeat_waiter = heartbeat_waiter.resubscribe();
             crate::debug!("[run_network_on_start] Waiting for heartbeat request");
-            let req = heartbeat_waiter.recv().await;
-            if req.is_err() {
-                crate::error!(
-                    "Failed to receive heartbeat request, error: {:?}",
-                    req.err()
-                );
-                return;
-            }
-
-            let req = req.unwrap();
+            let req = match heartbeat_waiter.recv().await {
+                Ok(req) => req,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    crate::warn!(
+                        "[run_network_on_start] Lagged behind, skipped {} heartbeats",
+                        skipped
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    crate::error!("Failed to receive heartbeat request, error: {:?}", e);
+                    return;
+                }
+            };
             crate::debug!(
                 "[run_network_on_start] Received heartbeat request: {:?}",
                 req
@@ -728,25 +1166,38 @@ impl Session {
         self.data.read().await.req()
     }
 
-    /// Run network instance
+    /// Push a network config to the connected device and start it
+    ///
+    /// Returns the instance id the device runs the network under.
     pub async fn run_network_instance(
-        &mut self,
-        req: RunNetworkInstanceRequest,
-    ) -> Result<(), anyhow::Error> {
+        &self,
+        config: NetworkConfig,
+    ) -> Result<uuid::Uuid, anyhow::Error> {
         crate::debug!("[SESSION] Starting to run network instance");
 
         let client = self.scoped_rpc_client();
 
         let ret = client
-            .run_network_instance(BaseController::default(), req)
+            .run_network_instance(
+                BaseController::default(),
+                RunNetworkInstanceRequest {
+                    inst_id: None,
+                    config: Some(config),
+                },
+            )
             .await
             .map_err(|e| {
                 crate::error!("[SESSION] Failed to run network instance: {:?}", e);
                 e
             })?;
 
-        crate::info!("[SESSION] Run network instance result: {:?}", ret);
-        Ok(())
+        let inst_id = ret
+            .inst_id
+            .map(uuid::Uuid::from)
+            .ok_or_else(|| anyhow::anyhow!("Device did not report a network instance id"))?;
+
+        crate::info!("[SESSION] Network instance {} is running", inst_id);
+        Ok(inst_id)
     }
 
     /// Stop network instance
@@ -1,12 +1,50 @@
 //! Storage management for EasyTier clients with MySQL backend
 
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 
 use dashmap::DashMap;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use crate::db::entities::devices::{DeviceStatus, DeviceType};
 use crate::db::{Database, OrgIdInDb};
 
+/// Capacity of the device status event channel, older events are dropped when it is full
+pub const DEVICE_EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Device status transition observed while processing a heartbeat
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DeviceStatusEvent {
+    pub device_id: Uuid,
+    pub organization_id: OrgIdInDb,
+    pub old_status: DeviceStatus,
+    pub new_status: DeviceStatus,
+}
+
+/// Which organizations get first-time devices approved without an admin
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AutoApprovalPolicy {
+    /// New devices wait as pending for manual approval
+    #[default]
+    Disabled,
+    /// New devices of every organization are approved
+    AllOrgs,
+    /// New devices of the listed organizations are approved
+    Orgs(HashSet<OrgIdInDb>),
+}
+
+impl AutoApprovalPolicy {
+    pub fn approves(&self, organization_id: &str) -> bool {
+        match self {
+            AutoApprovalPolicy::Disabled => false,
+            AutoApprovalPolicy::AllOrgs => true,
+            AutoApprovalPolicy::Orgs(orgs) => orgs.contains(organization_id),
+        }
+    }
+}
+
 /// Storage token for client identification
 /// Updated to align with cortex_server models: machines -> devices, user_id -> organization_id
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -21,6 +59,75 @@ pub struct StorageToken {
 struct ClientInfo {
     storage_token: StorageToken,
     report_time: i64,
+    /// Cleared by the session once its tunnel is gone, None if not tracked
+    tunnel_alive: Option<Arc<AtomicBool>>,
+}
+
+impl ClientInfo {
+    fn is_live(&self) -> bool {
+        self.tunnel_alive
+            .as_ref()
+            .map_or(true, |alive| alive.load(Ordering::Acquire))
+    }
+}
+
+/// Live connections of one device
+///
+/// A dual-stacked device may be connected over IPv4 and IPv6 at the same
+/// time; it is still listed once, through the connection that reported last.
+#[derive(Debug, Clone, Default)]
+struct DeviceClients(Vec<ClientInfo>);
+
+impl DeviceClients {
+    /// Connection with the most recent report
+    fn latest(&self) -> Option<&ClientInfo> {
+        self.0.iter().max_by_key(|info| info.report_time)
+    }
+
+    fn latest_url(&self) -> Option<url::Url> {
+        self.latest()
+            .map(|info| info.storage_token.client_url.clone())
+    }
+
+    fn urls(&self) -> Vec<url::Url> {
+        self.0
+            .iter()
+            .map(|info| info.storage_token.client_url.clone())
+            .collect()
+    }
+
+    fn update(&mut self, client_info: &ClientInfo) {
+        match self
+            .0
+            .iter_mut()
+            .find(|info| info.storage_token.client_url == client_info.storage_token.client_url)
+        {
+            Some(info) => {
+                if info.report_time < client_info.report_time {
+                    let tunnel_alive = client_info
+                        .tunnel_alive
+                        .clone()
+                        .or_else(|| info.tunnel_alive.take());
+                    *info = ClientInfo {
+                        tunnel_alive,
+                        ..client_info.clone()
+                    };
+                }
+            }
+            None => self.0.push(client_info.clone()),
+        }
+    }
+
+    fn contains(&self, client_url: &url::Url) -> bool {
+        self.0
+            .iter()
+            .any(|info| info.storage_token.client_url == *client_url)
+    }
+
+    fn remove(&mut self, client_url: &url::Url) {
+        self.0
+            .retain(|info| info.storage_token.client_url != *client_url);
+    }
 }
 
 /// Weak reference to storage for avoiding circular references
@@ -30,7 +137,13 @@ pub type WeakRefStorage = std::sync::Weak<StorageInner>;
 #[derive(Debug)]
 pub struct StorageInner {
     // some map for indexing
-    org_clients_map: DashMap<OrgIdInDb, DashMap<uuid::Uuid, ClientInfo>>,
+    org_clients_map: DashMap<OrgIdInDb, DashMap<uuid::Uuid, DeviceClients>>,
+    device_events: broadcast::Sender<DeviceStatusEvent>,
+    auto_approval: RwLock<AutoApprovalPolicy>,
+    default_device_type: RwLock<DeviceType>,
+    max_sessions_per_org: RwLock<Option<usize>>,
+    reject_unapproved_devices: RwLock<bool>,
+    reject_message: RwLock<Option<String>>,
     pub db: Database,
 }
 
@@ -48,35 +161,131 @@ impl TryFrom<WeakRefStorage> for Storage {
 
 impl Storage {
     pub fn new(db: Database) -> Self {
+        let (device_events, _) = broadcast::channel(DEVICE_EVENT_CHANNEL_CAPACITY);
         Storage(Arc::new(StorageInner {
             org_clients_map: DashMap::new(),
+            device_events,
+            auto_approval: RwLock::new(AutoApprovalPolicy::default()),
+            default_device_type: RwLock::new(DeviceType::Robot),
+            max_sessions_per_org: RwLock::new(None),
+            reject_unapproved_devices: RwLock::new(false),
+            reject_message: RwLock::new(None),
             db,
         }))
     }
 
+    /// Replace the policy applied to devices seen for the first time
+    pub fn set_auto_approval(&self, policy: AutoApprovalPolicy) {
+        *self.0.auto_approval.write().unwrap() = policy;
+    }
+
+    /// Whether a first-time device of this organization is approved right away
+    pub fn auto_approves(&self, organization_id: &str) -> bool {
+        self.0
+            .auto_approval
+            .read()
+            .unwrap()
+            .approves(organization_id)
+    }
+
+    /// Replace the type given to devices seen for the first time
+    pub fn set_default_device_type(&self, device_type: DeviceType) {
+        *self.0.default_device_type.write().unwrap() = device_type;
+    }
+
+    /// Type given to devices seen for the first time
+    pub fn default_device_type(&self) -> DeviceType {
+        self.0.default_device_type.read().unwrap().clone()
+    }
+
+    /// Limit the number of registered sessions per organization, None means unlimited
+    pub fn set_org_session_limit(&self, limit: Option<usize>) {
+        *self.0.max_sessions_per_org.write().unwrap() = limit;
+    }
+
+    /// Answer heartbeats of pending and rejected devices with a rejection
+    ///
+    /// Their heartbeats are still recorded so an admin can approve them, and a
+    /// rejected device stays rejected instead of going back to pending.
+    pub fn set_reject_unapproved_devices(&self, reject: bool) {
+        *self.0.reject_unapproved_devices.write().unwrap() = reject;
+    }
+
+    /// Whether heartbeats of pending and rejected devices are answered with a rejection
+    pub fn rejects_unapproved_devices(&self) -> bool {
+        *self.0.reject_unapproved_devices.read().unwrap()
+    }
+
+    /// Replace the message appended to heartbeat rejections, None sends the reason only
+    pub fn set_reject_message(&self, message: Option<String>) {
+        *self.0.reject_message.write().unwrap() = message;
+    }
+
+    /// Message appended to heartbeat rejections
+    pub fn reject_message(&self) -> Option<String> {
+        self.0.reject_message.read().unwrap().clone()
+    }
+
+    /// Move a device from status `from` to `to` in a single conditional update
+    ///
+    /// Moving to `Offline` remembers `from` as the previous status, any other
+    /// transition clears it. Returns false, leaving the record untouched, when the
+    /// device no longer has status `from` (e.g. an admin changed it after it was read).
+    pub async fn transition_device_status(
+        &self,
+        device_id: &str,
+        from: &DeviceStatus,
+        to: &DeviceStatus,
+    ) -> Result<bool, sea_orm::DbErr> {
+        use crate::db::entities::devices;
+        use sea_orm::sea_query::Expr;
+        use sea_orm::{ActiveEnum, ColumnTrait, EntityTrait, QueryFilter};
+
+        let previous_status: Option<String> =
+            (*to == DeviceStatus::Offline).then(|| from.to_value());
+        let now: sea_orm::prelude::DateTimeWithTimeZone = chrono::Utc::now().into();
+        let result = devices::Entity::update_many()
+            .col_expr(devices::Column::Status, Expr::value(to.clone()))
+            .col_expr(
+                devices::Column::PreviousStatus,
+                Expr::value(previous_status),
+            )
+            .col_expr(devices::Column::UpdatedAt, Expr::value(now))
+            .filter(devices::Column::Id.eq(device_id))
+            .filter(devices::Column::Status.eq(from.clone()))
+            .exec(self.db().orm())
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Subscribe to device status transitions
+    pub fn subscribe_device_events(&self) -> broadcast::Receiver<DeviceStatusEvent> {
+        self.0.device_events.subscribe()
+    }
+
+    /// Publish a device status transition, dropped silently when nobody subscribed
+    pub fn publish_device_event(&self, event: DeviceStatusEvent) {
+        let _ = self.0.device_events.send(event);
+    }
+
     fn remove_device_to_client_info_map(
-        map: &DashMap<uuid::Uuid, ClientInfo>,
+        map: &DashMap<uuid::Uuid, DeviceClients>,
         device_id: &uuid::Uuid,
         client_url: &url::Url,
     ) {
-        map.remove_if(device_id, |_, v| v.storage_token.client_url == *client_url);
+        map.remove_if_mut(device_id, |_, clients| {
+            clients.remove(client_url);
+            clients.0.is_empty()
+        });
     }
 
     fn update_device_to_client_info_map(
-        map: &DashMap<uuid::Uuid, ClientInfo>,
+        map: &DashMap<uuid::Uuid, DeviceClients>,
         client_info: &ClientInfo,
     ) {
         map.entry(client_info.storage_token.device_id)
-            .and_modify(|e| {
-                if e.report_time < client_info.report_time {
-                    assert_eq!(
-                        e.storage_token.device_id,
-                        client_info.storage_token.device_id
-                    );
-                    *e = client_info.clone();
-                }
-            })
-            .or_insert(client_info.clone());
+            .or_default()
+            .update(client_info);
     }
 
     pub fn update_client(&self, stoken: StorageToken, report_time: i64) {
@@ -89,11 +298,56 @@ impl Storage {
         let client_info = ClientInfo {
             storage_token: stoken.clone(),
             report_time,
+            tunnel_alive: None,
         };
 
         Self::update_device_to_client_info_map(&inner, &client_info);
     }
 
+    /// Register or refresh a client, unless its organization is at the session limit
+    ///
+    /// Already registered clients are always refreshed. Only clients whose
+    /// `tunnel_alive` is still set count toward the limit, so a session whose
+    /// tunnel dropped does not block a reconnect until it is cleaned up. The
+    /// count and the insert happen under the organization's map entry, so
+    /// concurrent first heartbeats of one organization are admitted one at a
+    /// time. Returns false, leaving the storage untouched, when a new client
+    /// would exceed the limit.
+    pub fn try_register_client(
+        &self,
+        stoken: StorageToken,
+        report_time: i64,
+        tunnel_alive: Arc<AtomicBool>,
+    ) -> bool {
+        let limit = *self.0.max_sessions_per_org.read().unwrap();
+        let inner = self
+            .0
+            .org_clients_map
+            .entry(stoken.organization_id.clone())
+            .or_default();
+
+        if let Some(limit) = limit {
+            let registered = inner
+                .get(&stoken.device_id)
+                .is_some_and(|clients| clients.contains(&stoken.client_url));
+            let active: usize = inner
+                .iter()
+                .map(|clients| clients.value().0.iter().filter(|c| c.is_live()).count())
+                .sum();
+            if !registered && active >= limit {
+                return false;
+            }
+        }
+
+        let client_info = ClientInfo {
+            storage_token: stoken,
+            report_time,
+            tunnel_alive: Some(tunnel_alive),
+        };
+        Self::update_device_to_client_info_map(&inner, &client_info);
+        true
+    }
+
     pub fn remove_client(&self, stoken: &StorageToken) {
         self.0
             .org_clients_map
@@ -118,10 +372,27 @@ impl Storage {
             .and_then(|info_map| {
                 info_map
                     .get(device_id)
-                    .map(|info| info.storage_token.client_url.clone())
+                    .and_then(|clients| clients.latest_url())
             })
     }
 
+    /// List the URLs of every connection of a device
+    pub fn get_client_urls_by_device_id(
+        &self,
+        organization_id: &OrgIdInDb,
+        device_id: &uuid::Uuid,
+    ) -> Vec<url::Url> {
+        self.0
+            .org_clients_map
+            .get(organization_id)
+            .and_then(|info_map| info_map.get(device_id).map(|clients| clients.urls()))
+            .unwrap_or_default()
+    }
+
+    /// List one client URL per connected device of an organization
+    ///
+    /// A device connected over several addresses, e.g. IPv4 and IPv6, is
+    /// listed once with the URL that reported last.
     pub fn list_organization_clients(&self, organization_id: &OrgIdInDb) -> Vec<url::Url> {
         self.0
             .org_clients_map
@@ -129,12 +400,33 @@ impl Storage {
             .map(|info_map| {
                 info_map
                     .iter()
-                    .map(|info| info.value().storage_token.client_url.clone())
+                    .filter_map(|clients| clients.value().latest_url())
                     .collect()
             })
             .unwrap_or_default()
     }
 
+    /// List client URLs of every organization, one per device
+    ///
+    /// Iterates the DashMaps shard by shard, so concurrent updates only block
+    /// the shard being read and each entry is seen either before or after a change.
+    pub fn list_all_clients(&self) -> Vec<(OrgIdInDb, url::Url)> {
+        self.0
+            .org_clients_map
+            .iter()
+            .flat_map(|org| {
+                org.value()
+                    .iter()
+                    .filter_map(|clients| {
+                        clients
+                            .latest_url()
+                            .map(|client_url| (org.key().clone(), client_url))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
     pub fn db(&self) -> &Database {
         &self.0.db
     }
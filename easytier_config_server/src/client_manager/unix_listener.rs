@@ -0,0 +1,118 @@
+//! UNIX domain socket tunnel listener
+//!
+//! Serves `unix://` URLs whose path is the socket path, for device clients
+//! running on the same host as the config server. Connections are framed the
+//! same way as EasyTier's TCP tunnel.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+use easytier::{
+    proto::common::TunnelInfo,
+    tunnel::{
+        common::{FramedReader, FramedWriter, TunnelWrapper},
+        Tunnel, TunnelError, TunnelListener,
+    },
+};
+use tokio::net::UnixListener;
+
+/// Maximum frame size, the same as EasyTier's TCP tunnel
+const UNIX_MTU_BYTES: usize = 2000;
+
+/// Tunnel listener accepting connections on a UNIX domain socket
+#[derive(Debug)]
+pub struct UnixTunnelListener {
+    addr: url::Url,
+    listener: Option<UnixListener>,
+    accepted: AtomicU64,
+}
+
+impl UnixTunnelListener {
+    pub fn new(addr: url::Url) -> Self {
+        UnixTunnelListener {
+            addr,
+            listener: None,
+            accepted: AtomicU64::new(0),
+        }
+    }
+
+    /// Socket path taken from the URL path
+    pub fn socket_path(&self) -> PathBuf {
+        PathBuf::from(self.addr.path())
+    }
+
+    /// Remove a socket file left behind by a previous run
+    ///
+    /// Only sockets are removed, any other file at the path is left for `bind` to reject.
+    fn remove_stale_socket(&self) -> std::io::Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = self.socket_path();
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                crate::info!(
+                    "[UNIX_LISTENER] Removing stale socket file {}",
+                    path.display()
+                );
+                std::fs::remove_file(&path)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl TunnelListener for UnixTunnelListener {
+    async fn listen(&mut self) -> Result<(), TunnelError> {
+        if self.addr.path().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("No socket path in {}", self.addr),
+            )
+            .into());
+        }
+
+        self.remove_stale_socket()?;
+        self.listener = Some(UnixListener::bind(self.socket_path())?);
+        Ok(())
+    }
+
+    async fn accept(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        let listener = self.listener.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotConnected, "Listener is not bound")
+        })?;
+        let (stream, _) = listener.accept().await?;
+
+        // Peers have no address of their own, number them so each gets a distinct session key
+        let conn_id = self.accepted.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut remote_url = self.addr.clone();
+        remote_url.set_query(Some(&format!("conn={}", conn_id)));
+
+        let info = TunnelInfo {
+            tunnel_type: "unix".to_owned(),
+            local_addr: Some(self.addr.clone().into()),
+            remote_addr: Some(remote_url.into()),
+            ..Default::default()
+        };
+
+        let (r, w) = stream.into_split();
+        Ok(Box::new(TunnelWrapper::new(
+            FramedReader::new(r, UNIX_MTU_BYTES),
+            FramedWriter::new(w),
+            Some(info),
+        )))
+    }
+
+    fn local_url(&self) -> url::Url {
+        self.addr.clone()
+    }
+}
+
+impl Drop for UnixTunnelListener {
+    fn drop(&mut self) {
+        if self.listener.take().is_some() {
+            let _ = std::fs::remove_file(self.socket_path());
+        }
+    }
+}
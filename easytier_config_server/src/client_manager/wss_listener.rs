@@ -0,0 +1,339 @@
+//! WebSocket-Secure tunnel listener with a configurable certificate
+//!
+//! EasyTier's websocket listener always presents its built-in self-signed
+//! certificate and only binds the IPv4 wildcard address. This listener
+//! terminates TLS with the certificate and key given in the URL query
+//! (`wss://0.0.0.0:443?cert=/path/cert.pem&key=/path/key.pem`) or configured
+//! through `CORTEX_WSS_CERT_PATH` / `CORTEX_WSS_KEY_PATH`, and binds exactly the
+//! host of its URL so IPv4 and IPv6 can be served by separate listeners.
+//! Messages are framed the same way as EasyTier's websocket tunnel, and the
+//! TLS and websocket crates are the ones EasyTier's tunnel already builds on.
+//!
+//! Handshakes run in their own tasks under a timeout, so a client that never
+//! completes one cannot hold up the connections accepted after it.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use easytier::{
+    proto::common::TunnelInfo,
+    tunnel::{
+        common::TunnelWrapper,
+        packet_def::{ZCPacket, ZCPacketType},
+        Tunnel, TunnelError, TunnelListener,
+    },
+};
+use futures::{SinkExt, StreamExt};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tokio_websockets::{Limits, Message, ServerBuilder};
+
+/// Time a client gets to complete the TLS handshake and websocket upgrade
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest websocket message accepted from a client
+///
+/// EasyTier splits RPC messages into small packets, so this is far above
+/// anything a well-behaved client sends.
+const MAX_MESSAGE_LEN: usize = 1024 * 1024;
+
+/// Certificate and key files of a `wss` listener
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WssCertPaths {
+    pub cert: String,
+    pub key: String,
+}
+
+impl WssCertPaths {
+    /// Paths from the `cert` and `key` query parameters, falling back to the configuration
+    ///
+    /// Returns None when no certificate is configured at all, the listener then
+    /// uses EasyTier's built-in self-signed certificate. A certificate without
+    /// its key, or the other way around, is an error.
+    pub fn from_url(url: &url::Url) -> Result<Option<Self>, String> {
+        let query = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .filter(|value| !value.is_empty())
+        };
+        match (query("cert"), query("key")) {
+            (Some(cert), Some(key)) => Ok(Some(WssCertPaths { cert, key })),
+            (Some(_), None) => Err("`cert` is given without `key`".to_string()),
+            (None, Some(_)) => Err("`key` is given without `cert`".to_string()),
+            (None, None) => match (
+                crate::config::get_wss_cert_path(),
+                crate::config::get_wss_key_path(),
+            ) {
+                (Some(cert), Some(key)) => Ok(Some(WssCertPaths { cert, key })),
+                (Some(_), None) => {
+                    Err("CORTEX_WSS_CERT_PATH is set without CORTEX_WSS_KEY_PATH".to_string())
+                }
+                (None, Some(_)) => {
+                    Err("CORTEX_WSS_KEY_PATH is set without CORTEX_WSS_CERT_PATH".to_string())
+                }
+                (None, None) => Ok(None),
+            },
+        }
+    }
+
+    fn load(&self) -> std::io::Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+        let invalid = |path: &str, e: rustls::pki_types::pem::Error| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to read PEM file '{}': {}", path, e),
+            )
+        };
+
+        let certs = CertificateDer::pem_file_iter(&self.cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| invalid(&self.cert, e))?;
+        if certs.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("No PEM certificate in '{}'", self.cert),
+            ));
+        }
+
+        let key = PrivateKeyDer::from_pem_file(&self.key).map_err(|e| invalid(&self.key, e))?;
+        Ok((certs, key))
+    }
+}
+
+/// Build the TLS configuration of a `wss` listener
+fn server_config(paths: Option<&WssCertPaths>) -> std::io::Result<ServerConfig> {
+    let (certs, key) = match paths {
+        Some(paths) => paths.load()?,
+        None => {
+            crate::warn!(
+                "[WSS_LISTENER] No certificate configured, using EasyTier's self-signed one"
+            );
+            easytier::tunnel::insecure_tls::get_insecure_tls_cert()
+        }
+    };
+    ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Bind a TCP listener on exactly `addr`, IPv6 addresses do not also accept IPv4
+fn bind(addr: SocketAddr) -> std::io::Result<TcpListener> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(addr),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
+
+/// Tunnel listener serving EasyTier's websocket tunnel over TLS
+#[derive(Debug)]
+pub struct WssTunnelListener {
+    addr: url::Url,
+    cert_paths: Option<WssCertPaths>,
+    listener: Option<TcpListener>,
+    tls_config: Option<Arc<ServerConfig>>,
+    /// Handshakes of accepted connections still in progress
+    handshakes: JoinSet<Option<Box<dyn Tunnel>>>,
+}
+
+impl WssTunnelListener {
+    /// Create a listener for `addr`, fails if the certificate is only half configured
+    pub fn new(addr: url::Url) -> Result<Self, String> {
+        let cert_paths = WssCertPaths::from_url(&addr)?;
+        Ok(WssTunnelListener {
+            addr,
+            cert_paths,
+            listener: None,
+            tls_config: None,
+            handshakes: JoinSet::new(),
+        })
+    }
+
+    /// Certificate and key files the listener loads, None for a self-signed certificate
+    pub fn cert_paths(&self) -> Option<&WssCertPaths> {
+        self.cert_paths.as_ref()
+    }
+
+    fn bind_addr(&self) -> std::io::Result<SocketAddr> {
+        let host = self.addr.host().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("No host in {}", self.addr),
+            )
+        })?;
+        let ip = match host {
+            url::Host::Ipv4(ip) => ip.into(),
+            url::Host::Ipv6(ip) => ip.into(),
+            url::Host::Domain(domain) => domain.parse().map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Listener host must be an IP address: {}", domain),
+                )
+            })?,
+        };
+        Ok(SocketAddr::new(
+            ip,
+            self.addr.port_or_known_default().unwrap_or(443),
+        ))
+    }
+}
+
+/// Decode a websocket message into a tunnel packet, None for control messages
+fn packet_from_message(
+    msg: Result<Message, tokio_websockets::Error>,
+) -> Option<Result<ZCPacket, TunnelError>> {
+    let msg = match msg {
+        Ok(msg) => msg,
+        Err(e) => return Some(Err(std::io::Error::other(e).into())),
+    };
+    if msg.is_close() || msg.is_ping() || msg.is_pong() {
+        return None;
+    }
+    if !msg.is_binary() {
+        return Some(Err(TunnelError::InvalidPacket(format!(
+            "Unexpected websocket message: {:?}",
+            msg
+        ))));
+    }
+    Some(Ok(ZCPacket::new_from_buf(
+        BytesMut::from(&*msg.into_payload()),
+        ZCPacketType::WS,
+    )))
+}
+
+/// Run the TLS handshake and websocket upgrade of an accepted connection
+async fn handshake(
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+    peer: SocketAddr,
+    local_url: url::Url,
+) -> Result<Box<dyn Tunnel>, String> {
+    let stream = acceptor
+        .accept(stream)
+        .await
+        .map_err(|e| format!("TLS handshake failed: {}", e))?;
+    let websocket = ServerBuilder::new()
+        .limits(Limits::default().max_payload_len(Some(MAX_MESSAGE_LEN)))
+        .accept(stream)
+        .await
+        .map_err(|e| format!("Websocket upgrade failed: {}", e))?;
+
+    let remote_url: url::Url = format!("{}://{}", local_url.scheme(), peer)
+        .parse()
+        .map_err(|e| format!("Invalid remote address: {}", e))?;
+    let info = TunnelInfo {
+        tunnel_type: local_url.scheme().to_owned(),
+        local_addr: Some(local_url.into()),
+        remote_addr: Some(remote_url.into()),
+        ..Default::default()
+    };
+
+    let (write, read) = websocket.split();
+    Ok(Box::new(TunnelWrapper::new(
+        read.filter_map(|msg| async move { packet_from_message(msg) }),
+        write
+            .sink_map_err(|e| TunnelError::from(std::io::Error::other(e)))
+            .with(|packet: ZCPacket| async move {
+                Ok::<_, TunnelError>(Message::binary(packet.tunnel_payload_bytes().freeze()))
+            }),
+        Some(info),
+    )))
+}
+
+/// Handshake of one connection, None if it failed or timed out
+async fn handshake_with_timeout(
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+    peer: SocketAddr,
+    local_url: url::Url,
+) -> Option<Box<dyn Tunnel>> {
+    match tokio::time::timeout(
+        HANDSHAKE_TIMEOUT,
+        handshake(acceptor, stream, peer, local_url),
+    )
+    .await
+    {
+        Ok(Ok(tunnel)) => Some(tunnel),
+        Ok(Err(e)) => {
+            crate::warn!("[WSS_LISTENER] Connection from {}: {}", peer, e);
+            None
+        }
+        Err(_) => {
+            crate::warn!(
+                "[WSS_LISTENER] Handshake with {} timed out after {:?}",
+                peer,
+                HANDSHAKE_TIMEOUT
+            );
+            None
+        }
+    }
+}
+
+#[async_trait]
+impl TunnelListener for WssTunnelListener {
+    async fn listen(&mut self) -> Result<(), TunnelError> {
+        let config = server_config(self.cert_paths.as_ref())?;
+        self.listener = Some(bind(self.bind_addr()?)?);
+        self.tls_config = Some(Arc::new(config));
+        Ok(())
+    }
+
+    async fn accept(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        let WssTunnelListener {
+            addr,
+            listener,
+            tls_config,
+            handshakes,
+            ..
+        } = self;
+        let (Some(listener), Some(tls_config)) = (listener.as_ref(), tls_config.as_ref()) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "Listener is not bound",
+            )
+            .into());
+        };
+
+        let acceptor = TlsAcceptor::from(tls_config.clone());
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, peer) = accepted?;
+                    let _ = stream.set_nodelay(true);
+                    // A failed handshake only drops that connection
+                    handshakes.spawn(handshake_with_timeout(
+                        acceptor.clone(),
+                        stream,
+                        peer,
+                        addr.clone(),
+                    ));
+                }
+                Some(done) = handshakes.join_next() => {
+                    if let Ok(Some(tunnel)) = done {
+                        return Ok(tunnel);
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_url(&self) -> url::Url {
+        self.addr.clone()
+    }
+}
@@ -15,6 +15,24 @@ const DEFAULT_TIMEZONE_OFFSET_HOURS: i32 = 8;
 /// Default GeoIP database path in project resources
 const DEFAULT_GEOIP_DB_PATH: &str = "./resources/geoip2-cn.mmdb";
 
+/// Default interval between device retention passes
+const DEFAULT_DEVICE_RETENTION_CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// Default interval between checks that mark silent devices offline
+const DEFAULT_OFFLINE_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Default limit for database operations run while handling a heartbeat
+const DEFAULT_DB_STATEMENT_TIMEOUT_SECS: u64 = 30;
+
+/// Default time an idempotency key of a network instance run is remembered
+const DEFAULT_IDEMPOTENCY_KEY_TTL_SECS: u64 = 600;
+
+/// Default number of client IPs whose GeoIP location is cached
+const DEFAULT_GEOIP_CACHE_CAPACITY: usize = 1024;
+
+/// Default lifetime of a cached GeoIP location
+const DEFAULT_GEOIP_CACHE_TTL_SECS: u64 = 3600;
+
 /// Global timezone configuration
 ///
 /// This can be configured via environment variable CORTEX_TIMEZONE_OFFSET_HOURS
@@ -34,6 +52,70 @@ pub fn get_timezone() -> FixedOffset {
     *TIMEZONE
 }
 
+/// Get the GeoLite2-ASN database path
+///
+/// Configured via environment variable CORTEX_GEOIP_ASN_DB_PATH, ASN lookup is
+/// disabled when it is not set
+pub fn get_geoip_asn_db_path() -> Option<String> {
+    env::var("CORTEX_GEOIP_ASN_DB_PATH")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Get the certificate file served by `wss` listeners
+///
+/// Configured via environment variable CORTEX_WSS_CERT_PATH (PEM), `wss`
+/// listeners use a self-signed certificate when neither it nor the key is set
+pub fn get_wss_cert_path() -> Option<String> {
+    env::var("CORTEX_WSS_CERT_PATH")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Get the private key file of the `wss` listener certificate
+///
+/// Configured via environment variable CORTEX_WSS_KEY_PATH (PEM)
+pub fn get_wss_key_path() -> Option<String> {
+    env::var("CORTEX_WSS_KEY_PATH")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Get the message appended to every heartbeat rejection sent to devices
+///
+/// Configured via environment variable CORTEX_HEARTBEAT_REJECT_MESSAGE, e.g. whom
+/// to contact about a rejected device. Not set by default
+pub fn get_heartbeat_reject_message() -> Option<String> {
+    env::var("CORTEX_HEARTBEAT_REJECT_MESSAGE")
+        .ok()
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty())
+}
+
+/// Get the number of client IPs whose GeoIP location is cached
+///
+/// This can be configured via environment variable CORTEX_GEOIP_CACHE_CAPACITY
+/// Default is 1024, 0 disables the cache
+pub fn get_geoip_cache_capacity() -> usize {
+    env::var("CORTEX_GEOIP_CACHE_CAPACITY")
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_GEOIP_CACHE_CAPACITY)
+}
+
+/// Get how long a cached GeoIP location stays valid
+///
+/// This can be configured via environment variable CORTEX_GEOIP_CACHE_TTL_SECS
+/// Default is 3600 seconds
+pub fn get_geoip_cache_ttl() -> std::time::Duration {
+    let secs = env::var("CORTEX_GEOIP_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_GEOIP_CACHE_TTL_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
 /// Get the GeoIP database path
 ///
 /// This can be configured via environment variable CORTEX_GEOIP_DB_PATH
@@ -102,6 +184,69 @@ pub fn get_database_url() -> Option<String> {
     Some(DEFAULT_DATABASE_URL.to_string())
 }
 
+/// Get the statement timeout for database operations run while handling a heartbeat
+///
+/// This can be configured via environment variable CORTEX_DB_STATEMENT_TIMEOUT_SECS
+/// Default is 30 seconds, 0 disables the timeout
+pub fn get_db_statement_timeout() -> Option<std::time::Duration> {
+    let secs = env::var("CORTEX_DB_STATEMENT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_DB_STATEMENT_TIMEOUT_SECS);
+    (secs > 0).then(|| std::time::Duration::from_secs(secs))
+}
+
+/// Get how long the instance id created under an idempotency key is remembered
+///
+/// This can be configured via environment variable CORTEX_IDEMPOTENCY_KEY_TTL_SECS
+/// Default is 600 seconds
+pub fn get_idempotency_key_ttl() -> std::time::Duration {
+    let secs = env::var("CORTEX_IDEMPOTENCY_KEY_TTL_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_IDEMPOTENCY_KEY_TTL_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Get the retention period for offline device records
+///
+/// This can be configured via environment variable CORTEX_DEVICE_RETENTION_HOURS
+/// Retention cleanup is disabled unless a positive number of hours is set
+pub fn get_device_retention_period() -> Option<chrono::Duration> {
+    env::var("CORTEX_DEVICE_RETENTION_HOURS")
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|hours| *hours > 0)
+        .and_then(chrono::Duration::try_hours)
+}
+
+/// Get the interval between device retention passes
+///
+/// This can be configured via environment variable CORTEX_DEVICE_RETENTION_CHECK_INTERVAL_SECS
+/// Default is 3600 seconds
+pub fn get_device_retention_check_interval() -> std::time::Duration {
+    let secs = env::var("CORTEX_DEVICE_RETENTION_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_DEVICE_RETENTION_CHECK_INTERVAL_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Get the interval between checks that mark silent devices offline
+///
+/// This can be configured via environment variable CORTEX_OFFLINE_CHECK_INTERVAL_SECS
+/// Default is 60 seconds
+pub fn get_offline_check_interval() -> std::time::Duration {
+    let secs = env::var("CORTEX_OFFLINE_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_OFFLINE_CHECK_INTERVAL_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
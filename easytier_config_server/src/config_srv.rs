@@ -1,3 +1,9 @@
+//! 网络配置服务
+//!
+//! `NetworkConfigService` 是纯异步 API，可以直接在调用方自己的 tokio 运行时中使用，
+//! 不依赖 FFI 层的全局运行时；`ffi.rs` 中的阻塞包装只是它的一个使用者。
+
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -5,15 +11,25 @@ use easytier::launcher::NetworkConfig;
 // 移除未使用的导入
 use easytier::proto::rpc_types::controller::BaseController;
 use easytier::proto::web::*;
+use tokio::sync::broadcast;
 
-use crate::client_manager::session::{Location, Session};
-use crate::client_manager::ClientManager;
+use crate::client_manager::idempotency::IdempotencyCache;
+use crate::client_manager::session::{Location, Session, SessionThroughput};
+use crate::client_manager::storage::{AutoApprovalPolicy, DeviceStatusEvent};
+use crate::client_manager::{ClientManager, ListenerInfo, StartReport};
 // Removed DatabaseExt and ListNetworkProps - using direct database calls instead
+use crate::db::entities::devices::{DeviceStatus, DeviceType};
 use crate::db::OrgIdInDb;
 
 /// 网络配置服务，提供网络配置的管理功能
+///
+/// 所有方法都可以在任意 tokio 多线程运行时中直接 `await`，先调用 `start`
+/// 启动监听器，依赖会话的操作才可用。
 pub struct NetworkConfigService {
     client_mgr: Arc<ClientManager>,
+    device_events: std::sync::Mutex<broadcast::Receiver<DeviceStatusEvent>>,
+    /// 按幂等键记录已创建的网络实例，避免重试时重复创建
+    run_keys: IdempotencyCache,
 }
 
 /// RPC 错误转换为 anyhow::Error
@@ -28,6 +44,13 @@ pub struct NetworkInstanceIds {
     pub disabled_inst_ids: Vec<uuid::Uuid>,
 }
 
+/// 已连接客户端项
+#[derive(Debug, serde::Serialize)]
+pub struct ClientUrlItem {
+    pub organization_id: OrgIdInDb,
+    pub client_url: url::Url,
+}
+
 /// 设备信息项
 #[derive(Debug, serde::Serialize)]
 pub struct DeviceItem {
@@ -66,33 +89,548 @@ impl From<HeartbeatRequest> for SerializableHeartbeatRequest {
     }
 }
 
+/// 设备最近一次心跳的快照
+#[derive(Debug, serde::Serialize)]
+pub struct HeartbeatSnapshotItem {
+    pub client_url: url::Url,
+    pub info: SerializableHeartbeatRequest,
+    /// 距收到该心跳经过的毫秒数
+    pub age_ms: u64,
+}
+
 /// 设备列表响应
 #[derive(Debug, serde::Serialize)]
 pub struct DeviceList {
     pub devices: Vec<DeviceItem>,
 }
 
+/// 数据库中的设备记录，不含 network_config（其中包含网络密钥）
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DeviceRecord {
+    pub id: String,
+    pub name: String,
+    pub serial_number: String,
+    pub device_type: DeviceType,
+    pub model: Option<String>,
+    pub status: DeviceStatus,
+    pub organization_id: Option<String>,
+    pub last_heartbeat: Option<sea_orm::prelude::DateTimeWithTimeZone>,
+    pub network_instance_id: Option<String>,
+    pub network_disabled: Option<bool>,
+    pub virtual_ip: Option<u32>,
+    pub virtual_ip_network_length: Option<u8>,
+    pub created_at: sea_orm::prelude::DateTimeWithTimeZone,
+    pub updated_at: sea_orm::prelude::DateTimeWithTimeZone,
+}
+
+impl From<crate::db::entities::devices::Model> for DeviceRecord {
+    fn from(device: crate::db::entities::devices::Model) -> Self {
+        DeviceRecord {
+            id: device.id,
+            name: device.name,
+            serial_number: device.serial_number,
+            device_type: device.device_type,
+            model: device.model,
+            status: device.status,
+            organization_id: device.organization_id,
+            last_heartbeat: device.last_heartbeat,
+            network_instance_id: device.network_instance_id,
+            network_disabled: device.network_disabled,
+            virtual_ip: device.virtual_ip,
+            virtual_ip_network_length: device.virtual_ip_network_length,
+            created_at: device.created_at,
+            updated_at: device.updated_at,
+        }
+    }
+}
+
+/// 分页设备列表响应，`total` 为组织内设备总数
+#[derive(Debug, serde::Serialize)]
+pub struct DevicePage {
+    pub devices: Vec<DeviceRecord>,
+    pub total: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+/// 设备查询过滤条件，所有字段均为可选
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct DeviceFilter {
+    /// 按设备状态精确匹配
+    #[serde(default)]
+    pub status: Option<DeviceStatus>,
+    /// 按名称或序列号进行不区分大小写的子串匹配
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// NetworkConfigService 的完整配置，由单个 JSON 对象描述
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ServiceConfig {
+    /// 数据库连接地址，必填
+    pub db_url: String,
+    /// GeoIP 数据库路径，未指定时自动探测
+    #[serde(default)]
+    pub geoip_path: Option<String>,
+    /// 自动批准所有组织首次出现的设备
+    #[serde(default)]
+    pub auto_approve_all_orgs: bool,
+    /// 自动批准这些组织首次出现的设备
+    #[serde(default)]
+    pub auto_approve_orgs: Vec<OrgIdInDb>,
+    /// 首次出现的设备使用的设备类型，默认为 `Robot`
+    #[serde(default)]
+    pub default_device_type: Option<DeviceType>,
+    /// 对待批准和已拒绝设备的心跳返回拒绝原因，心跳仍会被记录
+    #[serde(default)]
+    pub reject_unapproved_devices: bool,
+    /// 附加在每个心跳拒绝原因后的提示，未指定时使用 CORTEX_HEARTBEAT_REJECT_MESSAGE
+    #[serde(default)]
+    pub reject_message: Option<String>,
+    /// 无法识别的配置项，只记录警告
+    #[serde(flatten)]
+    pub unknown: HashMap<String, serde_json::Value>,
+}
+
+impl ServiceConfig {
+    /// 解析 JSON 配置，缺少必填项时返回错误，无法识别的配置项记录警告后忽略
+    pub fn from_json(json: &str) -> Result<Self> {
+        let config: ServiceConfig = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("Invalid service config: {}", e))?;
+        if config.db_url.trim().is_empty() {
+            anyhow::bail!("Invalid service config: db_url must not be empty");
+        }
+
+        let mut unknown: Vec<_> = config.unknown.keys().collect();
+        unknown.sort();
+        for key in unknown {
+            crate::warn!("Ignoring unknown service config key: {}", key);
+        }
+        Ok(config)
+    }
+
+    /// 首次出现的设备的自动批准策略
+    pub fn auto_approval_policy(&self) -> AutoApprovalPolicy {
+        if self.auto_approve_all_orgs {
+            AutoApprovalPolicy::AllOrgs
+        } else if self.auto_approve_orgs.is_empty() {
+            AutoApprovalPolicy::Disabled
+        } else {
+            AutoApprovalPolicy::Orgs(self.auto_approve_orgs.iter().cloned().collect())
+        }
+    }
+}
+
+/// 转义 LIKE 模式中的通配符
+fn escape_like_pattern(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// 网络配置语义校验问题
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ConfigIssue {
+    pub field: String,
+    pub message: String,
+}
+
+/// 网络配置语义校验失败，包含所有发现的问题
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConfigValidationError {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl std::fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid network config:")?;
+        for issue in &self.issues {
+            write!(f, " {}: {};", issue.field, issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
+/// 采集单台设备网络信息的超时时间，超时的设备在拓扑中记录错误
+const TOPOLOGY_DEVICE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 组织网络拓扑中的节点，对应一台已连接设备
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TopologyNode {
+    pub device_id: uuid::Uuid,
+    pub hostname: Option<String>,
+    pub client_url: url::Url,
+    /// 设备在各运行中网络实例里的 EasyTier peer ID
+    pub peer_ids: Vec<u32>,
+    /// 虚拟 IPv4 地址，如 `10.126.126.1/24`
+    pub virtual_ipv4: Vec<String>,
+    /// 采集失败的原因，失败的设备仍作为节点返回，但没有边
+    pub error: Option<String>,
+}
+
+/// 组织网络拓扑中的边，表示两个 peer 之间的直连
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TopologyEdge {
+    pub from: uuid::Uuid,
+    /// 对端是本组织设备时为其设备 ID，否则为 None（如公共服务器）
+    pub to: Option<uuid::Uuid>,
+    pub to_peer_id: u32,
+    /// 各连接中最小的延迟，设备未上报时为 None
+    pub latency_ms: Option<f64>,
+}
+
+/// 组织内设备之间的连接拓扑
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct OrgTopology {
+    pub nodes: Vec<TopologyNode>,
+    pub edges: Vec<TopologyEdge>,
+}
+
+/// 从 `CollectNetworkInfoResponse` 中提取的单台设备网络信息
+#[derive(Debug, Default)]
+struct DeviceNetworkInfo {
+    peer_ids: Vec<u32>,
+    virtual_ipv4: Vec<String>,
+    /// 直连的对端 peer ID 及其延迟
+    direct_peers: Vec<(u32, Option<f64>)>,
+}
+
+/// 解析设备所有运行中网络实例的 peer 信息
+fn parse_device_network_info(network_info: &CollectNetworkInfoResponse) -> DeviceNetworkInfo {
+    let mut parsed = DeviceNetworkInfo::default();
+    let Ok(json_value) = serde_json::to_value(network_info) else {
+        return parsed;
+    };
+    let Some(instances) = json_value
+        .get("info")
+        .and_then(|info| info.get("map"))
+        .and_then(|map| map.as_object())
+    else {
+        return parsed;
+    };
+
+    for instance in instances.values() {
+        if !instance
+            .get("running")
+            .and_then(|r| r.as_bool())
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        let my_node_info = instance.get("my_node_info");
+        let my_peer_id = my_node_info
+            .and_then(|n| n.get("peer_id"))
+            .and_then(|id| id.as_u64())
+            .map(|id| id as u32);
+        if let Some(peer_id) = my_peer_id {
+            parsed.peer_ids.push(peer_id);
+        }
+
+        let virtual_ipv4 = my_node_info.and_then(|n| n.get("virtual_ipv4"));
+        let addr = virtual_ipv4
+            .and_then(|v| v.get("address"))
+            .and_then(|a| a.get("addr"))
+            .and_then(|a| a.as_u64());
+        let network_length = virtual_ipv4
+            .and_then(|v| v.get("network_length"))
+            .and_then(|l| l.as_u64());
+        if let (Some(addr), Some(network_length)) = (addr, network_length) {
+            parsed.virtual_ipv4.push(format!(
+                "{}/{}",
+                std::net::Ipv4Addr::from(addr as u32),
+                network_length
+            ));
+        }
+
+        let peers = instance
+            .get("peers")
+            .and_then(|p| p.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for peer in peers {
+            let Some(peer_id) = peer.get("peer_id").and_then(|id| id.as_u64()) else {
+                continue;
+            };
+            if Some(peer_id as u32) == my_peer_id {
+                continue;
+            }
+            let latency_ms = peer
+                .get("conns")
+                .and_then(|c| c.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|conn| conn.get("stats")?.get("latency_us")?.as_u64())
+                .min()
+                .map(|latency_us| latency_us as f64 / 1000.0);
+            parsed.direct_peers.push((peer_id as u32, latency_ms));
+        }
+    }
+
+    parsed
+}
+
+/// 网络配置校验结果，逐字段列出未通过校验的原因
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ConfigValidationReport {
+    /// 配置是否通过本地语义校验和设备端校验
+    pub valid: bool,
+    /// 未通过校验的字段及原因，valid 为 true 时为空
+    pub issues: Vec<ConfigIssue>,
+    /// 设备端校验通过后生成的 TOML 配置
+    pub toml_config: Option<String>,
+}
+
+/// 对网络配置进行跨字段语义校验，返回所有发现的问题
+pub fn check_network_config(config: &NetworkConfig) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    let mut issue = |field: &str, message: String| {
+        issues.push(ConfigIssue {
+            field: field.to_string(),
+            message,
+        })
+    };
+
+    // 关闭 DHCP 时必须指定合法的虚拟 IP
+    if config.dhcp == Some(false) {
+        match config.virtual_ipv4.as_deref().map(str::trim) {
+            None | Some("") => issue(
+                "virtual_ipv4",
+                "virtual IPv4 is required when DHCP is disabled".to_string(),
+            ),
+            Some(ip) => {
+                if ip.parse::<std::net::Ipv4Addr>().is_err() {
+                    issue(
+                        "virtual_ipv4",
+                        format!("'{}' is not a valid IPv4 address", ip),
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(len) = config.network_length {
+        if !(1..=32).contains(&len) {
+            issue(
+                "network_length",
+                format!("network length {} is out of range 1-32", len),
+            );
+        }
+    }
+
+    // 手动组网可以不带对等节点（仅等待其他节点连入），公共服务器地址留空时使用 EasyTier 默认服务器，
+    // 这里只检查填写了的地址是否合法
+    match config
+        .networking_method
+        .and_then(|m| NetworkingMethod::try_from(m).ok())
+    {
+        Some(NetworkingMethod::Manual) => {
+            for peer in &config.peer_urls {
+                if url::Url::parse(peer).is_err() {
+                    issue("peer_urls", format!("'{}' is not a valid URL", peer));
+                }
+            }
+        }
+        Some(NetworkingMethod::PublicServer) => {
+            if let Some(u) = config.public_server_url.as_deref().map(str::trim) {
+                if !u.is_empty() && url::Url::parse(u).is_err() {
+                    issue("public_server_url", format!("'{}' is not a valid URL", u));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    issues
+}
+
+/// 追加一行 CSV 记录，含逗号、引号或换行的字段用双引号包裹，内部引号双写
+fn push_csv_record(csv: &mut String, fields: &[&str]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
 impl NetworkConfigService {
     /// 创建新的网络配置服务，同时创建新的 ClientManager
     pub async fn new(db_url: &str, geoip_path: Option<String>) -> Result<Self> {
+        Self::new_with_auto_approval(db_url, geoip_path, AutoApprovalPolicy::Disabled).await
+    }
+
+    /// 创建网络配置服务，首次出现的设备按 `auto_approval` 策略自动批准
+    pub async fn new_with_auto_approval(
+        db_url: &str,
+        geoip_path: Option<String>,
+        auto_approval: AutoApprovalPolicy,
+    ) -> Result<Self> {
         let client_mgr = ClientManager::new(db_url, geoip_path)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to create ClientManager: {:?}", e))?;
+            .map_err(|e| anyhow::Error::new(e).context("Failed to create ClientManager"))?
+            .with_auto_approval(auto_approval)
+            .with_heartbeat_reject_message(crate::config::get_heartbeat_reject_message());
+
+        let device_events = client_mgr.storage().subscribe_device_events();
 
         Ok(Self {
             client_mgr: Arc::new(client_mgr),
+            device_events: std::sync::Mutex::new(device_events),
+            run_keys: IdempotencyCache::new(crate::config::get_idempotency_key_ttl()),
         })
     }
 
+    /// 按 `config` 创建网络配置服务，`config.db_url` 需为 SeaORM 连接地址
+    pub async fn from_config(config: ServiceConfig) -> Result<Self> {
+        let auto_approval = config.auto_approval_policy();
+        let service =
+            Self::new_with_auto_approval(&config.db_url, config.geoip_path, auto_approval).await?;
+        let service = match config.default_device_type {
+            Some(device_type) => service.with_default_device_type(device_type),
+            None => service,
+        };
+        let storage = service.client_mgr.storage();
+        storage.set_reject_unapproved_devices(config.reject_unapproved_devices);
+        if config.reject_message.is_some() {
+            storage.set_reject_message(config.reject_message);
+        }
+        Ok(service)
+    }
+
+    /// 首次出现的设备使用 `device_type` 作为设备类型，默认为 `Robot`
+    pub fn with_default_device_type(self, device_type: DeviceType) -> Self {
+        self.client_mgr
+            .storage()
+            .set_default_device_type(device_type);
+        self
+    }
+
     /// 启动网络配置服务的监听器
-    pub async fn start(&mut self, protocol: &str, port: u16) -> Result<()> {
+    pub async fn start(&mut self, protocol: &str, port: u16) -> Result<StartReport> {
         let client_mgr = Arc::get_mut(&mut self.client_mgr)
             .ok_or_else(|| anyhow::anyhow!("Cannot get mutable reference to ClientManager"))?;
 
+        // 保留 StartError 以便调用方区分具体的绑定失败原因
         client_mgr
             .start(protocol, port)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to start listener: {:?}", e))
+            .map_err(|e| e.context("Failed to start listener"))
+    }
+
+    /// 列出已启动的监听器
+    pub fn list_listeners(&self) -> Vec<ListenerInfo> {
+        self.client_mgr.list_listeners()
+    }
+
+    /// 重新加载 GeoIP 数据库，加载成功后新连接使用新数据库，失败时继续使用旧数据库
+    pub fn reload_geoip(&self, path: &str) -> Result<()> {
+        self.client_mgr.reload_geoip(path)
+    }
+
+    /// 取出自上次调用以来累积的设备状态变更事件
+    pub fn poll_device_events(&self) -> Vec<DeviceStatusEvent> {
+        let mut events = vec![];
+        let Ok(mut rx) = self.device_events.lock() else {
+            return events;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(event) => events.push(event),
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    crate::warn!("Dropped {} device status events", skipped);
+                }
+                Err(_) => break,
+            }
+        }
+        events
+    }
+
+    /// 获取设备当前会话的收发字节数
+    pub async fn get_device_throughput(
+        &self,
+        org_id: &OrgIdInDb,
+        device_id: &uuid::Uuid,
+    ) -> Result<SessionThroughput> {
+        let session = self.get_session_by_device_id(org_id, device_id).await?;
+        Ok(session.throughput())
+    }
+
+    /// 获取组织内所有已连接设备的收发字节数
+    pub async fn list_device_throughputs(
+        &self,
+        org_id: &OrgIdInDb,
+    ) -> Result<HashMap<uuid::Uuid, SessionThroughput>> {
+        self.ensure_listeners_started()?;
+        Ok(self.client_mgr.device_throughputs(org_id).await)
+    }
+
+    /// 订阅设备会话的心跳广播
+    pub async fn heartbeat_waiter(
+        &self,
+        org_id: &OrgIdInDb,
+        device_id: &uuid::Uuid,
+    ) -> Result<broadcast::Receiver<HeartbeatRequest>> {
+        let session = self.get_session_by_device_id(org_id, device_id).await?;
+        let waiter = session.data().read().await.heartbeat_waiter();
+        Ok(waiter)
+    }
+
+    /// 等待订阅者收到下一次心跳，超时返回 None
+    pub async fn next_heartbeat(
+        mut waiter: broadcast::Receiver<HeartbeatRequest>,
+        timeout: std::time::Duration,
+    ) -> Result<Option<HeartbeatRequest>> {
+        let recv = async {
+            loop {
+                match waiter.recv().await {
+                    Ok(req) => return Ok(req),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        crate::debug!("Skipped {} stale heartbeats", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        return Err(anyhow::anyhow!(
+                            "Session closed while waiting for heartbeat"
+                        ));
+                    }
+                }
+            }
+        };
+
+        match tokio::time::timeout(timeout, recv).await {
+            Ok(req) => req.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// 等待设备的下一次心跳，超时返回 None
+    pub async fn wait_for_heartbeat(
+        &self,
+        org_id: &OrgIdInDb,
+        device_id: &uuid::Uuid,
+        timeout: std::time::Duration,
+    ) -> Result<Option<HeartbeatRequest>> {
+        let waiter = self.heartbeat_waiter(org_id, device_id).await?;
+        Self::next_heartbeat(waiter, timeout).await
+    }
+
+    /// 检查监听器是否已启动，依赖会话的操作必须在 start 之后调用
+    fn ensure_listeners_started(&self) -> Result<()> {
+        if !self.client_mgr.is_running() {
+            return Err(anyhow::anyhow!(
+                "listeners not started, call start (network_config_service_singleton_start over FFI) first"
+            ));
+        }
+        Ok(())
     }
 
     /// 根据设备 ID 获取会话
@@ -101,6 +639,8 @@ impl NetworkConfigService {
         user_id: &OrgIdInDb,
         device_id: &uuid::Uuid,
     ) -> Result<Arc<Session>> {
+        self.ensure_listeners_started()?;
+
         let Some(result) = self
             .client_mgr
             .get_session_by_device_id(user_id, device_id)
@@ -117,12 +657,24 @@ impl NetworkConfigService {
     }
 
     /// 验证网络配置
+    ///
+    /// 本地语义校验未通过时不会联系设备，直接返回包含所有问题字段的结果；
+    /// 设备不在线或 RPC 失败时返回错误
     pub async fn validate_config(
         &self,
         user_id: &OrgIdInDb,
         device_id: &uuid::Uuid,
         config: NetworkConfig,
-    ) -> Result<ValidateConfigResponse> {
+    ) -> Result<ConfigValidationReport> {
+        let issues = check_network_config(&config);
+        if !issues.is_empty() {
+            return Ok(ConfigValidationReport {
+                valid: false,
+                issues,
+                toml_config: None,
+            });
+        }
+
         let result = self.get_session_by_device_id(user_id, device_id).await?;
 
         let c = result.scoped_rpc_client();
@@ -135,7 +687,33 @@ impl NetworkConfigService {
             )
             .await
             .map_err(convert_rpc_error)?;
-        Ok(ret)
+        Ok(ConfigValidationReport {
+            valid: true,
+            issues: vec![],
+            toml_config: Some(ret.toml_config),
+        })
+    }
+
+    /// 运行网络实例，携带幂等键
+    ///
+    /// 同一设备在幂等键有效期内使用相同的键重试时，直接返回首次创建的实例 ID，
+    /// 不会再创建新实例；`idempotency_key` 为 `None` 时等同于 `run_network_instance`
+    pub async fn run_network_instance_with_key(
+        &self,
+        org_id: &OrgIdInDb,
+        device_id: &uuid::Uuid,
+        config: NetworkConfig,
+        idempotency_key: Option<&str>,
+    ) -> Result<uuid::Uuid> {
+        let Some(key) = idempotency_key else {
+            return self.run_network_instance(org_id, device_id, config).await;
+        };
+        let scoped_key = format!("{}/{}/{}", org_id, device_id, key);
+        self.run_keys
+            .get_or_run(&scoped_key, || {
+                self.run_network_instance(org_id, device_id, config)
+            })
+            .await
     }
 
     /// 运行网络实例
@@ -150,19 +728,7 @@ impl NetworkConfigService {
 
         let result = self.get_session_by_device_id(org_id, device_id).await?;
 
-        let c = result.scoped_rpc_client();
-        let resp = c
-            .run_network_instance(
-                BaseController::default(),
-                RunNetworkInstanceRequest {
-                    inst_id: None,
-                    config: Some(config.clone()),
-                },
-            )
-            .await
-            .map_err(convert_rpc_error)?;
-
-        let inst_id: uuid::Uuid = resp.inst_id.unwrap_or_default().into();
+        let inst_id = result.run_network_instance(config.clone()).await?;
 
         let db = self.client_mgr.db().await;
         // Update device with network configuration (ONE network per device)
@@ -379,6 +945,8 @@ impl NetworkConfigService {
 
     /// 列出设备
     pub async fn list_devices(&self, user_id: &OrgIdInDb) -> Result<DeviceList> {
+        self.ensure_listeners_started()?;
+
         let client_urls = self
             .client_mgr
             .list_devices_by_organization_id(user_id)
@@ -387,7 +955,10 @@ impl NetworkConfigService {
         let mut devices = vec![];
         for item in client_urls.iter() {
             let client_url = item.clone();
-            let heartbeat_request = self.client_mgr.get_heartbeat_requests(&client_url).await;
+            let heartbeat_request = self
+                .client_mgr
+                .get_heartbeat_requests(&client_url, None)
+                .await;
             let location = self.client_mgr.get_device_location(&client_url).await;
             devices.push(DeviceItem {
                 client_url: Some(client_url),
@@ -399,6 +970,435 @@ impl NetworkConfigService {
         Ok(DeviceList { devices })
     }
 
+    /// 获取组织内已连接设备之间的网络拓扑
+    ///
+    /// 并发采集每台设备的 peer 列表；单台设备采集失败或超时只记录在对应节点的
+    /// `error` 中，不影响其它设备
+    pub async fn get_org_topology(&self, org_id: &OrgIdInDb) -> Result<OrgTopology> {
+        self.ensure_listeners_started()?;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for (token, session) in self.client_mgr.organization_sessions(org_id).await {
+            tasks.spawn(async move {
+                let hostname = session.get_heartbeat_req().await.map(|req| req.hostname);
+                let c = session.scoped_rpc_client();
+                let ret = tokio::time::timeout(
+                    TOPOLOGY_DEVICE_TIMEOUT,
+                    c.collect_network_info(
+                        BaseController::default(),
+                        CollectNetworkInfoRequest { inst_ids: vec![] },
+                    ),
+                )
+                .await;
+                let info = match ret {
+                    Ok(Ok(network_info)) => Ok(parse_device_network_info(&network_info)),
+                    Ok(Err(e)) => Err(convert_rpc_error(e).to_string()),
+                    Err(_) => Err(format!(
+                        "timed out after {} ms",
+                        TOPOLOGY_DEVICE_TIMEOUT.as_millis()
+                    )),
+                };
+                (token, hostname, info)
+            });
+        }
+
+        let mut nodes = vec![];
+        let mut device_peers = vec![];
+        while let Some(joined) = tasks.join_next().await {
+            let (token, hostname, info) = match joined {
+                Ok(result) => result,
+                Err(e) => {
+                    crate::warn!("Topology collection task failed: {}", e);
+                    continue;
+                }
+            };
+            let (info, error) = match info {
+                Ok(info) => (info, None),
+                Err(e) => {
+                    crate::warn!(
+                        "Failed to collect network info of device {} for topology: {}",
+                        token.device_id,
+                        e
+                    );
+                    (DeviceNetworkInfo::default(), Some(e))
+                }
+            };
+            nodes.push(TopologyNode {
+                device_id: token.device_id,
+                hostname,
+                client_url: token.client_url,
+                peer_ids: info.peer_ids,
+                virtual_ipv4: info.virtual_ipv4,
+                error,
+            });
+            device_peers.push((token.device_id, info.direct_peers));
+        }
+
+        let peer_owners: HashMap<u32, uuid::Uuid> = nodes
+            .iter()
+            .flat_map(|node| node.peer_ids.iter().map(|id| (*id, node.device_id)))
+            .collect();
+        let mut edges: Vec<TopologyEdge> = device_peers
+            .into_iter()
+            .flat_map(|(device_id, peers)| {
+                let peer_owners = &peer_owners;
+                peers
+                    .into_iter()
+                    .map(move |(peer_id, latency_ms)| TopologyEdge {
+                        from: device_id,
+                        to: peer_owners.get(&peer_id).copied(),
+                        to_peer_id: peer_id,
+                        latency_ms,
+                    })
+            })
+            .collect();
+
+        nodes.sort_by_key(|node| node.device_id);
+        edges.sort_by_key(|edge| (edge.from, edge.to_peer_id));
+        Ok(OrgTopology { nodes, edges })
+    }
+
+    /// 列出所有组织的已连接客户端 URL
+    pub fn list_all_clients(&self) -> Result<Vec<ClientUrlItem>> {
+        self.ensure_listeners_started()?;
+
+        Ok(self
+            .client_mgr
+            .list_all_clients()
+            .into_iter()
+            .map(|(organization_id, client_url)| ClientUrlItem {
+                organization_id,
+                client_url,
+            })
+            .collect())
+    }
+
+    /// 一次性获取所有会话最近的心跳，以设备 ID 为键
+    ///
+    /// 同一设备有多个会话（如同时通过 IPv4 和 IPv6 连接）时保留最新的心跳
+    pub async fn snapshot_heartbeats(&self) -> Result<BTreeMap<String, HeartbeatSnapshotItem>> {
+        self.ensure_listeners_started()?;
+
+        let mut snapshot = BTreeMap::new();
+        for (client_url, req, received_at) in self.client_mgr.snapshot_all_heartbeats().await {
+            let Some(device_id) = req.machine_id.map(|id| uuid::Uuid::from(id).to_string()) else {
+                continue;
+            };
+            let age_ms = received_at.elapsed().as_millis() as u64;
+            let item = HeartbeatSnapshotItem {
+                client_url,
+                info: SerializableHeartbeatRequest::from(req),
+                age_ms,
+            };
+            match snapshot.entry(device_id) {
+                std::collections::btree_map::Entry::Vacant(entry) => {
+                    entry.insert(item);
+                }
+                std::collections::btree_map::Entry::Occupied(mut entry) => {
+                    if age_ms < entry.get().age_ms {
+                        entry.insert(item);
+                    }
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// 列出尚未应用的数据库迁移名称（只读，不执行迁移）
+    pub async fn pending_migrations(&self) -> Result<Vec<String>> {
+        Ok(self.client_mgr.storage().db().pending_migrations().await?)
+    }
+
+    /// 回滚最近应用的 `steps` 个数据库迁移
+    pub async fn rollback_migrations(&self, steps: u32) -> Result<()> {
+        Ok(self
+            .client_mgr
+            .storage()
+            .db()
+            .rollback_migrations(steps)
+            .await?)
+    }
+
+    /// 分页列出组织内的设备记录（从数据库读取，不依赖会话）
+    pub async fn list_devices_paginated(
+        &self,
+        org_id: &OrgIdInDb,
+        offset: u64,
+        limit: u64,
+    ) -> Result<DevicePage> {
+        use crate::db::entities::devices;
+        use sea_orm::{
+            ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+        };
+
+        if limit == 0 {
+            return Err(anyhow::anyhow!("limit must be greater than 0"));
+        }
+
+        let db = self.client_mgr.db().await;
+        let query = devices::Entity::find()
+            .filter(devices::Column::OrganizationId.eq(org_id.as_str()))
+            .order_by_asc(devices::Column::CreatedAt)
+            .order_by_asc(devices::Column::Id);
+
+        let total = query.clone().paginate(db.orm(), limit).num_items().await?;
+        let devices = query.offset(offset).limit(limit).all(db.orm()).await?;
+
+        Ok(DevicePage {
+            devices: devices.into_iter().map(DeviceRecord::from).collect(),
+            total,
+            offset,
+            limit,
+        })
+    }
+
+    /// 导出组织内所有设备为 RFC 4180 CSV
+    ///
+    /// 列为 id、name、serial_number、device_type、status、last_heartbeat、created_at，
+    /// 时间使用 RFC 3339 格式，没有心跳时 last_heartbeat 为空
+    pub async fn export_devices_csv(&self, org_id: &OrgIdInDb) -> Result<String> {
+        use crate::db::entities::devices;
+        use sea_orm::{ActiveEnum, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+        let db = self.client_mgr.db().await;
+        let devices = devices::Entity::find()
+            .filter(devices::Column::OrganizationId.eq(org_id.as_str()))
+            .order_by_asc(devices::Column::CreatedAt)
+            .order_by_asc(devices::Column::Id)
+            .all(db.orm())
+            .await?;
+
+        let mut csv = String::new();
+        push_csv_record(
+            &mut csv,
+            &[
+                "id",
+                "name",
+                "serial_number",
+                "device_type",
+                "status",
+                "last_heartbeat",
+                "created_at",
+            ],
+        );
+        for device in devices {
+            push_csv_record(
+                &mut csv,
+                &[
+                    &device.id,
+                    &device.name,
+                    &device.serial_number,
+                    &device.device_type.to_value(),
+                    &device.status.to_value(),
+                    &device
+                        .last_heartbeat
+                        .map(|time| time.to_rfc3339())
+                        .unwrap_or_default(),
+                    &device.created_at.to_rfc3339(),
+                ],
+            );
+        }
+        Ok(csv)
+    }
+
+    /// 按过滤条件查询组织内的设备记录，过滤在数据库中完成，结果不含 network_config
+    pub async fn query_devices(
+        &self,
+        org_id: &OrgIdInDb,
+        filter: &DeviceFilter,
+    ) -> Result<Vec<DeviceRecord>> {
+        use crate::db::entities::devices;
+        use sea_orm::sea_query::{Expr, Func};
+        use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
+
+        let mut query =
+            devices::Entity::find().filter(devices::Column::OrganizationId.eq(org_id.as_str()));
+
+        if let Some(status) = &filter.status {
+            query = query.filter(devices::Column::Status.eq(status.clone()));
+        }
+
+        if let Some(name) = filter.name.as_deref().filter(|n| !n.is_empty()) {
+            let pattern = format!("%{}%", escape_like_pattern(&name.to_lowercase()));
+            query = query.filter(
+                Condition::any()
+                    .add(
+                        Expr::expr(Func::lower(Expr::col(devices::Column::Name)))
+                            .like(pattern.as_str()),
+                    )
+                    .add(
+                        Expr::expr(Func::lower(Expr::col(devices::Column::SerialNumber)))
+                            .like(pattern.as_str()),
+                    ),
+            );
+        }
+
+        let db = self.client_mgr.db().await;
+        let devices = query
+            .order_by_asc(devices::Column::CreatedAt)
+            .order_by_asc(devices::Column::Id)
+            .all(db.orm())
+            .await?;
+        Ok(devices.into_iter().map(DeviceRecord::from).collect())
+    }
+
+    /// 按状态统计组织内的设备数量（使用 GROUP BY 聚合，不加载设备记录）
+    pub async fn count_devices_by_status(
+        &self,
+        org_id: &OrgIdInDb,
+    ) -> Result<HashMap<DeviceStatus, u64>> {
+        use crate::db::entities::devices;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+
+        let db = self.client_mgr.db().await;
+        let rows: Vec<(DeviceStatus, i64)> = devices::Entity::find()
+            .select_only()
+            .column(devices::Column::Status)
+            .column_as(devices::Column::Id.count(), "count")
+            .filter(devices::Column::OrganizationId.eq(org_id.as_str()))
+            .group_by(devices::Column::Status)
+            .into_tuple()
+            .all(db.orm())
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(status, count)| (status, count as u64))
+            .collect())
+    }
+
+    /// 在一个事务中批量设置设备状态，返回更新的行数
+    ///
+    /// 任一设备不属于该组织时返回错误，不做任何修改
+    pub async fn bulk_set_device_status(
+        &self,
+        org_id: &OrgIdInDb,
+        device_ids: &[uuid::Uuid],
+        status: DeviceStatus,
+    ) -> Result<u64> {
+        use crate::db::entities::devices;
+        use sea_orm::sea_query::Expr;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect, TransactionTrait};
+
+        let ids: std::collections::BTreeSet<String> =
+            device_ids.iter().map(|id| id.to_string()).collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let db = self.client_mgr.db().await;
+        let txn = db.orm().begin().await?;
+
+        let found: Vec<String> = devices::Entity::find()
+            .select_only()
+            .column(devices::Column::Id)
+            .filter(devices::Column::Id.is_in(ids.iter().cloned()))
+            .filter(devices::Column::OrganizationId.eq(org_id.as_str()))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+        if found.len() != ids.len() {
+            let missing: Vec<&String> = ids.iter().filter(|id| !found.contains(id)).collect();
+            return Err(anyhow::anyhow!(
+                "Devices not found in organization {}: {:?}",
+                org_id,
+                missing
+            ));
+        }
+
+        let now: sea_orm::prelude::DateTimeWithTimeZone = chrono::Utc::now().into();
+        let result = devices::Entity::update_many()
+            .col_expr(devices::Column::Status, Expr::value(status))
+            .col_expr(devices::Column::PreviousStatus, Expr::value(None::<String>))
+            .col_expr(devices::Column::UpdatedAt, Expr::value(now))
+            .filter(devices::Column::Id.is_in(ids))
+            .filter(devices::Column::OrganizationId.eq(org_id.as_str()))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// 断开设备当前的会话，返回是否找到了会话
+    pub async fn disconnect_device(
+        &self,
+        org_id: &OrgIdInDb,
+        device_id: &uuid::Uuid,
+    ) -> Result<bool> {
+        self.ensure_listeners_started()?;
+
+        Ok(self
+            .client_mgr
+            .close_device_session(org_id, device_id)
+            .await)
+    }
+
+    /// 删除设备记录，设备存在活动会话时先关闭会话
+    pub async fn delete_device(&self, org_id: &OrgIdInDb, device_id: &uuid::Uuid) -> Result<()> {
+        use crate::db::entities::devices;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let db = self.client_mgr.db().await;
+        // 只删除属于该组织的设备
+        let device = devices::Entity::find_by_id(device_id.to_string())
+            .filter(devices::Column::OrganizationId.eq(org_id.as_str()))
+            .one(db.orm())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Device not found: {}", device_id))?;
+
+        self.client_mgr
+            .close_device_session(org_id, device_id)
+            .await;
+
+        devices::Entity::delete_by_id(device.id)
+            .exec(db.orm())
+            .await?;
+        Ok(())
+    }
+
+    /// 检查组织是否存在
+    pub async fn organization_exists(&self, org_id: &OrgIdInDb) -> Result<bool> {
+        use crate::db::entities::organizations;
+        use sea_orm::EntityTrait;
+
+        let db = self.client_mgr.db().await;
+        let organization = organizations::Entity::find_by_id(org_id.clone())
+            .one(db.orm())
+            .await?;
+        Ok(organization.is_some())
+    }
+
+    /// 创建组织，组织已存在时直接返回成功（幂等）
+    pub async fn create_organization(&self, org_id: &OrgIdInDb, name: &str) -> Result<()> {
+        use crate::db::entities::organizations;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        if self.organization_exists(org_id).await? {
+            return Ok(());
+        }
+
+        let db = self.client_mgr.db().await;
+        let now = chrono::Utc::now();
+        let result = organizations::ActiveModel {
+            id: Set(org_id.clone()),
+            name: Set(name.to_string()),
+            status: Set(organizations::OrganizationStatus::Active),
+            created_at: Set(now.into()),
+            updated_at: Set(now.into()),
+            ..Default::default()
+        }
+        .insert(db.orm())
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            // 并发创建时插入可能因主键冲突失败，此时组织已存在
+            Err(_) if self.organization_exists(org_id).await? => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// 更新网络状态
     pub async fn update_network_state(
         &self,
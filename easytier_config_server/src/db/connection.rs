@@ -1,7 +1,21 @@
 //! Database connection management
 
 use crate::{error, info};
-use sea_orm::{ConnectionTrait, Database as SeaOrmDatabase, DatabaseConnection, DbErr};
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database as SeaOrmDatabase, DatabaseConnection, DbErr,
+};
+use std::time::Duration;
+
+/// Connection pool sizing and timeouts, `None` keeps the SeaORM default
+#[derive(Debug, Clone, Default)]
+pub struct PoolOptions {
+    /// Maximum number of pooled connections
+    pub max_connections: Option<u32>,
+    /// How long an operation waits for a free connection before failing
+    pub acquire_timeout: Option<Duration>,
+    /// How long an operation wrapped in `Database::with_statement_timeout` may run
+    pub statement_timeout: Option<Duration>,
+}
 
 /// Establish SeaORM database connection
 pub async fn establish_connection(database_url: &str) -> Result<DatabaseConnection, DbErr> {
@@ -18,6 +32,31 @@ pub async fn establish_connection(database_url: &str) -> Result<DatabaseConnecti
     Ok(orm_conn)
 }
 
+/// Establish SeaORM database connection with explicit pool options
+pub async fn establish_connection_with_pool_options(
+    database_url: &str,
+    pool_options: &PoolOptions,
+) -> Result<DatabaseConnection, DbErr> {
+    info!("Connecting to MySQL database with SeaORM...");
+
+    let mut options = ConnectOptions::new(database_url);
+    if let Some(max_connections) = pool_options.max_connections {
+        options.max_connections(max_connections);
+    }
+    if let Some(acquire_timeout) = pool_options.acquire_timeout {
+        options.acquire_timeout(acquire_timeout);
+    }
+
+    let orm_conn = SeaOrmDatabase::connect(options).await.map_err(|e| {
+        error!("Failed to create SeaORM connection: {}", e);
+        e
+    })?;
+
+    info!("Successfully connected to MySQL database");
+
+    Ok(orm_conn)
+}
+
 /// Establish SeaORM database connection optimized for testing
 pub async fn establish_test_connection(database_url: &str) -> Result<DatabaseConnection, DbErr> {
     info!("Connecting to test MySQL database with SeaORM...");
@@ -15,7 +15,11 @@ pub enum DeviceType {
 }
 
 /// Device status enumeration
-#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+///
+/// Serialized with the variant name ("Pending"), stored with the lowercase
+/// string value ("pending"). Both spellings are accepted case-insensitively
+/// when parsing or deserializing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "device_status")]
 pub enum DeviceStatus {
     // Registration states
@@ -39,6 +43,24 @@ pub enum DeviceStatus {
     Disabled,
 }
 
+impl std::str::FromStr for DeviceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|status| status.to_value().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown device status: {}", s))
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl DeviceStatus {
     /// Check if device is approved (can participate in networks)
     pub fn is_approved(&self) -> bool {
@@ -85,6 +107,10 @@ pub struct Model {
     #[sea_orm(default_value = "pending")]
     pub status: DeviceStatus,
 
+    /// Status before the device was marked offline, restored when it reconnects
+    #[sea_orm(nullable)]
+    pub previous_status: Option<DeviceStatus>,
+
     #[sea_orm(column_type = "Json", nullable)]
     pub capabilities: Option<serde_json::Value>,
 
@@ -0,0 +1,52 @@
+//! Migration to remember the status a device had before it was marked offline
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Devices::Table)
+                    .add_column(
+                        ColumnDef::new(Devices::PreviousStatus)
+                            .enumeration(
+                                Alias::new("device_status"),
+                                [
+                                    Alias::new("pending"),
+                                    Alias::new("rejected"),
+                                    Alias::new("online"),
+                                    Alias::new("offline"),
+                                    Alias::new("busy"),
+                                    Alias::new("maintenance"),
+                                    Alias::new("disabled"),
+                                ],
+                            )
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Devices::Table)
+                    .drop_column(Devices::PreviousStatus)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Devices {
+    Table,
+    PreviousStatus,
+}
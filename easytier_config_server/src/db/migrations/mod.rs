@@ -6,6 +6,7 @@ pub mod m20240101_000002_create_devices_table;
 pub mod m20240101_000005_create_organizations_table;
 pub mod m20240101_000007_drop_network_configs_table;
 pub mod m20240101_000008_update_device_status_enum;
+pub mod m20240101_000010_add_device_previous_status;
 
 pub struct Migrator;
 
@@ -17,6 +18,7 @@ impl MigratorTrait for Migrator {
             Box::new(m20240101_000005_create_organizations_table::Migration),
             Box::new(m20240101_000007_drop_network_configs_table::Migration),
             Box::new(m20240101_000008_update_device_status_enum::Migration),
+            Box::new(m20240101_000010_add_device_previous_status::Migration),
         ]
     }
 }
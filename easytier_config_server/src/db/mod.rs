@@ -6,17 +6,51 @@ pub mod connection;
 pub mod entities;
 pub mod migrations;
 
-use sea_orm::{DatabaseConnection, DbErr};
+use easytier_common::CortexErrorCode;
+use sea_orm::{ConnAcquireErr, DatabaseConnection, DbErr};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+
+pub use connection::PoolOptions;
 
 /// Organization ID type (String UUID)
 pub type OrgIdInDb = String;
 
+/// Message reported when an operation timed out waiting for a pooled connection
+pub const POOL_EXHAUSTED_MSG: &str = "database pool exhausted";
+
+/// Whether `err` is a pool acquire timeout rather than a failed query
+pub fn is_pool_exhausted(err: &DbErr) -> bool {
+    matches!(err, DbErr::ConnectionAcquire(ConnAcquireErr::Timeout))
+}
+
+/// Message of the error returned when an operation exceeds the statement timeout
+pub const STATEMENT_TIMEOUT_MSG: &str = "database statement timeout";
+
+/// Whether `err` was returned because an operation exceeded the statement timeout
+pub fn is_statement_timeout(err: &DbErr) -> bool {
+    matches!(err, DbErr::Custom(msg) if msg.starts_with(STATEMENT_TIMEOUT_MSG))
+}
+
+/// Error code of a database error, pool exhaustion and statement timeouts are reported separately
+pub fn db_error_code(err: &DbErr) -> CortexErrorCode {
+    if is_pool_exhausted(err) {
+        CortexErrorCode::PoolExhausted
+    } else if is_statement_timeout(err) {
+        CortexErrorCode::StatementTimeout
+    } else {
+        CortexErrorCode::DbError
+    }
+}
+
 /// Database connection wrapper
 #[derive(Debug, Clone)]
 pub struct Database {
     /// SeaORM database connection
     pub orm_conn: Arc<DatabaseConnection>,
+    /// Limit applied by `with_statement_timeout`, `None` waits indefinitely
+    statement_timeout: Option<Duration>,
 }
 
 impl Database {
@@ -26,6 +60,21 @@ impl Database {
 
         Ok(Self {
             orm_conn: Arc::new(orm_conn),
+            statement_timeout: None,
+        })
+    }
+
+    /// Create a new database instance with explicit pool sizing and timeouts
+    pub async fn new_with_pool_options(
+        database_url: &str,
+        pool_options: &PoolOptions,
+    ) -> Result<Self, DbErr> {
+        let orm_conn =
+            connection::establish_connection_with_pool_options(database_url, pool_options).await?;
+
+        Ok(Self {
+            orm_conn: Arc::new(orm_conn),
+            statement_timeout: pool_options.statement_timeout,
         })
     }
 
@@ -35,6 +84,7 @@ impl Database {
 
         Ok(Self {
             orm_conn: Arc::new(orm_conn),
+            statement_timeout: None,
         })
     }
 
@@ -42,4 +92,75 @@ impl Database {
     pub fn orm(&self) -> &DatabaseConnection {
         &self.orm_conn
     }
+
+    /// Statement timeout applied by `with_statement_timeout`
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        self.statement_timeout
+    }
+
+    /// Run a database operation, failing with a statement timeout error once it
+    /// takes longer than the configured limit
+    ///
+    /// The limit is enforced on the client: the operation is dropped at the
+    /// timeout so its caller is no longer blocked, but a statement already sent
+    /// keeps running on the server until it completes. Statements completed
+    /// before the timeout stay applied, operations writing several statements
+    /// must run them in a transaction, which is rolled back when dropped.
+    pub async fn with_statement_timeout<T, E, F>(&self, operation: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<DbErr>,
+    {
+        let Some(timeout) = self.statement_timeout else {
+            return operation.await;
+        };
+        match tokio::time::timeout(timeout, operation).await {
+            Ok(result) => result,
+            Err(_) => Err(DbErr::Custom(format!(
+                "{} after {} ms",
+                STATEMENT_TIMEOUT_MSG,
+                timeout.as_millis()
+            ))
+            .into()),
+        }
+    }
+
+    /// Names of the migrations not yet applied to this database, oldest first
+    ///
+    /// Read-only, no migration is run.
+    pub async fn pending_migrations(&self) -> Result<Vec<String>, DbErr> {
+        use migrations::Migrator;
+        use sea_orm_migration::{MigratorTrait, SchemaManager};
+
+        // get_pending_migrations creates the tracking table when it is missing
+        let tracking_table = Migrator::migration_table_name().to_string();
+        if !SchemaManager::new(self.orm())
+            .has_table(&tracking_table)
+            .await?
+        {
+            return Ok(Migrator::migrations()
+                .iter()
+                .map(|migration| migration.name().to_string())
+                .collect());
+        }
+
+        Ok(Migrator::get_pending_migrations(self.orm())
+            .await?
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect())
+    }
+
+    /// Roll back the most recently applied migration
+    pub async fn rollback_last_migration(&self) -> Result<(), DbErr> {
+        self.rollback_migrations(1).await
+    }
+
+    /// Roll back the `steps` most recently applied migrations, newest first
+    pub async fn rollback_migrations(&self, steps: u32) -> Result<(), DbErr> {
+        use migrations::Migrator;
+        use sea_orm_migration::MigratorTrait;
+
+        Migrator::down(self.orm(), Some(steps)).await
+    }
 }
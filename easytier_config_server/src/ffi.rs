@@ -1,14 +1,22 @@
 //! 简化的 FFI 接口，使用单例模式在 Golang 中安全地使用 NetworkConfigService
 
 use once_cell::sync::Lazy;
-use std::ffi::{c_char, CStr, CString};
+use std::ffi::{c_char, c_int, CStr, CString};
 use std::sync::Arc;
 use urlencoding::encode;
 use uuid::Uuid;
 
-use crate::config_srv::NetworkConfigService;
-use crate::db::OrgIdInDb;
+use crate::client_manager;
+use crate::config_srv::{
+    ConfigValidationError, DeviceFilter, NetworkConfigService, SerializableHeartbeatRequest,
+    ServiceConfig,
+};
+use crate::db::entities::devices::DeviceStatus;
+use crate::db::{db_error_code, OrgIdInDb, POOL_EXHAUSTED_MSG};
 use easytier::launcher::NetworkConfig;
+use easytier_common::{
+    enter_request_scope, register_shutdown_hook, set_error, to_c_string_lossy, CortexErrorCode,
+};
 
 // 全局 NetworkConfigService 单例
 static NETWORK_CONFIG_SERVICE: Lazy<
@@ -34,35 +42,90 @@ impl RuntimeManager {
     fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
         self.runtime.block_on(future)
     }
+
+    /// 获取 runtime 句柄，长时间等待时可以先释放管理器锁再 block_on
+    fn handle(&self) -> tokio::runtime::Handle {
+        self.runtime.handle().clone()
+    }
 }
 
 static RUNTIME_MANAGER: Lazy<tokio::sync::Mutex<RuntimeManager>> =
     Lazy::new(|| tokio::sync::Mutex::new(RuntimeManager::new()));
 
 /// 将 Go 的 DSN 字符串转换为 SeaORM 可用的连接字符串
+///
+/// 以最后一个 '@' 分隔凭据和地址，密码中可以包含 '@'、':' 和 '/'。
+/// DSN 被拆分为 user、pass、host:port、db、params 后重新组装，
+/// Go 驱动特有的参数（如 parseTime）会被转换或丢弃。
 pub fn convert_go_dsn_to_seaorm(dsn: &str) -> Result<String, String> {
-    let parts: Vec<&str> = dsn.split('@').collect();
-    if parts.len() != 2 {
-        return Err("Invalid DSN: Must contain exactly one '@'".to_string());
-    }
-
-    let (user_pass, host_db_params) = (parts[0], parts[1]);
+    let (user_pass, address) = dsn
+        .rsplit_once('@')
+        .ok_or_else(|| "Invalid DSN: Missing '@'".to_string())?;
 
     // Handle username:password encoding
-    let user_pass_encoded = if user_pass.contains(':') {
-        let (user, pass) = user_pass.split_once(':').unwrap();
-        format!("{user}:{}", encode(pass))
+    let user_pass_encoded = if let Some((user, pass)) = user_pass.split_once(':') {
+        format!("{}:{}", encode(user), encode(pass))
     } else {
-        user_pass.to_string()
+        encode(user_pass).into_owned()
+    };
+
+    // Split tcp(host:port)/db?params into components
+    let (address, params) = match address.split_once('?') {
+        Some((address, params)) => (address, Some(params)),
+        None => (address, None),
     };
+    let (host, db) = address.split_once('/').unwrap_or((address, ""));
+    let host = host
+        .strip_prefix("tcp(")
+        .and_then(|h| h.strip_suffix(')'))
+        .unwrap_or(host);
+    if host.is_empty() {
+        return Err("Invalid DSN: Missing host".to_string());
+    }
 
-    // Clean up host:port/db?params
-    let cleaned = host_db_params
-        .replace("tcp(", "")
-        .replace(")", "")
-        .replace(")/", "/");
+    let query = params
+        .unwrap_or_default()
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            translate_dsn_param(key, value)
+                .map(|(key, value)| format!("{}={}", key, encode(&value)))
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut url = format!("mysql://{user_pass_encoded}@{host}/{db}");
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query);
+    }
+    Ok(url)
+}
 
-    Ok(format!("mysql://{user_pass_encoded}@{cleaned}"))
+/// 将 Go MySQL 驱动的 DSN 参数转换为 sqlx 参数，不支持的参数返回 None
+fn translate_dsn_param(key: &str, value: &str) -> Option<(&'static str, String)> {
+    match key {
+        "charset" => Some((
+            "charset",
+            value.split(',').next().unwrap_or(value).to_string(),
+        )),
+        "collation" => Some(("collation", value.to_string())),
+        "tls" => {
+            let ssl_mode = match value {
+                "true" => "VERIFY_IDENTITY",
+                "skip-verify" => "REQUIRED",
+                "preferred" => "PREFERRED",
+                "false" => "DISABLED",
+                _ => return None,
+            };
+            Some(("ssl-mode", ssl_mode.to_string()))
+        }
+        _ => {
+            crate::debug!("Dropping unsupported DSN parameter: {}", key);
+            None
+        }
+    }
 }
 
 //
@@ -71,23 +134,86 @@ pub fn convert_go_dsn_to_seaorm(dsn: &str) -> Result<String, String> {
 
 /// 创建 NetworkConfigService 单例
 ///
+/// 已弃用：请使用 `create_network_config_service_singleton_json`
+///
 /// # Safety
 ///
 /// 这个函数是不安全的，因为它接受原始指针作为参数
+#[deprecated(note = "use create_network_config_service_singleton_json")]
 #[no_mangle]
 pub unsafe extern "C" fn create_network_config_service_singleton(
     db_url: *const c_char,
     geoip_path: *const c_char,
     err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("create_network_config_service_singleton");
+    create_service_singleton(err_msg, || {
+        // 解析数据库 URL
+        if db_url.is_null() {
+            return Err("db_url is null".to_string());
+        }
+        let db_url = CStr::from_ptr(db_url)
+            .to_str()
+            .map_err(|e| format!("Invalid db_url: {}", e))?;
+
+        // 解析 GeoIP 路径
+        let geoip_path = if !geoip_path.is_null() {
+            match CStr::from_ptr(geoip_path).to_str() {
+                Ok(s) => Some(s.to_string()),
+                Err(e) => return Err(format!("Invalid geoip_path: {}", e)),
+            }
+        } else {
+            None
+        };
+
+        Ok(ServiceConfig {
+            db_url: db_url.to_string(),
+            geoip_path,
+            ..Default::default()
+        })
+    })
+}
+
+/// 使用 JSON 配置创建 NetworkConfigService 单例
+///
+/// config_json 示例：`{"db_url": "user:pass@tcp(host:3306)/db", "geoip_path": "...",
+/// "auto_approve_all_orgs": false, "auto_approve_orgs": ["org"], "default_device_type": "Edge",
+/// "reject_unapproved_devices": false, "reject_message": "..."}`，
+/// 其中 db_url 为 Go DSN 格式且必填，其余配置项可选，无法识别的配置项只记录警告
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn create_network_config_service_singleton_json(
+    config_json: *const c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("create_network_config_service_singleton_json");
+    create_service_singleton(err_msg, || {
+        if config_json.is_null() {
+            return Err("config_json is null".to_string());
+        }
+        let config_json = CStr::from_ptr(config_json)
+            .to_str()
+            .map_err(|e| format!("Invalid config_json: {}", e))?;
+        ServiceConfig::from_json(config_json).map_err(|e| e.to_string())
+    })
+}
+
+/// 按 `config` 创建并保存 NetworkConfigService 单例，已经初始化时直接返回成功
+///
+/// `config` 中的 db_url 为 Go DSN 格式，创建前转换为 SeaORM 连接地址
+unsafe fn create_service_singleton(
+    err_msg: *mut *mut c_char,
+    config: impl FnOnce() -> Result<ServiceConfig, String>,
 ) -> bool {
     // 获取 runtime 管理器
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -101,65 +227,34 @@ pub unsafe extern "C" fn create_network_config_service_singleton(
             return true; // 已经初始化，直接返回成功
         }
 
-        // 解析数据库 URL
-        let db_url = if !db_url.is_null() {
-            match CStr::from_ptr(db_url).to_str() {
-                Ok(s) => match convert_go_dsn_to_seaorm(s) {
-                    Ok(converted) => converted,
-                    Err(e) => {
-                        if !err_msg.is_null() {
-                            *err_msg = CString::new(format!("Failed to convert DSN: {}", e))
-                                .unwrap_or_default()
-                                .into_raw();
-                        }
-                        return false;
-                    }
-                },
-                Err(e) => {
-                    if !err_msg.is_null() {
-                        *err_msg = CString::new(format!("Invalid db_url: {}", e))
-                            .unwrap_or_default()
-                            .into_raw();
-                    }
-                    return false;
-                }
-            }
-        } else {
-            if !err_msg.is_null() {
-                *err_msg = CString::new("db_url is null")
-                    .unwrap_or_default()
-                    .into_raw();
+        let mut config = match config() {
+            Ok(config) => config,
+            Err(e) => {
+                report_error(err_msg, CortexErrorCode::InvalidArgument, &e);
+                return false;
             }
-            return false;
         };
-
-        // 解析 GeoIP 路径
-        let geoip_path = if !geoip_path.is_null() {
-            match CStr::from_ptr(geoip_path).to_str() {
-                Ok(s) => Some(s.to_string()),
-                Err(e) => {
-                    if !err_msg.is_null() {
-                        *err_msg = CString::new(format!("Invalid geoip_path: {}", e))
-                            .unwrap_or_default()
-                            .into_raw();
-                    }
-                    return false;
-                }
+        config.db_url = match convert_go_dsn_to_seaorm(&config.db_url) {
+            Ok(converted) => converted,
+            Err(e) => {
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidArgument,
+                    &format!("Failed to convert DSN: {}", e),
+                );
+                return false;
             }
-        } else {
-            None
         };
 
         // 创建 NetworkConfigService 实例
-        let network_config_service = match NetworkConfigService::new(&db_url, geoip_path).await {
+        let network_config_service = match NetworkConfigService::from_config(config).await {
             Ok(service) => service,
             Err(e) => {
-                if !err_msg.is_null() {
-                    *err_msg =
-                        CString::new(format!("Failed to create NetworkConfigService: {:?}", e))
-                            .unwrap_or_default()
-                            .into_raw();
-                }
+                report_error(
+                    err_msg,
+                    anyhow_error_code(&e),
+                    &format!("Failed to create NetworkConfigService: {:?}", e),
+                );
                 return false;
             }
         };
@@ -167,10 +262,25 @@ pub unsafe extern "C" fn create_network_config_service_singleton(
         // 存储到全局变量
         let mut service_opt = NETWORK_CONFIG_SERVICE.lock().await;
         *service_opt = Some(Arc::new(tokio::sync::Mutex::new(network_config_service)));
+        register_shutdown_hook("network_config_service", destroy_service_for_shutdown);
         true
     })
 }
 
+/// 进程退出时由 `cortex_shutdown_all` 调用，销毁 NetworkConfigService 单例
+fn destroy_service_for_shutdown() -> Result<(), String> {
+    let mut err_msg: *mut c_char = std::ptr::null_mut();
+    if unsafe { destroy_network_config_service_singleton(&mut err_msg) } {
+        return Ok(());
+    }
+
+    if err_msg.is_null() {
+        return Err("failed to destroy NetworkConfigService".to_string());
+    }
+    let msg = unsafe { CString::from_raw(err_msg) };
+    Err(msg.to_string_lossy().into_owned())
+}
+
 /// 启动 NetworkConfigService 的监听器
 ///
 /// # Safety
@@ -182,24 +292,21 @@ pub unsafe extern "C" fn network_config_service_singleton_start(
     port: u16,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_singleton_start");
     // 解析协议
     let protocol = if !protocol.is_null() {
         match CStr::from_ptr(protocol).to_str() {
             Ok(s) => s.to_string(),
             Err(e) => {
                 if !err_msg.is_null() {
-                    *err_msg = CString::new(format!("Invalid protocol: {}", e))
-                        .unwrap_or_default()
-                        .into_raw();
+                    *err_msg = to_c_string_lossy(&format!("Invalid protocol: {}", e));
                 }
                 return false;
             }
         }
     } else {
         if !err_msg.is_null() {
-            *err_msg = CString::new("protocol is null")
-                .unwrap_or_default()
-                .into_raw();
+            *err_msg = to_c_string_lossy("protocol is null");
         }
         return false;
     };
@@ -209,9 +316,7 @@ pub unsafe extern "C" fn network_config_service_singleton_start(
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -226,9 +331,7 @@ pub unsafe extern "C" fn network_config_service_singleton_start(
                 Some(service) => service.clone(),
                 None => {
                     if !err_msg.is_null() {
-                        *err_msg = CString::new("NetworkConfigService not initialized")
-                            .unwrap_or_default()
-                            .into_raw();
+                        *err_msg = to_c_string_lossy("NetworkConfigService not initialized");
                     }
                     return false;
                 }
@@ -244,9 +347,7 @@ pub unsafe extern "C" fn network_config_service_singleton_start(
             Ok(_) => true,
             Err(e) => {
                 if !err_msg.is_null() {
-                    *err_msg = CString::new(format!("Failed to start listener: {:?}", e))
-                        .unwrap_or_default()
-                        .into_raw();
+                    *err_msg = to_c_string_lossy(&format!("Failed to start listener: {:?}", e));
                 }
                 false
             }
@@ -263,14 +364,13 @@ pub unsafe extern "C" fn network_config_service_singleton_start(
 pub unsafe extern "C" fn destroy_network_config_service_singleton(
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("destroy_network_config_service_singleton");
     // 获取 runtime 管理器
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -296,6 +396,35 @@ pub unsafe extern "C" fn free_c_char(s: *mut c_char) {
     }
 }
 
+/// 获取版本信息 JSON：`{bridge_version, easytier_version, rerun_version, build_profile}`
+///
+/// 成功返回 0，失败返回 -1；返回的字符串需要用 `free_c_char` 释放
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn cortex_get_version_info(out_json: *mut *mut c_char) -> c_int {
+    if out_json.is_null() {
+        set_error(CortexErrorCode::NullPointer, "out_json is null");
+        return -1;
+    }
+
+    match serde_json::to_string(&crate::version_info()) {
+        Ok(json) => {
+            *out_json = CString::new(json).unwrap_or_default().into_raw();
+            0
+        }
+        Err(e) => {
+            set_error(
+                CortexErrorCode::Internal,
+                &format!("Failed to serialize version info: {}", e),
+            );
+            -1
+        }
+    }
+}
+
 /// 收集单个网络实例信息
 ///
 /// # Safety
@@ -309,6 +438,7 @@ pub unsafe extern "C" fn network_config_service_collect_one_network_info(
     result_json_out: *mut *mut c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_collect_one_network_info");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -337,11 +467,11 @@ pub unsafe extern "C" fn network_config_service_collect_one_network_info(
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
-            }
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
             return false;
         }
     };
@@ -361,12 +491,11 @@ pub unsafe extern "C" fn network_config_service_collect_one_network_info(
                         true
                     }
                     Err(e) => {
-                        if !err_msg.is_null() {
-                            *err_msg =
-                                CString::new(format!("Failed to serialize network info: {}", e))
-                                    .unwrap_or_default()
-                                    .into_raw();
-                        }
+                        report_error(
+                            err_msg,
+                            CortexErrorCode::Internal,
+                            &format!("Failed to serialize network info: {}", e),
+                        );
                         false
                     }
                 }
@@ -375,11 +504,93 @@ pub unsafe extern "C" fn network_config_service_collect_one_network_info(
             }
         }
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to collect network info: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to collect network info: {:?}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 获取设备当前会话的收发字节数
+///
+/// 输出 `{"tx_bytes": ..., "rx_bytes": ...}`，隧道没有计数时对应字段为 null
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_get_device_throughput(
+    org_id: *const c_char,
+    device_id: *const c_char,
+    out_json: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_get_device_throughput");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析设备ID
+    let device_id = match parse_uuid(device_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard
+            .get_device_throughput(&org_id, &device_id)
+            .await
+    }) {
+        Ok(throughput) => {
+            if out_json.is_null() {
+                return true;
+            }
+            match serde_json::to_string(&throughput) {
+                Ok(json) => {
+                    *out_json = CString::new(json).unwrap_or_default().into_raw();
+                    true
+                }
+                Err(e) => {
+                    report_error(
+                        err_msg,
+                        CortexErrorCode::Internal,
+                        &format!("Failed to serialize device throughput: {}", e),
+                    );
+                    false
+                }
             }
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to get device throughput: {:?}", e),
+            );
             false
         }
     }
@@ -398,6 +609,7 @@ pub unsafe extern "C" fn network_config_service_collect_network_info(
     result_json_out: *mut *mut c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_collect_network_info");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -426,11 +638,11 @@ pub unsafe extern "C" fn network_config_service_collect_network_info(
                         match Uuid::parse_str(&id_str) {
                             Ok(uuid) => ids.push(uuid),
                             Err(e) => {
-                                if !err_msg.is_null() {
-                                    *err_msg = CString::new(format!("Invalid UUID in list: {}", e))
-                                        .unwrap_or_default()
-                                        .into_raw();
-                                }
+                                report_error(
+                                    err_msg,
+                                    CortexErrorCode::InvalidArgument,
+                                    &format!("Invalid UUID in list: {}", e),
+                                );
                                 return false;
                             }
                         }
@@ -438,20 +650,20 @@ pub unsafe extern "C" fn network_config_service_collect_network_info(
                     Some(ids)
                 }
                 Err(e) => {
-                    if !err_msg.is_null() {
-                        *err_msg = CString::new(format!("Invalid inst_ids JSON: {}", e))
-                            .unwrap_or_default()
-                            .into_raw();
-                    }
+                    report_error(
+                        err_msg,
+                        CortexErrorCode::InvalidArgument,
+                        &format!("Invalid inst_ids JSON: {}", e),
+                    );
                     return false;
                 }
             },
             Err(e) => {
-                if !err_msg.is_null() {
-                    *err_msg = CString::new(format!("Invalid inst_ids_json: {}", e))
-                        .unwrap_or_default()
-                        .into_raw();
-                }
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidUtf8,
+                    &format!("Invalid inst_ids_json: {}", e),
+                );
                 return false;
             }
         }
@@ -463,11 +675,11 @@ pub unsafe extern "C" fn network_config_service_collect_network_info(
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
-            }
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
             return false;
         }
     };
@@ -487,12 +699,11 @@ pub unsafe extern "C" fn network_config_service_collect_network_info(
                         true
                     }
                     Err(e) => {
-                        if !err_msg.is_null() {
-                            *err_msg =
-                                CString::new(format!("Failed to serialize network info: {}", e))
-                                    .unwrap_or_default()
-                                    .into_raw();
-                        }
+                        report_error(
+                            err_msg,
+                            CortexErrorCode::Internal,
+                            &format!("Failed to serialize network info: {}", e),
+                        );
                         false
                     }
                 }
@@ -501,11 +712,11 @@ pub unsafe extern "C" fn network_config_service_collect_network_info(
             }
         }
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to collect network info: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
-            }
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to collect network info: {:?}", e),
+            );
             false
         }
     }
@@ -523,6 +734,7 @@ pub unsafe extern "C" fn network_config_service_list_network_instance_ids(
     result_json_out: *mut *mut c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_list_network_instance_ids");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -546,9 +758,7 @@ pub unsafe extern "C" fn network_config_service_list_network_instance_ids(
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -570,12 +780,10 @@ pub unsafe extern "C" fn network_config_service_list_network_instance_ids(
                     }
                     Err(e) => {
                         if !err_msg.is_null() {
-                            *err_msg = CString::new(format!(
+                            *err_msg = to_c_string_lossy(&format!(
                                 "Failed to serialize network instance IDs: {}",
                                 e
-                            ))
-                            .unwrap_or_default()
-                            .into_raw();
+                            ));
                         }
                         false
                     }
@@ -586,9 +794,8 @@ pub unsafe extern "C" fn network_config_service_list_network_instance_ids(
         }
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to list network instance IDs: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg =
+                    to_c_string_lossy(&format!("Failed to list network instance IDs: {:?}", e));
             }
             false
         }
@@ -607,6 +814,7 @@ pub unsafe extern "C" fn network_config_service_remove_network_instance(
     inst_id: *const c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_remove_network_instance");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -636,9 +844,7 @@ pub unsafe extern "C" fn network_config_service_remove_network_instance(
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -654,26 +860,26 @@ pub unsafe extern "C" fn network_config_service_remove_network_instance(
         Ok(_) => true,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to remove network instance: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg =
+                    to_c_string_lossy(&format!("Failed to remove network instance: {:?}", e));
             }
             false
         }
     }
 }
 
-/// 列出设备
+/// 删除设备记录，设备存在活动会话时先关闭会话
 ///
 /// # Safety
 ///
 /// 这个函数是不安全的，因为它接受原始指针作为参数
 #[no_mangle]
-pub unsafe extern "C" fn network_config_service_list_devices(
+pub unsafe extern "C" fn network_config_service_delete_device(
     org_id: *const c_char,
-    result_json_out: *mut *mut c_char,
+    device_id: *const c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_delete_device");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -686,68 +892,50 @@ pub unsafe extern "C" fn network_config_service_list_devices(
         None => return false,
     };
 
+    // 解析设备ID
+    let device_id = match parse_uuid(device_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
     // 获取 runtime 管理器
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
     };
 
-    // 调用列出设备方法
+    // 调用删除设备方法
     match runtime_manager.block_on(async {
         let service_guard = service.lock().await;
-        service_guard.list_devices(&org_id).await
+        service_guard.delete_device(&org_id, &device_id).await
     }) {
-        Ok(devices) => {
-            if !result_json_out.is_null() {
-                match serde_json::to_string(&devices) {
-                    Ok(json) => {
-                        *result_json_out = CString::new(json).unwrap_or_default().into_raw();
-                        true
-                    }
-                    Err(e) => {
-                        if !err_msg.is_null() {
-                            *err_msg = CString::new(format!("Failed to serialize devices: {}", e))
-                                .unwrap_or_default()
-                                .into_raw();
-                        }
-                        false
-                    }
-                }
-            } else {
-                true
-            }
-        }
+        Ok(_) => true,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to list devices: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to delete device: {:?}", e));
             }
             false
         }
     }
 }
 
-/// 更新网络状态
+/// 从服务端断开设备的当前会话，设备没有连接时返回 NotFound 错误
 ///
 /// # Safety
 ///
 /// 这个函数是不安全的，因为它接受原始指针作为参数
 #[no_mangle]
-pub unsafe extern "C" fn network_config_service_update_network_state(
+pub unsafe extern "C" fn network_config_service_disconnect_device(
     org_id: *const c_char,
     device_id: *const c_char,
-    inst_id: *const c_char,
-    disabled: bool,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_disconnect_device");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -766,61 +954,1304 @@ pub unsafe extern "C" fn network_config_service_update_network_state(
         None => return false,
     };
 
-    // 解析实例ID
-    let inst_id = match parse_uuid(inst_id, err_msg) {
-        Some(id) => id,
-        None => return false,
-    };
-
     // 获取 runtime 管理器
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
-            }
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
             return false;
         }
     };
 
-    // 调用更新网络状态方法
     match runtime_manager.block_on(async {
         let service_guard = service.lock().await;
-        service_guard
-            .update_network_state(&org_id, &device_id, &inst_id, disabled)
-            .await
+        service_guard.disconnect_device(&org_id, &device_id).await
     }) {
-        Ok(_) => true,
+        Ok(true) => true,
+        Ok(false) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::NotFound,
+                &format!("No active session for device: {}", device_id),
+            );
+            false
+        }
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to update network state: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
-            }
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to disconnect device: {:?}", e),
+            );
             false
         }
     }
 }
 
-/// 获取服务实例的辅助函数
+/// 列出设备
 ///
 /// # Safety
 ///
 /// 这个函数是不安全的，因为它接受原始指针作为参数
-unsafe fn get_service_instance(
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_list_devices(
+    org_id: *const c_char,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_list_devices");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用列出设备方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.list_devices(&org_id).await
+    }) {
+        Ok(devices) => {
+            if !result_json_out.is_null() {
+                match serde_json::to_string(&devices) {
+                    Ok(json) => {
+                        *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+                        true
+                    }
+                    Err(e) => {
+                        if !err_msg.is_null() {
+                            *err_msg =
+                                to_c_string_lossy(&format!("Failed to serialize devices: {}", e));
+                        }
+                        false
+                    }
+                }
+            } else {
+                true
+            }
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to list devices: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 获取组织内已连接设备之间的网络拓扑，返回 JSON
+///
+/// 结果包含节点（设备 ID、主机名、peer ID、虚拟 IP）和边（直连的 peer 及延迟）；
+/// 单台设备采集失败时该节点的 `error` 字段记录原因，其它设备不受影响
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_get_org_topology(
+    org_id: *const c_char,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_get_org_topology");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    let topology = match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.get_org_topology(&org_id).await
+    }) {
+        Ok(topology) => topology,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to get organization topology: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&topology) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to serialize topology: {}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 按状态统计设备数量，返回 JSON 对象（状态 -> 数量）
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_count_devices_by_status(
+    org_id: *const c_char,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_count_devices_by_status");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用统计方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.count_devices_by_status(&org_id).await
+    }) {
+        Ok(counts) => {
+            if !result_json_out.is_null() {
+                match serde_json::to_string(&counts) {
+                    Ok(json) => {
+                        *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+                        true
+                    }
+                    Err(e) => {
+                        if !err_msg.is_null() {
+                            *err_msg = to_c_string_lossy(&format!(
+                                "Failed to serialize device counts: {}",
+                                e
+                            ));
+                        }
+                        false
+                    }
+                }
+            } else {
+                true
+            }
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to count devices: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 按过滤条件查询设备记录，`filter_json` 可包含可选的 `status` 和 `name` 字段
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_query_devices(
+    org_id: *const c_char,
+    filter_json: *const c_char,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_query_devices");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析过滤条件
+    let filter = match parse_device_filter(filter_json, err_msg) {
+        Some(filter) => filter,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用查询设备方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.query_devices(&org_id, &filter).await
+    }) {
+        Ok(devices) => {
+            if !result_json_out.is_null() {
+                match serde_json::to_string(&devices) {
+                    Ok(json) => {
+                        *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+                        true
+                    }
+                    Err(e) => {
+                        if !err_msg.is_null() {
+                            *err_msg =
+                                to_c_string_lossy(&format!("Failed to serialize devices: {}", e));
+                        }
+                        false
+                    }
+                }
+            } else {
+                true
+            }
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to query devices: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 分页列出设备记录，返回当前页及设备总数
+///
+/// 设备记录不含 network_config，避免泄露网络密钥
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_list_devices_paginated(
+    org_id: *const c_char,
+    offset: u64,
+    limit: u64,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_list_devices_paginated");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用分页列出设备方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard
+            .list_devices_paginated(&org_id, offset, limit)
+            .await
+    }) {
+        Ok(page) => {
+            if !result_json_out.is_null() {
+                match serde_json::to_string(&page) {
+                    Ok(json) => {
+                        *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+                        true
+                    }
+                    Err(e) => {
+                        if !err_msg.is_null() {
+                            *err_msg = to_c_string_lossy(&format!(
+                                "Failed to serialize device page: {}",
+                                e
+                            ));
+                        }
+                        false
+                    }
+                }
+            } else {
+                true
+            }
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to list devices: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 导出组织内所有设备为 RFC 4180 CSV
+///
+/// 列为 id、name、serial_number、device_type、status、last_heartbeat、created_at
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_export_devices_csv(
+    org_id: *const c_char,
+    out_csv: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_export_devices_csv");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    let csv = match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.export_devices_csv(&org_id).await
+    }) {
+        Ok(csv) => csv,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to export devices: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if out_csv.is_null() {
+        return true;
+    }
+
+    match CString::new(csv) {
+        Ok(csv) => {
+            *out_csv = csv.into_raw();
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Device CSV contains a NUL byte: {}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 批量设置组织内设备的状态，`device_ids_json` 为设备 ID 的 JSON 数组，
+/// `status` 为设备状态，大小写不敏感（如 "Online"、"rejected"），与查询过滤和状态统计的写法一致
+///
+/// 所有更新在同一事务中完成，任一设备不属于该组织时不做修改并返回 false，
+/// 更新的行数写入 `out_count`（可为空）
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_bulk_set_device_status(
+    org_id: *const c_char,
+    device_ids_json: *const c_char,
+    status: *const c_char,
+    out_count: *mut u64,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_bulk_set_device_status");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析设备ID列表
+    let device_ids = match parse_uuid_list(device_ids_json, "device_ids_json", err_msg) {
+        Some(ids) => ids,
+        None => return false,
+    };
+
+    // 解析设备状态
+    let status = match parse_device_status(status, err_msg) {
+        Some(status) => status,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard
+            .bulk_set_device_status(&org_id, &device_ids, status)
+            .await
+    }) {
+        Ok(count) => {
+            if !out_count.is_null() {
+                *out_count = count;
+            }
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to update device status: {:?}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 创建组织，组织已存在时直接返回成功
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_create_organization(
+    org_id: *const c_char,
+    name: *const c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_create_organization");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析组织名称
+    let name = match parse_org_name(name, err_msg) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用创建组织方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.create_organization(&org_id, &name).await
+    }) {
+        Ok(_) => true,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to create organization: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 检查组织是否存在，结果写入 `exists_out`
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_organization_exists(
+    org_id: *const c_char,
+    exists_out: *mut bool,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_organization_exists");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    if exists_out.is_null() {
+        if !err_msg.is_null() {
+            *err_msg = to_c_string_lossy("exists_out is null");
+        }
+        return false;
+    }
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用检查组织方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.organization_exists(&org_id).await
+    }) {
+        Ok(exists) => {
+            *exists_out = exists;
+            true
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to check organization: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 更新网络状态
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_update_network_state(
+    org_id: *const c_char,
+    device_id: *const c_char,
+    inst_id: *const c_char,
+    disabled: bool,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_update_network_state");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析设备ID
+    let device_id = match parse_uuid(device_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析实例ID
+    let inst_id = match parse_uuid(inst_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    // 调用更新网络状态方法
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard
+            .update_network_state(&org_id, &device_id, &inst_id, disabled)
+            .await
+    }) {
+        Ok(_) => true,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to update network state: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 列出已启动的监听器，返回包含 protocol、bind_addr、port、ip_version 的 JSON 数组
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_list_listeners(
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_list_listeners");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    let listeners = runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.list_listeners()
+    });
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&listeners) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to serialize listeners: {}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 重新加载 GeoIP 数据库
+///
+/// 新数据库加载成功后原子替换，之后的新连接使用新数据；加载失败时继续使用旧数据库。
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_reload_geoip(
+    path: *const c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_reload_geoip");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析数据库路径
+    if path.is_null() {
+        report_error(err_msg, CortexErrorCode::NullPointer, "path is null");
+        return false;
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidUtf8,
+                &format!("Invalid path: {}", e),
+            );
+            return false;
+        }
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.reload_geoip(path)
+    }) {
+        Ok(()) => true,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidArgument,
+                &format!("Failed to reload GeoIP database: {:?}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 列出所有组织的已连接客户端，返回包含 organization_id、client_url 的 JSON 数组
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_list_all_clients(
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_list_all_clients");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    let clients = match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.list_all_clients()
+    }) {
+        Ok(clients) => clients,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to list clients: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&clients) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to serialize clients: {}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 一次性获取所有会话最近的心跳，返回以设备 ID 为键的 JSON 对象
+///
+/// 每项包含 client_url、info（心跳内容）和 age_ms（距收到心跳的毫秒数）
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_snapshot_heartbeats(
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_snapshot_heartbeats");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    let snapshot = match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.snapshot_heartbeats().await
+    }) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to snapshot heartbeats: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&snapshot) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to serialize heartbeats: {}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 检查尚未应用的数据库迁移，返回迁移名称的 JSON 数组（空数组表示已是最新）
+///
+/// 只读检查，不会修改数据库
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_check_migrations(
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_check_migrations");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    let pending = match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.pending_migrations().await
+    }) {
+        Ok(pending) => pending,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to check migrations: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&pending) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to serialize pending migrations: {}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 回滚最近应用的 `steps` 个数据库迁移，用于发布失败后的运维恢复
+///
+/// `steps` 必须大于 0
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_rollback_migration(
+    steps: c_int,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_rollback_migration");
+    let steps = match u32::try_from(steps) {
+        Ok(steps) if steps > 0 => steps,
+        _ => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidArgument,
+                &format!("steps must be positive, got {}", steps),
+            );
+            return false;
+        }
+    };
+
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.rollback_migrations(steps).await
+    }) {
+        Ok(()) => true,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to roll back migrations: {:?}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 取出待处理的设备状态变更事件，返回包含 device_id、organization_id、old_status、new_status 的 JSON 数组
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_poll_device_events(
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_poll_device_events");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
+            }
+            return false;
+        }
+    };
+
+    let events = runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard.poll_device_events()
+    });
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&events) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            if !err_msg.is_null() {
+                *err_msg = to_c_string_lossy(&format!("Failed to serialize device events: {}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 等待设备的下一次心跳，返回心跳的 JSON；超时返回 true 且输出 "null"
+///
+/// 等待期间不持有服务锁和 runtime 管理器锁，不会阻塞其他 FFI 调用
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_wait_for_heartbeat(
+    org_id: *const c_char,
+    device_id: *const c_char,
+    timeout_ms: u64,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_wait_for_heartbeat");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析设备ID
+    let device_id = match parse_uuid(device_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 获取 runtime 句柄后立即释放管理器锁
+    let handle = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager.handle(),
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    let result = handle.block_on(async {
+        // 只在订阅时持有服务锁，没有会话时直接返回错误
+        let waiter = service
+            .lock()
+            .await
+            .heartbeat_waiter(&org_id, &device_id)
+            .await?;
+        NetworkConfigService::next_heartbeat(waiter, std::time::Duration::from_millis(timeout_ms))
+            .await
+    });
+
+    let heartbeat = match result {
+        Ok(heartbeat) => heartbeat.map(SerializableHeartbeatRequest::from),
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to wait for heartbeat: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&heartbeat) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to serialize heartbeat: {}", e),
+            );
+            false
+        }
+    }
+}
+
+/// 写入错误信息并记录错误码的辅助函数
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+unsafe fn report_error(err_msg: *mut *mut c_char, code: CortexErrorCode, msg: &str) {
+    // 连接池耗尽使用固定前缀，便于运维单独告警
+    let msg = if code == CortexErrorCode::PoolExhausted {
+        format!("{}: {}", POOL_EXHAUSTED_MSG, msg)
+    } else {
+        msg.to_string()
+    };
+    set_error(code, &msg);
+    if !err_msg.is_null() {
+        *err_msg = to_c_string_lossy(&msg);
+    }
+}
+
+/// 根据服务返回的错误推断错误码
+fn anyhow_error_code(e: &anyhow::Error) -> CortexErrorCode {
+    let client_manager_error = e
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<client_manager::Error>());
+    if let Some(client_manager::Error::MigrationError(_)) = client_manager_error {
+        return CortexErrorCode::MigrationError;
+    }
+
+    // 连接池耗尽优先于其它数据库错误
+    let db_err = e
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<sea_orm::DbErr>());
+    if let Some(db_err) = db_err {
+        return db_error_code(db_err);
+    }
+    match client_manager_error {
+        Some(client_manager::Error::DatabaseError(_)) => CortexErrorCode::DbError,
+        _ => CortexErrorCode::Internal,
+    }
+}
+
+/// 获取服务实例的辅助函数
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+unsafe fn get_service_instance(
     err_msg: *mut *mut c_char,
 ) -> Option<Arc<tokio::sync::Mutex<NetworkConfigService>>> {
     // 获取 runtime 管理器
     let runtime_manager = match RUNTIME_MANAGER.try_lock() {
         Ok(manager) => manager,
         Err(e) => {
-            if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
-            }
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
             return None;
         }
     };
@@ -830,11 +2261,11 @@ unsafe fn get_service_instance(
         match &*service_opt {
             Some(service) => Some(service.clone()),
             None => {
-                if !err_msg.is_null() {
-                    *err_msg = CString::new("NetworkConfigService not initialized")
-                        .unwrap_or_default()
-                        .into_raw();
-                }
+                report_error(
+                    err_msg,
+                    CortexErrorCode::Internal,
+                    "NetworkConfigService not initialized",
+                );
                 None
             }
         }
@@ -849,21 +2280,41 @@ unsafe fn get_service_instance(
 unsafe fn parse_org_id(org_id: *const c_char, err_msg: *mut *mut c_char) -> Option<OrgIdInDb> {
     if !org_id.is_null() {
         match CStr::from_ptr(org_id).to_str() {
+            Ok(s) => Some(s.to_string()),
+            Err(e) => {
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidUtf8,
+                    &format!("Invalid org_id: {}", e),
+                );
+                None
+            }
+        }
+    } else {
+        report_error(err_msg, CortexErrorCode::NullPointer, "org_id is null");
+        None
+    }
+}
+
+/// 解析组织名称的辅助函数
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+unsafe fn parse_org_name(name: *const c_char, err_msg: *mut *mut c_char) -> Option<String> {
+    if !name.is_null() {
+        match CStr::from_ptr(name).to_str() {
             Ok(s) => Some(s.to_string()),
             Err(e) => {
                 if !err_msg.is_null() {
-                    *err_msg = CString::new(format!("Invalid org_id: {}", e))
-                        .unwrap_or_default()
-                        .into_raw();
+                    *err_msg = to_c_string_lossy(&format!("Invalid name: {}", e));
                 }
                 None
             }
         }
     } else {
         if !err_msg.is_null() {
-            *err_msg = CString::new("org_id is null")
-                .unwrap_or_default()
-                .into_raw();
+            *err_msg = to_c_string_lossy("name is null");
         }
         None
     }
@@ -880,27 +2331,25 @@ unsafe fn parse_uuid(uuid_str: *const c_char, err_msg: *mut *mut c_char) -> Opti
             Ok(s) => match Uuid::parse_str(s) {
                 Ok(uuid) => Some(uuid),
                 Err(e) => {
-                    if !err_msg.is_null() {
-                        *err_msg = CString::new(format!("Invalid UUID: {}", e))
-                            .unwrap_or_default()
-                            .into_raw();
-                    }
+                    report_error(
+                        err_msg,
+                        CortexErrorCode::InvalidArgument,
+                        &format!("Invalid UUID: {}", e),
+                    );
                     None
                 }
             },
             Err(e) => {
-                if !err_msg.is_null() {
-                    *err_msg = CString::new(format!("Invalid UUID string: {}", e))
-                        .unwrap_or_default()
-                        .into_raw();
-                }
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidUtf8,
+                    &format!("Invalid UUID string: {}", e),
+                );
                 None
             }
         }
     } else {
-        if !err_msg.is_null() {
-            *err_msg = CString::new("UUID is null").unwrap_or_default().into_raw();
-        }
+        report_error(err_msg, CortexErrorCode::NullPointer, "UUID is null");
         None
     }
 }
@@ -920,32 +2369,148 @@ unsafe fn parse_network_config(
                 Ok(config) => Some(config),
                 Err(e) => {
                     if !err_msg.is_null() {
-                        *err_msg = CString::new(format!("Invalid network config JSON: {}", e))
-                            .unwrap_or_default()
-                            .into_raw();
+                        *err_msg =
+                            to_c_string_lossy(&format!("Invalid network config JSON: {}", e));
                     }
                     None
                 }
             },
             Err(e) => {
                 if !err_msg.is_null() {
-                    *err_msg = CString::new(format!("Invalid config_json: {}", e))
-                        .unwrap_or_default()
-                        .into_raw();
+                    *err_msg = to_c_string_lossy(&format!("Invalid config_json: {}", e));
                 }
                 None
             }
         }
     } else {
         if !err_msg.is_null() {
-            *err_msg = CString::new("config_json is null")
-                .unwrap_or_default()
-                .into_raw();
+            *err_msg = to_c_string_lossy("config_json is null");
         }
         None
     }
 }
 
+/// 解析 UUID 字符串组成的 JSON 数组
+unsafe fn parse_uuid_list(
+    json: *const c_char,
+    name: &str,
+    err_msg: *mut *mut c_char,
+) -> Option<Vec<Uuid>> {
+    if json.is_null() {
+        report_error(
+            err_msg,
+            CortexErrorCode::NullPointer,
+            &format!("{} is null", name),
+        );
+        return None;
+    }
+
+    let json = match CStr::from_ptr(json).to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidUtf8,
+                &format!("Invalid {}: {}", name, e),
+            );
+            return None;
+        }
+    };
+
+    let ids = match serde_json::from_str::<Vec<String>>(json) {
+        Ok(ids) => ids,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidArgument,
+                &format!("Invalid {} JSON: {}", name, e),
+            );
+            return None;
+        }
+    };
+
+    let mut uuids = Vec::with_capacity(ids.len());
+    for id in ids {
+        match Uuid::parse_str(&id) {
+            Ok(uuid) => uuids.push(uuid),
+            Err(e) => {
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidArgument,
+                    &format!("Invalid UUID in list: {}", e),
+                );
+                return None;
+            }
+        }
+    }
+    Some(uuids)
+}
+
+/// 解析设备状态，大小写不敏感，如 "Pending"、"online"
+unsafe fn parse_device_status(
+    status: *const c_char,
+    err_msg: *mut *mut c_char,
+) -> Option<DeviceStatus> {
+    if status.is_null() {
+        report_error(err_msg, CortexErrorCode::NullPointer, "status is null");
+        return None;
+    }
+
+    match CStr::from_ptr(status).to_str() {
+        Ok(s) => match s.parse::<DeviceStatus>() {
+            Ok(status) => Some(status),
+            Err(_) => {
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidArgument,
+                    &format!("Unknown device status: {}", s),
+                );
+                None
+            }
+        },
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidUtf8,
+                &format!("Invalid status: {}", e),
+            );
+            None
+        }
+    }
+}
+
+/// 解析设备过滤条件 JSON，空指针表示不过滤
+unsafe fn parse_device_filter(
+    filter_json: *const c_char,
+    err_msg: *mut *mut c_char,
+) -> Option<DeviceFilter> {
+    if filter_json.is_null() {
+        return Some(DeviceFilter::default());
+    }
+
+    match CStr::from_ptr(filter_json).to_str() {
+        Ok(s) => match serde_json::from_str::<DeviceFilter>(s) {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidArgument,
+                    &format!("Invalid device filter JSON: {}", e),
+                );
+                None
+            }
+        },
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::InvalidUtf8,
+                &format!("Invalid filter_json: {}", e),
+            );
+            None
+        }
+    }
+}
+
 /// 验证网络配置
 ///
 /// # Safety
@@ -958,6 +2523,7 @@ pub unsafe extern "C" fn network_config_service_validate_config(
     config_json: *const c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_validate_config");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -987,9 +2553,7 @@ pub unsafe extern "C" fn network_config_service_validate_config(
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -1002,18 +2566,116 @@ pub unsafe extern "C" fn network_config_service_validate_config(
             .validate_config(&org_id, &device_id, config)
             .await
     }) {
-        Ok(_) => true,
+        Ok(report) if report.valid => true,
+        Ok(report) => {
+            if !err_msg.is_null() {
+                let e = ConfigValidationError {
+                    issues: report.issues,
+                };
+                *err_msg = to_c_string_lossy(&format!("Config validation failed: {}", e));
+            }
+            false
+        }
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Config validation failed: {:?}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Config validation failed: {:?}", e));
             }
             false
         }
     }
 }
 
+/// 验证网络配置，以 JSON 返回逐字段的校验结果
+///
+/// 校验完成即返回 true，配置是否有效见结果中的 `valid`，未通过的字段及原因见 `issues`；
+/// 参数错误、设备不在线等无法完成校验的情况返回 false
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_validate_config_detailed(
+    org_id: *const c_char,
+    device_id: *const c_char,
+    config_json: *const c_char,
+    result_json_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_validate_config_detailed");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析设备ID
+    let device_id = match parse_uuid(device_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析网络配置
+    let config = match parse_network_config(config_json, err_msg) {
+        Some(c) => c,
+        None => return false,
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    let report = match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard
+            .validate_config(&org_id, &device_id, config)
+            .await
+    }) {
+        Ok(report) => report,
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Config validation failed: {:?}", e),
+            );
+            return false;
+        }
+    };
+
+    if result_json_out.is_null() {
+        return true;
+    }
+
+    match serde_json::to_string(&report) {
+        Ok(json) => {
+            *result_json_out = CString::new(json).unwrap_or_default().into_raw();
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to serialize validation result: {}", e),
+            );
+            false
+        }
+    }
+}
+
 /// 运行网络实例
 ///
 /// # Safety
@@ -1027,6 +2689,7 @@ pub unsafe extern "C" fn network_config_service_run_network_instance(
     inst_id_out: *mut *mut c_char,
     err_msg: *mut *mut c_char,
 ) -> bool {
+    let _request = enter_request_scope("network_config_service_run_network_instance");
     // 获取服务实例
     let service = match get_service_instance(err_msg) {
         Some(s) => s,
@@ -1056,9 +2719,7 @@ pub unsafe extern "C" fn network_config_service_run_network_instance(
         Ok(manager) => manager,
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to lock runtime manager: {}", e))
-                    .unwrap_or_default()
-                    .into_raw();
+                *err_msg = to_c_string_lossy(&format!("Failed to lock runtime manager: {}", e));
             }
             return false;
         }
@@ -1081,10 +2742,106 @@ pub unsafe extern "C" fn network_config_service_run_network_instance(
         }
         Err(e) => {
             if !err_msg.is_null() {
-                *err_msg = CString::new(format!("Failed to run network instance: {:?}", e))
+                *err_msg = to_c_string_lossy(&format!("Failed to run network instance: {:?}", e));
+            }
+            false
+        }
+    }
+}
+
+/// 运行网络实例，携带幂等键
+///
+/// 调用超时后使用相同的 `idempotency_key` 重试，若首次调用已成功则返回同一个实例 ID，
+/// 不会重复创建实例；`idempotency_key` 为空指针或空字符串时不做幂等处理
+///
+/// # Safety
+///
+/// 这个函数是不安全的，因为它接受原始指针作为参数
+#[no_mangle]
+pub unsafe extern "C" fn network_config_service_run_network_instance_with_key(
+    org_id: *const c_char,
+    device_id: *const c_char,
+    config_json: *const c_char,
+    idempotency_key: *const c_char,
+    inst_id_out: *mut *mut c_char,
+    err_msg: *mut *mut c_char,
+) -> bool {
+    let _request = enter_request_scope("network_config_service_run_network_instance_with_key");
+    // 获取服务实例
+    let service = match get_service_instance(err_msg) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // 解析组织ID
+    let org_id = match parse_org_id(org_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析设备ID
+    let device_id = match parse_uuid(device_id, err_msg) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // 解析网络配置
+    let config = match parse_network_config(config_json, err_msg) {
+        Some(c) => c,
+        None => return false,
+    };
+
+    // 解析幂等键，空指针或空字符串表示不使用
+    let idempotency_key = if idempotency_key.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(idempotency_key).to_str() {
+            Ok("") => None,
+            Ok(key) => Some(key.to_string()),
+            Err(e) => {
+                report_error(
+                    err_msg,
+                    CortexErrorCode::InvalidUtf8,
+                    &format!("Invalid idempotency_key: {}", e),
+                );
+                return false;
+            }
+        }
+    };
+
+    // 获取 runtime 管理器
+    let runtime_manager = match RUNTIME_MANAGER.try_lock() {
+        Ok(manager) => manager,
+        Err(e) => {
+            report_error(
+                err_msg,
+                CortexErrorCode::Internal,
+                &format!("Failed to lock runtime manager: {}", e),
+            );
+            return false;
+        }
+    };
+
+    match runtime_manager.block_on(async {
+        let service_guard = service.lock().await;
+        service_guard
+            .run_network_instance_with_key(&org_id, &device_id, config, idempotency_key.as_deref())
+            .await
+    }) {
+        Ok(inst_id) => {
+            if !inst_id_out.is_null() {
+                *inst_id_out = CString::new(inst_id.to_string())
                     .unwrap_or_default()
                     .into_raw();
             }
+            true
+        }
+        Err(e) => {
+            report_error(
+                err_msg,
+                anyhow_error_code(&e),
+                &format!("Failed to run network instance: {:?}", e),
+            );
             false
         }
     }
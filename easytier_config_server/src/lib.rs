@@ -3,6 +3,9 @@
 //! Server-side config server for managing device connections.
 //! This crate handles device registration, heartbeat processing,
 //! and network configuration distribution.
+//!
+//! Rust integrators can drive [`NetworkConfigService`] directly from their
+//! own tokio runtime; the C FFI is a blocking wrapper around the same API.
 
 pub mod client_manager;
 pub mod config;
@@ -10,8 +13,12 @@ pub mod config_srv;
 pub mod db;
 mod ffi;
 
-pub use client_manager::{session::Session, storage::Storage, ClientManager};
-pub use config_srv::NetworkConfigService;
+pub use client_manager::{
+    session::Session,
+    storage::{AutoApprovalPolicy, Storage},
+    ClientManager, StartReport,
+};
+pub use config_srv::{DeviceItem, DeviceList, NetworkConfigService};
 pub use db::Database;
 pub use ffi::*;
 
@@ -20,3 +27,27 @@ pub use easytier_common::*;
 
 /// Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Versions of the bridge and of the libraries it is built with
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VersionInfo {
+    pub bridge_version: String,
+    pub easytier_version: String,
+    pub rerun_version: String,
+    pub build_profile: String,
+}
+
+/// Collect the bridge, EasyTier and Rerun versions of this build
+pub fn version_info() -> VersionInfo {
+    VersionInfo {
+        bridge_version: VERSION.to_string(),
+        easytier_version: easytier::common::constants::EASYTIER_VERSION.to_string(),
+        rerun_version: re_build_info::CrateVersion::LOCAL.to_string(),
+        build_profile: if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+        .to_string(),
+    }
+}
@@ -4,9 +4,21 @@
 //! for each test function, enabling true concurrent testing.
 //! 包含自动清理机制，确保测试数据库不会残留
 
+use easytier::proto::{
+    rpc_impl::bidirect::BidirectRpcManager,
+    rpc_types::{self, controller::BaseController},
+    web::*,
+};
+use easytier::tunnel::{
+    common::tests::wait_for_condition, tcp::TcpTunnelConnector, TunnelConnector,
+};
+use easytier::web_client::WebClient;
+use easytier_config_server::db::entities::devices::{self, DeviceStatus};
 use easytier_config_server::db::Database;
+use easytier_config_server::{DeviceItem, NetworkConfigService};
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
@@ -206,6 +218,251 @@ pub fn test_location_json() -> serde_json::Value {
     })
 }
 
+/// Device record with the given name and status, created now and never seen
+#[allow(dead_code)]
+pub fn device_record(
+    device_id: Uuid,
+    org_id: &str,
+    name: &str,
+    status: DeviceStatus,
+) -> devices::ActiveModel {
+    use sea_orm::Set;
+
+    let now = chrono::Utc::now();
+    devices::ActiveModel {
+        id: Set(device_id.to_string()),
+        name: Set(name.to_string()),
+        serial_number: Set(device_id.to_string()),
+        device_type: Set(devices::DeviceType::Robot),
+        organization_id: Set(Some(org_id.to_string())),
+        status: Set(status),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+        ..Default::default()
+    }
+}
+
+/// Device record with the given status whose last heartbeat was `age` ago
+#[allow(dead_code)]
+pub fn device_last_seen(
+    device_id: Uuid,
+    org_id: &str,
+    status: DeviceStatus,
+    age: chrono::Duration,
+) -> devices::ActiveModel {
+    use sea_orm::Set;
+
+    let last_seen = chrono::Utc::now() - age;
+    let mut device = device_record(device_id, org_id, &format!("Device {}", device_id), status);
+    device.last_heartbeat = Set(Some(last_seen.into()));
+    device.created_at = Set(last_seen.into());
+    device.updated_at = Set(last_seen.into());
+    device
+}
+
+/// Insert a device with the given name and status into an organization
+#[allow(dead_code)]
+pub async fn insert_device(db: &Database, org_id: &str, name: &str, status: DeviceStatus) -> Uuid {
+    use sea_orm::ActiveModelTrait;
+
+    let device_id = Uuid::new_v4();
+    device_record(device_id, org_id, name, status)
+        .insert(db.orm())
+        .await
+        .expect("Failed to insert device");
+    device_id
+}
+
+/// Wait for the first device record of an organization to be created
+#[allow(dead_code)]
+pub async fn wait_for_device_record(db: &Database, org_id: &str) -> devices::Model {
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+    let find_device = || async {
+        devices::Entity::find()
+            .filter(devices::Column::OrganizationId.eq(org_id))
+            .one(db.orm())
+            .await
+            .unwrap()
+    };
+    wait_for_condition(
+        || async { find_device().await.is_some() },
+        Duration::from_secs(10),
+    )
+    .await;
+    find_device().await.unwrap()
+}
+
+/// Heartbeat a device reports right after connecting
+#[allow(dead_code)]
+pub fn heartbeat_request(org_id: &str, device_id: Uuid, hostname: &str) -> HeartbeatRequest {
+    HeartbeatRequest {
+        machine_id: Some(device_id.into()),
+        user_token: org_id.to_string(),
+        hostname: hostname.to_string(),
+        easytier_version: "1.0.0".to_string(),
+        report_time: chrono::Utc::now().to_rfc3339(),
+        running_network_instances: vec![],
+        inst_id: None,
+    }
+}
+
+/// Start EasyTier's web client against a local TCP listener on `port`
+#[allow(dead_code)]
+pub fn start_web_client(port: u16, org_id: &str, hostname: &str) -> WebClient {
+    let connector = TcpTunnelConnector::new(format!("tcp://127.0.0.1:{}", port).parse().unwrap());
+    WebClient::new(connector, org_id, hostname)
+}
+
+/// Wait until the organization lists exactly one connected device, return it with its id
+#[allow(dead_code)]
+pub async fn wait_for_single_device(
+    service: &NetworkConfigService,
+    org_id: &str,
+) -> (Uuid, DeviceItem) {
+    let org_id = org_id.to_string();
+    wait_for_condition(
+        || async { service.list_devices(&org_id).await.unwrap().devices.len() == 1 },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let device = service
+        .list_devices(&org_id)
+        .await
+        .unwrap()
+        .devices
+        .remove(0);
+    let device_id = device
+        .info
+        .as_ref()
+        .and_then(|info| info.machine_id.as_ref())
+        .expect("Device should report a machine id")
+        .parse()
+        .unwrap();
+    (device_id, device)
+}
+
+/// Device side of the RPC reporting no running network instances
+#[allow(dead_code)]
+#[derive(Clone, Default)]
+pub struct IdleWebClientService;
+
+#[async_trait::async_trait]
+impl WebClientService for IdleWebClientService {
+    type Controller = BaseController;
+
+    async fn validate_config(
+        &self,
+        _: Self::Controller,
+        _: ValidateConfigRequest,
+    ) -> rpc_types::error::Result<ValidateConfigResponse> {
+        Ok(Default::default())
+    }
+
+    async fn run_network_instance(
+        &self,
+        _: Self::Controller,
+        _: RunNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<RunNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn retain_network_instance(
+        &self,
+        _: Self::Controller,
+        _: RetainNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<RetainNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn collect_network_info(
+        &self,
+        _: Self::Controller,
+        _: CollectNetworkInfoRequest,
+    ) -> rpc_types::error::Result<CollectNetworkInfoResponse> {
+        Ok(Default::default())
+    }
+
+    async fn list_network_instance(
+        &self,
+        _: Self::Controller,
+        _: ListNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<ListNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn delete_network_instance(
+        &self,
+        _: Self::Controller,
+        _: DeleteNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<DeleteNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn get_network_instance_config(
+        &self,
+        _: Self::Controller,
+        _: GetNetworkInstanceConfigRequest,
+    ) -> rpc_types::error::Result<GetNetworkInstanceConfigResponse> {
+        Ok(Default::default())
+    }
+}
+
+/// Connect a fake device to a local TCP listener on `port` and report one heartbeat
+///
+/// Returns the device's RPC manager, which keeps the connection open until
+/// dropped, and the heartbeat result.
+#[allow(dead_code)]
+pub async fn connect_device(
+    port: u16,
+    org_id: &str,
+    device_id: Uuid,
+    hostname: &str,
+) -> (
+    BidirectRpcManager,
+    rpc_types::error::Result<HeartbeatResponse>,
+) {
+    let mut connector =
+        TcpTunnelConnector::new(format!("tcp://127.0.0.1:{}", port).parse().unwrap());
+    let tunnel = connector.connect().await.expect("Failed to connect");
+
+    let device_rpc = BidirectRpcManager::new();
+    device_rpc
+        .rpc_server()
+        .registry()
+        .register(WebClientServiceServer::new(IdleWebClientService), "");
+    device_rpc.run_with_tunnel(tunnel);
+
+    let ret = device_rpc
+        .rpc_client()
+        .scoped_client::<WebServerServiceClientFactory<BaseController>>(1, 1, "".to_string())
+        .heartbeat(
+            BaseController::default(),
+            heartbeat_request(org_id, device_id, hostname),
+        )
+        .await;
+    (device_rpc, ret)
+}
+
+/// Record a migration that has no migration file, which makes `Migrator::up` fail
+#[allow(dead_code)]
+pub async fn break_migration_state(test_name: &str) {
+    use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    db.orm()
+        .execute(Statement::from_string(
+            DatabaseBackend::MySql,
+            "INSERT INTO seaql_migrations (version, applied_at) \
+             VALUES ('m29991231_000000_missing_migration', 0)",
+        ))
+        .await
+        .expect("Failed to insert bogus migration record");
+}
+
 /// Macro to setup test database for a test function
 #[allow(unused_macros)]
 macro_rules! setup_test_db {
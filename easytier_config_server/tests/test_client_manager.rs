@@ -1,7 +1,10 @@
 //! Client manager integration tests with isolated databases for concurrent testing
 //!
 //! Each test uses an isolated database for true concurrent testing.
-use easytier_config_server::client_manager::ClientManager;
+use easytier_config_server::client_manager::{
+    get_listener_by_url, get_stack_listener, is_dual_stack_protocol, ClientManager, Error,
+    StackPreference,
+};
 use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
 use std::sync::Arc;
 use url::Url;
@@ -498,7 +501,9 @@ async fn test_heartbeat_requests() {
     let client_url = test_client_url();
 
     // Test getting heartbeat requests for non-existent client
-    let heartbeat = client_manager.get_heartbeat_requests(&client_url).await;
+    let heartbeat = client_manager
+        .get_heartbeat_requests(&client_url, None)
+        .await;
 
     assert!(
         heartbeat.is_none(),
@@ -514,6 +519,129 @@ async fn test_heartbeat_requests() {
         .expect("Failed to remove test database");
 }
 
+#[tokio::test]
+async fn test_heartbeat_requests_max_age() {
+    use easytier::tunnel::tcp::TcpTunnelListener;
+    use std::time::Duration;
+
+    let test_name = "test_heartbeat_requests_max_age";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+    client_manager
+        .add_listener(Box::new(TcpTunnelListener::new(
+            "tcp://127.0.0.1:54550".parse().unwrap(),
+        )))
+        .await
+        .unwrap();
+
+    // A device that reports once and then goes quiet without disconnecting
+    let (device_rpc, ret) =
+        connect_device(54550, &org_id, uuid::Uuid::new_v4(), "quiet-device").await;
+    ret.expect("Heartbeat should be accepted");
+
+    let sessions = client_manager.list_sessions().await;
+    assert_eq!(sessions.len(), 1, "Should have exactly one session");
+    let client_url = sessions[0].client_url.clone();
+    assert!(client_manager
+        .get_heartbeat_requests(&client_url, Some(Duration::from_secs(60)))
+        .await
+        .is_some());
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    // The session is still alive, but its heartbeat is older than requested
+    assert!(
+        client_manager
+            .get_heartbeat_requests(&client_url, Some(Duration::from_secs(1)))
+            .await
+            .is_none(),
+        "Stale heartbeat should not be returned to a fresh-only query"
+    );
+    let heartbeat = client_manager
+        .get_heartbeat_requests(&client_url, None)
+        .await
+        .expect("Unbounded query should return the stale heartbeat");
+    assert_eq!(heartbeat.hostname, "quiet-device");
+
+    drop(device_rpc);
+    client_manager.shutdown().await;
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_snapshot_all_heartbeats() {
+    use easytier::tunnel::tcp::TcpTunnelListener;
+
+    let test_name = "test_snapshot_all_heartbeats";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+    client_manager
+        .add_listener(Box::new(TcpTunnelListener::new(
+            "tcp://127.0.0.1:54560".parse().unwrap(),
+        )))
+        .await
+        .unwrap();
+
+    assert!(client_manager.snapshot_all_heartbeats().await.is_empty());
+
+    let (device_a, ret) = connect_device(54560, &org_id, uuid::Uuid::new_v4(), "snapshot-a").await;
+    ret.expect("Heartbeat should be accepted");
+    let (device_b, ret) = connect_device(54560, &org_id, uuid::Uuid::new_v4(), "snapshot-b").await;
+    ret.expect("Heartbeat should be accepted");
+
+    let snapshot = client_manager.snapshot_all_heartbeats().await;
+    assert_eq!(snapshot.len(), 2, "Both sessions should be in one snapshot");
+    let mut hostnames: Vec<_> = snapshot
+        .iter()
+        .map(|(_, req, _)| req.hostname.as_str())
+        .collect();
+    hostnames.sort();
+    assert_eq!(hostnames, vec!["snapshot-a", "snapshot-b"]);
+
+    // Each entry carries the URL of its own session
+    let mut client_urls: Vec<_> = client_manager
+        .list_sessions()
+        .await
+        .into_iter()
+        .map(|token| token.client_url)
+        .collect();
+    client_urls.sort();
+    let mut snapshot_urls: Vec<_> = snapshot.into_iter().map(|(url, _, _)| url).collect();
+    snapshot_urls.sort();
+    assert_eq!(snapshot_urls, client_urls);
+
+    drop(device_a);
+    drop(device_b);
+    client_manager.shutdown().await;
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
 #[tokio::test]
 async fn test_machine_location() {
     let db = get_test_database("test_machine_location")
@@ -673,3 +801,1023 @@ async fn test_concurrent_session_access() {
         .await
         .expect("Failed to remove test database");
 }
+
+#[test]
+fn test_get_listener_by_url_schemes() {
+    // wss is served by the TLS websocket listener
+    let wss_url = Url::parse("wss://0.0.0.0:11443").unwrap();
+    assert!(
+        get_listener_by_url(&wss_url).is_ok(),
+        "wss:// listener should be supported"
+    );
+
+    let ws_url = Url::parse("ws://0.0.0.0:11080").unwrap();
+    assert!(get_listener_by_url(&ws_url).is_ok());
+
+    // Unknown schemes are rejected with InvalidUrl
+    let unknown_url = Url::parse("foo://0.0.0.0:11020").unwrap();
+    assert!(matches!(
+        get_listener_by_url(&unknown_url),
+        Err(Error::InvalidUrl(_))
+    ));
+
+    assert!(is_dual_stack_protocol("tcp"));
+    assert!(is_dual_stack_protocol(" UDP "));
+    assert!(is_dual_stack_protocol("wss"));
+    assert!(!is_dual_stack_protocol("ws"));
+}
+
+#[tokio::test]
+async fn test_list_listeners_after_start() {
+    let test_name = "test_list_listeners_after_start";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+    assert!(client_manager.list_listeners().is_empty());
+
+    client_manager
+        .start("tcp", 54370)
+        .await
+        .expect("Failed to start tcp listener");
+
+    let listeners = client_manager.list_listeners();
+    assert!(
+        listeners
+            .iter()
+            .any(|l| l.protocol == "tcp" && l.port == 54370 && l.ip_version == "ipv4"),
+        "tcp listener should be listed: {:?}",
+        listeners
+    );
+
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_sessions_per_listener() {
+    use easytier::tunnel::{
+        common::tests::wait_for_condition,
+        tcp::{TcpTunnelConnector, TcpTunnelListener},
+        TunnelConnector,
+    };
+
+    let test_name = "test_sessions_per_listener";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+    assert_eq!(client_manager.session_count(), 0);
+    assert!(client_manager.sessions_per_listener().is_empty());
+
+    // Listener 1 on 54390, listener 2 on 54391
+    for port in [54390, 54391] {
+        client_manager
+            .add_listener(TcpTunnelListener::new(
+                format!("tcp://0.0.0.0:{}", port).parse().unwrap(),
+            ))
+            .await
+            .expect("Failed to add listener");
+    }
+
+    // One session on the first listener, two on the second
+    let mut tunnels = Vec::new();
+    for port in [54390, 54391, 54391] {
+        let mut connector =
+            TcpTunnelConnector::new(format!("tcp://127.0.0.1:{}", port).parse().unwrap());
+        tunnels.push(connector.connect().await.expect("Should connect"));
+    }
+
+    wait_for_condition(
+        || async { client_manager.session_count() == 3 },
+        std::time::Duration::from_secs(10),
+    )
+    .await;
+
+    let counts = client_manager.sessions_per_listener();
+    assert_eq!(counts.len(), 2, "Sessions should span two listeners");
+    assert_eq!(counts.get(&1), Some(&1));
+    assert_eq!(counts.get(&2), Some(&2));
+
+    drop(tunnels);
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_custom_session_rx_timeout() {
+    use easytier_config_server::client_manager::session::{Session, DEFAULT_SESSION_RX_TIMEOUT};
+    use std::time::Duration;
+
+    let test_name = "test_custom_session_rx_timeout";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let db_url = get_test_database_url(test_name);
+    let default_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+    assert_eq!(
+        default_manager.session_rx_timeout(),
+        DEFAULT_SESSION_RX_TIMEOUT
+    );
+    assert_eq!(DEFAULT_SESSION_RX_TIMEOUT, Duration::from_secs(30));
+
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager")
+        .with_session_rx_timeout(Duration::from_secs(90));
+    assert_eq!(client_manager.session_rx_timeout(), Duration::from_secs(90));
+
+    // Sessions are created with the timeout handed down by the manager
+    let session = Session::new_with_rx_timeout(
+        client_manager.storage().weak_ref(),
+        test_client_url(),
+        None,
+        client_manager.session_rx_timeout(),
+    );
+    assert_eq!(session.rx_timeout(), Duration::from_secs(90));
+
+    let default_session =
+        Session::new(client_manager.storage().weak_ref(), test_client_url(), None);
+    assert_eq!(default_session.rx_timeout(), DEFAULT_SESSION_RX_TIMEOUT);
+
+    drop(default_manager);
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+mod fake_listener {
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+
+    use easytier::proto::common::TunnelInfo;
+    use easytier::tunnel::{Tunnel, TunnelError, TunnelListener, ZCPacketSink, ZCPacketStream};
+
+    /// Tunnel wrapper reporting a fixed (possibly missing) tunnel info
+    pub struct FakeTunnel {
+        pub inner: Box<dyn Tunnel>,
+        pub info: Option<TunnelInfo>,
+    }
+
+    impl Tunnel for FakeTunnel {
+        fn split(&self) -> (Pin<Box<dyn ZCPacketStream>>, Pin<Box<dyn ZCPacketSink>>) {
+            self.inner.split()
+        }
+
+        fn info(&self) -> Option<TunnelInfo> {
+            self.info.clone()
+        }
+    }
+
+    /// Listener that hands out queued tunnels, then waits forever
+    pub struct FakeTunnelListener {
+        pub tunnels: VecDeque<Box<dyn Tunnel>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TunnelListener for FakeTunnelListener {
+        async fn listen(&mut self) -> Result<(), TunnelError> {
+            Ok(())
+        }
+
+        async fn accept(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+            match self.tunnels.pop_front() {
+                Some(tunnel) => Ok(tunnel),
+                None => std::future::pending().await,
+            }
+        }
+
+        fn local_url(&self) -> url::Url {
+            "tcp://127.0.0.1:54430".parse().unwrap()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_listener_survives_tunnel_without_info() {
+    use easytier::proto::common::TunnelInfo;
+    use easytier::tunnel::{common::tests::wait_for_condition, ring::create_ring_tunnel_pair};
+    use fake_listener::{FakeTunnel, FakeTunnelListener};
+
+    let test_name = "test_listener_survives_tunnel_without_info";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+
+    // First tunnel reports no info at all, second one is a regular client
+    let (bad_server, bad_client) = create_ring_tunnel_pair();
+    let (good_server, good_client) = create_ring_tunnel_pair();
+    let good_info = TunnelInfo {
+        tunnel_type: "tcp".to_string(),
+        local_addr: Some(Url::parse("tcp://127.0.0.1:54430").unwrap().into()),
+        remote_addr: Some(Url::parse("tcp://10.0.0.2:40000").unwrap().into()),
+        ..Default::default()
+    };
+    let listener = FakeTunnelListener {
+        tunnels: [
+            Box::new(FakeTunnel {
+                inner: bad_server,
+                info: None,
+            }) as Box<dyn easytier::tunnel::Tunnel>,
+            Box::new(FakeTunnel {
+                inner: good_server,
+                info: Some(good_info),
+            }),
+        ]
+        .into(),
+    };
+
+    client_manager
+        .add_listener(listener)
+        .await
+        .expect("Failed to add listener");
+
+    wait_for_condition(
+        || async { client_manager.session_count() == 1 },
+        std::time::Duration::from_secs(10),
+    )
+    .await;
+    assert!(
+        client_manager.is_running(),
+        "Listener should keep running after a bad tunnel"
+    );
+    assert_eq!(client_manager.sessions_per_listener().get(&1), Some(&1));
+
+    drop((bad_client, good_client));
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_start_reports_port_in_use() {
+    use easytier_config_server::client_manager::{BindErrorKind, StartError};
+
+    let test_name = "test_start_reports_port_in_use";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    // Occupy the IPv4 port before the manager tries to bind it
+    let blocker = std::net::TcpListener::bind("0.0.0.0:54440").expect("Failed to bind blocker");
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+
+    match client_manager.start("tcp", 54440).await {
+        // IPv6 bound on its own socket, only IPv4 failed
+        Ok(report) => {
+            assert!(report.is_partial(), "Start should be partial: {:?}", report);
+            assert!(report
+                .bound
+                .iter()
+                .all(|url| url.host_str() != Some("0.0.0.0")));
+            let v4_failure = report
+                .failed
+                .iter()
+                .find(|f| f.url.host_str() == Some("0.0.0.0"))
+                .expect("IPv4 listener should be reported as failed");
+            assert_eq!(v4_failure.kind, BindErrorKind::AddrInUse);
+        }
+        // No usable IPv6 (or dual-stack bind), nothing could be bound
+        Err(e) => {
+            let start_error = e
+                .downcast_ref::<StartError>()
+                .expect("Error should be a StartError");
+            assert!(
+                start_error
+                    .failed
+                    .iter()
+                    .any(|f| f.kind == BindErrorKind::AddrInUse),
+                "Failure should report the port in use: {:?}",
+                start_error
+            );
+        }
+    }
+
+    drop(blocker);
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_cleanup_sessions_now() {
+    use easytier::tunnel::{
+        common::tests::wait_for_condition,
+        tcp::{TcpTunnelConnector, TcpTunnelListener},
+        TunnelConnector,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    let test_name = "test_cleanup_sessions_now";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let db_url = get_test_database_url(test_name);
+    let mut client_manager = ClientManager::new(&db_url, None)
+        .await
+        .expect("Failed to create ClientManager");
+    client_manager
+        .add_listener(TcpTunnelListener::new(
+            "tcp://0.0.0.0:54450".parse().unwrap(),
+        ))
+        .await
+        .expect("Failed to add listener");
+
+    // A dead session: the client disconnects right away
+    let mut connector = TcpTunnelConnector::new("tcp://127.0.0.1:54450".parse().unwrap());
+    let tunnel = connector.connect().await.expect("Should connect");
+    wait_for_condition(
+        || async { client_manager.session_count() == 1 },
+        Duration::from_secs(10),
+    )
+    .await;
+    drop(tunnel);
+
+    let removed = AtomicUsize::new(0);
+    wait_for_condition(
+        || async {
+            removed.fetch_add(
+                client_manager.cleanup_sessions_now(None).await,
+                Ordering::Relaxed,
+            );
+            client_manager.session_count() == 0
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(
+        removed.load(Ordering::Relaxed),
+        1,
+        "Dead session should be removed once"
+    );
+
+    // A live session is kept unless it exceeds the idle threshold
+    let mut connector = TcpTunnelConnector::new("tcp://127.0.0.1:54450".parse().unwrap());
+    let tunnel = connector.connect().await.expect("Should connect");
+    wait_for_condition(
+        || async { client_manager.session_count() == 1 },
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(client_manager.cleanup_sessions_now(None).await, 0);
+    assert_eq!(
+        client_manager
+            .cleanup_sessions_now(Some(Duration::from_secs(3600)))
+            .await,
+        0
+    );
+    assert_eq!(
+        client_manager
+            .cleanup_sessions_now(Some(Duration::ZERO))
+            .await,
+        1,
+        "Session without heartbeat should count as idle"
+    );
+    assert_eq!(client_manager.session_count(), 0);
+
+    drop(tunnel);
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_v4_only_stack_selection() {
+    let (v6_listener, v4_listener) = get_stack_listener("tcp", 54520, StackPreference::V4Only)
+        .await
+        .expect("IPv4 listener should be created");
+    assert!(
+        v6_listener.is_none(),
+        "V4Only should not create an IPv6 listener"
+    );
+    let v4_listener = v4_listener.expect("V4Only should create an IPv4 listener");
+    assert_eq!(v4_listener.local_url().host_str(), Some("0.0.0.0"));
+
+    let test_name = "test_v4_only_stack_selection";
+    get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    let mut client_manager = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create ClientManager");
+    let report = client_manager
+        .start_with_stack("tcp", 54520, StackPreference::V4Only)
+        .await
+        .expect("IPv4 listener should bind");
+    assert_eq!(report.bound.len(), 1);
+    assert!(client_manager
+        .list_listeners()
+        .iter()
+        .all(|l| l.ip_version == "ipv4"));
+
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_v6_only_stack_selection() {
+    let result = get_stack_listener("tcp", 54521, StackPreference::V6Only).await;
+    if easytier::common::network::local_ipv6().await.is_ok() {
+        let (v6_listener, v4_listener) = result.expect("IPv6 listener should be created");
+        assert!(
+            v4_listener.is_none(),
+            "V6Only should not create an IPv4 listener"
+        );
+        let v6_listener = v6_listener.expect("V6Only should create an IPv6 listener");
+        assert_eq!(v6_listener.local_url().host_str(), Some("[::]"));
+    } else {
+        assert!(
+            result.is_err(),
+            "V6Only should fail when IPv6 is unavailable"
+        );
+    }
+
+    // Websocket listeners only bind IPv4
+    assert!(get_stack_listener("ws", 54522, StackPreference::V6Only)
+        .await
+        .is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_listener_accepts_connection() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket_path = dir.path().join("config_server.sock");
+
+    // A socket file left behind by a previous run
+    drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+    assert!(socket_path.exists());
+
+    let url = Url::parse(&format!("unix://{}", socket_path.display())).unwrap();
+    let mut listener = get_listener_by_url(&url).expect("unix:// should be supported");
+    listener
+        .listen()
+        .await
+        .expect("The stale socket file should be replaced");
+
+    let _client = tokio::net::UnixStream::connect(&socket_path).await.unwrap();
+    let tunnel = tokio::time::timeout(std::time::Duration::from_secs(5), listener.accept())
+        .await
+        .expect("Accept should not time out")
+        .expect("The loopback connection should be accepted");
+    let info = tunnel.info().expect("Tunnel should report its info");
+    assert_eq!(info.tunnel_type, "unix");
+    assert!(
+        info.remote_addr.is_some(),
+        "Sessions are keyed by the remote address"
+    );
+
+    drop(listener);
+    assert!(
+        !socket_path.exists(),
+        "The socket file should be removed with the listener"
+    );
+}
+
+#[test]
+fn test_rapid_reconnects_are_throttled_per_host() {
+    use easytier_config_server::client_manager::rate_limit::{
+        ConnectionRateLimit, ConnectionRateLimiter,
+    };
+    use std::time::{Duration, Instant};
+
+    let limiter = ConnectionRateLimiter::new(ConnectionRateLimit {
+        burst: 3,
+        per_second: 1.0,
+    });
+    let start = Instant::now();
+
+    // A crash-looping host exhausts its burst
+    let accepted = (0..10)
+        .filter(|_| limiter.try_acquire_at("10.0.0.1", start))
+        .count();
+    assert_eq!(accepted, 3);
+
+    // A host connecting at a normal rate is unaffected
+    for i in 0..10 {
+        let now = start + Duration::from_secs(i * 2);
+        assert!(limiter.try_acquire_at("10.0.0.2", now));
+    }
+
+    // The throttled host regains a token per second
+    assert!(!limiter.try_acquire_at("10.0.0.1", start + Duration::from_millis(500)));
+    assert!(limiter.try_acquire_at("10.0.0.1", start + Duration::from_secs(2)));
+}
+
+#[test]
+fn test_tracked_hosts_are_capped_when_all_are_throttled() {
+    use easytier_config_server::client_manager::rate_limit::{
+        ConnectionRateLimit, ConnectionRateLimiter, MAX_TRACKED_HOSTS,
+    };
+    use std::time::{Duration, Instant};
+
+    let limiter = ConnectionRateLimiter::new(ConnectionRateLimit {
+        burst: 1,
+        per_second: 0.001,
+    });
+    let start = Instant::now();
+
+    // Every host empties its bucket, so none can be pruned as refilled
+    for i in 0..MAX_TRACKED_HOSTS + 100 {
+        let now = start + Duration::from_millis(i as u64);
+        assert!(limiter.try_acquire_at(&format!("host-{}", i), now));
+        assert!(limiter.tracked_hosts() <= MAX_TRACKED_HOSTS);
+    }
+
+    // The most recent hosts are still throttled
+    let last = MAX_TRACKED_HOSTS + 99;
+    assert!(!limiter.try_acquire_at(
+        &format!("host-{}", last),
+        start + Duration::from_millis(last as u64)
+    ));
+}
+
+#[tokio::test]
+async fn test_listener_drops_connections_over_the_limit() {
+    use easytier::tunnel::{
+        common::tests::wait_for_condition, tcp::TcpTunnelConnector, TunnelConnector,
+    };
+    use easytier_config_server::client_manager::rate_limit::ConnectionRateLimit;
+    use std::time::Duration;
+
+    let test_name = "listener_drops_connections_over_the_limit";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+
+    let mut client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap()
+        .with_connection_rate_limit(ConnectionRateLimit {
+            burst: 1,
+            per_second: 0.001,
+        });
+    client_mgr.start("tcp", 54510).await.unwrap();
+
+    let mut connector = TcpTunnelConnector::new("tcp://127.0.0.1:54510".parse().unwrap());
+    let _first = connector.connect().await.unwrap();
+    wait_for_condition(
+        || async { client_mgr.session_count() == 1 },
+        Duration::from_secs(5),
+    )
+    .await;
+
+    // Reconnects from the same host are dropped without creating sessions
+    let _second = connector.connect().await.unwrap();
+    let _third = connector.connect().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(client_mgr.session_count(), 1);
+
+    client_mgr.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_clients_are_not_throttled() {
+    use easytier::tunnel::common::tests::wait_for_condition;
+    use easytier_config_server::client_manager::rate_limit::ConnectionRateLimit;
+    use easytier_config_server::client_manager::unix_listener::UnixTunnelListener;
+    use std::time::Duration;
+
+    let test_name = "unix_socket_clients_are_not_throttled";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+
+    let mut client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap()
+        .with_connection_rate_limit(ConnectionRateLimit {
+            burst: 1,
+            per_second: 0.001,
+        });
+    let dir = tempfile::tempdir().unwrap();
+    let socket_path = dir.path().join("config_server.sock");
+    let url = format!("unix://{}", socket_path.display()).parse().unwrap();
+    client_mgr
+        .add_listener(UnixTunnelListener::new(url))
+        .await
+        .unwrap();
+
+    // Local peers share no host, each of them gets a session
+    let mut clients = vec![];
+    for _ in 0..3 {
+        clients.push(tokio::net::UnixStream::connect(&socket_path).await.unwrap());
+    }
+    wait_for_condition(
+        || async { client_mgr.session_count() == 3 },
+        Duration::from_secs(5),
+    )
+    .await;
+
+    drop(clients);
+    client_mgr.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+mod capture_layer {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::layer::Context;
+    use tracing_subscriber::Layer;
+
+    pub type CapturedEvents = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+    /// Records the fields of every event as strings
+    pub struct CaptureLayer {
+        pub events: CapturedEvents,
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for CaptureLayer {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = HashMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.events.lock().unwrap().push(fields);
+        }
+    }
+
+    pub fn find_event(events: &CapturedEvents, name: &str) -> Option<HashMap<String, String>> {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|fields| fields.get("event").map(String::as_str) == Some(name))
+            .cloned()
+    }
+}
+
+#[tokio::test]
+async fn test_connect_event_has_structured_fields() {
+    use capture_layer::{find_event, CaptureLayer, CapturedEvents};
+    use easytier::tunnel::{
+        common::tests::wait_for_condition,
+        tcp::{TcpTunnelConnector, TcpTunnelListener},
+        TunnelConnector,
+    };
+    use std::time::Duration;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let test_name = "connect_event_has_structured_fields";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    drop(db);
+
+    // The current-thread test runtime keeps spawned tasks on this thread,
+    // so a thread-local default subscriber sees the accept loop's events
+    let events: CapturedEvents = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let subscriber = tracing_subscriber::registry().with(CaptureLayer {
+        events: events.clone(),
+    });
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut client_manager = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create ClientManager");
+    client_manager
+        .add_listener(TcpTunnelListener::new(
+            "tcp://0.0.0.0:54380".parse().unwrap(),
+        ))
+        .await
+        .unwrap();
+
+    let mut connector = TcpTunnelConnector::new("tcp://127.0.0.1:54380".parse().unwrap());
+    let _tunnel = connector.connect().await.expect("Should connect");
+
+    wait_for_condition(
+        || async { find_event(&events, "client_connected").is_some() },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let fields = find_event(&events, "client_connected").unwrap();
+    assert!(
+        fields
+            .get("client_url")
+            .is_some_and(|url| url.starts_with("tcp://127.0.0.1:")),
+        "client_url field should hold the remote address, got {:?}",
+        fields
+    );
+    assert_eq!(
+        fields.get("listener_id").map(String::as_str),
+        Some("1"),
+        "listener_id field should identify the accepting listener"
+    );
+
+    client_manager.shutdown().await;
+
+    // 删除测试数据库
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+/// Write a self-signed certificate and its key as PEM files
+fn write_cert(dir: &tempfile::TempDir) -> (String, String) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = dir.path().join("cert.pem");
+    let key_path = dir.path().join("key.pem");
+    std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+    (
+        cert_path.to_string_lossy().into_owned(),
+        key_path.to_string_lossy().into_owned(),
+    )
+}
+
+fn wss_url(host: &str, port: u16, cert: &str, key: &str) -> Url {
+    let mut url: Url = format!("wss://{}:{}", host, port).parse().unwrap();
+    url.query_pairs_mut()
+        .append_pair("cert", cert)
+        .append_pair("key", key);
+    url
+}
+
+#[tokio::test]
+async fn test_wss_listener_serves_configured_cert() {
+    use easytier::tunnel::{websocket::WSTunnelConnector, TunnelConnector, TunnelListener};
+    use easytier_config_server::client_manager::wss_listener::{WssCertPaths, WssTunnelListener};
+
+    let dir = tempfile::tempdir().unwrap();
+    let (cert, key) = write_cert(&dir);
+
+    let mut listener = WssTunnelListener::new(wss_url("127.0.0.1", 54570, &cert, &key)).unwrap();
+    assert_eq!(
+        listener.cert_paths(),
+        Some(&WssCertPaths {
+            cert: cert.clone(),
+            key: key.clone(),
+        })
+    );
+    listener.listen().await.expect("wss listener should bind");
+
+    let mut connector = WSTunnelConnector::new("wss://127.0.0.1:54570".parse().unwrap());
+    let (accepted, connected) = tokio::join!(listener.accept(), connector.connect());
+    let accepted = accepted.expect("TLS websocket connection should be accepted");
+    connected.expect("Device should connect over wss");
+    assert_eq!(accepted.info().unwrap().tunnel_type, "wss");
+}
+
+#[tokio::test]
+async fn test_wss_listener_binds_ipv4_and_ipv6_separately() {
+    use easytier::tunnel::TunnelListener;
+    use easytier_config_server::client_manager::wss_listener::WssTunnelListener;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (cert, key) = write_cert(&dir);
+
+    let mut v4 = WssTunnelListener::new(wss_url("0.0.0.0", 54571, &cert, &key)).unwrap();
+    v4.listen().await.expect("IPv4 wss listener should bind");
+
+    let mut v6 = WssTunnelListener::new(wss_url("[::]", 54571, &cert, &key)).unwrap();
+    match v6.listen().await {
+        Ok(()) => {}
+        Err(e) if easytier::common::network::local_ipv6().await.is_err() => {
+            println!("⚠️ Skipping IPv6 check, IPv6 is not available: {}", e);
+        }
+        Err(e) => panic!("IPv6 wss listener should bind next to IPv4: {}", e),
+    }
+}
+
+#[tokio::test]
+async fn test_wss_listener_rejects_missing_cert() {
+    use easytier::tunnel::TunnelListener;
+    use easytier_config_server::client_manager::wss_listener::WssTunnelListener;
+
+    let mut listener = WssTunnelListener::new(wss_url(
+        "127.0.0.1",
+        54572,
+        "/nonexistent/cert.pem",
+        "/nonexistent/key.pem",
+    ))
+    .unwrap();
+    assert!(listener.listen().await.is_err());
+}
+
+#[test]
+fn test_wss_listener_rejects_half_specified_cert() {
+    use easytier_config_server::client_manager::wss_listener::{WssCertPaths, WssTunnelListener};
+
+    let cert_only: Url = "wss://127.0.0.1:54573?cert=/path/cert.pem".parse().unwrap();
+    assert!(WssCertPaths::from_url(&cert_only).is_err());
+    assert!(WssTunnelListener::new(cert_only.clone()).is_err());
+    assert!(matches!(
+        get_listener_by_url(&cert_only),
+        Err(Error::InvalidUrl(_))
+    ));
+
+    let key_only: Url = "wss://127.0.0.1:54573?key=/path/key.pem".parse().unwrap();
+    assert!(WssCertPaths::from_url(&key_only).is_err());
+
+    // Nothing configured falls back to the self-signed certificate
+    let plain: Url = "wss://127.0.0.1:54573".parse().unwrap();
+    assert_eq!(WssCertPaths::from_url(&plain), Ok(None));
+}
+
+#[tokio::test]
+async fn test_wss_listener_stalled_handshake_does_not_block_accept() {
+    use easytier::tunnel::{websocket::WSTunnelConnector, TunnelConnector, TunnelListener};
+    use easytier_config_server::client_manager::wss_listener::WssTunnelListener;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (cert, key) = write_cert(&dir);
+
+    let mut listener = WssTunnelListener::new(wss_url("127.0.0.1", 54574, &cert, &key)).unwrap();
+    listener.listen().await.expect("wss listener should bind");
+
+    // Opens TCP but never sends a ClientHello
+    let _stalled = tokio::net::TcpStream::connect("127.0.0.1:54574")
+        .await
+        .unwrap();
+
+    let mut connector = WSTunnelConnector::new("wss://127.0.0.1:54574".parse().unwrap());
+    let (accepted, connected) = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        tokio::join!(listener.accept(), connector.connect())
+    })
+    .await
+    .expect("A stalled client must not block later connections");
+    accepted.expect("TLS websocket connection should be accepted");
+    connected.expect("Device should connect over wss");
+}
+
+#[tokio::test]
+async fn test_disconnect_device_removes_session() {
+    use easytier_config_server::NetworkConfigService;
+
+    let test_name = "disconnect_device_removes_session";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+    service.start("tcp", 54500).await.expect("Failed to start");
+
+    // No session for an unknown device
+    assert!(!service
+        .disconnect_device(&org_id, &uuid::Uuid::new_v4())
+        .await
+        .unwrap());
+
+    let _web_client = start_web_client(54500, &org_id, "disconnect-host");
+    let (device_id, device) = wait_for_single_device(&service, &org_id).await;
+    let client_url = device.client_url.expect("Session should have a client url");
+
+    assert!(service
+        .disconnect_device(&org_id, &device_id)
+        .await
+        .unwrap());
+
+    // The client may reconnect, but never through the closed session
+    let client_urls = service
+        .list_devices(&org_id)
+        .await
+        .unwrap()
+        .devices
+        .into_iter()
+        .filter_map(|d| d.client_url)
+        .collect::<Vec<_>>();
+    assert!(
+        !client_urls.contains(&client_url),
+        "The disconnected session should be removed"
+    );
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_disconnect_device_closes_every_connection() {
+    use easytier::tunnel::{common::tests::wait_for_condition, tcp::TcpTunnelListener};
+    use std::time::Duration;
+
+    let test_name = "disconnect_device_closes_every_connection";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create ClientManager");
+    client_mgr
+        .add_listener(TcpTunnelListener::new(
+            "tcp://0.0.0.0:54501".parse().unwrap(),
+        ))
+        .await
+        .expect("Failed to add listener");
+
+    // Both clients report this host's machine id, like a device connected
+    // over IPv4 and IPv6 at the same time
+    let _web_clients =
+        ["dual-host-v4", "dual-host-v6"].map(|hostname| start_web_client(54501, &org_id, hostname));
+
+    let device_id: uuid::Uuid = wait_for_device_record(&db, &org_id)
+        .await
+        .id
+        .parse()
+        .unwrap();
+    wait_for_condition(
+        || async {
+            client_mgr
+                .storage()
+                .get_client_urls_by_device_id(&org_id, &device_id)
+                .len()
+                == 2
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    let client_urls = client_mgr
+        .storage()
+        .get_client_urls_by_device_id(&org_id, &device_id);
+    assert_eq!(client_urls.len(), 2, "Device should hold two connections");
+
+    assert!(client_mgr.close_device_session(&org_id, &device_id).await);
+
+    // The clients may reconnect, but never through the closed sessions
+    let remaining = client_mgr
+        .storage()
+        .get_client_urls_by_device_id(&org_id, &device_id);
+    for client_url in &client_urls {
+        assert!(
+            !remaining.contains(client_url),
+            "Session {} should be closed",
+            client_url
+        );
+    }
+
+    drop(client_mgr);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
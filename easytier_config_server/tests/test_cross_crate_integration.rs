@@ -73,6 +73,34 @@ mod cross_crate_tests {
         }
     }
 
+    #[test]
+    fn test_version_info_ffi() {
+        use easytier_config_server::{cortex_get_version_info, free_c_char};
+
+        let mut out_json: *mut std::ffi::c_char = ptr::null_mut();
+        let result = unsafe { cortex_get_version_info(&mut out_json) };
+        assert_eq!(result, 0);
+        assert!(!out_json.is_null());
+
+        let json = unsafe { std::ffi::CStr::from_ptr(out_json) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { free_c_char(out_json) };
+
+        let info: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(info["bridge_version"], env!("CARGO_PKG_VERSION"));
+        for field in ["easytier_version", "rerun_version", "build_profile"] {
+            assert!(
+                !info[field].as_str().unwrap_or_default().is_empty(),
+                "{} should be reported",
+                field
+            );
+        }
+
+        assert_eq!(unsafe { cortex_get_version_info(ptr::null_mut()) }, -1);
+    }
+
     #[test]
     fn test_device_client_and_gateway_independent() {
         // Test that device_client and gateway can be used independently
@@ -0,0 +1,353 @@
+//! Device query tests for NetworkConfigService
+//!
+//! Aggregate queries only read the devices table, so they work without
+//! started listeners. Listing connected devices and deleting a device go
+//! through the sessions and need the listeners.
+
+use easytier_config_server::config_srv::DeviceFilter;
+use easytier_config_server::db::entities::devices::{self, DeviceStatus};
+use easytier_config_server::{DeviceList, NetworkConfigService};
+use sea_orm::EntityTrait;
+
+#[path = "common/mod.rs"]
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_count_devices_by_status() {
+    let test_name = "count_devices_by_status";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+    let other_org_id = setup_test_organization(&db).await.unwrap();
+
+    insert_device(&db, &org_id, "Device", DeviceStatus::Pending).await;
+    insert_device(&db, &org_id, "Device", DeviceStatus::Pending).await;
+    insert_device(&db, &org_id, "Device", DeviceStatus::Online).await;
+    // Devices of other organizations must not be counted
+    insert_device(&db, &other_org_id, "Device", DeviceStatus::Online).await;
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let counts = service
+        .count_devices_by_status(&org_id)
+        .await
+        .expect("Should count devices");
+    assert_eq!(counts.len(), 2, "Unexpected counts: {:?}", counts);
+    assert_eq!(counts.get(&DeviceStatus::Pending), Some(&2));
+    assert_eq!(counts.get(&DeviceStatus::Online), Some(&1));
+
+    let json = serde_json::to_value(&counts).unwrap();
+    assert_eq!(json["Pending"], 2);
+    assert_eq!(json["Online"], 1);
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_list_devices_paginated() {
+    let test_name = "list_devices_paginated";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    for _ in 0..5 {
+        insert_device(&db, &org_id, "Device", DeviceStatus::Offline).await;
+    }
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let mut seen = Vec::new();
+    for (offset, expected_len) in [(0, 2), (2, 2), (4, 1)] {
+        let page = service
+            .list_devices_paginated(&org_id, offset, 2)
+            .await
+            .expect("Should list devices page");
+        assert_eq!(page.total, 5);
+        assert_eq!(page.devices.len(), expected_len, "offset {}", offset);
+        seen.extend(page.devices.into_iter().map(|d| d.id));
+    }
+
+    // Pages must not overlap
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 5);
+
+    let empty = service
+        .list_devices_paginated(&org_id, 6, 2)
+        .await
+        .expect("Should list past the end");
+    assert!(empty.devices.is_empty());
+    assert_eq!(empty.total, 5);
+
+    assert!(
+        service.list_devices_paginated(&org_id, 0, 0).await.is_err(),
+        "limit 0 should be rejected"
+    );
+
+    // Network configs carry the network secret and are left out of the page
+    {
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let secret_org_id = setup_test_organization(&db).await.unwrap();
+        let mut device = device_record(
+            uuid::Uuid::new_v4(),
+            &secret_org_id,
+            "Secret Device",
+            DeviceStatus::Online,
+        );
+        device.network_config = Set(Some(
+            serde_json::json!({"network_secret": "top-secret-value"}),
+        ));
+        device.insert(db.orm()).await.unwrap();
+
+        let page = service
+            .list_devices_paginated(&secret_org_id, 0, 10)
+            .await
+            .expect("Should list devices page");
+        assert_eq!(page.devices.len(), 1);
+        let json = serde_json::to_string(&page).unwrap();
+        assert!(
+            !json.contains("network_config"),
+            "Unexpected page: {}",
+            json
+        );
+        assert!(
+            !json.contains("top-secret-value"),
+            "Unexpected page: {}",
+            json
+        );
+    }
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_query_devices_by_status() {
+    let test_name = "query_devices_by_status";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    insert_device(&db, &org_id, "Pending Robot", DeviceStatus::Pending).await;
+    insert_device(&db, &org_id, "Online Robot", DeviceStatus::Online).await;
+    insert_device(&db, &org_id, "Another Online", DeviceStatus::Online).await;
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let filter: DeviceFilter = serde_json::from_str(r#"{"status": "Online"}"#).unwrap();
+    let devices = service
+        .query_devices(&org_id, &filter)
+        .await
+        .expect("Should query devices");
+    assert_eq!(devices.len(), 2);
+    assert!(devices.iter().all(|d| d.status == DeviceStatus::Online));
+
+    // The stored lowercase spelling filters the same way
+    let filter: DeviceFilter = serde_json::from_str(r#"{"status": "online"}"#).unwrap();
+    let lowercase = service
+        .query_devices(&org_id, &filter)
+        .await
+        .expect("Should query devices");
+    assert_eq!(lowercase.len(), 2);
+
+    let all = service
+        .query_devices(&org_id, &DeviceFilter::default())
+        .await
+        .expect("Should query devices without filter");
+    assert_eq!(all.len(), 3);
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_query_devices_by_name_substring() {
+    let test_name = "query_devices_by_name_substring";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    insert_device(&db, &org_id, "Warehouse Robot A", DeviceStatus::Online).await;
+    insert_device(&db, &org_id, "warehouse robot b", DeviceStatus::Pending).await;
+    insert_device(&db, &org_id, "Edge Gateway", DeviceStatus::Online).await;
+    insert_device(&db, &org_id, "100% Robot_X", DeviceStatus::Online).await;
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    // Case-insensitive match
+    let filter = DeviceFilter {
+        name: Some("WAREHOUSE".to_string()),
+        ..Default::default()
+    };
+    let devices = service.query_devices(&org_id, &filter).await.unwrap();
+    assert_eq!(devices.len(), 2, "Unexpected devices: {:?}", devices);
+
+    // Combined with a status filter
+    let filter = DeviceFilter {
+        status: Some(DeviceStatus::Pending),
+        name: Some("robot".to_string()),
+    };
+    let devices = service.query_devices(&org_id, &filter).await.unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].name, "warehouse robot b");
+
+    // LIKE wildcards are matched literally
+    let filter = DeviceFilter {
+        name: Some("0% robot_".to_string()),
+        ..Default::default()
+    };
+    let devices = service.query_devices(&org_id, &filter).await.unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].name, "100% Robot_X");
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_export_devices_csv_escapes_fields() {
+    let test_name = "export_devices_csv_escapes_fields";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    insert_device(&db, &org_id, "Plain Robot", DeviceStatus::Online).await;
+    insert_device(&db, &org_id, "Robot, \"Dock\" 2", DeviceStatus::Pending).await;
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let csv = service.export_devices_csv(&org_id).await.unwrap();
+    let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+    assert_eq!(lines.len(), 3, "Header plus one line per device: {}", csv);
+    assert_eq!(
+        lines[0],
+        "id,name,serial_number,device_type,status,last_heartbeat,created_at"
+    );
+
+    let plain = lines
+        .iter()
+        .find(|line| line.contains("Plain Robot"))
+        .unwrap();
+    assert!(plain.contains(",Plain Robot,"));
+    assert!(
+        plain.contains(",robot,online,,"),
+        "Unexpected line: {}",
+        plain
+    );
+
+    let escaped = lines.iter().find(|line| line.contains("Dock")).unwrap();
+    assert!(
+        escaped.contains(",\"Robot, \"\"Dock\"\" 2\","),
+        "Name with comma and quotes should be quoted: {}",
+        escaped
+    );
+    assert!(escaped.contains(",robot,pending,,"));
+
+    // Another organization's devices are not exported
+    let other_csv = service
+        .export_devices_csv(&uuid::Uuid::new_v4().to_string())
+        .await
+        .unwrap();
+    assert_eq!(other_csv.split_terminator("\r\n").count(), 1);
+}
+
+#[tokio::test]
+async fn test_list_devices_without_ffi() {
+    let test_name = "async_service_list_devices";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    // Session-based operations need the listeners first
+    assert!(service.list_devices(&org_id).await.is_err());
+
+    service.start("tcp", 54530).await.expect("Failed to start");
+
+    let devices: DeviceList = service.list_devices(&org_id).await.unwrap();
+    assert!(devices.devices.is_empty());
+
+    let _web_client = start_web_client(54530, &org_id, "async-host");
+    let (_, device) = wait_for_single_device(&service, &org_id).await;
+    let info = device
+        .info
+        .as_ref()
+        .expect("Device should have reported a heartbeat");
+    assert_eq!(info.hostname, "async-host");
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_delete_device_removes_session_and_record() {
+    let test_name = "delete_device_removes_session_and_record";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+    service.start("tcp", 54410).await.expect("Failed to start");
+
+    let web_client = start_web_client(54410, &org_id, "delete-device-host");
+    let (device_id, _) = wait_for_single_device(&service, &org_id).await;
+
+    // Stop the client so it does not reconnect and register the device again
+    drop(web_client);
+
+    service
+        .delete_device(&org_id, &device_id)
+        .await
+        .expect("Should delete device");
+
+    assert!(
+        service
+            .list_devices(&org_id)
+            .await
+            .unwrap()
+            .devices
+            .is_empty(),
+        "Deleted device should not be listed"
+    );
+    let record = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap();
+    assert!(record.is_none(), "Device record should be removed");
+
+    // Deleting again reports the device as missing
+    assert!(service.delete_device(&org_id, &device_id).await.is_err());
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
@@ -3,8 +3,10 @@
 //! This module tests the specific behaviors implemented for device status:
 //! - Only approved devices are marked offline on timeout
 //! - Pending and rejected devices maintain their status when not heartbeating
-//! - Offline devices return to approved when reconnecting
+//! - Offline devices return to the status they had before going offline when reconnecting
 //! - Rejected devices return to pending when reconnecting
+//! - New devices are auto-approved or get the configured default type
+//! - The serial number is seeded once and survives heartbeats
 
 use serial_test::serial;
 
@@ -561,3 +563,401 @@ async fn test_rejected_reconnect_becomes_pending_workflow() {
 
     cleanup_test_database(&db).await.unwrap();
 }
+
+/// Test that a status change made after the heartbeat read the device is not overwritten
+/// by the heartbeat's status transition
+#[tokio::test]
+#[serial]
+async fn test_concurrent_admin_status_change_survives_heartbeat_transition() {
+    use easytier_config_server::client_manager::storage::Storage;
+    use easytier_config_server::db::entities::devices;
+    use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+    let test_name = "concurrent_admin_status_change_survives_heartbeat_transition";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let device_id = uuid::Uuid::new_v4();
+    let device = devices::ActiveModel {
+        id: Set(device_id.to_string()),
+        name: Set("Racing Device".to_string()),
+        serial_number: Set(device_id.to_string()),
+        device_type: Set(devices::DeviceType::Robot),
+        organization_id: Set(Some(org_id.clone())),
+        status: Set(devices::DeviceStatus::Rejected),
+        created_at: Set(Utc::now().into()),
+        updated_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(db.orm())
+    .await
+    .unwrap();
+
+    // The heartbeat read the device as rejected, then an admin approves it
+    let mut active: devices::ActiveModel = device.into();
+    active.status = Set(devices::DeviceStatus::Online);
+    active.update(db.orm()).await.unwrap();
+
+    // The heartbeat's rejected -> pending transition must not apply anymore
+    let storage = Storage::new(db.clone());
+    let applied = storage
+        .transition_device_status(
+            &device_id.to_string(),
+            &devices::DeviceStatus::Rejected,
+            &devices::DeviceStatus::Pending,
+        )
+        .await
+        .unwrap();
+    assert!(!applied, "Transition from a stale status should not apply");
+
+    let status = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .unwrap()
+        .status;
+    assert_eq!(
+        status,
+        devices::DeviceStatus::Online,
+        "Admin status change should survive"
+    );
+
+    // A transition from the current status still applies
+    assert!(storage
+        .transition_device_status(
+            &device_id.to_string(),
+            &devices::DeviceStatus::Online,
+            &devices::DeviceStatus::Offline,
+        )
+        .await
+        .unwrap());
+
+    cleanup_test_database(&db).await.unwrap();
+}
+
+/// Test that an offline device reconnecting gets back the status it had before going
+/// offline instead of being approved
+#[tokio::test]
+#[serial]
+async fn test_pending_offline_reconnect_returns_to_pending() {
+    use easytier_config_server::client_manager::session::SessionRpcService;
+    use easytier_config_server::db::entities::devices;
+    use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+    let test_name = "pending_offline_reconnect_returns_to_pending";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let device_id = uuid::Uuid::new_v4();
+    devices::ActiveModel {
+        id: Set(device_id.to_string()),
+        name: Set("Pending Device".to_string()),
+        serial_number: Set(device_id.to_string()),
+        device_type: Set(devices::DeviceType::Robot),
+        organization_id: Set(Some(org_id.clone())),
+        status: Set(devices::DeviceStatus::Pending),
+        created_at: Set(Utc::now().into()),
+        updated_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(db.orm())
+    .await
+    .unwrap();
+
+    let client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap();
+
+    // The pending device goes offline
+    assert!(client_mgr
+        .storage()
+        .transition_device_status(
+            &device_id.to_string(),
+            &devices::DeviceStatus::Pending,
+            &devices::DeviceStatus::Offline,
+        )
+        .await
+        .unwrap());
+    let device = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(device.status, devices::DeviceStatus::Offline);
+    assert_eq!(device.previous_status, Some(devices::DeviceStatus::Pending));
+
+    // It reconnects
+    let session = Session::new(client_mgr.storage().weak_ref(), test_client_url(), None);
+    let rpc_service = SessionRpcService {
+        data: session.data().clone(),
+    };
+    rpc_service
+        .handle_heartbeat(HeartbeatRequest {
+            machine_id: Some(device_id.into()),
+            user_token: org_id.clone(),
+            hostname: "pending-device".to_string(),
+            easytier_version: "1.0.0".to_string(),
+            report_time: chrono::Utc::now().to_rfc3339(),
+            running_network_instances: vec![],
+            inst_id: None,
+        })
+        .await
+        .unwrap();
+
+    let device = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        device.status,
+        devices::DeviceStatus::Pending,
+        "Pending device should not be approved by reconnecting"
+    );
+    assert_eq!(device.previous_status, None);
+
+    cleanup_test_database(&db).await.unwrap();
+}
+
+#[test]
+fn test_auto_approval_policy_matches_orgs() {
+    use easytier_config_server::client_manager::storage::AutoApprovalPolicy;
+    use std::collections::HashSet;
+
+    assert!(!AutoApprovalPolicy::default().approves("org-a"));
+    assert!(AutoApprovalPolicy::AllOrgs.approves("org-a"));
+
+    let allowlist = AutoApprovalPolicy::Orgs(HashSet::from(["org-a".to_string()]));
+    assert!(allowlist.approves("org-a"));
+    assert!(!allowlist.approves("org-b"));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_first_heartbeat_creates_approved_device() {
+    use easytier_config_server::client_manager::storage::AutoApprovalPolicy;
+    use easytier_config_server::db::entities::devices;
+    use easytier_config_server::NetworkConfigService;
+    use std::collections::HashSet;
+
+    let test_name = "first_heartbeat_creates_approved_device";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new_with_auto_approval(
+        &get_test_database_url(test_name),
+        None,
+        AutoApprovalPolicy::Orgs(HashSet::from([org_id.clone()])),
+    )
+    .await
+    .expect("Failed to create NetworkConfigService");
+    service.start("tcp", 54460).await.expect("Failed to start");
+
+    let _web_client = start_web_client(54460, &org_id, "auto-approved-host");
+
+    let device = wait_for_device_record(&db, &org_id).await;
+    assert_eq!(device.serial_number, device.id);
+    assert!(
+        device.status.is_approved(),
+        "New device should be approved, got {:?}",
+        device.status
+    );
+    assert_ne!(device.status, devices::DeviceStatus::Pending);
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_new_device_gets_configured_default_type() {
+    use easytier_config_server::db::entities::devices;
+    use easytier_config_server::NetworkConfigService;
+
+    let test_name = "new_device_gets_configured_default_type";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService")
+        .with_default_device_type(devices::DeviceType::Edge);
+    service.start("tcp", 54480).await.expect("Failed to start");
+
+    let _web_client = start_web_client(54480, &org_id, "edge-gateway-host");
+
+    let device = wait_for_device_record(&db, &org_id).await;
+    assert_eq!(device.device_type, devices::DeviceType::Edge);
+    assert!(!device.is_robot());
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_admin_serial_number_survives_heartbeats() {
+    use easytier::tunnel::common::tests::wait_for_condition;
+    use easytier_config_server::db::entities::devices;
+    use easytier_config_server::NetworkConfigService;
+    use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+    use std::time::Duration;
+
+    let test_name = "admin_serial_number_survives_heartbeats";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+    service.start("tcp", 54470).await.expect("Failed to start");
+
+    let _web_client = start_web_client(54470, &org_id, "serial-host");
+
+    let device = wait_for_device_record(&db, &org_id).await;
+    assert_eq!(device.name, "serial-host");
+    assert_eq!(
+        device.serial_number, device.id,
+        "Serial number is seeded from the machine id"
+    );
+
+    // An admin records the real serial number
+    let device_id = device.id.clone();
+    let last_heartbeat = device.last_heartbeat;
+    let mut active: devices::ActiveModel = device.into();
+    active.serial_number = Set("SN-0001".to_string());
+    active.update(db.orm()).await.unwrap();
+
+    let find_device = || async {
+        devices::Entity::find_by_id(device_id.clone())
+            .one(db.orm())
+            .await
+            .unwrap()
+            .unwrap()
+    };
+    // Wait for a later heartbeat to be processed
+    wait_for_condition(
+        || async { find_device().await.last_heartbeat > last_heartbeat },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let device = find_device().await;
+    assert_eq!(
+        device.serial_number, "SN-0001",
+        "Heartbeats must not overwrite the serial number"
+    );
+    assert_eq!(device.name, "serial-host");
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_overlong_hostname_is_stored_truncated() {
+    use easytier_config_server::client_manager::session::SessionRpcService;
+    use easytier_config_server::db::entities::devices;
+    use sea_orm::EntityTrait;
+
+    let test_name = "overlong_hostname_is_stored_truncated";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap();
+    let session = Session::new(client_mgr.storage().weak_ref(), test_client_url(), None);
+    let rpc_service = SessionRpcService {
+        data: session.data().clone(),
+    };
+
+    let hostname = format!("robot\u{7}\t-{}", "x".repeat(300));
+    let device_id = uuid::Uuid::new_v4();
+    rpc_service
+        .handle_heartbeat(heartbeat_request(&org_id, device_id, &hostname))
+        .await
+        .expect("An overlong hostname should not fail the heartbeat");
+
+    let device = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .expect("Device should be created");
+    assert_eq!(device.name.chars().count(), 100);
+    assert!(device.name.starts_with("robot-xxx"));
+    assert!(device.name.ends_with('…'));
+    assert!(!device.name.chars().any(char::is_control));
+    assert_eq!(device.serial_number, device_id.to_string());
+
+    // The in-memory heartbeat keeps what the device reported
+    let req = session.data().read().await.req().unwrap();
+    assert_eq!(req.hostname, hostname);
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_devices_sharing_a_hostname_stay_distinct() {
+    use easytier_config_server::client_manager::session::SessionRpcService;
+    use easytier_config_server::db::entities::devices;
+    use sea_orm::EntityTrait;
+
+    let test_name = "devices_sharing_a_hostname_stay_distinct";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap();
+
+    // Two devices with the default image hostname join one after the other
+    let device_ids = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
+    let mut sessions = Vec::new();
+    for (i, device_id) in device_ids.iter().enumerate() {
+        let session = Session::new(
+            client_mgr.storage().weak_ref(),
+            format!("tcp://127.0.0.1:{}", 50010 + i).parse().unwrap(),
+            None,
+        );
+        let rpc_service = SessionRpcService {
+            data: session.data().clone(),
+        };
+        rpc_service
+            .handle_heartbeat(heartbeat_request(&org_id, *device_id, "raspberrypi"))
+            .await
+            .expect("Heartbeat should be accepted");
+        sessions.push(session);
+    }
+
+    for device_id in device_ids {
+        let device = devices::Entity::find_by_id(device_id.to_string())
+            .one(db.orm())
+            .await
+            .unwrap()
+            .expect("Each device should keep its own record");
+        assert_eq!(device.name, "raspberrypi");
+        assert_eq!(device.serial_number, device_id.to_string());
+    }
+
+    drop(sessions);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
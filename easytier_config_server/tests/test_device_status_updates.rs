@@ -4,6 +4,8 @@
 //! - Heartbeat handling and status transitions
 //! - Device timeout and offline marking
 //! - Status preservation during device edits
+//! - Bulk status updates
+//! - Retention of offline devices and background task health
 //! - Integration with cortex_server device service
 
 use serial_test::serial;
@@ -234,6 +236,7 @@ async fn test_device_status_transition_offline_to_approved() {
     };
 
     // Create a mock session and handle heartbeat
+    let mut device_events = client_mgr.storage().subscribe_device_events();
     let storage = client_mgr.storage().weak_ref();
     let session = Session::new(storage, client_url, None);
 
@@ -248,6 +251,20 @@ async fn test_device_status_transition_offline_to_approved() {
     // Process the heartbeat request - this should transition offline to approved
     let _response = rpc_service.handle_heartbeat(_heartbeat_req).await.unwrap();
 
+    // The transition must be published exactly once
+    {
+        use easytier_config_server::db::entities::devices::DeviceStatus;
+
+        let event = device_events
+            .try_recv()
+            .expect("Status transition should emit an event");
+        assert_eq!(event.device_id, device_id);
+        assert_eq!(event.organization_id, org_id);
+        assert_eq!(event.old_status, DeviceStatus::Offline);
+        assert_eq!(event.new_status, DeviceStatus::Online);
+        assert!(device_events.try_recv().is_err());
+    }
+
     // Test that heartbeat transitions offline device to approved
     {
         use easytier_config_server::db::entities::devices;
@@ -629,3 +646,323 @@ async fn test_concurrent_heartbeat_handling() {
 
     cleanup_test_database(&db).await.unwrap();
 }
+
+#[tokio::test]
+#[serial]
+async fn test_retention_pass_removes_only_stale_offline_devices() {
+    use easytier_config_server::db::entities::devices::{self, DeviceStatus};
+    use sea_orm::{ActiveModelTrait, EntityTrait};
+
+    let test_name = "retention_pass_removes_only_stale_offline_devices";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let stale_device_id = uuid::Uuid::new_v4();
+    let recent_device_id = uuid::Uuid::new_v4();
+    let online_device_id = uuid::Uuid::new_v4();
+
+    // Offline for 30 days - SHOULD be removed
+    device_last_seen(
+        stale_device_id,
+        &org_id,
+        DeviceStatus::Offline,
+        chrono::Duration::days(30),
+    )
+    .insert(db.orm())
+    .await
+    .unwrap();
+    // Offline but seen an hour ago - should NOT be removed
+    device_last_seen(
+        recent_device_id,
+        &org_id,
+        DeviceStatus::Offline,
+        chrono::Duration::hours(1),
+    )
+    .insert(db.orm())
+    .await
+    .unwrap();
+    // Approved with an old heartbeat but not offline - should NOT be removed
+    device_last_seen(
+        online_device_id,
+        &org_id,
+        DeviceStatus::Online,
+        chrono::Duration::days(30),
+    )
+    .insert(db.orm())
+    .await
+    .unwrap();
+
+    let mut client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap();
+
+    let removed = client_mgr
+        .run_device_retention_pass(chrono::Duration::days(7))
+        .await
+        .expect("Retention pass should succeed");
+    assert_eq!(removed, 1, "Only the stale device should be removed");
+
+    let stale = devices::Entity::find_by_id(stale_device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap();
+    assert!(stale.is_none(), "Stale offline device should be removed");
+
+    let recent = devices::Entity::find_by_id(recent_device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap();
+    assert!(recent.is_some(), "Recently seen device should be kept");
+
+    let online = devices::Entity::find_by_id(online_device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap();
+    assert!(online.is_some(), "Non-offline device should be kept");
+
+    client_mgr.shutdown().await;
+    cleanup_test_database(&db).await.unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_scheduled_retention_removes_stale_devices() {
+    use easytier::tunnel::common::tests::wait_for_condition;
+    use easytier_config_server::db::entities::devices::{self, DeviceStatus};
+    use sea_orm::{ActiveModelTrait, EntityTrait};
+    use std::time::Duration;
+
+    let test_name = "scheduled_retention_removes_stale_devices";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let stale_device_id = uuid::Uuid::new_v4();
+    let recent_device_id = uuid::Uuid::new_v4();
+    device_last_seen(
+        stale_device_id,
+        &org_id,
+        DeviceStatus::Offline,
+        chrono::Duration::days(30),
+    )
+    .insert(db.orm())
+    .await
+    .unwrap();
+    device_last_seen(
+        recent_device_id,
+        &org_id,
+        DeviceStatus::Offline,
+        chrono::Duration::hours(1),
+    )
+    .insert(db.orm())
+    .await
+    .unwrap();
+
+    // Retention is read once when the manager starts its background tasks
+    std::env::set_var("CORTEX_DEVICE_RETENTION_HOURS", "24");
+    std::env::set_var("CORTEX_DEVICE_RETENTION_CHECK_INTERVAL_SECS", "1");
+    let client_mgr = ClientManager::new(&get_test_database_url(test_name), None).await;
+    std::env::remove_var("CORTEX_DEVICE_RETENTION_HOURS");
+    std::env::remove_var("CORTEX_DEVICE_RETENTION_CHECK_INTERVAL_SECS");
+    let mut client_mgr = client_mgr.unwrap();
+
+    // No explicit pass: the background task must remove the stale device
+    wait_for_condition(
+        || async {
+            devices::Entity::find_by_id(stale_device_id.to_string())
+                .one(db.orm())
+                .await
+                .unwrap()
+                .is_none()
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let recent = devices::Entity::find_by_id(recent_device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap();
+    assert!(recent.is_some(), "Recently seen device should be kept");
+    assert!(client_mgr.is_healthy());
+
+    client_mgr.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_offline_task_failure_marks_unhealthy_until_recovery() {
+    use easytier::tunnel::common::tests::wait_for_condition;
+    use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+    use std::time::Duration;
+
+    let test_name = "offline_task_failure_marks_unhealthy";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    std::env::set_var("CORTEX_OFFLINE_CHECK_INTERVAL_SECS", "1");
+    let client_manager = ClientManager::new(&get_test_database_url(test_name), None).await;
+    std::env::remove_var("CORTEX_OFFLINE_CHECK_INTERVAL_SECS");
+    let mut client_manager = client_manager.expect("Failed to create ClientManager");
+    assert!(
+        client_manager.is_healthy(),
+        "A new manager should be healthy"
+    );
+
+    // Make every offline-marking query fail
+    db.orm()
+        .execute(Statement::from_string(
+            DatabaseBackend::MySql,
+            "RENAME TABLE devices TO devices_hidden",
+        ))
+        .await
+        .expect("Failed to hide devices table");
+
+    wait_for_condition(
+        || async { !client_manager.is_healthy() },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    // Once the database works again the next pass restores the flag
+    db.orm()
+        .execute(Statement::from_string(
+            DatabaseBackend::MySql,
+            "RENAME TABLE devices_hidden TO devices",
+        ))
+        .await
+        .expect("Failed to restore devices table");
+    wait_for_condition(
+        || async { client_manager.is_healthy() },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    client_manager.shutdown().await;
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+async fn device_status(
+    db: &easytier_config_server::Database,
+    device_id: &uuid::Uuid,
+) -> easytier_config_server::db::entities::devices::DeviceStatus {
+    use easytier_config_server::db::entities::devices;
+    use sea_orm::EntityTrait;
+
+    devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .unwrap()
+        .status
+}
+
+#[tokio::test]
+#[serial]
+async fn test_bulk_approve_pending_devices() {
+    use easytier_config_server::db::entities::devices::DeviceStatus;
+    use easytier_config_server::NetworkConfigService;
+
+    let test_name = "bulk_approve_pending_devices";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut pending = Vec::new();
+    for _ in 0..3 {
+        pending.push(insert_device(&db, &org_id, "Bulk Device", DeviceStatus::Pending).await);
+    }
+    let untouched = insert_device(&db, &org_id, "Bulk Device", DeviceStatus::Pending).await;
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let count = service
+        .bulk_set_device_status(&org_id, &pending, DeviceStatus::Online)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+
+    for device_id in &pending {
+        let status = device_status(&db, device_id).await;
+        assert!(
+            status.is_approved(),
+            "Device should be approved: {:?}",
+            status
+        );
+    }
+    assert_eq!(device_status(&db, &untouched).await, DeviceStatus::Pending);
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_bulk_status_rejects_foreign_device() {
+    use easytier_config_server::db::entities::devices::DeviceStatus;
+    use easytier_config_server::NetworkConfigService;
+
+    let test_name = "bulk_status_rejects_foreign_device";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+    let other_org_id = setup_test_organization(&db).await.unwrap();
+
+    let own = insert_device(&db, &org_id, "Bulk Device", DeviceStatus::Pending).await;
+    let foreign = insert_device(&db, &other_org_id, "Bulk Device", DeviceStatus::Pending).await;
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let result = service
+        .bulk_set_device_status(&org_id, &[own, foreign], DeviceStatus::Online)
+        .await;
+    assert!(
+        result.is_err(),
+        "A foreign device id should fail the update"
+    );
+
+    // Nothing was changed
+    assert_eq!(device_status(&db, &own).await, DeviceStatus::Pending);
+    assert_eq!(device_status(&db, &foreign).await, DeviceStatus::Pending);
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[test]
+fn test_device_status_accepts_both_spellings() {
+    use easytier_config_server::db::entities::devices::DeviceStatus;
+
+    // Variant name as in events and status counts, string value as stored
+    for spelling in ["Pending", "pending", "PENDING"] {
+        assert_eq!(
+            spelling.parse::<DeviceStatus>().unwrap(),
+            DeviceStatus::Pending
+        );
+        assert_eq!(
+            serde_json::from_value::<DeviceStatus>(serde_json::json!(spelling)).unwrap(),
+            DeviceStatus::Pending
+        );
+    }
+    assert_eq!(
+        serde_json::to_value(DeviceStatus::Pending).unwrap(),
+        "Pending"
+    );
+    assert!("approved".parse::<DeviceStatus>().is_err());
+}
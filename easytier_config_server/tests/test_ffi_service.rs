@@ -0,0 +1,236 @@
+//! FFI tests for the NetworkConfigService singleton
+//!
+//! The singleton and the last FFI error code are process-wide, so every test
+//! here runs serially. They cover creation from a JSON configuration, calls
+//! made before the listeners are started and the error codes of failed
+//! creation.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use easytier_config_server::client_manager::storage::AutoApprovalPolicy;
+use easytier_config_server::config_srv::ServiceConfig;
+use easytier_config_server::db::entities::devices::DeviceType;
+use easytier_config_server::{
+    cortex_get_last_error_code, create_network_config_service_singleton,
+    create_network_config_service_singleton_json, destroy_network_config_service_singleton,
+    free_c_char, network_config_service_check_migrations, network_config_service_list_devices,
+    network_config_service_organization_exists, network_config_service_query_devices,
+    network_config_service_rollback_migration, CortexErrorCode,
+};
+use serial_test::serial;
+
+#[path = "common/mod.rs"]
+mod common;
+use common::*;
+
+#[test]
+#[serial]
+fn test_create_singleton_from_json_config() {
+    let test_name = "create_singleton_from_json_config";
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let org_id = rt.block_on(async {
+        let db = get_test_database(test_name).await.unwrap();
+        cleanup_test_database(&db).await.unwrap();
+        setup_test_organization(&db).await.unwrap()
+    });
+
+    let config_json = serde_json::json!({
+        "db_url": format!(
+            "root:root123@tcp(127.0.0.1:3306)/{}",
+            create_test_db_name(test_name)
+        ),
+        "auto_approve_orgs": [org_id],
+        "default_device_type": "Edge",
+        "not_a_setting": 1,
+    })
+    .to_string();
+
+    // Optional fields are parsed, unknown keys are kept aside and ignored
+    let config = ServiceConfig::from_json(&config_json).unwrap();
+    assert!(config.geoip_path.is_none());
+    assert_eq!(config.default_device_type, Some(DeviceType::Edge));
+    assert!(matches!(
+        config.auto_approval_policy(),
+        AutoApprovalPolicy::Orgs(orgs) if orgs.contains(&org_id)
+    ));
+    assert!(config.unknown.contains_key("not_a_setting"));
+
+    unsafe {
+        // db_url is required
+        let missing_db_url = CString::new(r#"{"geoip_path": "GeoLite2-City.mmdb"}"#).unwrap();
+        let mut err_msg: *mut c_char = ptr::null_mut();
+        assert!(!create_network_config_service_singleton_json(
+            missing_db_url.as_ptr(),
+            &mut err_msg
+        ));
+        assert_eq!(
+            CortexErrorCode::from_c_int(cortex_get_last_error_code()),
+            Some(CortexErrorCode::InvalidArgument)
+        );
+        assert!(!err_msg.is_null());
+        let err = CStr::from_ptr(err_msg).to_string_lossy().to_string();
+        assert!(err.contains("db_url"), "Unexpected error: {}", err);
+        free_c_char(err_msg);
+        err_msg = ptr::null_mut();
+
+        let config_json = CString::new(config_json).unwrap();
+        assert!(
+            create_network_config_service_singleton_json(config_json.as_ptr(), &mut err_msg),
+            "Service creation should succeed"
+        );
+        assert!(err_msg.is_null());
+
+        assert!(destroy_network_config_service_singleton(&mut err_msg));
+    }
+
+    rt.block_on(remove_test_database(test_name)).unwrap();
+}
+
+#[test]
+#[serial]
+#[allow(deprecated)]
+fn test_session_calls_before_start_report_listeners_not_started() {
+    let test_name = "session_calls_before_start_report_listeners_not_started";
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let org_id = rt.block_on(async {
+        let db = get_test_database(test_name).await.unwrap();
+        cleanup_test_database(&db).await.unwrap();
+        setup_test_organization(&db).await.unwrap()
+    });
+    drop(rt);
+
+    let db_url = CString::new(format!(
+        "root:root123@tcp(127.0.0.1:3306)/{}",
+        create_test_db_name(test_name)
+    ))
+    .unwrap();
+    let c_org_id = CString::new(org_id).unwrap();
+
+    unsafe {
+        let mut err_msg: *mut c_char = ptr::null_mut();
+        assert!(
+            create_network_config_service_singleton(db_url.as_ptr(), ptr::null(), &mut err_msg),
+            "Service creation should succeed"
+        );
+
+        // Session-dependent call before start
+        let mut result_json: *mut c_char = ptr::null_mut();
+        let ok =
+            network_config_service_list_devices(c_org_id.as_ptr(), &mut result_json, &mut err_msg);
+        assert!(!ok, "list_devices should fail before start");
+        assert!(!err_msg.is_null());
+        let err = CStr::from_ptr(err_msg).to_string_lossy().to_string();
+        assert!(
+            err.contains("listeners not started"),
+            "Unexpected error: {}",
+            err
+        );
+        free_c_char(err_msg);
+        err_msg = ptr::null_mut();
+
+        // Database-only call before start
+        let mut exists = false;
+        let ok = network_config_service_organization_exists(
+            c_org_id.as_ptr(),
+            &mut exists,
+            &mut err_msg,
+        );
+        assert!(ok, "organization_exists should work before start");
+        assert!(exists);
+
+        // A malformed device filter is reported as an invalid argument
+        let bad_filter = CString::new("{\"status\": 1}").unwrap();
+        let ok = network_config_service_query_devices(
+            c_org_id.as_ptr(),
+            bad_filter.as_ptr(),
+            &mut result_json,
+            &mut err_msg,
+        );
+        assert!(!ok, "query_devices should reject a malformed filter");
+        assert_eq!(
+            CortexErrorCode::from_c_int(cortex_get_last_error_code()),
+            Some(CortexErrorCode::InvalidArgument)
+        );
+        assert!(!err_msg.is_null());
+        free_c_char(err_msg);
+        err_msg = ptr::null_mut();
+
+        // Creating the service applied every migration
+        let ok = network_config_service_check_migrations(&mut result_json, &mut err_msg);
+        assert!(ok, "check_migrations should work before start");
+        let json = CStr::from_ptr(result_json).to_string_lossy().to_string();
+        assert_eq!(json, "[]");
+        free_c_char(result_json);
+
+        // Rolling back requires a positive step count
+        for steps in [0, -1] {
+            assert!(
+                !network_config_service_rollback_migration(steps, &mut err_msg),
+                "rollback with {} steps should be rejected",
+                steps
+            );
+            assert!(!err_msg.is_null());
+            free_c_char(err_msg);
+            err_msg = ptr::null_mut();
+        }
+
+        assert!(destroy_network_config_service_singleton(&mut err_msg));
+    }
+
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(remove_test_database(test_name))
+        .unwrap();
+}
+
+#[test]
+#[serial]
+#[allow(deprecated)]
+fn test_ffi_migration_error_code() {
+    let test_name = "ffi_migration_error_code";
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(break_migration_state(test_name));
+
+    // Reachable server, missing database: a connection failure
+    let missing_dsn =
+        CString::new("root:root123@tcp(127.0.0.1:3306)/cortex_missing_error_code_db").unwrap();
+    let mut err_msg: *mut c_char = ptr::null_mut();
+    let created = unsafe {
+        create_network_config_service_singleton(missing_dsn.as_ptr(), ptr::null(), &mut err_msg)
+    };
+    assert!(
+        !created,
+        "Creating the service without a database should fail"
+    );
+    assert_eq!(
+        CortexErrorCode::from_c_int(cortex_get_last_error_code()),
+        Some(CortexErrorCode::DbError)
+    );
+    unsafe { free_c_char(err_msg) };
+
+    // Reachable database with a broken migration state
+    let broken_dsn = CString::new(format!(
+        "root:root123@tcp(127.0.0.1:3306)/{}",
+        create_test_db_name(test_name)
+    ))
+    .unwrap();
+    let mut err_msg: *mut c_char = ptr::null_mut();
+    let created = unsafe {
+        create_network_config_service_singleton(broken_dsn.as_ptr(), ptr::null(), &mut err_msg)
+    };
+    assert!(
+        !created,
+        "Creating the service with a broken schema should fail"
+    );
+    assert_eq!(
+        CortexErrorCode::from_c_int(cortex_get_last_error_code()),
+        Some(CortexErrorCode::MigrationError)
+    );
+    assert!(!err_msg.is_null(), "Error message should be reported");
+    unsafe { free_c_char(err_msg) };
+
+    rt.block_on(remove_test_database(test_name))
+        .expect("Failed to remove test database");
+}
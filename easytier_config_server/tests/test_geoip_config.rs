@@ -1,11 +1,17 @@
 //! Test GeoIP configuration and auto-detection functionality
 //!
 //! This test verifies that the GeoIP database can be automatically detected
-//! from the project resources directory.
+//! from the project resources directory, and that lookups, ASN data, runtime
+//! reloads and the lookup cache work against tiny MaxMind DB files written by
+//! the tests.
 
+use easytier_config_server::client_manager::geoip::GeoIpDb;
+use easytier_config_server::client_manager::session::Location;
 use easytier_config_server::client_manager::ClientManager;
 use easytier_config_server::config::get_geoip_db_path;
 use std::path::Path;
+use std::time::Duration;
+use test_mmdb::{write_asn_db, write_city_db};
 
 #[path = "common/mod.rs"]
 mod common;
@@ -617,3 +623,242 @@ fn test_geoip_path_special_characters() {
     // Clean up environment variable
     std::env::remove_var("CORTEX_GEOIP_DB_PATH");
 }
+
+/// Tiny MaxMind DB files mapping every IPv4 address to a single record
+mod test_mmdb {
+    use std::path::{Path, PathBuf};
+
+    fn mmdb_string(out: &mut Vec<u8>, s: &str) {
+        if s.len() < 29 {
+            out.push(0x40 | s.len() as u8);
+        } else {
+            assert!(s.len() < 29 + 256);
+            out.extend_from_slice(&[0x40 | 29, (s.len() - 29) as u8]);
+        }
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn mmdb_map(out: &mut Vec<u8>, entries: usize) {
+        out.push(0xE0 | entries as u8);
+    }
+
+    /// Write a database whose single search tree node points every address at
+    /// the record written by `write_record`
+    fn write_test_mmdb(
+        dir: &Path,
+        name: &str,
+        database_type: &str,
+        write_record: impl FnOnce(&mut Vec<u8>),
+    ) -> PathBuf {
+        let node_count = 1u8;
+        // Data pointers are offset by the node count and the 16 byte separator
+        let data_pointer = node_count + 16;
+        let mut db = vec![0, 0, data_pointer, 0, 0, data_pointer];
+        db.extend_from_slice(&[0; 16]);
+
+        write_record(&mut db);
+
+        db.extend_from_slice(b"\xAB\xCD\xEFMaxMind.com");
+        mmdb_map(&mut db, 9);
+        mmdb_string(&mut db, "binary_format_major_version");
+        db.extend_from_slice(&[0xA1, 2]);
+        mmdb_string(&mut db, "binary_format_minor_version");
+        db.push(0xA0);
+        mmdb_string(&mut db, "build_epoch");
+        db.extend_from_slice(&[0x01, 0x02, 1]);
+        mmdb_string(&mut db, "database_type");
+        mmdb_string(&mut db, database_type);
+        mmdb_string(&mut db, "description");
+        mmdb_map(&mut db, 1);
+        mmdb_string(&mut db, "en");
+        mmdb_string(&mut db, "geoip test");
+        mmdb_string(&mut db, "ip_version");
+        db.extend_from_slice(&[0xA1, 4]);
+        mmdb_string(&mut db, "languages");
+        db.extend_from_slice(&[0x01, 0x04]);
+        mmdb_string(&mut db, "en");
+        mmdb_string(&mut db, "node_count");
+        db.extend_from_slice(&[0xC1, node_count]);
+        mmdb_string(&mut db, "record_size");
+        db.extend_from_slice(&[0xA1, 24]);
+
+        let path = dir.join(name);
+        std::fs::write(&path, db).unwrap();
+        path
+    }
+
+    /// City database resolving every address to `country`
+    pub fn write_city_db(dir: &Path, name: &str, country: &str) -> PathBuf {
+        write_test_mmdb(dir, name, "GeoIP2-City", |db| {
+            mmdb_map(db, 1);
+            mmdb_string(db, "country");
+            mmdb_map(db, 1);
+            mmdb_string(db, "names");
+            mmdb_map(db, 1);
+            mmdb_string(db, "en");
+            mmdb_string(db, country);
+        })
+    }
+
+    /// ASN database resolving every address to `asn` and `org`
+    pub fn write_asn_db(dir: &Path, asn: u16, org: &str) -> PathBuf {
+        write_test_mmdb(dir, "asn.mmdb", "GeoLite2-ASN", |db| {
+            mmdb_map(db, 2);
+            mmdb_string(db, "autonomous_system_number");
+            db.push(0xC2);
+            db.extend_from_slice(&asn.to_be_bytes());
+            mmdb_string(db, "autonomous_system_organization");
+            mmdb_string(db, org);
+        })
+    }
+}
+
+fn location_of(geoip: &GeoIpDb, ip: &str) -> Location {
+    let url: url::Url = format!("tcp://{}:11010", ip).parse().unwrap();
+    geoip.lookup_location(&url).unwrap()
+}
+
+fn country_of(geoip: &GeoIpDb, ip: &str) -> String {
+    location_of(geoip, ip).country
+}
+
+#[test]
+fn test_asn_filled_for_public_ip() {
+    let dir = tempfile::tempdir().unwrap();
+    let city_db = write_city_db(dir.path(), "city.mmdb", "Testland");
+    let asn_db = write_asn_db(dir.path(), 15169, "Example ISP");
+
+    let geoip = GeoIpDb::open_with_asn(
+        Some(city_db.to_string_lossy().into_owned()),
+        Some(asn_db.to_string_lossy().into_owned()),
+    );
+    let location = location_of(&geoip, "8.8.8.8");
+    assert_eq!(location.country, "Testland");
+    assert_eq!(location.asn, Some(15169));
+    assert_eq!(location.asn_org.as_deref(), Some("Example ISP"));
+
+    // Private addresses are never looked up
+    let location = location_of(&geoip, "192.168.1.10");
+    assert_eq!(location.asn, None);
+    assert_eq!(location.asn_org, None);
+}
+
+#[test]
+fn test_asn_empty_without_asn_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let city_db = write_city_db(dir.path(), "city.mmdb", "Testland");
+
+    let geoip = GeoIpDb::open(Some(city_db.to_string_lossy().into_owned()));
+    let location = location_of(&geoip, "8.8.8.8");
+    assert_eq!(location.country, "Testland");
+    assert_eq!(location.asn, None);
+    assert_eq!(location.asn_org, None);
+
+    // The ASN database can be added later without touching the city database
+    let asn_db = write_asn_db(dir.path(), 64500, "Late ISP");
+    geoip.reload_asn(asn_db.to_str().unwrap()).unwrap();
+    let location = location_of(&geoip, "8.8.8.8");
+    assert_eq!(location.country, "Testland");
+    assert_eq!(location.asn, Some(64500));
+    assert_eq!(location.asn_org.as_deref(), Some("Late ISP"));
+}
+
+#[test]
+fn test_reload_swaps_lookup_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let old_db = write_city_db(dir.path(), "old.mmdb", "Oldland");
+    let new_db = write_city_db(dir.path(), "new.mmdb", "Newland");
+
+    let geoip = GeoIpDb::open(Some(old_db.to_string_lossy().into_owned()));
+    assert!(geoip.is_loaded());
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Oldland");
+
+    geoip.reload(new_db.to_str().unwrap()).unwrap();
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Newland");
+
+    // A failed reload keeps serving the current database
+    let missing = dir.path().join("missing.mmdb");
+    assert!(geoip.reload(missing.to_str().unwrap()).is_err());
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Newland");
+}
+
+#[test]
+fn test_reload_without_initial_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = write_city_db(dir.path(), "first.mmdb", "Firstland");
+
+    let geoip = GeoIpDb::open(None);
+    assert!(!geoip.is_loaded());
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "未知");
+
+    geoip.reload(db.to_str().unwrap()).unwrap();
+    assert!(geoip.is_loaded());
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Firstland");
+}
+
+#[test]
+fn test_repeated_lookup_served_from_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let old_db = write_city_db(dir.path(), "old.mmdb", "Oldland");
+    let new_db = write_city_db(dir.path(), "new.mmdb", "Newland");
+
+    let geoip = GeoIpDb::open(Some(old_db.to_string_lossy().into_owned()))
+        .with_cache(16, Duration::from_secs(60));
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Oldland");
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Oldland");
+    assert_eq!(geoip.db_lookups(), 1);
+    assert_eq!(geoip.cache_hits(), 1);
+
+    // Reloading invalidates the cached location
+    geoip.reload(new_db.to_str().unwrap()).unwrap();
+    assert_eq!(country_of(&geoip, "8.8.8.8"), "Newland");
+    assert_eq!(geoip.db_lookups(), 2);
+    assert_eq!(geoip.cache_hits(), 1);
+}
+
+#[test]
+fn test_cache_evicts_least_recently_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = write_city_db(dir.path(), "lru.mmdb", "Cacheland");
+
+    let geoip = GeoIpDb::open(Some(db.to_string_lossy().into_owned()))
+        .with_cache(2, Duration::from_secs(60));
+    country_of(&geoip, "8.8.8.8");
+    country_of(&geoip, "1.1.1.1");
+    // Touch 8.8.8.8 so 1.1.1.1 is the least recently used entry
+    country_of(&geoip, "8.8.8.8");
+    country_of(&geoip, "9.9.9.9");
+    assert_eq!(geoip.db_lookups(), 3);
+
+    country_of(&geoip, "8.8.8.8");
+    assert_eq!(geoip.db_lookups(), 3);
+    country_of(&geoip, "1.1.1.1");
+    assert_eq!(geoip.db_lookups(), 4);
+}
+
+#[test]
+fn test_cached_location_expires() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = write_city_db(dir.path(), "ttl.mmdb", "Cacheland");
+
+    let geoip = GeoIpDb::open(Some(db.to_string_lossy().into_owned()))
+        .with_cache(16, Duration::from_millis(100));
+    country_of(&geoip, "8.8.8.8");
+    std::thread::sleep(Duration::from_millis(300));
+    country_of(&geoip, "8.8.8.8");
+    assert_eq!(geoip.db_lookups(), 2);
+    assert_eq!(geoip.cache_hits(), 0);
+}
+
+#[test]
+fn test_cache_disabled_with_zero_capacity() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = write_city_db(dir.path(), "nocache.mmdb", "Plainland");
+
+    let geoip = GeoIpDb::open(Some(db.to_string_lossy().into_owned()))
+        .with_cache(0, Duration::from_secs(60));
+    country_of(&geoip, "8.8.8.8");
+    country_of(&geoip, "8.8.8.8");
+    assert_eq!(geoip.db_lookups(), 2);
+    assert_eq!(geoip.cache_hits(), 0);
+}
@@ -10,6 +10,7 @@ use easytier_config_server::client_manager::{
     ClientManager,
 };
 use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+use serial_test::serial;
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -353,6 +354,8 @@ async fn test_heartbeat_with_location_and_organization_validation() {
         country: "测试国家".to_string(),
         city: Some("测试城市".to_string()),
         region: Some("测试地区".to_string()),
+        asn: None,
+        asn_org: None,
     };
 
     let session = Session::new(weak_storage, client_url, Some(location.clone()));
@@ -519,7 +522,10 @@ async fn test_heartbeat_organization_validation_with_udp_tunnel() {
     let mut heartbeat_req = None;
     for _ in 0..50 {
         // Wait up to 5 seconds
-        if let Some(req) = client_manager.get_heartbeat_requests(&client_url).await {
+        if let Some(req) = client_manager
+            .get_heartbeat_requests(&client_url, None)
+            .await
+        {
             heartbeat_req = Some(req);
             heartbeat_available = true;
             break;
@@ -635,7 +641,11 @@ async fn test_heartbeat_multiple_udp_clients() {
         // Wait for heartbeat to be available
         let mut heartbeat_available = false;
         for _ in 0..30 {
-            if (client_manager.get_heartbeat_requests(client_url).await).is_some() {
+            if (client_manager
+                .get_heartbeat_requests(client_url, None)
+                .await)
+                .is_some()
+            {
                 heartbeat_available = true;
                 break;
             }
@@ -644,7 +654,7 @@ async fn test_heartbeat_multiple_udp_clients() {
 
         if heartbeat_available {
             let heartbeat_req = client_manager
-                .get_heartbeat_requests(client_url)
+                .get_heartbeat_requests(client_url, None)
                 .await
                 .unwrap();
             heartbeat_count += 1;
@@ -674,3 +684,191 @@ async fn test_heartbeat_multiple_udp_clients() {
         .await
         .expect("Failed to remove test database");
 }
+
+#[tokio::test]
+#[serial]
+async fn test_heartbeat_rejection_reason_codes() {
+    use easytier_config_server::client_manager::session::{HeartbeatRejectCode, SessionRpcService};
+
+    let test_name = "test_heartbeat_rejection_reason_codes";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let storage = Storage::new(db.clone());
+    let session = Session::new(storage.weak_ref(), test_client_url(), None);
+    let service = SessionRpcService {
+        data: session.data().clone(),
+    };
+
+    let base_request = heartbeat_request("org-does-not-exist", test_device_id(), "reject-device");
+
+    // Missing device id
+    let err = service
+        .handle_heartbeat(HeartbeatRequest {
+            machine_id: None,
+            ..base_request.clone()
+        })
+        .await
+        .expect_err("Heartbeat without device id should be rejected");
+    assert!(
+        err.to_string()
+            .contains(HeartbeatRejectCode::InvalidDeviceId.as_str()),
+        "Unexpected error: {}",
+        err
+    );
+
+    // Unknown organization
+    let err = service
+        .handle_heartbeat(base_request)
+        .await
+        .expect_err("Heartbeat for unknown organization should be rejected");
+    assert!(
+        err.to_string()
+            .contains(HeartbeatRejectCode::OrganizationNotFound.as_str()),
+        "Unexpected error: {}",
+        err
+    );
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_heartbeat_unapproved_rejection_codes() {
+    use easytier_config_server::client_manager::session::{HeartbeatRejectCode, SessionRpcService};
+    use easytier_config_server::db::entities::devices;
+    use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+    let test_name = "test_heartbeat_unapproved_rejection_codes";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let storage = Storage::new(db.clone());
+    storage.set_reject_message(Some("Contact ops@example.com".to_string()));
+    storage.set_reject_unapproved_devices(true);
+
+    let session = Session::new(storage.weak_ref(), test_client_url(), None);
+    let service = SessionRpcService {
+        data: session.data().clone(),
+    };
+    let device_id = test_device_id();
+    let request = heartbeat_request(&org_id, device_id, "unapproved-device");
+
+    // A new device is recorded as pending and told to wait for approval
+    let err = service
+        .handle_heartbeat(request.clone())
+        .await
+        .expect_err("Heartbeat of a pending device should be rejected");
+    let err = err.to_string();
+    assert!(
+        err.contains(HeartbeatRejectCode::AwaitingApproval.as_str()),
+        "Unexpected error: {}",
+        err
+    );
+    assert!(
+        err.contains("Contact ops@example.com"),
+        "Unexpected error: {}",
+        err
+    );
+    let device = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .expect("Pending device should be recorded");
+    assert_eq!(device.status, devices::DeviceStatus::Pending);
+    assert!(session.get_heartbeat_req().await.is_some());
+
+    // A rejected device stays rejected
+    let mut device: devices::ActiveModel = device.into();
+    device.status = Set(devices::DeviceStatus::Rejected);
+    device.update(db.orm()).await.unwrap();
+    let err = service
+        .handle_heartbeat(request.clone())
+        .await
+        .expect_err("Heartbeat of a rejected device should be rejected");
+    assert!(
+        err.to_string()
+            .contains(HeartbeatRejectCode::Rejected.as_str()),
+        "Unexpected error: {}",
+        err
+    );
+    let device = devices::Entity::find_by_id(device_id.to_string())
+        .one(db.orm())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(device.status, devices::DeviceStatus::Rejected);
+
+    // An approved, online device is accepted
+    let mut device: devices::ActiveModel = device.into();
+    device.status = Set(devices::DeviceStatus::Online);
+    device.update(db.orm()).await.unwrap();
+    service
+        .handle_heartbeat(request)
+        .await
+        .expect("Heartbeat of an online device should be accepted");
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_create_organization_accepts_heartbeat() {
+    use easytier_config_server::client_manager::session::SessionRpcService;
+    use easytier_config_server::NetworkConfigService;
+
+    let test_name = "create_organization_accepts_heartbeat";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    let org_id = test_organization_id();
+    assert!(!service.organization_exists(&org_id).await.unwrap());
+
+    service
+        .create_organization(&org_id, "Managed Organization")
+        .await
+        .expect("Should create organization");
+    assert!(service.organization_exists(&org_id).await.unwrap());
+
+    // Creating it again is a no-op
+    service
+        .create_organization(&org_id, "Managed Organization")
+        .await
+        .expect("Creating an existing organization should succeed");
+
+    // A heartbeat for the new organization is accepted
+    let storage = Storage::new(db.clone());
+    let session = Session::new(storage.weak_ref(), test_client_url(), None);
+    let rpc_service = SessionRpcService {
+        data: session.data().clone(),
+    };
+    rpc_service
+        .handle_heartbeat(heartbeat_request(
+            &org_id,
+            test_device_id(),
+            "managed-org-device",
+        ))
+        .await
+        .expect("Heartbeat for created organization should be accepted");
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
@@ -0,0 +1,143 @@
+//! Semantic validation tests for NetworkConfig
+//!
+//! These checks run before a config is sent to the device, so they do not
+//! need an active session. The service reports every invalid field with its
+//! reason, so a UI can highlight each of them.
+
+use easytier::proto::web::{NetworkConfig, NetworkingMethod};
+use easytier_config_server::config_srv::check_network_config;
+use easytier_config_server::NetworkConfigService;
+
+#[path = "common/mod.rs"]
+mod common;
+use common::*;
+
+#[test]
+fn test_dhcp_off_without_ip_is_rejected() {
+    let config = NetworkConfig {
+        network_name: Some("test_network".to_string()),
+        network_secret: Some("test_secret".to_string()),
+        dhcp: Some(false),
+        virtual_ipv4: None,
+        ..Default::default()
+    };
+
+    let issues = check_network_config(&config);
+    assert_eq!(issues.len(), 1, "Unexpected issues: {:?}", issues);
+    assert_eq!(issues[0].field, "virtual_ipv4");
+}
+
+#[test]
+fn test_valid_config_is_accepted() {
+    let config = NetworkConfig {
+        network_name: Some("test_network".to_string()),
+        network_secret: Some("test_secret".to_string()),
+        dhcp: Some(false),
+        virtual_ipv4: Some("10.126.126.1".to_string()),
+        network_length: Some(24),
+        networking_method: Some(NetworkingMethod::Manual as i32),
+        peer_urls: vec!["tcp://public.easytier.top:11010".to_string()],
+        ..Default::default()
+    };
+
+    let issues = check_network_config(&config);
+    assert!(issues.is_empty(), "Unexpected issues: {:?}", issues);
+}
+
+#[test]
+fn test_manual_networking_without_peers_is_accepted() {
+    // A node without peers only waits for others to connect to it
+    let config = NetworkConfig {
+        network_name: Some("test_network".to_string()),
+        dhcp: Some(true),
+        networking_method: Some(NetworkingMethod::Manual as i32),
+        ..Default::default()
+    };
+
+    let issues = check_network_config(&config);
+    assert!(issues.is_empty(), "Unexpected issues: {:?}", issues);
+}
+
+#[test]
+fn test_public_server_networking_with_default_server_is_accepted() {
+    // No public server URL means EasyTier's default public server
+    for public_server_url in [None, Some(String::new())] {
+        let config = NetworkConfig {
+            network_name: Some("test_network".to_string()),
+            dhcp: Some(true),
+            networking_method: Some(NetworkingMethod::PublicServer as i32),
+            public_server_url,
+            ..Default::default()
+        };
+
+        let issues = check_network_config(&config);
+        assert!(issues.is_empty(), "Unexpected issues: {:?}", issues);
+    }
+}
+
+#[test]
+fn test_invalid_peer_and_public_server_urls_are_rejected() {
+    let config = NetworkConfig {
+        network_name: Some("test_network".to_string()),
+        networking_method: Some(NetworkingMethod::Manual as i32),
+        peer_urls: vec!["not a url".to_string()],
+        ..Default::default()
+    };
+    let issues = check_network_config(&config);
+    assert!(issues.iter().any(|i| i.field == "peer_urls"));
+
+    let config = NetworkConfig {
+        network_name: Some("test_network".to_string()),
+        networking_method: Some(NetworkingMethod::PublicServer as i32),
+        public_server_url: Some("not a url".to_string()),
+        ..Default::default()
+    };
+    let issues = check_network_config(&config);
+    assert!(issues.iter().any(|i| i.field == "public_server_url"));
+}
+
+#[tokio::test]
+async fn test_validate_config_reports_each_invalid_field() {
+    let test_name = "validate_config_reports_each_invalid_field";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    // DHCP off without a virtual IP and an out of range network length
+    let config = NetworkConfig {
+        network_name: Some("test_network".to_string()),
+        dhcp: Some(false),
+        virtual_ipv4: None,
+        network_length: Some(40),
+        ..Default::default()
+    };
+
+    // Local issues are reported without contacting the device
+    let report = service
+        .validate_config(&org_id, &uuid::Uuid::new_v4(), config)
+        .await
+        .expect("Validation should complete");
+    assert!(!report.valid);
+    let mut fields: Vec<_> = report.issues.iter().map(|i| i.field.as_str()).collect();
+    fields.sort();
+    assert_eq!(fields, vec!["network_length", "virtual_ipv4"]);
+    assert!(report.toml_config.is_none());
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["valid"], false);
+    assert_eq!(json["issues"].as_array().unwrap().len(), 2);
+    assert!(json["issues"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|issue| issue["message"].as_str().is_some_and(|m| !m.is_empty())));
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
@@ -1,18 +1,30 @@
 //! Advanced session tests for comprehensive coverage
 //!
 //! This file contains advanced test cases for the Session module,
-//! focusing on RPC operations, network instance management, and error handling.
+//! focusing on RPC operations, network instance management, error handling,
+//! per-organization session limits, throughput and heartbeat delivery.
 
 use easytier::proto::{
     common::Uuid as ProtoUuid,
-    web::{HeartbeatRequest, NetworkConfig, RunNetworkInstanceRequest},
+    rpc_impl::bidirect::BidirectRpcManager,
+    rpc_types::{self, controller::BaseController},
+    web::*,
 };
+use easytier::tunnel::{common::tests::wait_for_condition, tcp::TcpTunnelListener};
 use easytier_config_server::client_manager::{
-    session::{Location, Session},
+    idempotency::IdempotencyCache,
+    session::{
+        HeartbeatRejectCode, Location, Session, SessionRpcService, DEFAULT_SESSION_RX_TIMEOUT,
+    },
     storage::{Storage, StorageToken},
+    ClientManager,
 };
+use easytier_config_server::db::entities::devices;
+use easytier_config_server::NetworkConfigService;
+use sea_orm::EntityTrait;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
 #[path = "common/mod.rs"]
@@ -55,6 +67,8 @@ async fn test_session_data_creation() {
         country: "测试国家".to_string(),
         city: Some("测试城市".to_string()),
         region: Some("测试地区".to_string()),
+        asn: None,
+        asn_org: None,
     };
 
     let session_with_location = Session::new(weak_storage, client_url, Some(location.clone()));
@@ -148,12 +162,7 @@ async fn test_session_network_instance_operations() {
         ..Default::default()
     };
 
-    let run_request = RunNetworkInstanceRequest {
-        inst_id: None,
-        config: Some(network_config),
-    };
-
-    let run_result = session.run_network_instance(run_request).await;
+    let run_result = session.run_network_instance(network_config).await;
     // This will likely fail since there's no actual RPC connection, but we test the method exists
     // and handles the error gracefully
     assert!(
@@ -226,16 +235,22 @@ async fn test_location_edge_cases() {
             country: "".to_string(), // Empty country
             city: None,
             region: None,
+            asn: None,
+            asn_org: None,
         },
         Location {
             country: "很长的国家名称测试".to_string(), // Long country name
             city: Some("很长的城市名称测试".to_string()),
             region: Some("很长的地区名称测试".to_string()),
+            asn: None,
+            asn_org: None,
         },
         Location {
             country: "Country with special chars: !@#$%^&*()".to_string(),
             city: Some("City with unicode: 北京市 🏙️".to_string()),
             region: Some("Region with numbers: 123456".to_string()),
+            asn: None,
+            asn_org: None,
         },
     ];
 
@@ -280,6 +295,8 @@ async fn test_session_debug_formatting() {
         country: "测试国家".to_string(),
         city: Some("测试城市".to_string()),
         region: Some("测试地区".to_string()),
+        asn: None,
+        asn_org: None,
     };
 
     let session = Session::new(weak_storage, client_url, Some(location));
@@ -500,3 +517,587 @@ async fn test_chrono_datetime_parsing() {
         let _ = parse_result; // Just verify the parsing attempt doesn't panic
     }
 }
+
+/// Device side of the RPC that records every RunNetworkInstance call
+#[derive(Clone, Default)]
+struct MockWebClientService {
+    run_requests: Arc<std::sync::Mutex<Vec<RunNetworkInstanceRequest>>>,
+    inst_id: uuid::Uuid,
+}
+
+#[async_trait::async_trait]
+impl WebClientService for MockWebClientService {
+    type Controller = BaseController;
+
+    async fn validate_config(
+        &self,
+        _: BaseController,
+        _: ValidateConfigRequest,
+    ) -> rpc_types::error::Result<ValidateConfigResponse> {
+        Ok(Default::default())
+    }
+
+    async fn run_network_instance(
+        &self,
+        _: BaseController,
+        req: RunNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<RunNetworkInstanceResponse> {
+        self.run_requests.lock().unwrap().push(req);
+        Ok(RunNetworkInstanceResponse {
+            inst_id: Some(self.inst_id.into()),
+        })
+    }
+
+    async fn retain_network_instance(
+        &self,
+        _: BaseController,
+        _: RetainNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<RetainNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn collect_network_info(
+        &self,
+        _: BaseController,
+        _: CollectNetworkInfoRequest,
+    ) -> rpc_types::error::Result<CollectNetworkInfoResponse> {
+        Ok(Default::default())
+    }
+
+    async fn list_network_instance(
+        &self,
+        _: BaseController,
+        _: ListNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<ListNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn delete_network_instance(
+        &self,
+        _: BaseController,
+        _: DeleteNetworkInstanceRequest,
+    ) -> rpc_types::error::Result<DeleteNetworkInstanceResponse> {
+        Ok(Default::default())
+    }
+
+    async fn get_network_instance_config(
+        &self,
+        _: BaseController,
+        _: GetNetworkInstanceConfigRequest,
+    ) -> rpc_types::error::Result<GetNetworkInstanceConfigResponse> {
+        Ok(Default::default())
+    }
+}
+
+#[tokio::test]
+async fn test_session_run_network_instance_reaches_device() {
+    let db = get_test_database("test_session_run_network_instance_reaches_device")
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let storage = Storage::new(db);
+    let mut session = Session::new(storage.weak_ref(), test_client_url(), None);
+
+    // Connect the session to a mock device over an in-memory tunnel
+    let mock = MockWebClientService {
+        inst_id: uuid::Uuid::new_v4(),
+        ..Default::default()
+    };
+    let (server_tunnel, device_tunnel) = easytier::tunnel::ring::create_ring_tunnel_pair();
+    let device_rpc = BidirectRpcManager::new();
+    device_rpc
+        .rpc_server()
+        .registry()
+        .register(WebClientServiceServer::new(mock.clone()), "");
+    device_rpc.run_with_tunnel(device_tunnel);
+    session.serve(server_tunnel).await;
+
+    let network_config = NetworkConfig {
+        network_name: Some("pushed_network".to_string()),
+        network_secret: Some("pushed_secret".to_string()),
+        ..Default::default()
+    };
+    let inst_id = session
+        .run_network_instance(network_config)
+        .await
+        .expect("RPC should reach the mock device");
+
+    assert_eq!(
+        inst_id, mock.inst_id,
+        "Instance id should come from the device"
+    );
+    let requests = mock.run_requests.lock().unwrap().clone();
+    assert_eq!(
+        requests.len(),
+        1,
+        "Exactly one RunNetworkInstance RPC expected"
+    );
+    assert_eq!(
+        requests[0]
+            .config
+            .as_ref()
+            .and_then(|c| c.network_name.clone())
+            .as_deref(),
+        Some("pushed_network")
+    );
+
+    session.shutdown().await;
+
+    // 删除测试数据库
+    remove_test_database("test_session_run_network_instance_reaches_device")
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_run_network_instance_retry_with_same_key_runs_once() {
+    let test_name = "test_run_network_instance_retry_with_same_key_runs_once";
+    let db = get_test_database(test_name)
+        .await
+        .expect("Failed to setup test database");
+    cleanup_test_database(&db)
+        .await
+        .expect("Failed to cleanup test database");
+
+    let storage = Storage::new(db);
+    let mut session = Session::new(storage.weak_ref(), test_client_url(), None);
+
+    let mock = MockWebClientService {
+        inst_id: uuid::Uuid::new_v4(),
+        ..Default::default()
+    };
+    let (server_tunnel, device_tunnel) = easytier::tunnel::ring::create_ring_tunnel_pair();
+    let device_rpc = BidirectRpcManager::new();
+    device_rpc
+        .rpc_server()
+        .registry()
+        .register(WebClientServiceServer::new(mock.clone()), "");
+    device_rpc.run_with_tunnel(device_tunnel);
+    session.serve(server_tunnel).await;
+
+    let network_config = NetworkConfig {
+        network_name: Some("idempotent_network".to_string()),
+        ..Default::default()
+    };
+    let run_keys = IdempotencyCache::new(std::time::Duration::from_secs(60));
+
+    // The retry after a lost response reuses the key of the first call
+    let first = run_keys
+        .get_or_run("retry-key", || {
+            session.run_network_instance(network_config.clone())
+        })
+        .await
+        .expect("First run should reach the device");
+    let retry = run_keys
+        .get_or_run("retry-key", || {
+            session.run_network_instance(network_config.clone())
+        })
+        .await
+        .expect("Retry should succeed");
+
+    assert_eq!(first, mock.inst_id);
+    assert_eq!(retry, first, "Retry should return the first instance id");
+    assert_eq!(
+        mock.run_requests.lock().unwrap().len(),
+        1,
+        "The retry must not create a second instance"
+    );
+
+    // A different key runs the operation again
+    run_keys
+        .get_or_run("other-key", || {
+            session.run_network_instance(network_config.clone())
+        })
+        .await
+        .expect("Run with a new key should reach the device");
+    assert_eq!(mock.run_requests.lock().unwrap().len(), 2);
+
+    session.shutdown().await;
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_idempotency_keys_lock_independently() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    let run_keys = Arc::new(IdempotencyCache::new(Duration::from_secs(60)));
+    let runs = Arc::new(AtomicUsize::new(0));
+    let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+    // A slow run holds its key until released
+    let slow = {
+        let run_keys = run_keys.clone();
+        let runs = runs.clone();
+        tokio::spawn(async move {
+            run_keys
+                .get_or_run("slow-key", || async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    release_rx.await?;
+                    Ok(uuid::Uuid::new_v4())
+                })
+                .await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // A concurrent retry of the same key waits for the first run
+    let retry = {
+        let run_keys = run_keys.clone();
+        let runs = runs.clone();
+        tokio::spawn(async move {
+            run_keys
+                .get_or_run("slow-key", || async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(uuid::Uuid::new_v4())
+                })
+                .await
+        })
+    };
+
+    // Another key is not blocked by the slow run
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        run_keys.get_or_run("other-key", || async { Ok(uuid::Uuid::new_v4()) }),
+    )
+    .await
+    .expect("Other keys should not wait for the slow run")
+    .unwrap();
+
+    release_tx.send(()).unwrap();
+    let first = slow.await.unwrap().unwrap();
+    let retried = retry.await.unwrap().unwrap();
+    assert_eq!(retried, first, "Retry should return the first instance id");
+    assert_eq!(runs.load(Ordering::SeqCst), 1, "The key must only run once");
+}
+
+#[tokio::test]
+async fn test_org_session_limit_rejects_second_session() {
+    let test_name = "org_session_limit_rejects_second_session";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut client_manager =
+        ClientManager::new_with_org_session_limit(&get_test_database_url(test_name), None, Some(1))
+            .await
+            .expect("Failed to create ClientManager");
+    client_manager
+        .add_listener(TcpTunnelListener::new("tcp://127.0.0.1:0".parse().unwrap()))
+        .await
+        .unwrap();
+    let port = client_manager.list_listeners()[0].port;
+
+    let (first, ret) = connect_device(port, &org_id, uuid::Uuid::new_v4(), "limit-a").await;
+    ret.expect("First session should be accepted");
+
+    let (_second, ret) = connect_device(port, &org_id, uuid::Uuid::new_v4(), "limit-b").await;
+    let err = ret.expect_err("Second session for the same organization should be rejected");
+    assert!(
+        err.to_string()
+            .contains(HeartbeatRejectCode::OverQuota.as_str()),
+        "Unexpected error: {}",
+        err
+    );
+    let org_sessions = client_manager
+        .list_sessions()
+        .await
+        .into_iter()
+        .filter(|t| t.organization_id == org_id)
+        .count();
+    assert_eq!(org_sessions, 1);
+
+    // The rejected session is closed without waiting for the cleanup task
+    wait_for_condition(
+        || async { client_manager.session_count() == 1 },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    // Disconnecting the first device frees its slot before its session is cleaned up
+    drop(first);
+    let third = std::sync::Mutex::new(None);
+    wait_for_condition(
+        || async {
+            let (device, ret) =
+                connect_device(port, &org_id, uuid::Uuid::new_v4(), "limit-c").await;
+            let accepted = ret.is_ok();
+            if accepted {
+                *third.lock().unwrap() = Some(device);
+            }
+            accepted
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    assert!(third.lock().unwrap().is_some());
+
+    client_manager.shutdown().await;
+    remove_test_database(test_name).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_org_session_limit_admits_one_of_concurrent_first_heartbeats() {
+    let test_name = "org_session_limit_concurrent_first_heartbeats";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let storage = Storage::new(db.clone());
+    storage.set_org_session_limit(Some(1));
+
+    let device_ids = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
+    let sessions = [
+        Session::new(
+            storage.weak_ref(),
+            "tcp://127.0.0.1:50001".parse().unwrap(),
+            None,
+        ),
+        Session::new(
+            storage.weak_ref(),
+            "tcp://127.0.0.1:50002".parse().unwrap(),
+            None,
+        ),
+    ];
+    let services = sessions
+        .iter()
+        .map(|session| SessionRpcService {
+            data: session.data().clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let (ret1, ret2) = tokio::join!(
+        services[0].handle_heartbeat(heartbeat_request(&org_id, device_ids[0], "limit-a")),
+        services[1].handle_heartbeat(heartbeat_request(&org_id, device_ids[1], "limit-b")),
+    );
+    assert_eq!(
+        [ret1.is_ok(), ret2.is_ok()]
+            .iter()
+            .filter(|ok| **ok)
+            .count(),
+        1,
+        "Exactly one of the concurrent sessions should be admitted"
+    );
+    assert_eq!(storage.list_organization_clients(&org_id).len(), 1);
+
+    // The rejected device was never written to the database
+    let rejected = if ret1.is_ok() {
+        device_ids[1]
+    } else {
+        device_ids[0]
+    };
+    let record = devices::Entity::find_by_id(rejected.to_string())
+        .one(db.orm())
+        .await
+        .unwrap();
+    assert!(record.is_none(), "Rejected device should not be stored");
+
+    drop(services);
+    drop(sessions);
+    remove_test_database(test_name).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unserved_session_has_no_counters() {
+    let db = get_test_database("unserved_session_has_no_counters")
+        .await
+        .unwrap();
+    let storage = Storage::new(db);
+    let session = Session::new(storage.weak_ref(), test_client_url(), None);
+
+    assert_eq!(session.tx_bytes(), None);
+    assert_eq!(session.rx_bytes(), None);
+
+    remove_test_database("unserved_session_has_no_counters")
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_live_session_reports_throughput() {
+    let test_name = "live_session_reports_throughput";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+    service.start("tcp", 54490).await.expect("Failed to start");
+
+    let _web_client = start_web_client(54490, &org_id, "throughput-host");
+    let (device_id, _) = wait_for_single_device(&service, &org_id).await;
+
+    let throughput = service
+        .get_device_throughput(&org_id, &device_id)
+        .await
+        .expect("Connected device should have a session");
+    let rx_bytes = throughput
+        .rx_bytes
+        .expect("Served session should count bytes");
+    assert!(rx_bytes > 0, "The heartbeat should have been counted");
+    assert!(throughput.tx_bytes.is_some());
+    assert_eq!(
+        service
+            .list_device_throughputs(&org_id)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_wait_for_heartbeat_receives_next_heartbeat() {
+    let test_name = "wait_for_heartbeat_receives_next_heartbeat";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+    service.start("tcp", 54420).await.expect("Failed to start");
+
+    // No session yet for this device
+    let unknown_device = uuid::Uuid::new_v4();
+    assert!(
+        service
+            .wait_for_heartbeat(&org_id, &unknown_device, Duration::from_millis(100))
+            .await
+            .is_err(),
+        "Waiting on a device without session should fail"
+    );
+
+    let _web_client = start_web_client(54420, &org_id, "heartbeat-waiter-host");
+    let (device_id, _) = wait_for_single_device(&service, &org_id).await;
+
+    let heartbeat = service
+        .wait_for_heartbeat(&org_id, &device_id, Duration::from_secs(10))
+        .await
+        .expect("Waiting for heartbeat should succeed")
+        .expect("Device should send a heartbeat before the timeout");
+    assert_eq!(heartbeat.hostname, "heartbeat-waiter-host");
+    assert_eq!(heartbeat.user_token, org_id);
+    assert_eq!(
+        heartbeat.machine_id.map(uuid::Uuid::from),
+        Some(device_id),
+        "Heartbeat should come from the waited device"
+    );
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_heartbeat_subscriber_recovers_from_lag() {
+    let test_name = "heartbeat_subscriber_recovers_from_lag";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let client_mgr = ClientManager::new(&get_test_database_url(test_name), None)
+        .await
+        .unwrap();
+    let session = Session::new_with_heartbeat_capacity(
+        client_mgr.storage().weak_ref(),
+        test_client_url(),
+        None,
+        DEFAULT_SESSION_RX_TIMEOUT,
+        2,
+    );
+    let waiter = session.data().read().await.heartbeat_waiter();
+    let rpc_service = SessionRpcService {
+        data: session.data().clone(),
+    };
+
+    // Send more heartbeats than the channel holds before the subscriber reads
+    let device_id = uuid::Uuid::new_v4();
+    for i in 0..5 {
+        rpc_service
+            .handle_heartbeat(heartbeat_request(
+                &org_id,
+                device_id,
+                &format!("lag-host-{}", i),
+            ))
+            .await
+            .unwrap();
+    }
+
+    let heartbeat = NetworkConfigService::next_heartbeat(waiter, Duration::from_secs(1))
+        .await
+        .expect("A lagged subscriber should not fail")
+        .expect("The retained heartbeats should be delivered");
+    assert_eq!(
+        heartbeat.hostname, "lag-host-3",
+        "The oldest retained heartbeat should be delivered after the lag"
+    );
+
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
+
+#[tokio::test]
+async fn test_topology_contains_every_connected_device() {
+    let test_name = "topology_contains_every_connected_device";
+    let db = get_test_database(test_name).await.unwrap();
+    cleanup_test_database(&db).await.unwrap();
+    let org_id = setup_test_organization(&db).await.unwrap();
+
+    let mut service = NetworkConfigService::new(&get_test_database_url(test_name), None)
+        .await
+        .expect("Failed to create NetworkConfigService");
+
+    // Session-based operations need the listeners first
+    assert!(service.get_org_topology(&org_id).await.is_err());
+
+    service.start("tcp", 54540).await.expect("Failed to start");
+
+    let device_a = uuid::Uuid::new_v4();
+    let device_b = uuid::Uuid::new_v4();
+    let (_rpc_a, ret) = connect_device(54540, &org_id, device_a, "topology-a").await;
+    ret.expect("Heartbeat should be accepted");
+    let (_rpc_b, ret) = connect_device(54540, &org_id, device_b, "topology-b").await;
+    ret.expect("Heartbeat should be accepted");
+
+    wait_for_condition(
+        || async { service.get_org_topology(&org_id).await.unwrap().nodes.len() == 2 },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let topology = service.get_org_topology(&org_id).await.unwrap();
+    let mut expected = vec![device_a, device_b];
+    expected.sort();
+    let device_ids: Vec<_> = topology.nodes.iter().map(|n| n.device_id).collect();
+    assert_eq!(device_ids, expected);
+    assert!(topology.nodes.iter().all(|n| n.error.is_none()));
+    assert!(topology
+        .nodes
+        .iter()
+        .any(|n| n.hostname.as_deref() == Some("topology-a")));
+    // Neither device runs a network instance yet
+    assert!(topology.edges.is_empty());
+
+    let json = serde_json::to_value(&topology).unwrap();
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+
+    drop(service);
+    remove_test_database(test_name)
+        .await
+        .expect("Failed to remove test database");
+}
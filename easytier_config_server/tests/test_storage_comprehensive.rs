@@ -347,3 +347,105 @@ async fn test_storage_database_access() {
         "Database should be accessible through storage"
     );
 }
+
+#[tokio::test]
+async fn test_storage_list_all_clients_across_organizations() {
+    init_tracing();
+    let test_function_name = "test_storage_list_all_clients_across_organizations";
+    let db = get_test_database(test_function_name).await.unwrap();
+    let storage = Arc::new(Storage::new(db));
+
+    let org_a = "test-org-all-a".to_string();
+    let org_b = "test-org-all-b".to_string();
+    let mut expected = Vec::new();
+    let mut handles = Vec::new();
+
+    // Register clients under two organizations concurrently while listing
+    for i in 0..6 {
+        let org_id = if i % 2 == 0 {
+            org_a.clone()
+        } else {
+            org_b.clone()
+        };
+        let client_url = Url::parse(&format!("udp://127.0.0.1:{}", 16000 + i)).unwrap();
+        expected.push((org_id.clone(), client_url.clone()));
+
+        let storage_clone = Arc::clone(&storage);
+        handles.push(tokio::spawn(async move {
+            let token = StorageToken {
+                token: format!("all_clients_token_{:03}", i),
+                client_url,
+                device_id: Uuid::new_v4(),
+                organization_id: org_id,
+            };
+            storage_clone.update_client(token, chrono::Utc::now().timestamp());
+            // Listing while other tasks insert must not deadlock
+            let _ = storage_clone.list_all_clients();
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let mut all_clients = storage.list_all_clients();
+    all_clients.sort();
+    expected.sort();
+    assert_eq!(all_clients, expected);
+    assert_eq!(
+        all_clients.iter().filter(|(org, _)| *org == org_a).count(),
+        3
+    );
+    assert_eq!(
+        all_clients.iter().filter(|(org, _)| *org == org_b).count(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_storage_dual_stack_device_listed_once() {
+    init_tracing();
+    let test_function_name = "test_storage_dual_stack_device_listed_once";
+    let db = get_test_database(test_function_name).await.unwrap();
+    let storage = Storage::new(db);
+
+    let org_id = "test-org-dual-stack".to_string();
+    let device_id = Uuid::new_v4();
+    let v4_token = StorageToken {
+        token: "dual_stack_token".to_string(),
+        client_url: Url::parse("udp://203.0.113.7:17001").unwrap(),
+        device_id,
+        organization_id: org_id.clone(),
+    };
+    let v6_token = StorageToken {
+        client_url: Url::parse("udp://[2001:db8::7]:17001").unwrap(),
+        ..v4_token.clone()
+    };
+
+    // The same device reports over both stacks
+    storage.update_client(v4_token.clone(), 1000);
+    storage.update_client(v6_token.clone(), 2000);
+
+    let org_clients = storage.list_organization_clients(&org_id);
+    assert_eq!(org_clients, vec![v6_token.client_url.clone()]);
+    assert_eq!(storage.list_all_clients().len(), 1);
+    assert_eq!(
+        storage.get_client_url_by_device_id(&org_id, &device_id),
+        Some(v6_token.client_url.clone())
+    );
+
+    // A newer report over IPv4 makes it the listed connection
+    storage.update_client(v4_token.clone(), 3000);
+    let org_clients = storage.list_organization_clients(&org_id);
+    assert_eq!(org_clients, vec![v4_token.client_url.clone()]);
+
+    // Dropping one stack keeps the device listed through the other
+    storage.remove_client(&v4_token);
+    let org_clients = storage.list_organization_clients(&org_id);
+    assert_eq!(org_clients, vec![v6_token.client_url.clone()]);
+
+    storage.remove_client(&v6_token);
+    assert!(storage.list_organization_clients(&org_id).is_empty());
+    assert!(storage
+        .get_client_url_by_device_id(&org_id, &device_id)
+        .is_none());
+}
@@ -3,40 +3,157 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Connecting (or reconnecting) to the config server
+ */
+#define CORTEX_CONNECTION_STATE_CONNECTING 0
+
+/**
+ * Tunnel to the config server is established
+ */
+#define CORTEX_CONNECTION_STATE_CONNECTED 1
+
+/**
+ * Last connection attempt failed, the client will retry
+ */
+#define CORTEX_CONNECTION_STATE_DISCONNECTED 2
+
+/**
+ * Connection cannot be established with the current configuration
+ */
+#define CORTEX_CONNECTION_STATE_ERROR 3
+
+/**
+ * `max_attempts` value that keeps retrying until the client is stopped
+ */
+#define CORTEX_RETRY_FOREVER 0
+
 typedef struct CortexWebClient {
   const char *config_server_url;
   const char *machine_id;
+  /**
+   * Reject an invalid `machine_id` instead of falling back to the system default
+   */
+  bool strict_machine_id;
+  /**
+   * Path to a PEM CA bundle the `wss://` config server must chain to, may be null
+   */
+  const char *ca_cert_path;
+  /**
+   * Inline PEM CA bundle, alternative to `ca_cert_path`, may be null
+   */
+  const char *ca_cert_pem;
 } CortexWebClient;
 
+/**
+ * Reconnect policy passed to `cortex_start_web_client_ex`
+ */
+typedef struct CortexRetryPolicy {
+  /**
+   * Connection attempts before giving up, `CORTEX_RETRY_FOREVER` (or any value <= 0) never gives up
+   */
+  int max_attempts;
+  /**
+   * Delay before the first retry in milliseconds
+   */
+  uint32_t base_interval_ms;
+  /**
+   * Upper bound for the delay between retries in milliseconds
+   */
+  uint32_t max_interval_ms;
+  /**
+   * Factor applied to the delay after every failed attempt, must be >= 1.0
+   */
+  double backoff_multiplier;
+  /**
+   * Timeout of a single connection attempt in milliseconds, 0 disables it
+   */
+  uint32_t connect_timeout_ms;
+} CortexRetryPolicy;
+
+typedef struct CortexPeerInfo {
+  uint32_t peer_id;
+  const char *virtual_ipv4;
+  const char *hostname;
+  int latency_ms;
+  bool is_connected;
+} CortexPeerInfo;
+
+typedef struct CortexRouteInfo {
+  const char *destination;
+  uint32_t next_hop;
+  int metric;
+} CortexRouteInfo;
+
 typedef struct CortexNetworkInfo {
   const char *instance_name;
   const char *network_name;
   const char *virtual_ipv4;
   const char *hostname;
   const char *version;
+  int peer_count;
+  int route_count;
 } CortexNetworkInfo;
 
 /**
  * Start web client in config mode
  *
+ * Unreachable servers are retried forever with the default backoff, use
+ * `cortex_start_web_client_ex` to customize the retry policy.
+ *
  * # Safety
  *
  * The caller must ensure that `client_config` is a valid pointer to a properly initialized `CortexWebClient` struct.
  */
 int cortex_start_web_client(const struct CortexWebClient *client_config);
 
+/**
+ * Start web client in config mode with a reconnect policy
+ *
+ * A null `retry_policy` uses the default policy (retry forever, 1s doubling up to 30s,
+ * 10s connect timeout). Connection attempts run in the background, so the call does not
+ * fail when the server is unreachable. `cortex_stop_web_client` cancels pending retries.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `client_config` is a valid pointer to a properly initialized `CortexWebClient` struct
+ * and `retry_policy` is either null or a valid pointer to a `CortexRetryPolicy` struct.
+ */
+int cortex_start_web_client_ex(const struct CortexWebClient *client_config,
+                               const struct CortexRetryPolicy *retry_policy);
+
 /**
  * Stop web client
  *
+ * Shuts down the instance runtime, which also cancels any pending reconnect attempts.
+ *
  * # Safety
  *
  * The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string.
  */
 int cortex_stop_web_client(const char *instance_name);
 
+/**
+ * Point a running web client at a new config server URL
+ *
+ * The instance keeps its name, runtime, machine_id and connection options. The current
+ * session is closed and the client reconnects to the new server in the background,
+ * while the network instances it already runs keep running.
+ * The new URL must carry the same organization ID as the instance.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `instance_name` and `new_url` are valid pointers to
+ * null-terminated C strings.
+ */
+int cortex_update_web_client_server(const char *instance_name, const char *new_url);
+
 /**
  * Get network info
  *
+ * Network info may be empty while the client is still connecting, use
+ * `cortex_get_web_client_connection_state` to check the tunnel first.
+ *
  * # Safety
  *
  * The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
@@ -45,6 +162,69 @@ int cortex_stop_web_client(const char *instance_name);
 int cortex_get_web_client_network_info(const char *instance_name,
                                        const struct CortexNetworkInfo **info);
 
+/**
+ * Get web client connection state
+ *
+ * Writes one of the `CORTEX_CONNECTION_STATE_*` values to `out_state`.
+ * Network info may be empty while the state is `CONNECTING`.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
+ * and `out_state` is a valid mutable pointer.
+ */
+int cortex_get_web_client_connection_state(const char *instance_name, int *out_state);
+
+/**
+ * Get peers of a web client instance
+ *
+ * Allocates an array of `out_count` peers, release it with `cortex_free_peer_list`.
+ * Returns 0 with `out_count` set to 0 when there are no peers.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
+ * and `out_peers` and `out_count` are valid mutable pointers.
+ */
+int cortex_get_web_client_peers(const char *instance_name,
+                                struct CortexPeerInfo **out_peers,
+                                int *out_count);
+
+/**
+ * Free a peer list returned by `cortex_get_web_client_peers`
+ *
+ * # Safety
+ *
+ * The caller must ensure that `peers` and `count` come from a single call to
+ * `cortex_get_web_client_peers` and that the list is freed only once.
+ */
+void cortex_free_peer_list(struct CortexPeerInfo *peers, int count);
+
+/**
+ * Get routes of a web client instance
+ *
+ * Allocates an array of `out_count` routes, release it with `cortex_free_route_list`.
+ * Returns 0 with `out_count` set to 0 when the route table is empty.
+ *
+ * # Safety
+ *
+ * The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
+ * and `out_routes` and `out_count` are valid mutable pointers.
+ */
+int cortex_get_web_client_routes(const char *instance_name,
+                                 struct CortexRouteInfo **out_routes,
+                                 int *out_count);
+
+/**
+ * Free a route list returned by `cortex_get_web_client_routes`
+ *
+ * # Safety
+ *
+ * The caller must ensure that `routes` and `count` come from a single call to
+ * `cortex_get_web_client_routes` and that the list is freed only once.
+ */
+void cortex_free_route_list(struct CortexRouteInfo *routes, int count);
+
 /**
  * List web client instances
  *
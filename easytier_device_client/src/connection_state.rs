@@ -0,0 +1,112 @@
+//! Connection state tracking for the web client tunnel
+
+use async_trait::async_trait;
+use easytier::tunnel::{common::TunnelWrapper, IpVersion, Tunnel, TunnelConnector, TunnelError};
+use futures::StreamExt;
+use std::ffi::c_int;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+/// Connecting (or reconnecting) to the config server
+pub const CORTEX_CONNECTION_STATE_CONNECTING: c_int = 0;
+/// Tunnel to the config server is established
+pub const CORTEX_CONNECTION_STATE_CONNECTED: c_int = 1;
+/// Last connection attempt failed, the client will retry
+pub const CORTEX_CONNECTION_STATE_DISCONNECTED: c_int = 2;
+/// Connection cannot be established with the current configuration
+pub const CORTEX_CONNECTION_STATE_ERROR: c_int = 3;
+
+/// Shared connection state, updated by `StateTrackingConnector`
+pub type ConnectionState = Arc<AtomicI32>;
+
+/// Connector wrapper that records the outcome of every connect attempt
+///
+/// A connected tunnel reports `DISCONNECTED` as soon as its stream ends or
+/// fails, and the web client's reconnect then moves it on to `CONNECTING`.
+pub struct StateTrackingConnector<C> {
+    inner: C,
+    state: ConnectionState,
+}
+
+impl<C: TunnelConnector> StateTrackingConnector<C> {
+    pub fn new(inner: C) -> Self {
+        Self::with_state(
+            inner,
+            Arc::new(AtomicI32::new(CORTEX_CONNECTION_STATE_CONNECTING)),
+        )
+    }
+
+    /// Report into an existing state, e.g. when the connector is replaced
+    pub fn with_state(inner: C, state: ConnectionState) -> Self {
+        Self { inner, state }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state.clone()
+    }
+}
+
+/// Move `state` from `CONNECTED` to `DISCONNECTED` once the tunnel's stream ends or fails
+///
+/// A state already moved on by a newer connect attempt is left alone.
+fn report_disconnect(tunnel: Box<dyn Tunnel>, state: ConnectionState) -> Box<dyn Tunnel> {
+    let info = tunnel.info();
+    let (stream, sink) = tunnel.split();
+    let disconnected = move || {
+        let _ = state.compare_exchange(
+            CORTEX_CONNECTION_STATE_CONNECTED,
+            CORTEX_CONNECTION_STATE_DISCONNECTED,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    };
+    let on_error = disconnected.clone();
+    let stream = stream
+        .inspect(move |packet| {
+            if packet.is_err() {
+                on_error();
+            }
+        })
+        .chain(futures::stream::poll_fn(move |_| {
+            disconnected();
+            std::task::Poll::Ready(None)
+        }))
+        // Keep the original tunnel alive as long as its stream
+        .map(move |packet| {
+            let _ = &tunnel;
+            packet
+        });
+    Box::new(TunnelWrapper::new(stream, sink, info))
+}
+
+#[async_trait]
+impl<C: TunnelConnector> TunnelConnector for StateTrackingConnector<C> {
+    async fn connect(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        self.state
+            .store(CORTEX_CONNECTION_STATE_CONNECTING, Ordering::Relaxed);
+
+        let ret = self.inner.connect().await;
+        let state = match &ret {
+            Ok(_) => CORTEX_CONNECTION_STATE_CONNECTED,
+            Err(TunnelError::InvalidProtocol(_)) | Err(TunnelError::InvalidAddr(_)) => {
+                CORTEX_CONNECTION_STATE_ERROR
+            }
+            Err(_) => CORTEX_CONNECTION_STATE_DISCONNECTED,
+        };
+        self.state.store(state, Ordering::Relaxed);
+        ret.map(|tunnel| report_disconnect(tunnel, self.state.clone()))
+    }
+
+    fn remote_url(&self) -> url::Url {
+        self.inner.remote_url()
+    }
+
+    fn set_bind_addrs(&mut self, addrs: Vec<SocketAddr>) {
+        self.inner.set_bind_addrs(addrs)
+    }
+
+    fn set_ip_version(&mut self, ip_version: IpVersion) {
+        self.inner.set_ip_version(ip_version)
+    }
+}
@@ -4,10 +4,18 @@
 //! This crate is used by cortex_agent (devices) to establish connection
 //! with cortex_server's config server.
 
+mod connection_state;
+mod retry;
+mod server_switch;
 mod stun_wrapper;
+mod tls;
 mod web_client;
 
+pub use connection_state::*;
+pub use retry::*;
+pub use server_switch::{ConnectorSwitch, SwitchableConnector};
 pub use stun_wrapper::MockStunInfoCollectorWrapper;
+pub use tls::{parse_ca_bundle, PinnedCaWssConnector};
 pub use web_client::*;
 
 // Re-export common utilities
@@ -0,0 +1,179 @@
+//! Reconnect policy for the web client tunnel
+
+use async_trait::async_trait;
+use easytier::tunnel::{IpVersion, Tunnel, TunnelConnector, TunnelError};
+use std::ffi::c_int;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::connection_state::{
+    ConnectionState, CORTEX_CONNECTION_STATE_DISCONNECTED, CORTEX_CONNECTION_STATE_ERROR,
+};
+
+/// `max_attempts` value that keeps retrying until the client is stopped
+pub const CORTEX_RETRY_FOREVER: c_int = 0;
+
+/// Reconnect policy passed to `cortex_start_web_client_ex`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CortexRetryPolicy {
+    /// Connection attempts before giving up, `CORTEX_RETRY_FOREVER` (or any value <= 0) never gives up
+    pub max_attempts: c_int,
+    /// Delay before the first retry in milliseconds
+    pub base_interval_ms: u32,
+    /// Upper bound for the delay between retries in milliseconds
+    pub max_interval_ms: u32,
+    /// Factor applied to the delay after every failed attempt, must be >= 1.0
+    pub backoff_multiplier: f64,
+    /// Timeout of a single connection attempt in milliseconds, 0 disables it
+    pub connect_timeout_ms: u32,
+}
+
+/// Validated reconnect policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: Option<u32>,
+    pub base_interval: Duration,
+    pub max_interval: Duration,
+    pub backoff_multiplier: f64,
+    pub connect_timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            base_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            backoff_multiplier: 2.0,
+            connect_timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+impl TryFrom<&CortexRetryPolicy> for RetryPolicy {
+    type Error = String;
+
+    fn try_from(policy: &CortexRetryPolicy) -> Result<Self, Self::Error> {
+        if !policy.backoff_multiplier.is_finite() || policy.backoff_multiplier < 1.0 {
+            return Err(format!(
+                "backoff_multiplier must be >= 1.0, got {}",
+                policy.backoff_multiplier
+            ));
+        }
+        if policy.max_interval_ms < policy.base_interval_ms {
+            return Err(format!(
+                "max_interval_ms ({}) is smaller than base_interval_ms ({})",
+                policy.max_interval_ms, policy.base_interval_ms
+            ));
+        }
+
+        Ok(Self {
+            max_attempts: (policy.max_attempts > 0).then_some(policy.max_attempts as u32),
+            base_interval: Duration::from_millis(policy.base_interval_ms as u64),
+            max_interval: Duration::from_millis(policy.max_interval_ms as u64),
+            backoff_multiplier: policy.backoff_multiplier,
+            connect_timeout: (policy.connect_timeout_ms > 0)
+                .then(|| Duration::from_millis(policy.connect_timeout_ms as u64)),
+        })
+    }
+}
+
+impl RetryPolicy {
+    /// Delay to wait after the given (1-based) failed attempt
+    pub fn delay_after(&self, attempt: u32) -> Duration {
+        let factor = self
+            .backoff_multiplier
+            .powi(attempt.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay_ms = self.base_interval.as_millis() as f64 * factor;
+        if !delay_ms.is_finite() || delay_ms >= self.max_interval.as_millis() as f64 {
+            self.max_interval
+        } else {
+            Duration::from_millis(delay_ms as u64)
+        }
+    }
+}
+
+/// Connector wrapper that retries failed connects according to a `RetryPolicy`
+///
+/// Once `max_attempts` is exhausted the state becomes `ERROR` and `connect`
+/// never resolves, so the web client stops reconnecting until it is stopped.
+pub struct RetryConnector<C> {
+    inner: C,
+    policy: RetryPolicy,
+    state: ConnectionState,
+}
+
+impl<C: TunnelConnector> RetryConnector<C> {
+    pub fn new(inner: C, policy: RetryPolicy, state: ConnectionState) -> Self {
+        Self {
+            inner,
+            policy,
+            state,
+        }
+    }
+
+    async fn connect_once(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        match self.policy.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.inner.connect())
+                .await
+                .map_err(TunnelError::from)?,
+            None => self.inner.connect().await,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: TunnelConnector> TunnelConnector for RetryConnector<C> {
+    async fn connect(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        let mut attempt = 0u32;
+        loop {
+            attempt = attempt.saturating_add(1);
+            let err = match self.connect_once().await {
+                Ok(tunnel) => return Ok(tunnel),
+                Err(e) => e,
+            };
+
+            if matches!(err, TunnelError::Timeout(_)) {
+                self.state
+                    .store(CORTEX_CONNECTION_STATE_DISCONNECTED, Ordering::Relaxed);
+            }
+
+            if self.policy.max_attempts.is_some_and(|max| attempt >= max) {
+                warn!(
+                    "Giving up connecting to {} after {} attempts: {}",
+                    self.inner.remote_url(),
+                    attempt,
+                    err
+                );
+                self.state
+                    .store(CORTEX_CONNECTION_STATE_ERROR, Ordering::Relaxed);
+                return std::future::pending().await;
+            }
+
+            let delay = self.policy.delay_after(attempt);
+            info!(
+                "Connect attempt {} to {} failed: {}, retrying in {:?}",
+                attempt,
+                self.inner.remote_url(),
+                err,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn remote_url(&self) -> url::Url {
+        self.inner.remote_url()
+    }
+
+    fn set_bind_addrs(&mut self, addrs: Vec<SocketAddr>) {
+        self.inner.set_bind_addrs(addrs)
+    }
+
+    fn set_ip_version(&mut self, ip_version: IpVersion) {
+        self.inner.set_ip_version(ip_version)
+    }
+}
@@ -0,0 +1,109 @@
+//! Re-pointing a running web client at another config server
+
+use async_trait::async_trait;
+use easytier::tunnel::common::TunnelWrapper;
+use easytier::tunnel::{IpVersion, Tunnel, TunnelConnector, TunnelError};
+use futures::StreamExt;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Handle used to move a `SwitchableConnector` to another connector
+#[derive(Clone)]
+pub struct ConnectorSwitch {
+    next: Arc<Mutex<Option<Box<dyn TunnelConnector>>>>,
+    generation: Arc<watch::Sender<u64>>,
+}
+
+impl ConnectorSwitch {
+    fn new() -> Self {
+        Self {
+            next: Arc::new(Mutex::new(None)),
+            generation: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    /// Use `connector` for the next connect and close the current tunnel
+    ///
+    /// A connect attempt still in progress is abandoned, so a client stuck
+    /// retrying an unreachable server moves over right away.
+    pub fn switch_to(&self, connector: Box<dyn TunnelConnector>) {
+        *self.next.lock().unwrap() = Some(connector);
+        self.generation.send_modify(|generation| *generation += 1);
+    }
+
+    fn take_next(&self) -> Option<Box<dyn TunnelConnector>> {
+        self.next.lock().unwrap().take()
+    }
+}
+
+/// Connector whose target can be replaced while the web client keeps running
+///
+/// The web client reconnects whenever its tunnel closes, so replacing the
+/// connector and closing the tunnel moves its session to the new server
+/// without dropping the client or the network instances it runs.
+pub struct SwitchableConnector {
+    current: Box<dyn TunnelConnector>,
+    switch: ConnectorSwitch,
+}
+
+impl SwitchableConnector {
+    pub fn new(connector: Box<dyn TunnelConnector>) -> Self {
+        Self {
+            current: connector,
+            switch: ConnectorSwitch::new(),
+        }
+    }
+
+    pub fn switch(&self) -> ConnectorSwitch {
+        self.switch.clone()
+    }
+}
+
+/// End the tunnel's stream once the connector is switched
+fn close_on_switch(tunnel: Box<dyn Tunnel>, mut switched: watch::Receiver<u64>) -> Box<dyn Tunnel> {
+    let info = tunnel.info();
+    let (stream, sink) = tunnel.split();
+    let stream = stream
+        .take_until(async move {
+            let _ = switched.changed().await;
+        })
+        // Keep the original tunnel alive as long as its stream
+        .map(move |packet| {
+            let _ = &tunnel;
+            packet
+        });
+    Box::new(TunnelWrapper::new(stream, sink, info))
+}
+
+#[async_trait]
+impl TunnelConnector for SwitchableConnector {
+    async fn connect(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        loop {
+            if let Some(next) = self.switch.take_next() {
+                self.current = next;
+            }
+
+            let mut switched = self.switch.generation.subscribe();
+            let ret = tokio::select! {
+                ret = self.current.connect() => Some(ret),
+                _ = switched.changed() => None,
+            };
+            if let Some(ret) = ret {
+                return ret.map(|tunnel| close_on_switch(tunnel, switched));
+            }
+        }
+    }
+
+    fn remote_url(&self) -> url::Url {
+        self.current.remote_url()
+    }
+
+    fn set_bind_addrs(&mut self, addrs: Vec<SocketAddr>) {
+        self.current.set_bind_addrs(addrs)
+    }
+
+    fn set_ip_version(&mut self, ip_version: IpVersion) {
+        self.current.set_ip_version(ip_version)
+    }
+}
@@ -9,6 +9,7 @@ use std::ops::{Deref, DerefMut};
 
 pub struct MockStunInfoCollectorWrapper {
     inner: MockStunInfoCollector,
+    public_endpoint: Option<SocketAddr>,
 }
 
 impl MockStunInfoCollectorWrapper {
@@ -17,8 +18,21 @@ impl MockStunInfoCollectorWrapper {
             inner: MockStunInfoCollector {
                 udp_nat_type: NatType::Unknown,
             },
+            public_endpoint: None,
         }
     }
+
+    /// Report the given NAT type for UDP
+    pub fn with_nat_type(mut self, nat_type: NatType) -> Self {
+        self.inner.udp_nat_type = nat_type;
+        self
+    }
+
+    /// Report a fixed public ip:port for STUN results and UDP port mappings
+    pub fn with_public_endpoint(mut self, endpoint: SocketAddr) -> Self {
+        self.public_endpoint = Some(endpoint);
+        self
+    }
 }
 
 impl Deref for MockStunInfoCollectorWrapper {
@@ -38,11 +52,18 @@ impl DerefMut for MockStunInfoCollectorWrapper {
 #[async_trait]
 impl StunInfoCollectorTrait for MockStunInfoCollectorWrapper {
     fn get_stun_info(&self) -> StunInfo {
-        self.inner.get_stun_info()
+        let mut info = self.inner.get_stun_info();
+        if let Some(endpoint) = self.public_endpoint {
+            info.public_ip = vec![endpoint.ip().to_string()];
+        }
+        info
     }
 
     async fn get_udp_port_mapping(&self, local_port: u16) -> Result<SocketAddr, Error> {
-        self.inner.get_udp_port_mapping(local_port).await
+        match self.public_endpoint {
+            Some(endpoint) => Ok(endpoint),
+            None => self.inner.get_udp_port_mapping(local_port).await,
+        }
     }
 }
 
@@ -0,0 +1,216 @@
+//! Private CA pinning for TLS connections to the config server
+//!
+//! The EasyTier `wss` connector does not expose its root store, so a pinned
+//! CA is served by a connector of our own: it terminates TLS with a root store
+//! holding only the pinned CA and runs EasyTier's websocket tunnel framing over
+//! that same connection.
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use easytier::proto::common::TunnelInfo;
+use easytier::tunnel::common::TunnelWrapper;
+use easytier::tunnel::packet_def::{ZCPacket, ZCPacketType};
+use easytier::tunnel::{IpVersion, Tunnel, TunnelConnector, TunnelError};
+use futures::{SinkExt, StreamExt};
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpSocket, TcpStream};
+use tokio_rustls::TlsConnector;
+use tokio_websockets::{ClientBuilder, Limits, Message};
+use tracing::warn;
+
+/// Config server URL schemes that run over TLS
+const TLS_SCHEMES: &[&str] = &["wss"];
+
+/// Check whether a config server URL scheme uses TLS
+pub fn is_tls_scheme(scheme: &str) -> bool {
+    TLS_SCHEMES.contains(&scheme)
+}
+
+/// Parse a PEM bundle into a root store, failing if it holds no certificate
+pub fn parse_ca_bundle(pem: &[u8]) -> Result<RootCertStore, String> {
+    let certs = rustls_pemfile::certs(&mut &*pem)
+        .collect::<Result<Vec<CertificateDer<'static>>, _>>()
+        .map_err(|e| format!("failed to parse CA bundle: {}", e))?;
+    if certs.is_empty() {
+        return Err("CA bundle contains no PEM certificate".to_string());
+    }
+
+    let mut roots = RootCertStore::empty();
+    for cert in certs {
+        roots
+            .add(cert)
+            .map_err(|e| format!("invalid CA certificate: {}", e))?;
+    }
+    Ok(roots)
+}
+
+/// Load a CA bundle from a PEM file
+pub fn load_ca_bundle_file(path: &str) -> Result<RootCertStore, String> {
+    let pem =
+        std::fs::read(path).map_err(|e| format!("failed to read CA bundle '{}': {}", path, e))?;
+    parse_ca_bundle(&pem)
+}
+
+/// `wss` connector that only trusts the pinned CA
+///
+/// The tunnel is carried by the TLS session verified against the pinned
+/// roots, so a server without a certificate chaining to them never sees it.
+pub struct PinnedCaWssConnector {
+    url: url::Url,
+    tls: TlsConnector,
+    bind_addrs: Vec<SocketAddr>,
+    ip_version: IpVersion,
+}
+
+impl PinnedCaWssConnector {
+    pub fn new(url: url::Url, roots: RootCertStore) -> Result<Self, String> {
+        if !is_tls_scheme(url.scheme()) {
+            return Err(format!("'{}' is not a wss:// URL", url));
+        }
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|e| format!("failed to configure TLS: {}", e))?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+
+        Ok(Self {
+            url,
+            tls: TlsConnector::from(Arc::new(config)),
+            bind_addrs: vec![],
+            ip_version: IpVersion::Both,
+        })
+    }
+
+    fn host(&self) -> std::io::Result<String> {
+        Ok(self
+            .url
+            .host_str()
+            .ok_or_else(|| std::io::Error::other(format!("no host in '{}'", self.url)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string())
+    }
+
+    /// Resolve the server address of the allowed IP version
+    async fn resolve(&self, host: &str) -> std::io::Result<SocketAddr> {
+        let port = self
+            .url
+            .port_or_known_default()
+            .ok_or_else(|| std::io::Error::other(format!("no port in '{}'", self.url)))?;
+        tokio::net::lookup_host((host, port))
+            .await?
+            .find(|addr| match self.ip_version {
+                IpVersion::V4 => addr.is_ipv4(),
+                IpVersion::V6 => addr.is_ipv6(),
+                IpVersion::Both => true,
+            })
+            .ok_or_else(|| {
+                std::io::Error::other(format!(
+                    "no {:?} address for '{}'",
+                    self.ip_version, self.url
+                ))
+            })
+    }
+
+    /// Connect TCP from the first bind address of the server's family, if any
+    async fn connect_tcp(&self, addr: SocketAddr) -> std::io::Result<TcpStream> {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        if let Some(bind_addr) = self
+            .bind_addrs
+            .iter()
+            .find(|bind_addr| bind_addr.is_ipv4() == addr.is_ipv4())
+        {
+            socket.bind(*bind_addr)?;
+        }
+        let stream = socket.connect(addr).await?;
+        let _ = stream.set_nodelay(true);
+        Ok(stream)
+    }
+}
+
+/// Decode a websocket message into a tunnel packet, None for control messages
+fn packet_from_message(
+    msg: Result<Message, tokio_websockets::Error>,
+) -> Option<Result<ZCPacket, TunnelError>> {
+    let msg = match msg {
+        Ok(msg) => msg,
+        Err(e) => return Some(Err(std::io::Error::other(e).into())),
+    };
+    if msg.is_close() || msg.is_ping() || msg.is_pong() {
+        return None;
+    }
+    if !msg.is_binary() {
+        return Some(Err(TunnelError::InvalidPacket(format!(
+            "Unexpected websocket message: {:?}",
+            msg
+        ))));
+    }
+    Some(Ok(ZCPacket::new_from_buf(
+        BytesMut::from(&*msg.into_payload()),
+        ZCPacketType::WS,
+    )))
+}
+
+#[async_trait]
+impl TunnelConnector for PinnedCaWssConnector {
+    async fn connect(&mut self) -> Result<Box<dyn Tunnel>, TunnelError> {
+        let host = self.host()?;
+        let server_name = ServerName::try_from(host.clone())
+            .map_err(|e| std::io::Error::other(format!("invalid server name: {}", e)))?;
+        let stream = self.connect_tcp(self.resolve(&host).await?).await?;
+        let local_addr = stream.local_addr()?;
+
+        let stream = self.tls.connect(server_name, stream).await.map_err(|e| {
+            warn!("Config server {} failed CA pinning: {}", self.url, e);
+            e
+        })?;
+        let (websocket, _) = ClientBuilder::new()
+            .uri(self.url.as_str())
+            .map_err(std::io::Error::other)?
+            .limits(Limits::unlimited())
+            .connect_on(stream)
+            .await
+            .map_err(std::io::Error::other)?;
+
+        let local_url: url::Url = format!("{}://{}", self.url.scheme(), local_addr)
+            .parse()
+            .map_err(std::io::Error::other)?;
+        let info = TunnelInfo {
+            tunnel_type: self.url.scheme().to_owned(),
+            local_addr: Some(local_url.into()),
+            remote_addr: Some(self.url.clone().into()),
+            ..Default::default()
+        };
+
+        let (write, read) = websocket.split();
+        Ok(Box::new(TunnelWrapper::new(
+            read.filter_map(|msg| async move { packet_from_message(msg) }),
+            write
+                .sink_map_err(|e| TunnelError::from(std::io::Error::other(e)))
+                .with(|packet: ZCPacket| async move {
+                    Ok::<_, TunnelError>(Message::binary(packet.tunnel_payload_bytes().freeze()))
+                }),
+            Some(info),
+        )))
+    }
+
+    fn remote_url(&self) -> url::Url {
+        self.url.clone()
+    }
+
+    fn set_bind_addrs(&mut self, addrs: Vec<SocketAddr>) {
+        self.bind_addrs = addrs;
+    }
+
+    fn set_ip_version(&mut self, ip_version: IpVersion) {
+        self.ip_version = ip_version;
+    }
+}
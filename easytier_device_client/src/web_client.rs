@@ -5,27 +5,49 @@ use easytier::common::config::TomlConfigLoader;
 use easytier::common::global_ctx::GlobalCtx;
 use easytier::common::set_default_machine_id;
 use easytier::connector::create_connector_by_url;
-use easytier::proto::cli::{PeerManageRpcClientFactory, ShowNodeInfoRequest};
+use easytier::proto::cli::{
+    ListPeerRequest, ListRouteRequest, PeerInfo, PeerManageRpcClientFactory, Route,
+    ShowNodeInfoRequest,
+};
 use easytier::proto::rpc_impl::standalone::StandAloneClient;
 use easytier::proto::rpc_types::controller::BaseController;
 use easytier::tunnel::tcp::TcpTunnelConnector;
-use easytier::tunnel::IpVersion;
+use easytier::tunnel::{IpVersion, TunnelConnector};
 use easytier::web_client::WebClient;
-use easytier_common::{c_str_to_string, set_error_msg};
+use easytier_common::{c_str_to_string, set_error, set_error_msg, CortexErrorCode};
 use once_cell::sync::Lazy;
+use rustls::RootCertStore;
 use std::collections::HashMap;
 use std::ffi::{c_char, c_int, CString};
+use std::sync::atomic::AtomicI32;
 use std::sync::{Arc, Mutex};
 use tracing::{error, info, warn};
 
+use crate::connection_state::{
+    ConnectionState, StateTrackingConnector, CORTEX_CONNECTION_STATE_CONNECTING,
+};
+use crate::retry::{CortexRetryPolicy, RetryConnector, RetryPolicy};
+use crate::server_switch::{ConnectorSwitch, SwitchableConnector};
+use crate::tls::{is_tls_scheme, load_ca_bundle_file, parse_ca_bundle, PinnedCaWssConnector};
 use crate::MockStunInfoCollectorWrapper;
 
+/// Connection options kept to rebuild the connector when the server changes
+#[derive(Clone)]
+struct WebClientOptions {
+    retry_policy: RetryPolicy,
+    ca_roots: Option<RootCertStore>,
+    hostname: String,
+}
+
 // Type alias - store GlobalCtx and current virtual IP
 type WebClientInstance = (
     Arc<WebClient>,
     Arc<GlobalCtx>,
     tokio::runtime::Runtime,
     Arc<std::sync::Mutex<Option<String>>>, // Cached virtual IP
+    ConnectionState,                       // Tunnel connection state
+    WebClientOptions,                      // Options used to (re)build the connector
+    ConnectorSwitch,                       // Re-points the client at another server
 );
 type WebClientMap = HashMap<String, WebClientInstance>;
 
@@ -38,6 +60,30 @@ static WEB_CLIENT_INSTANCES: Lazy<Mutex<WebClientMap>> = Lazy::new(|| Mutex::new
 pub struct CortexWebClient {
     pub config_server_url: *const c_char,
     pub machine_id: *const c_char,
+    /// Reject an invalid `machine_id` instead of falling back to the system default
+    pub strict_machine_id: bool,
+    /// Path to a PEM CA bundle the `wss://` config server must chain to, may be null
+    pub ca_cert_path: *const c_char,
+    /// Inline PEM CA bundle, alternative to `ca_cert_path`, may be null
+    pub ca_cert_pem: *const c_char,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct CortexPeerInfo {
+    pub peer_id: u32,
+    pub virtual_ipv4: *const c_char,
+    pub hostname: *const c_char,
+    pub latency_ms: c_int,
+    pub is_connected: bool,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct CortexRouteInfo {
+    pub destination: *const c_char,
+    pub next_hop: u32,
+    pub metric: c_int,
 }
 
 #[repr(C)]
@@ -48,68 +94,190 @@ pub struct CortexNetworkInfo {
     pub virtual_ipv4: *const c_char,
     pub hostname: *const c_char,
     pub version: *const c_char,
+    pub peer_count: c_int,
+    pub route_count: c_int,
+}
+
+/// Validate a machine_id string and parse it as a UUID
+pub fn validate_machine_id(id_str: &str) -> Result<uuid::Uuid, String> {
+    uuid::Uuid::parse_str(id_str.trim())
+        .map_err(|e| format!("'{}' is not a valid UUID: {}", id_str, e))
+}
+
+/// Split a config server URL into its base URL and organization ID
+fn parse_config_server_url(raw: &str) -> Result<(url::Url, String), String> {
+    let mut url =
+        url::Url::parse(raw).map_err(|e| format!("invalid config_server_url format: {}", e))?;
+    let organization_id = url.path().trim_start_matches('/').to_string();
+    if organization_id.is_empty() {
+        return Err("no organization ID in config_server_url path".to_string());
+    }
+    url.set_path("");
+    Ok((url, organization_id))
+}
+
+/// Create a connector to `base_url`, reporting its tunnel state to `state`
+async fn create_server_connector(
+    base_url: &url::Url,
+    global_ctx: &Arc<GlobalCtx>,
+    options: &WebClientOptions,
+    state: ConnectionState,
+) -> Result<Box<dyn TunnelConnector>, String> {
+    // A pinned CA needs our own wss connector, EasyTier's uses its default trust roots
+    let connector: Box<dyn TunnelConnector> = match &options.ca_roots {
+        Some(roots) => Box::new(PinnedCaWssConnector::new(base_url.clone(), roots.clone())?),
+        None => create_connector_by_url(base_url.as_str(), global_ctx, IpVersion::Both)
+            .await
+            .map_err(|e| format!("failed to create connector: {}", e))?,
+    };
+
+    // Track the tunnel state through the connector
+    let connector = StateTrackingConnector::with_state(connector, state.clone());
+    let connector = RetryConnector::new(connector, options.retry_policy.clone(), state);
+    Ok(Box::new(connector))
+}
+
+/// Load the CA bundle configured on a `CortexWebClient`, if any
+unsafe fn load_web_client_ca(config: &CortexWebClient) -> Result<Option<RootCertStore>, String> {
+    match (config.ca_cert_path.is_null(), config.ca_cert_pem.is_null()) {
+        (true, true) => Ok(None),
+        (false, false) => Err("set only one of ca_cert_path and ca_cert_pem".to_string()),
+        (false, true) => {
+            let path = c_str_to_string(config.ca_cert_path)
+                .map_err(|e| format!("invalid ca_cert_path: {}", e))?;
+            load_ca_bundle_file(&path).map(Some)
+        }
+        (true, false) => {
+            let pem = c_str_to_string(config.ca_cert_pem)
+                .map_err(|e| format!("invalid ca_cert_pem: {}", e))?;
+            parse_ca_bundle(pem.as_bytes()).map(Some)
+        }
+    }
 }
 
 /// Start web client in config mode
 ///
+/// Unreachable servers are retried forever with the default backoff, use
+/// `cortex_start_web_client_ex` to customize the retry policy.
+///
 /// # Safety
 ///
 /// The caller must ensure that `client_config` is a valid pointer to a properly initialized `CortexWebClient` struct.
 #[no_mangle]
 pub unsafe extern "C" fn cortex_start_web_client(client_config: *const CortexWebClient) -> c_int {
+    cortex_start_web_client_ex(client_config, std::ptr::null())
+}
+
+/// Start web client in config mode with a reconnect policy
+///
+/// A null `retry_policy` uses the default policy (retry forever, 1s doubling up to 30s,
+/// 10s connect timeout). Connection attempts run in the background, so the call does not
+/// fail when the server is unreachable. `cortex_stop_web_client` cancels pending retries.
+///
+/// # Safety
+///
+/// The caller must ensure that `client_config` is a valid pointer to a properly initialized `CortexWebClient` struct
+/// and `retry_policy` is either null or a valid pointer to a `CortexRetryPolicy` struct.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_start_web_client_ex(
+    client_config: *const CortexWebClient,
+    retry_policy: *const CortexRetryPolicy,
+) -> c_int {
     if client_config.is_null() {
         error!("cortex_start_web_client: client_config is null");
-        set_error_msg("client_config is null");
+        set_error(CortexErrorCode::NullPointer, "client_config is null");
         return -1;
     }
 
     let config = &*client_config;
 
+    let retry_policy = if retry_policy.is_null() {
+        RetryPolicy::default()
+    } else {
+        match RetryPolicy::try_from(&*retry_policy) {
+            Ok(policy) => policy,
+            Err(e) => {
+                error!("Invalid retry_policy: {}", e);
+                set_error(
+                    CortexErrorCode::InvalidArgument,
+                    &format!("invalid retry_policy: {}", e),
+                );
+                return -1;
+            }
+        }
+    };
+
     // Parse config server URL
     let config_server_url = match c_str_to_string(config.config_server_url) {
         Ok(url) => url,
         Err(e) => {
             error!("Invalid config_server_url: {}", e);
-            set_error_msg(&format!("invalid config_server_url: {}", e));
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid config_server_url: {}", e),
+            );
             return -1;
         }
     };
 
     // Extract organization ID from config_server_url path
-    let organization_id = match url::Url::parse(&config_server_url) {
-        Ok(url) => {
-            let path = url.path().trim_start_matches('/');
-            if path.is_empty() {
-                error!("No organization ID in config_server_url path");
-                set_error_msg("no organization ID in config_server_url path");
-                return -1;
-            }
-            path.to_string()
+    let (base_url, organization_id) = match parse_config_server_url(&config_server_url) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Invalid config_server_url: {}", e);
+            set_error(CortexErrorCode::InvalidUrl, &e);
+            return -1;
         }
+    };
+    let config_server_scheme = base_url.scheme().to_string();
+
+    // Load the pinned CA bundle, only meaningful for TLS config server URLs
+    let has_ca = !config.ca_cert_path.is_null() || !config.ca_cert_pem.is_null();
+    if has_ca && !is_tls_scheme(&config_server_scheme) {
+        error!("CA bundle set for non-TLS config_server_url");
+        set_error(
+            CortexErrorCode::InvalidArgument,
+            &format!(
+                "CA bundle requires a TLS config_server_url, got '{}://'",
+                config_server_scheme
+            ),
+        );
+        return -1;
+    }
+    let ca_roots = match load_web_client_ca(config) {
+        Ok(roots) => roots,
         Err(e) => {
-            error!("Invalid config_server_url format: {}", e);
-            set_error_msg(&format!("invalid config_server_url format: {}", e));
+            error!("Invalid CA bundle: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("invalid CA bundle: {}", e),
+            );
             return -1;
         }
     };
 
     // Parse machine_id
     let machine_id = if !config.machine_id.is_null() {
-        match c_str_to_string(config.machine_id) {
-            Ok(id_str) => match uuid::Uuid::parse_str(&id_str) {
-                Ok(id) => {
-                    info!("Using persistent machine_id: {}", id);
-                    Some(id)
-                }
-                Err(e) => {
-                    warn!(
-                        "Invalid machine_id '{}': {}, using system default",
-                        id_str, e
-                    );
-                    None
-                }
-            },
-            Err(_) => None,
+        match c_str_to_string(config.machine_id)
+            .map_err(|e| e.to_string())
+            .and_then(|id_str| validate_machine_id(&id_str))
+        {
+            Ok(id) => {
+                info!("Using persistent machine_id: {}", id);
+                Some(id)
+            }
+            Err(e) if config.strict_machine_id => {
+                error!("Invalid machine_id: {}", e);
+                set_error(
+                    CortexErrorCode::InvalidArgument,
+                    &format!("invalid machine_id: {}", e),
+                );
+                return -1;
+            }
+            Err(e) => {
+                warn!("Invalid machine_id: {}, using system default", e);
+                None
+            }
         }
     } else {
         None
@@ -127,14 +295,6 @@ pub unsafe extern "C" fn cortex_start_web_client(client_config: *const CortexWeb
 
     // Execute async code
     let result = runtime.block_on(async {
-        let config_server_url = match url::Url::parse(&config_server_url) {
-            Ok(u) => u,
-            Err(e) => return Err(format!("failed to parse URL: {}", e)),
-        };
-
-        // Extract base URL and token (organization_id) from URL path
-        let mut base_url = config_server_url.clone();
-        base_url.set_path("");
         let token = organization_id.clone();
 
         info!(
@@ -142,10 +302,6 @@ pub unsafe extern "C" fn cortex_start_web_client(client_config: *const CortexWeb
             base_url, token
         );
 
-        if token.is_empty() {
-            return Err("empty organization_id".to_string());
-        }
-
         // Set machine_id if provided
         if let Some(mid) = machine_id {
             set_default_machine_id(Some(mid.to_string()));
@@ -164,13 +320,19 @@ pub unsafe extern "C" fn cortex_start_web_client(client_config: *const CortexWeb
         let hostname = gethostname::gethostname().to_string_lossy().to_string();
         info!("Device hostname: {}", hostname);
 
-        // Create connector
-        let connector = create_connector_by_url(base_url.as_str(), &global_ctx, IpVersion::Both)
-            .await
-            .map_err(|e| format!("failed to create connector: {}", e))?;
-
-        // Create WebClient
-        let web_client = WebClient::new(connector, token.clone(), hostname);
+        let options = WebClientOptions {
+            retry_policy,
+            ca_roots,
+            hostname,
+        };
+        let connection_state: ConnectionState =
+            Arc::new(AtomicI32::new(CORTEX_CONNECTION_STATE_CONNECTING));
+        let connector = SwitchableConnector::new(
+            create_server_connector(&base_url, &global_ctx, &options, connection_state.clone())
+                .await?,
+        );
+        let switch = connector.switch();
+        let web_client = WebClient::new(connector, token.clone(), options.hostname.clone());
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
         info!("Web client created successfully");
@@ -196,15 +358,39 @@ pub unsafe extern "C" fn cortex_start_web_client(client_config: *const CortexWeb
             }
         });
 
-        Ok((web_client, global_ctx, virtual_ip_cache, token))
+        Ok((
+            web_client,
+            global_ctx,
+            virtual_ip_cache,
+            connection_state,
+            options,
+            switch,
+            token,
+        ))
     });
 
     match result {
-        Ok((web_client, global_ctx, virtual_ip_cache, instance_name)) => {
+        Ok((
+            web_client,
+            global_ctx,
+            virtual_ip_cache,
+            connection_state,
+            options,
+            switch,
+            instance_name,
+        )) => {
             let mut instances = WEB_CLIENT_INSTANCES.lock().unwrap();
             instances.insert(
                 instance_name.clone(),
-                (Arc::new(web_client), global_ctx, runtime, virtual_ip_cache),
+                (
+                    Arc::new(web_client),
+                    global_ctx,
+                    runtime,
+                    virtual_ip_cache,
+                    connection_state,
+                    options,
+                    switch,
+                ),
             );
             info!("Web client instance '{}' registered", instance_name);
             0
@@ -219,6 +405,8 @@ pub unsafe extern "C" fn cortex_start_web_client(client_config: *const CortexWeb
 
 /// Stop web client
 ///
+/// Shuts down the instance runtime, which also cancels any pending reconnect attempts.
+///
 /// # Safety
 ///
 /// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string.
@@ -228,7 +416,10 @@ pub unsafe extern "C" fn cortex_stop_web_client(instance_name: *const c_char) ->
         Ok(name) => name,
         Err(e) => {
             error!("Invalid instance_name: {}", e);
-            set_error_msg(&format!("invalid instance_name: {}", e));
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
             return -1;
         }
     };
@@ -239,50 +430,252 @@ pub unsafe extern "C" fn cortex_stop_web_client(instance_name: *const c_char) ->
         0
     } else {
         warn!("Web client instance '{}' not found", name);
-        set_error_msg(&format!("instance '{}' not found", name));
+        set_error(
+            CortexErrorCode::NotFound,
+            &format!("instance '{}' not found", name),
+        );
         -1
     }
 }
 
-/// Helper function to query virtual IP via RPC
-async fn query_virtual_ip_via_rpc() -> String {
+/// Point a running web client at a new config server URL
+///
+/// The instance keeps its name, runtime, machine_id and connection options. The current
+/// session is closed and the client reconnects to the new server in the background,
+/// while the network instances it already runs keep running.
+/// The new URL must carry the same organization ID as the instance.
+///
+/// # Safety
+///
+/// The caller must ensure that `instance_name` and `new_url` are valid pointers to
+/// null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_update_web_client_server(
+    instance_name: *const c_char,
+    new_url: *const c_char,
+) -> c_int {
+    let name = match c_str_to_string(instance_name) {
+        Ok(name) => name,
+        Err(e) => {
+            error!("Invalid instance_name: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
+            return -1;
+        }
+    };
+
+    let new_url = match c_str_to_string(new_url) {
+        Ok(url) => url,
+        Err(e) => {
+            error!("Invalid config_server_url: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid config_server_url: {}", e),
+            );
+            return -1;
+        }
+    };
+
+    let (base_url, organization_id) = match parse_config_server_url(&new_url) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Invalid config_server_url: {}", e);
+            set_error(CortexErrorCode::InvalidUrl, &e);
+            return -1;
+        }
+    };
+    if organization_id != name {
+        error!(
+            "Organization ID '{}' does not match instance '{}'",
+            organization_id, name
+        );
+        set_error_msg(&format!(
+            "organization ID '{}' does not match instance '{}'",
+            organization_id, name
+        ));
+        return -1;
+    }
+
+    // Collect what the new connector needs, without holding the lock while building it
+    let instance = WEB_CLIENT_INSTANCES.lock().unwrap().get(&name).map(
+        |(_web_client, global_ctx, runtime, _ip_cache, state, options, _switch)| {
+            (
+                global_ctx.clone(),
+                runtime.handle().clone(),
+                state.clone(),
+                options.clone(),
+            )
+        },
+    );
+    let Some((global_ctx, runtime, state, options)) = instance else {
+        set_error(
+            CortexErrorCode::NotFound,
+            &format!("instance '{}' not found", name),
+        );
+        return -1;
+    };
+
+    if options.ca_roots.is_some() && !is_tls_scheme(base_url.scheme()) {
+        error!("CA bundle set for non-TLS config_server_url");
+        set_error(
+            CortexErrorCode::InvalidArgument,
+            &format!(
+                "CA bundle requires a TLS config_server_url, got '{}://'",
+                base_url.scheme()
+            ),
+        );
+        return -1;
+    }
+
+    let connector = match runtime.block_on(create_server_connector(
+        &base_url,
+        &global_ctx,
+        &options,
+        state,
+    )) {
+        Ok(connector) => connector,
+        Err(e) => {
+            error!("Failed to update web client: {}", e);
+            set_error_msg(&format!("failed to update web client: {}", e));
+            return -1;
+        }
+    };
+
+    // Only the connector changes, the running network instances are kept
+    let instances = WEB_CLIENT_INSTANCES.lock().unwrap();
+    match instances.get(&name) {
+        Some((_web_client, _global_ctx, _runtime, _ip_cache, _state, _options, switch)) => {
+            switch.switch_to(connector);
+            info!(
+                "Web client instance '{}' switched to config server {}",
+                name, base_url
+            );
+            0
+        }
+        None => {
+            set_error(
+                CortexErrorCode::NotFound,
+                &format!("instance '{}' not found", name),
+            );
+            -1
+        }
+    }
+}
+
+/// Live status of the local EasyTier node
+struct NodeStatus {
+    virtual_ipv4: String,
+    version: String,
+    peer_count: usize,
+    route_count: usize,
+}
+
+impl Default for NodeStatus {
+    fn default() -> Self {
+        Self {
+            virtual_ipv4: "0.0.0.0/0".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            peer_count: 0,
+            route_count: 0,
+        }
+    }
+}
+
+/// Helper function to query node status via RPC
+async fn query_node_status_via_rpc() -> NodeStatus {
+    let mut status = NodeStatus::default();
+
     let mut rpc_client = StandAloneClient::new(TcpTunnelConnector::new(
         "tcp://127.0.0.1:15888".parse().unwrap(),
     ));
 
-    match rpc_client
+    let peer_client = match rpc_client
         .scoped_client::<PeerManageRpcClientFactory<BaseController>>("".to_string())
         .await
     {
-        Ok(peer_client) => {
-            match peer_client
-                .show_node_info(BaseController::default(), ShowNodeInfoRequest::default())
-                .await
-            {
-                Ok(resp) => {
-                    if let Some(node_info) = resp.node_info {
-                        info!("Got node info via RPC: {}", node_info.ipv4_addr);
-                        node_info.ipv4_addr
-                    } else {
-                        warn!("No node_info in RPC response");
-                        "0.0.0.0/0".to_string()
-                    }
-                }
-                Err(e) => {
-                    warn!("RPC show_node_info failed: {}", e);
-                    "0.0.0.0/0".to_string()
+        Ok(peer_client) => peer_client,
+        Err(e) => {
+            warn!("Failed to create RPC client: {}", e);
+            return status;
+        }
+    };
+
+    match peer_client
+        .show_node_info(BaseController::default(), ShowNodeInfoRequest::default())
+        .await
+    {
+        Ok(resp) => {
+            if let Some(node_info) = resp.node_info {
+                info!(
+                    "Got node info via RPC: {} (version {})",
+                    node_info.ipv4_addr, node_info.version
+                );
+                status.virtual_ipv4 = node_info.ipv4_addr;
+                if !node_info.version.is_empty() {
+                    status.version = node_info.version;
                 }
+            } else {
+                warn!("No node_info in RPC response");
             }
         }
         Err(e) => {
-            warn!("Failed to create RPC client: {}", e);
-            "0.0.0.0/0".to_string()
+            warn!("RPC show_node_info failed: {}", e);
+            return status;
         }
     }
+
+    match peer_client
+        .list_peer(BaseController::default(), ListPeerRequest::default())
+        .await
+    {
+        Ok(resp) => status.peer_count = resp.peer_infos.len(),
+        Err(e) => warn!("RPC list_peer failed: {}", e),
+    }
+
+    match peer_client
+        .list_route(BaseController::default(), ListRouteRequest::default())
+        .await
+    {
+        Ok(resp) => status.route_count = resp.routes.len(),
+        Err(e) => warn!("RPC list_route failed: {}", e),
+    }
+
+    status
+}
+
+/// Helper function to query routes and directly connected peers via RPC
+async fn query_routes_and_peers_via_rpc() -> Result<(Vec<Route>, Vec<PeerInfo>), String> {
+    let mut rpc_client = StandAloneClient::new(TcpTunnelConnector::new(
+        "tcp://127.0.0.1:15888".parse().unwrap(),
+    ));
+
+    let peer_client = rpc_client
+        .scoped_client::<PeerManageRpcClientFactory<BaseController>>("".to_string())
+        .await
+        .map_err(|e| format!("failed to create RPC client: {}", e))?;
+
+    let routes = peer_client
+        .list_route(BaseController::default(), ListRouteRequest::default())
+        .await
+        .map_err(|e| format!("RPC list_route failed: {}", e))?
+        .routes;
+
+    let peers = peer_client
+        .list_peer(BaseController::default(), ListPeerRequest::default())
+        .await
+        .map_err(|e| format!("RPC list_peer failed: {}", e))?
+        .peer_infos;
+
+    Ok((routes, peers))
 }
 
 /// Get network info
 ///
+/// Network info may be empty while the client is still connecting, use
+/// `cortex_get_web_client_connection_state` to check the tunnel first.
+///
 /// # Safety
 ///
 /// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
@@ -294,7 +687,7 @@ pub unsafe extern "C" fn cortex_get_web_client_network_info(
 ) -> c_int {
     if instance_name.is_null() || info.is_null() {
         error!("Null pointer argument");
-        set_error_msg("null pointer argument");
+        set_error(CortexErrorCode::NullPointer, "null pointer argument");
         return -1;
     }
 
@@ -302,7 +695,10 @@ pub unsafe extern "C" fn cortex_get_web_client_network_info(
         Ok(name) => name,
         Err(e) => {
             error!("Invalid instance_name: {}", e);
-            set_error_msg(&format!("invalid instance_name: {}", e));
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
             return -1;
         }
     };
@@ -311,31 +707,301 @@ pub unsafe extern "C" fn cortex_get_web_client_network_info(
     let instance = match instances.get(&name) {
         Some(inst) => inst,
         None => {
-            set_error_msg(&format!("instance '{}' not found", name));
+            set_error(
+                CortexErrorCode::NotFound,
+                &format!("instance '{}' not found", name),
+            );
             return -1;
         }
     };
 
-    let (_web_client, _global_ctx, runtime, _ip_cache) = instance;
+    let (_web_client, _global_ctx, runtime, _ip_cache, _state, _options, _switch) = instance;
 
     // Query network info via RPC like easytier-cli does
-    let virtual_ipv4 = runtime.block_on(query_virtual_ip_via_rpc());
+    let status = runtime.block_on(query_node_status_via_rpc());
 
     // Create network info with actual values
     let network_info = Box::new(CortexNetworkInfo {
         instance_name: CString::new(name.clone()).unwrap().into_raw(),
         network_name: CString::new(name).unwrap().into_raw(),
-        virtual_ipv4: CString::new(virtual_ipv4).unwrap().into_raw(),
+        virtual_ipv4: CString::new(status.virtual_ipv4).unwrap().into_raw(),
         hostname: CString::new(gethostname::gethostname().to_string_lossy().to_string())
             .unwrap()
             .into_raw(),
-        version: CString::new(env!("CARGO_PKG_VERSION")).unwrap().into_raw(),
+        version: CString::new(status.version).unwrap_or_default().into_raw(),
+        peer_count: status.peer_count as c_int,
+        route_count: status.route_count as c_int,
     });
 
     *info = Box::into_raw(network_info);
     0
 }
 
+/// Get web client connection state
+///
+/// Writes one of the `CORTEX_CONNECTION_STATE_*` values to `out_state`.
+/// Network info may be empty while the state is `CONNECTING`.
+///
+/// # Safety
+///
+/// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
+/// and `out_state` is a valid mutable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_get_web_client_connection_state(
+    instance_name: *const c_char,
+    out_state: *mut c_int,
+) -> c_int {
+    if instance_name.is_null() || out_state.is_null() {
+        error!("Null pointer argument");
+        set_error(CortexErrorCode::NullPointer, "null pointer argument");
+        return -1;
+    }
+
+    let name = match c_str_to_string(instance_name) {
+        Ok(name) => name,
+        Err(e) => {
+            error!("Invalid instance_name: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
+            return -1;
+        }
+    };
+
+    let instances = WEB_CLIENT_INSTANCES.lock().unwrap();
+    let (_web_client, _global_ctx, _runtime, _ip_cache, state, _options, _switch) =
+        match instances.get(&name) {
+            Some(inst) => inst,
+            None => {
+                set_error(
+                    CortexErrorCode::NotFound,
+                    &format!("instance '{}' not found", name),
+                );
+                return -1;
+            }
+        };
+
+    *out_state = state.load(std::sync::atomic::Ordering::Relaxed);
+    0
+}
+
+/// Get peers of a web client instance
+///
+/// Allocates an array of `out_count` peers, release it with `cortex_free_peer_list`.
+/// Returns 0 with `out_count` set to 0 when there are no peers.
+///
+/// # Safety
+///
+/// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
+/// and `out_peers` and `out_count` are valid mutable pointers.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_get_web_client_peers(
+    instance_name: *const c_char,
+    out_peers: *mut *mut CortexPeerInfo,
+    out_count: *mut c_int,
+) -> c_int {
+    if instance_name.is_null() || out_peers.is_null() || out_count.is_null() {
+        error!("Null pointer argument");
+        set_error(CortexErrorCode::NullPointer, "null pointer argument");
+        return -1;
+    }
+
+    let name = match c_str_to_string(instance_name) {
+        Ok(name) => name,
+        Err(e) => {
+            error!("Invalid instance_name: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
+            return -1;
+        }
+    };
+
+    let instances = WEB_CLIENT_INSTANCES.lock().unwrap();
+    let (_web_client, _global_ctx, runtime, _ip_cache, _state, _options, _switch) =
+        match instances.get(&name) {
+            Some(inst) => inst,
+            None => {
+                set_error(
+                    CortexErrorCode::NotFound,
+                    &format!("instance '{}' not found", name),
+                );
+                return -1;
+            }
+        };
+
+    let (routes, peers) = match runtime.block_on(query_routes_and_peers_via_rpc()) {
+        Ok(ret) => ret,
+        Err(e) => {
+            error!("Failed to query peers: {}", e);
+            set_error_msg(&format!("failed to query peers: {}", e));
+            return -1;
+        }
+    };
+
+    let peer_list: Vec<CortexPeerInfo> = routes
+        .iter()
+        .map(|route| {
+            let is_connected = peers
+                .iter()
+                .any(|p| p.peer_id == route.peer_id && !p.conns.is_empty());
+            let virtual_ipv4 = route
+                .ipv4_addr
+                .as_ref()
+                .map(|ip| ip.to_string())
+                .unwrap_or_default();
+
+            CortexPeerInfo {
+                peer_id: route.peer_id,
+                virtual_ipv4: CString::new(virtual_ipv4).unwrap_or_default().into_raw(),
+                hostname: CString::new(route.hostname.clone())
+                    .unwrap_or_default()
+                    .into_raw(),
+                latency_ms: route.path_latency,
+                is_connected,
+            }
+        })
+        .collect();
+
+    *out_count = peer_list.len() as c_int;
+    if peer_list.is_empty() {
+        *out_peers = std::ptr::null_mut();
+        return 0;
+    }
+
+    let boxed = peer_list.into_boxed_slice();
+    *out_peers = Box::into_raw(boxed) as *mut CortexPeerInfo;
+    0
+}
+
+/// Free a peer list returned by `cortex_get_web_client_peers`
+///
+/// # Safety
+///
+/// The caller must ensure that `peers` and `count` come from a single call to
+/// `cortex_get_web_client_peers` and that the list is freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_free_peer_list(peers: *mut CortexPeerInfo, count: c_int) {
+    if peers.is_null() || count <= 0 {
+        return;
+    }
+
+    let peer_list = Box::from_raw(std::ptr::slice_from_raw_parts_mut(peers, count as usize));
+    for peer in peer_list.iter() {
+        if !peer.virtual_ipv4.is_null() {
+            drop(CString::from_raw(peer.virtual_ipv4 as *mut c_char));
+        }
+        if !peer.hostname.is_null() {
+            drop(CString::from_raw(peer.hostname as *mut c_char));
+        }
+    }
+}
+
+/// Get routes of a web client instance
+///
+/// Allocates an array of `out_count` routes, release it with `cortex_free_route_list`.
+/// Returns 0 with `out_count` set to 0 when the route table is empty.
+///
+/// # Safety
+///
+/// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string
+/// and `out_routes` and `out_count` are valid mutable pointers.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_get_web_client_routes(
+    instance_name: *const c_char,
+    out_routes: *mut *mut CortexRouteInfo,
+    out_count: *mut c_int,
+) -> c_int {
+    if instance_name.is_null() || out_routes.is_null() || out_count.is_null() {
+        error!("Null pointer argument");
+        set_error(CortexErrorCode::NullPointer, "null pointer argument");
+        return -1;
+    }
+
+    let name = match c_str_to_string(instance_name) {
+        Ok(name) => name,
+        Err(e) => {
+            error!("Invalid instance_name: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
+            return -1;
+        }
+    };
+
+    let instances = WEB_CLIENT_INSTANCES.lock().unwrap();
+    let (_web_client, _global_ctx, runtime, _ip_cache, _state, _options, _switch) =
+        match instances.get(&name) {
+            Some(inst) => inst,
+            None => {
+                set_error(
+                    CortexErrorCode::NotFound,
+                    &format!("instance '{}' not found", name),
+                );
+                return -1;
+            }
+        };
+
+    let routes = match runtime.block_on(query_routes_and_peers_via_rpc()) {
+        Ok((routes, _peers)) => routes,
+        Err(e) => {
+            error!("Failed to query routes: {}", e);
+            set_error_msg(&format!("failed to query routes: {}", e));
+            return -1;
+        }
+    };
+
+    let route_list: Vec<CortexRouteInfo> = routes
+        .iter()
+        .map(|route| {
+            let destination = route
+                .ipv4_addr
+                .as_ref()
+                .map(|ip| ip.to_string())
+                .unwrap_or_default();
+
+            CortexRouteInfo {
+                destination: CString::new(destination).unwrap_or_default().into_raw(),
+                next_hop: route.next_hop_peer_id,
+                metric: route.cost,
+            }
+        })
+        .collect();
+
+    *out_count = route_list.len() as c_int;
+    if route_list.is_empty() {
+        *out_routes = std::ptr::null_mut();
+        return 0;
+    }
+
+    let boxed = route_list.into_boxed_slice();
+    *out_routes = Box::into_raw(boxed) as *mut CortexRouteInfo;
+    0
+}
+
+/// Free a route list returned by `cortex_get_web_client_routes`
+///
+/// # Safety
+///
+/// The caller must ensure that `routes` and `count` come from a single call to
+/// `cortex_get_web_client_routes` and that the list is freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn cortex_free_route_list(routes: *mut CortexRouteInfo, count: c_int) {
+    if routes.is_null() || count <= 0 {
+        return;
+    }
+
+    let route_list = Box::from_raw(std::ptr::slice_from_raw_parts_mut(routes, count as usize));
+    for route in route_list.iter() {
+        if !route.destination.is_null() {
+            drop(CString::from_raw(route.destination as *mut c_char));
+        }
+    }
+}
+
 /// List web client instances
 ///
 /// # Safety
@@ -0,0 +1,77 @@
+//! Tests for the error codes reported by the web client FFI
+//!
+//! The last error code is process-wide, so every failure category is checked
+//! sequentially in a single test inside a dedicated test binary.
+
+use std::ffi::CString;
+use std::ptr;
+
+#[cfg(test)]
+mod error_code_tests {
+    use super::*;
+    use easytier_device_client::{
+        cortex_get_last_error_code, cortex_get_web_client_connection_state,
+        cortex_start_web_client, cortex_stop_web_client, CortexErrorCode, CortexWebClient,
+    };
+
+    fn web_client_config(url: &CString) -> CortexWebClient {
+        CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        }
+    }
+
+    fn assert_last_error(expected: CortexErrorCode) {
+        assert_eq!(
+            CortexErrorCode::from_c_int(cortex_get_last_error_code()),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_failure_categories_map_to_error_codes() {
+        unsafe {
+            // Null arguments
+            assert_eq!(cortex_start_web_client(ptr::null()), -1);
+            assert_last_error(CortexErrorCode::NullPointer);
+
+            let name = CString::new("error-code-org").unwrap();
+            assert_eq!(
+                cortex_get_web_client_connection_state(name.as_ptr(), ptr::null_mut()),
+                -1
+            );
+            assert_last_error(CortexErrorCode::NullPointer);
+
+            // Non UTF-8 instance name
+            let invalid_utf8 = [0xffu8, 0xfe, 0];
+            assert_eq!(
+                cortex_stop_web_client(invalid_utf8.as_ptr() as *const std::ffi::c_char),
+                -1
+            );
+            assert_last_error(CortexErrorCode::InvalidUtf8);
+
+            // Malformed URL and URL without organization ID
+            for url in ["not-a-valid-url", "tcp://127.0.0.1:1"] {
+                let url = CString::new(url).unwrap();
+                assert_eq!(cortex_start_web_client(&web_client_config(&url)), -1);
+                assert_last_error(CortexErrorCode::InvalidUrl);
+            }
+
+            // Invalid option value
+            let url = CString::new("tcp://127.0.0.1:1/error-code-org").unwrap();
+            let bad_machine_id = CString::new("not-a-uuid").unwrap();
+            let mut config = web_client_config(&url);
+            config.machine_id = bad_machine_id.as_ptr();
+            config.strict_machine_id = true;
+            assert_eq!(cortex_start_web_client(&config), -1);
+            assert_last_error(CortexErrorCode::InvalidArgument);
+
+            // Unknown instance
+            assert_eq!(cortex_stop_web_client(name.as_ptr()), -1);
+            assert_last_error(CortexErrorCode::NotFound);
+        }
+    }
+}
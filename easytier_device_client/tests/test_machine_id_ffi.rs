@@ -20,6 +20,9 @@ mod machine_id_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: config_url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: std::ptr::null(),
+            ca_cert_pem: std::ptr::null(),
         };
 
         // Struct should be created successfully
@@ -35,6 +38,9 @@ mod machine_id_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: config_url.as_ptr(),
             machine_id: std::ptr::null(), // No machine_id provided
+            strict_machine_id: false,
+            ca_cert_path: std::ptr::null(),
+            ca_cert_pem: std::ptr::null(),
         };
 
         assert!(!client_config.config_server_url.is_null());
@@ -0,0 +1,138 @@
+//! Pinned CA tests for the `wss` config server connector
+//!
+//! A local TLS websocket server presents a certificate signed by a test CA.
+//! The tunnel must be carried by the session verified against the pinned CA,
+//! and a server signed by another CA must never see a websocket upgrade.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use easytier::tunnel::packet_def::ZCPacket;
+use easytier::tunnel::{IpVersion, TunnelConnector};
+use easytier_device_client::{parse_ca_bundle, PinnedCaWssConnector};
+use futures::{SinkExt, StreamExt};
+use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+
+/// Test CA, returns its PEM and a certificate for `localhost` signed by it
+fn ca_and_server_cert() -> (String, CertificateDer<'static>, PrivateKeyDer<'static>) {
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_key = KeyPair::generate().unwrap();
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let server_key = KeyPair::generate().unwrap();
+    let server = CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .signed_by(&server_key, &ca, &ca_key)
+        .unwrap();
+    (
+        ca.pem(),
+        server.der().clone(),
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(server_key.serialize_der())),
+    )
+}
+
+/// Serve one TLS websocket connection, forwarding its binary messages
+///
+/// Returns the port, the received messages and the number of websocket upgrades.
+async fn serve_wss(
+    cert: CertificateDer<'static>,
+    key: PrivateKeyDer<'static>,
+) -> (u16, mpsc::UnboundedReceiver<Vec<u8>>, Arc<AtomicUsize>) {
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_no_client_auth()
+    .with_single_cert(vec![cert], key)
+    .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let upgrades = Arc::new(AtomicUsize::new(0));
+    let upgrades_clone = upgrades.clone();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let Ok(stream) = acceptor.accept(stream).await else {
+            return;
+        };
+        let Ok(mut websocket) = tokio_websockets::ServerBuilder::new().accept(stream).await else {
+            return;
+        };
+        upgrades_clone.fetch_add(1, Ordering::SeqCst);
+        while let Some(Ok(msg)) = websocket.next().await {
+            if msg.is_binary() {
+                let _ = tx.send(msg.into_payload().to_vec());
+            }
+        }
+    });
+
+    (port, rx, upgrades)
+}
+
+#[tokio::test]
+async fn test_tunnel_runs_over_pinned_ca_session() {
+    let (ca_pem, cert, key) = ca_and_server_cert();
+    let (port, mut received, upgrades) = serve_wss(cert, key).await;
+
+    let mut connector = PinnedCaWssConnector::new(
+        format!("wss://localhost:{}", port).parse().unwrap(),
+        parse_ca_bundle(ca_pem.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    connector.set_ip_version(IpVersion::V4);
+    let tunnel = connector
+        .connect()
+        .await
+        .expect("Server signed by the pinned CA should be accepted");
+    assert_eq!(tunnel.info().unwrap().tunnel_type, "wss");
+
+    let (_stream, mut sink) = tunnel.split();
+    sink.send(ZCPacket::new_with_payload(b"pinned-payload"))
+        .await
+        .unwrap();
+
+    let payload = received.recv().await.expect("Server should get the packet");
+    assert!(
+        payload
+            .windows(b"pinned-payload".len())
+            .any(|w| w == b"pinned-payload"),
+        "Unexpected payload: {:?}",
+        payload
+    );
+    assert_eq!(upgrades.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_server_signed_by_other_ca_is_rejected() {
+    let (_, cert, key) = ca_and_server_cert();
+    let (other_ca_pem, _, _) = ca_and_server_cert();
+    let (port, _received, upgrades) = serve_wss(cert, key).await;
+
+    let mut connector = PinnedCaWssConnector::new(
+        format!("wss://localhost:{}", port).parse().unwrap(),
+        parse_ca_bundle(other_ca_pem.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    connector.set_ip_version(IpVersion::V4);
+    assert!(
+        connector.connect().await.is_err(),
+        "Server signed by another CA should be rejected"
+    );
+    assert_eq!(upgrades.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_pinned_connector_requires_wss_url() {
+    let (ca_pem, _, _) = ca_and_server_cert();
+    let roots = parse_ca_bundle(ca_pem.as_bytes()).unwrap();
+    assert!(PinnedCaWssConnector::new("tcp://localhost:11020".parse().unwrap(), roots).is_err());
+}
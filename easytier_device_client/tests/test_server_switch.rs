@@ -0,0 +1,105 @@
+//! Tests for re-pointing a connector at another config server
+
+use easytier::tunnel::tcp::{TcpTunnelConnector, TcpTunnelListener};
+use easytier::tunnel::{TunnelConnector, TunnelListener};
+use futures::StreamExt;
+use std::sync::atomic::AtomicI32;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+mod server_switch_tests {
+    use super::*;
+    use easytier_device_client::{
+        RetryConnector, RetryPolicy, StateTrackingConnector, SwitchableConnector,
+        CORTEX_CONNECTION_STATE_CONNECTED, CORTEX_CONNECTION_STATE_CONNECTING,
+        CORTEX_CONNECTION_STATE_DISCONNECTED,
+    };
+    use std::sync::atomic::Ordering;
+
+    async fn listen() -> TcpTunnelListener {
+        let mut listener = TcpTunnelListener::new("tcp://127.0.0.1:0".parse().unwrap());
+        listener.listen().await.expect("Failed to listen");
+        listener
+    }
+
+    #[tokio::test]
+    async fn test_switch_closes_tunnel_and_reconnects_to_new_server() {
+        let mut server_a = listen().await;
+        let mut server_b = listen().await;
+
+        let mut connector =
+            SwitchableConnector::new(Box::new(TcpTunnelConnector::new(server_a.local_url())));
+        let switch = connector.switch();
+
+        let tunnel = connector.connect().await.expect("Failed to connect to A");
+        let _server_tunnel = server_a.accept().await.unwrap();
+        let (mut stream, _sink) = tunnel.split();
+
+        switch.switch_to(Box::new(TcpTunnelConnector::new(server_b.local_url())));
+        let next = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("Tunnel should close once the connector is switched");
+        assert!(next.is_none(), "Tunnel stream should end after the switch");
+
+        let _tunnel = connector.connect().await.expect("Failed to connect to B");
+        tokio::time::timeout(Duration::from_secs(5), server_b.accept())
+            .await
+            .expect("Reconnect should reach the new server")
+            .unwrap();
+        assert_eq!(connector.remote_url(), server_b.local_url());
+    }
+
+    #[tokio::test]
+    async fn test_switch_abandons_retries_of_unreachable_server() {
+        let mut server = listen().await;
+
+        let unreachable = RetryConnector::new(
+            TcpTunnelConnector::new("tcp://127.0.0.1:2".parse().unwrap()),
+            RetryPolicy::default(),
+            Arc::new(AtomicI32::new(CORTEX_CONNECTION_STATE_CONNECTING)),
+        );
+        let mut connector = SwitchableConnector::new(Box::new(unreachable));
+        let switch = connector.switch();
+
+        let connecting = tokio::spawn(async move { connector.connect().await.map(|_| ()) });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        switch.switch_to(Box::new(TcpTunnelConnector::new(server.local_url())));
+        tokio::time::timeout(Duration::from_secs(5), connecting)
+            .await
+            .expect("Connect should move to the new server")
+            .unwrap()
+            .expect("Connect to the new server should succeed");
+        server.accept().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_state_tracking_reports_dropped_tunnel() {
+        let mut server = listen().await;
+
+        let mut connector =
+            StateTrackingConnector::new(TcpTunnelConnector::new(server.local_url()));
+        let state = connector.state();
+
+        let tunnel = connector.connect().await.expect("Failed to connect");
+        let server_tunnel = server.accept().await.unwrap();
+        assert_eq!(
+            state.load(Ordering::Relaxed),
+            CORTEX_CONNECTION_STATE_CONNECTED
+        );
+
+        let (mut stream, _sink) = tunnel.split();
+        drop(server_tunnel);
+        while tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("Tunnel should close once the server drops it")
+            .is_some()
+        {}
+        assert_eq!(
+            state.load(Ordering::Relaxed),
+            CORTEX_CONNECTION_STATE_DISCONNECTED,
+            "A dropped tunnel should not be reported as connected"
+        );
+    }
+}
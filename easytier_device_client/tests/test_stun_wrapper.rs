@@ -0,0 +1,35 @@
+//! Tests for the configurable STUN info collector mock
+
+use easytier::common::stun::StunInfoCollectorTrait;
+use easytier::proto::common::NatType;
+use std::net::SocketAddr;
+
+#[cfg(test)]
+mod stun_wrapper_tests {
+    use super::*;
+    use easytier_device_client::MockStunInfoCollectorWrapper;
+
+    #[test]
+    fn test_default_nat_type_is_unknown() {
+        let collector = MockStunInfoCollectorWrapper::new();
+        assert_eq!(
+            collector.get_stun_info().udp_nat_type,
+            NatType::Unknown as i32
+        );
+    }
+
+    #[tokio::test]
+    async fn test_symmetric_nat_with_public_endpoint() {
+        let endpoint: SocketAddr = "203.0.113.10:40000".parse().unwrap();
+        let collector = MockStunInfoCollectorWrapper::new()
+            .with_nat_type(NatType::Symmetric)
+            .with_public_endpoint(endpoint);
+
+        let info = collector.get_stun_info();
+        assert_eq!(info.udp_nat_type, NatType::Symmetric as i32);
+        assert_eq!(info.public_ip, vec!["203.0.113.10".to_string()]);
+
+        let mapped = collector.get_udp_port_mapping(12345).await.unwrap();
+        assert_eq!(mapped, endpoint);
+    }
+}
@@ -13,9 +13,14 @@ use std::ptr;
 mod web_client_ffi_tests {
     use super::*;
     use easytier_device_client::{
-        cortex_get_web_client_network_info, cortex_list_web_client_instances,
-        cortex_start_web_client, cortex_stop_web_client, CortexNetworkInfo, CortexWebClient,
+        cortex_free_peer_list, cortex_free_route_list, cortex_get_web_client_connection_state,
+        cortex_get_web_client_network_info, cortex_get_web_client_peers,
+        cortex_get_web_client_routes, cortex_list_web_client_instances, cortex_start_web_client,
+        cortex_start_web_client_ex, cortex_stop_web_client, CortexNetworkInfo, CortexPeerInfo,
+        CortexRetryPolicy, CortexRouteInfo, CortexWebClient, RetryPolicy,
+        CORTEX_CONNECTION_STATE_CONNECTED, CORTEX_RETRY_FOREVER,
     };
+    use std::time::Duration;
 
     #[test]
     fn test_start_web_client_null_config() {
@@ -35,6 +40,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: invalid_url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -52,6 +60,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -72,6 +83,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -100,6 +114,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -125,6 +142,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -151,6 +171,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: invalid_machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -168,6 +191,367 @@ mod web_client_ffi_tests {
         }
     }
 
+    #[test]
+    fn test_start_web_client_strict_machine_id_rejects_invalid_uuid() {
+        // In strict mode an invalid machine_id must be rejected instead of replaced
+        let url = CString::new("tcp://localhost:11020/test-org-strict-uuid").unwrap();
+        let invalid_machine_id = CString::new("not-a-uuid").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: invalid_machine_id.as_ptr(),
+            strict_machine_id: true,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        };
+
+        unsafe {
+            let result = cortex_start_web_client(&client_config);
+            assert_eq!(result, -1, "Strict mode should reject invalid machine_id");
+
+            let error_msg = easytier_common::easytier_common_get_error_msg();
+            assert!(!error_msg.is_null());
+            let error_str = std::ffi::CStr::from_ptr(error_msg).to_string_lossy();
+            assert!(
+                error_str.contains("UUID"),
+                "Error should mention UUID, got: {}",
+                error_str
+            );
+        }
+    }
+
+    /// Start a client and return the error message of the failed call
+    unsafe fn start_expecting_error(client_config: &CortexWebClient) -> String {
+        let result = cortex_start_web_client(client_config);
+        assert_eq!(result, -1, "Start should fail");
+
+        let error_msg = easytier_common::easytier_common_get_error_msg();
+        assert!(!error_msg.is_null());
+        std::ffi::CStr::from_ptr(error_msg)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_start_web_client_unreadable_ca_path() {
+        let url = CString::new("wss://localhost:11020/test-org-bad-ca-path").unwrap();
+        let ca_path = CString::new("/nonexistent/cortex-ca.pem").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ca_path.as_ptr(),
+            ca_cert_pem: ptr::null(),
+        };
+
+        unsafe {
+            let error_str = start_expecting_error(&client_config);
+            assert!(
+                error_str.contains("CA bundle") && error_str.contains("/nonexistent/cortex-ca.pem"),
+                "Error should name the unreadable CA bundle, got: {}",
+                error_str
+            );
+        }
+    }
+
+    #[test]
+    fn test_start_web_client_invalid_ca_pem() {
+        let url = CString::new("wss://localhost:11020/test-org-bad-ca-pem").unwrap();
+        let ca_pem = CString::new("not a certificate").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ca_pem.as_ptr(),
+        };
+
+        unsafe {
+            let error_str = start_expecting_error(&client_config);
+            assert!(
+                error_str.contains("no PEM certificate"),
+                "Error should reject the bundle, got: {}",
+                error_str
+            );
+        }
+    }
+
+    #[test]
+    fn test_start_web_client_ca_requires_tls_url() {
+        let url = CString::new("tcp://localhost:11020/test-org-ca-plain-tcp").unwrap();
+        let ca_path = CString::new("/nonexistent/cortex-ca.pem").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ca_path.as_ptr(),
+            ca_cert_pem: ptr::null(),
+        };
+
+        unsafe {
+            let error_str = start_expecting_error(&client_config);
+            assert!(
+                error_str.contains("requires a TLS"),
+                "Error should reject the plain TCP URL, got: {}",
+                error_str
+            );
+        }
+    }
+
+    #[test]
+    fn test_connection_state_unreachable_server() {
+        // Nothing listens on port 1, so the client must never report connected
+        let url = CString::new("tcp://127.0.0.1:1/test-org-conn-state").unwrap();
+        let machine_id = CString::new("550e8400-e29b-41d4-a716-446655440000").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        };
+
+        unsafe {
+            let result = cortex_start_web_client(&client_config);
+            assert_eq!(
+                result, 0,
+                "Client should start even if server is unreachable"
+            );
+
+            std::thread::sleep(std::time::Duration::from_millis(500));
+
+            let instance_name = CString::new("test-org-conn-state").unwrap();
+            let mut state: std::ffi::c_int = -1;
+            let result = cortex_get_web_client_connection_state(instance_name.as_ptr(), &mut state);
+            assert_eq!(result, 0, "Should get connection state");
+            assert!(
+                (0..=3).contains(&state),
+                "Unexpected state value: {}",
+                state
+            );
+            assert_ne!(
+                state, CORTEX_CONNECTION_STATE_CONNECTED,
+                "Unreachable server should not be connected"
+            );
+
+            let _ = cortex_stop_web_client(instance_name.as_ptr());
+        }
+    }
+
+    #[test]
+    fn test_network_info_reports_version() {
+        // Works without a server: version falls back when the node RPC is unavailable
+        let url = CString::new("tcp://127.0.0.1:1/test-org-net-info-version").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        };
+
+        unsafe {
+            if cortex_start_web_client(&client_config) != 0 {
+                return;
+            }
+
+            let instance_name = CString::new("test-org-net-info-version").unwrap();
+            let mut info_ptr: *const CortexNetworkInfo = ptr::null();
+            let result = cortex_get_web_client_network_info(instance_name.as_ptr(), &mut info_ptr);
+            assert_eq!(result, 0, "Should get network info for running instance");
+            assert!(!info_ptr.is_null());
+
+            let info = &*info_ptr;
+            assert!(!info.version.is_null());
+            let version = std::ffi::CStr::from_ptr(info.version).to_string_lossy();
+            let major = version.split('.').next().unwrap_or_default();
+            assert!(
+                version.contains('.') && major.parse::<u32>().is_ok(),
+                "Version should look like semver, got: {}",
+                version
+            );
+            assert!(info.peer_count >= 0);
+            assert!(info.route_count >= 0);
+
+            let _ = cortex_stop_web_client(instance_name.as_ptr());
+        }
+    }
+
+    #[test]
+    fn test_start_ex_retries_unreachable_server_until_stopped() {
+        // Nothing listens on port 1, the client must keep retrying in the background
+        let url = CString::new("tcp://127.0.0.1:1/test-org-retry-forever").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        };
+        let retry_policy = CortexRetryPolicy {
+            max_attempts: CORTEX_RETRY_FOREVER,
+            base_interval_ms: 50,
+            max_interval_ms: 200,
+            backoff_multiplier: 2.0,
+            connect_timeout_ms: 1000,
+        };
+
+        unsafe {
+            let result = cortex_start_web_client_ex(&client_config, &retry_policy);
+            assert_eq!(result, 0, "Unreachable server should not fail start");
+
+            std::thread::sleep(Duration::from_millis(500));
+
+            let instance_name = CString::new("test-org-retry-forever").unwrap();
+            let mut state: std::ffi::c_int = -1;
+            let result = cortex_get_web_client_connection_state(instance_name.as_ptr(), &mut state);
+            assert_eq!(result, 0, "Instance should still be running while retrying");
+            assert_ne!(state, CORTEX_CONNECTION_STATE_CONNECTED);
+
+            assert_eq!(
+                cortex_stop_web_client(instance_name.as_ptr()),
+                0,
+                "Stop should cancel the retry loop"
+            );
+            assert_eq!(
+                cortex_get_web_client_connection_state(instance_name.as_ptr(), &mut state),
+                -1,
+                "Instance should be gone after stop"
+            );
+        }
+    }
+
+    #[test]
+    fn test_start_ex_rejects_invalid_retry_policy() {
+        let url = CString::new("tcp://127.0.0.1:1/test-org-bad-retry").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        };
+        let retry_policy = CortexRetryPolicy {
+            max_attempts: 3,
+            base_interval_ms: 1000,
+            max_interval_ms: 100,
+            backoff_multiplier: 0.5,
+            connect_timeout_ms: 0,
+        };
+
+        unsafe {
+            let result = cortex_start_web_client_ex(&client_config, &retry_policy);
+            assert_eq!(result, -1, "Invalid retry policy should fail start");
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_backoff_is_capped() {
+        let policy = RetryPolicy::try_from(&CortexRetryPolicy {
+            max_attempts: 5,
+            base_interval_ms: 100,
+            max_interval_ms: 1000,
+            backoff_multiplier: 2.0,
+            connect_timeout_ms: 0,
+        })
+        .unwrap();
+
+        assert_eq!(policy.max_attempts, Some(5));
+        assert_eq!(policy.connect_timeout, None);
+        assert_eq!(policy.delay_after(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_after(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_after(4), Duration::from_millis(800));
+        assert_eq!(policy.delay_after(5), Duration::from_millis(1000));
+        assert_eq!(policy.delay_after(u32::MAX), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_get_peers_unknown_instance() {
+        let instance_name = CString::new("non-existent-peers").unwrap();
+        let mut peers: *mut CortexPeerInfo = ptr::null_mut();
+        let mut count: std::ffi::c_int = 0;
+        unsafe {
+            let result =
+                cortex_get_web_client_peers(instance_name.as_ptr(), &mut peers, &mut count);
+            assert_eq!(result, -1, "Should fail for unknown instance");
+            assert!(peers.is_null());
+        }
+    }
+
+    #[test]
+    fn test_get_peers_null_pointers() {
+        let instance_name = CString::new("non-existent-peers").unwrap();
+        let mut peers: *mut CortexPeerInfo = ptr::null_mut();
+        let mut count: std::ffi::c_int = 0;
+        unsafe {
+            assert_eq!(
+                cortex_get_web_client_peers(ptr::null(), &mut peers, &mut count),
+                -1
+            );
+            assert_eq!(
+                cortex_get_web_client_peers(instance_name.as_ptr(), ptr::null_mut(), &mut count),
+                -1
+            );
+            assert_eq!(
+                cortex_get_web_client_peers(instance_name.as_ptr(), &mut peers, ptr::null_mut()),
+                -1
+            );
+
+            // Freeing an empty list is a no-op
+            cortex_free_peer_list(ptr::null_mut(), 0);
+        }
+    }
+
+    #[test]
+    fn test_get_routes_unknown_instance() {
+        let instance_name = CString::new("non-existent-routes").unwrap();
+        let mut routes: *mut CortexRouteInfo = ptr::null_mut();
+        let mut count: std::ffi::c_int = 0;
+        unsafe {
+            let result =
+                cortex_get_web_client_routes(instance_name.as_ptr(), &mut routes, &mut count);
+            assert_eq!(result, -1, "Should fail for unknown instance");
+            assert!(routes.is_null());
+        }
+    }
+
+    #[test]
+    fn test_get_routes_null_out_pointers() {
+        let instance_name = CString::new("non-existent-routes").unwrap();
+        let mut routes: *mut CortexRouteInfo = ptr::null_mut();
+        let mut count: std::ffi::c_int = 0;
+        unsafe {
+            assert_eq!(
+                cortex_get_web_client_routes(instance_name.as_ptr(), ptr::null_mut(), &mut count),
+                -1
+            );
+            assert_eq!(
+                cortex_get_web_client_routes(instance_name.as_ptr(), &mut routes, ptr::null_mut()),
+                -1
+            );
+
+            // Freeing an empty list is a no-op
+            cortex_free_route_list(ptr::null_mut(), 0);
+        }
+    }
+
+    #[test]
+    fn test_connection_state_unknown_instance() {
+        let instance_name = CString::new("non-existent-conn-state").unwrap();
+        let mut state: std::ffi::c_int = -1;
+        unsafe {
+            let result = cortex_get_web_client_connection_state(instance_name.as_ptr(), &mut state);
+            assert_eq!(result, -1, "Should fail for unknown instance");
+        }
+    }
+
     #[test]
     fn test_stop_web_client_null_instance_name() {
         // Test stopping with null instance name
@@ -272,6 +656,9 @@ mod web_client_ffi_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -310,6 +697,9 @@ mod web_client_ffi_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -346,6 +736,9 @@ mod web_client_ffi_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -382,6 +775,9 @@ mod web_client_ffi_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -412,6 +808,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: empty_url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -430,6 +829,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -456,6 +858,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -488,6 +893,9 @@ mod web_client_ffi_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -517,6 +925,9 @@ mod web_client_ffi_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -539,10 +950,12 @@ mod web_client_ffi_tests {
     #[test]
     fn test_struct_memory_layout() {
         // Test that CortexWebClient has expected memory layout
+        // (2 pointers, the strict_machine_id flag padded to pointer alignment, then the
+        // ca_cert_path and ca_cert_pem pointers)
         assert_eq!(
             std::mem::size_of::<CortexWebClient>(),
-            std::mem::size_of::<*const i8>() * 2,
-            "CortexWebClient should contain exactly 2 pointers"
+            std::mem::size_of::<*const i8>() * 5,
+            "CortexWebClient should contain 4 pointers and a padded bool flag"
         );
     }
 
@@ -579,6 +992,9 @@ mod web_client_ffi_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -613,9 +1029,29 @@ mod web_client_ffi_tests {
 mod web_client_lifecycle_tests {
     use super::*;
     use easytier_device_client::{
-        cortex_start_web_client, cortex_stop_web_client, CortexWebClient,
+        cortex_list_web_client_instances, cortex_start_web_client, cortex_stop_web_client,
+        cortex_update_web_client_server, CortexWebClient,
     };
 
+    /// Collect the names of all running web client instances
+    unsafe fn list_instance_names() -> Vec<String> {
+        let mut instances_ptr: *const *const std::ffi::c_char = ptr::null();
+        let count = cortex_list_web_client_instances(&mut instances_ptr, 64);
+        assert!(count >= 0, "Listing instances should succeed");
+        if count == 0 {
+            return Vec::new();
+        }
+
+        std::slice::from_raw_parts(instances_ptr, count as usize)
+            .iter()
+            .map(|name| {
+                std::ffi::CStr::from_ptr(*name)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
     #[test]
     fn test_start_stop_lifecycle() {
         // Test complete start-stop lifecycle
@@ -625,6 +1061,9 @@ mod web_client_lifecycle_tests {
         let client_config = CortexWebClient {
             config_server_url: url.as_ptr(),
             machine_id: machine_id.as_ptr(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
         };
 
         unsafe {
@@ -640,6 +1079,57 @@ mod web_client_lifecycle_tests {
         }
     }
 
+    #[test]
+    fn test_update_server_url_keeps_instance() {
+        let url = CString::new("tcp://127.0.0.1:1/org-update-url").unwrap();
+
+        let client_config = CortexWebClient {
+            config_server_url: url.as_ptr(),
+            machine_id: ptr::null(),
+            strict_machine_id: false,
+            ca_cert_path: ptr::null(),
+            ca_cert_pem: ptr::null(),
+        };
+
+        unsafe {
+            assert_eq!(cortex_start_web_client(&client_config), 0);
+            let instance_name = CString::new("org-update-url").unwrap();
+
+            let new_url = CString::new("tcp://127.0.0.1:2/org-update-url").unwrap();
+            assert_eq!(
+                cortex_update_web_client_server(instance_name.as_ptr(), new_url.as_ptr()),
+                0,
+                "Update to a valid URL should succeed"
+            );
+            assert!(
+                list_instance_names().contains(&"org-update-url".to_string()),
+                "Instance should keep its name after the update"
+            );
+
+            // Rejected updates leave the instance untouched
+            let no_org = CString::new("tcp://127.0.0.1:2").unwrap();
+            assert_eq!(
+                cortex_update_web_client_server(instance_name.as_ptr(), no_org.as_ptr()),
+                -1,
+                "URL without organization ID should be rejected"
+            );
+            let other_org = CString::new("tcp://127.0.0.1:2/other-org").unwrap();
+            assert_eq!(
+                cortex_update_web_client_server(instance_name.as_ptr(), other_org.as_ptr()),
+                -1,
+                "URL of another organization should be rejected"
+            );
+            let unknown = CString::new("org-update-url-unknown").unwrap();
+            assert_eq!(
+                cortex_update_web_client_server(unknown.as_ptr(), new_url.as_ptr()),
+                -1,
+                "Unknown instance should be rejected"
+            );
+
+            assert_eq!(cortex_stop_web_client(instance_name.as_ptr()), 0);
+        }
+    }
+
     #[test]
     fn test_multiple_sequential_starts() {
         // Test starting multiple instances sequentially
@@ -650,6 +1140,9 @@ mod web_client_lifecycle_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -713,6 +1206,9 @@ mod error_handling_tests {
             let client_config = CortexWebClient {
                 config_server_url: url_cstring.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -755,6 +1251,9 @@ mod error_handling_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -792,6 +1291,9 @@ mod error_handling_tests {
             let client_config = CortexWebClient {
                 config_server_url: url.as_ptr(),
                 machine_id: machine_id.as_ptr(),
+                strict_machine_id: false,
+                ca_cert_path: ptr::null(),
+                ca_cert_pem: ptr::null(),
             };
 
             unsafe {
@@ -3,6 +3,17 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Returned by the start functions when an instance with the requested
+ * name is already running
+ */
+#define EASYTIER_CORE_ALREADY_RUNNING 1
+
+/**
+ * Maximum length of an instance name in characters, after trimming
+ */
+#define MAX_INSTANCE_NAME_LEN 64
+
 /**
  * C-compatible structure for EasyTier Core configuration
  */
@@ -18,6 +29,8 @@ typedef struct EasyTierCoreConfig {
   const char *network_secret;
   const char *const *peer_urls;
   int peer_urls_count;
+  const char *const *proxy_networks;
+  int proxy_networks_count;
   const char *default_protocol;
   const char *dev_name;
   int enable_encryption;
@@ -32,11 +45,17 @@ typedef struct EasyTierCoreConfig {
   int relay_all_peer_rpc;
   int disable_udp_hole_punching;
   int private_mode;
+  const char *listener_urls_file;
+  const char *peer_urls_file;
+  int port_retry_count;
 } EasyTierCoreConfig;
 
 /**
  * Create and start an EasyTier core instance using Builder API
- * Returns 0 on success, -1 on error
+ * Returns 0 on success, 1 (`EASYTIER_CORE_ALREADY_RUNNING`) if an instance
+ * with the same name is already running (it is left untouched), -1 on error
+ *
+ * On failure `cortex_get_last_error_code` reports the error category.
  *
  * # Safety
  *
@@ -45,6 +64,21 @@ typedef struct EasyTierCoreConfig {
  */
 int start_easytier_core(const struct EasyTierCoreConfig *core_config);
 
+/**
+ * Create and start an EasyTier core instance from a JSON-serialized
+ * EasyTier `NetworkConfig`, bypassing the `EasyTierCoreConfig` struct.
+ * The instance is registered under `instance_name`, which overrides any
+ * instance name in the config.
+ * Returns 0 on success, 1 (`EASYTIER_CORE_ALREADY_RUNNING`) if an instance
+ * with the same name is already running (it is left untouched), -1 on error
+ *
+ * # Safety
+ *
+ * The caller must ensure that `instance_name` and `config_json` are valid pointers
+ * to null-terminated C strings.
+ */
+int start_easytier_core_from_config(const char *instance_name, const char *config_json);
+
 /**
  * Stop an EasyTier core instance
  * Returns 0 on success, -1 on error
@@ -55,6 +89,13 @@ int start_easytier_core(const struct EasyTierCoreConfig *core_config);
  */
 int stop_easytier_core(const char *instance_name);
 
+/**
+ * Stop every running EasyTier core instance
+ * Returns the number of instances stopped (0 if none were running),
+ * or -1 if the instance registry lock is poisoned
+ */
+int stop_all_easytier_cores(void);
+
 /**
  * Get gateway instance status (optional extension)
  *
@@ -1,18 +1,37 @@
 //! EasyTier core wrapper using Builder API (improved from original TOML string approach)
 
 use easytier::common::config::{ConfigLoader, NetworkIdentity, PeerConfig, TomlConfigLoader};
-use easytier::launcher::{ConfigSource, NetworkInstance};
-use easytier_common::{c_str_to_string, parse_string_array, set_error_msg};
+use easytier::common::global_ctx::{EventBusSubscriber, GlobalCtxEvent};
+use easytier::launcher::{ConfigSource, NetworkConfig, NetworkInstance};
+use easytier_common::{
+    c_str_to_string, parse_string_array, register_shutdown_hook, set_error, set_error_msg,
+    CortexErrorCode, ACTIVE_GATEWAY_INSTANCES,
+};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::ffi::{c_char, c_int};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::TryRecvError;
 use tracing::{error, info, warn};
 
+/// A running gateway instance and the listener URLs it was started with
+struct GatewayInstance {
+    instance: NetworkInstance,
+    listeners: Vec<url::Url>,
+}
+
 // Global storage for gateway instances
-static GATEWAY_INSTANCES: Lazy<Mutex<HashMap<String, NetworkInstance>>> =
+static GATEWAY_INSTANCES: Lazy<Mutex<HashMap<String, GatewayInstance>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// Returned by the start functions when an instance with the requested
+/// name is already running
+pub const EASYTIER_CORE_ALREADY_RUNNING: c_int = 1;
+
+/// Maximum length of an instance name in characters, after trimming
+pub const MAX_INSTANCE_NAME_LEN: usize = 64;
+
 /// C-compatible structure for EasyTier Core configuration
 #[repr(C)]
 #[derive(Debug)]
@@ -38,6 +57,10 @@ pub struct EasyTierCoreConfig {
     pub peer_urls: *const *const c_char,
     pub peer_urls_count: c_int,
 
+    // Subnet proxy configuration (CIDRs advertised to peers, e.g. "192.168.1.0/24")
+    pub proxy_networks: *const *const c_char,
+    pub proxy_networks_count: c_int,
+
     // Flags configuration
     pub default_protocol: *const c_char, // "tcp", "udp", etc.
     pub dev_name: *const c_char,
@@ -53,10 +76,251 @@ pub struct EasyTierCoreConfig {
     pub relay_all_peer_rpc: c_int,                // 0 = false, 1 = true
     pub disable_udp_hole_punching: c_int,         // 0 = false, 1 = true
     pub private_mode: c_int,                      // 0 = false, 1 = true
+
+    // Optional newline-delimited URL files, appended to the arrays above
+    pub listener_urls_file: *const c_char,
+    pub peer_urls_file: *const c_char,
+
+    // Next ports to try when a listener port is already in use (default 0)
+    pub port_retry_count: c_int,
+}
+
+/// Validate a foreign network whitelist and normalize it to the
+/// space-separated form EasyTier expects.
+///
+/// Entries are network-name globs separated by commas and/or whitespace.
+/// An empty token between two commas (or a leading/trailing comma) is
+/// rejected, as are entries containing control characters.
+fn normalize_network_whitelist(raw: &str) -> Result<String, String> {
+    let mut entries = Vec::new();
+
+    for segment in raw.split(',') {
+        let tokens: Vec<&str> = segment.split_whitespace().collect();
+        if tokens.is_empty() {
+            // A fully blank whitelist is allowed (relay no foreign networks)
+            if raw.contains(',') {
+                return Err(format!("empty entry in whitelist '{}'", raw));
+            }
+            continue;
+        }
+        for token in tokens {
+            if token.chars().any(char::is_control) {
+                return Err(format!(
+                    "entry '{}' contains control characters",
+                    token.escape_default()
+                ));
+            }
+            entries.push(token);
+        }
+    }
+
+    Ok(entries.join(" "))
+}
+
+/// Trim an instance name and validate it, so start and stop calls using the
+/// same raw string always resolve to the same registry key.
+fn normalize_instance_name(raw: &str) -> Result<String, String> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err("instance name is empty".to_string());
+    }
+    if name.chars().count() > MAX_INSTANCE_NAME_LEN {
+        return Err(format!(
+            "instance name is longer than {} characters",
+            MAX_INSTANCE_NAME_LEN
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(format!(
+            "instance name '{}' contains control characters",
+            name.escape_default()
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Read and normalize an instance name passed over FFI, reporting failures
+/// through `set_error`.
+///
+/// # Safety
+///
+/// `instance_name` must be null or point to a null-terminated string.
+unsafe fn parse_instance_name(instance_name: *const c_char) -> Option<String> {
+    let raw = match c_str_to_string(instance_name) {
+        Ok(raw) => raw,
+        Err(e) => {
+            error!("Invalid instance_name: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid instance_name: {}", e),
+            );
+            return None;
+        }
+    };
+    match normalize_instance_name(&raw) {
+        Ok(name) => Some(name),
+        Err(e) => {
+            error!("Invalid instance_name: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("invalid instance_name: {}", e),
+            );
+            None
+        }
+    }
+}
+
+/// Parse a newline-delimited URL list, skipping blank lines and `#` comments.
+fn parse_url_list(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read the URL list file at `path`, `None` when `path` is null.
+///
+/// # Safety
+///
+/// `path` must be null or point to a null-terminated string.
+unsafe fn read_url_file(path: *const c_char) -> Option<Result<Vec<String>, String>> {
+    if path.is_null() {
+        return None;
+    }
+    let path = match c_str_to_string(path) {
+        Ok(path) => path,
+        Err(e) => return Some(Err(e.to_string())),
+    };
+    Some(
+        std::fs::read_to_string(&path)
+            .map(|contents| parse_url_list(&contents))
+            .map_err(|e| format!("'{}': {}", path, e)),
+    )
+}
+
+/// How long a started instance gets to report whether its listeners are bound
+const LISTENER_STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether a `ListenerAddFailed` message reports an address already in use
+fn is_addr_in_use(msg: &str) -> bool {
+    msg.contains("AddrInUse") || msg.contains("Address already in use")
+}
+
+/// Whether two listener URLs denote the same listener, whatever their host spelling
+fn same_listener(a: &url::Url, b: &url::Url) -> bool {
+    a.scheme() == b.scheme() && a.port() == b.port()
+}
+
+/// Wait until each of `listeners` was bound or failed, returns those whose
+/// address is in use. Other failures are left to EasyTier, which keeps
+/// retrying them in the background.
+fn listeners_in_use(events: &mut EventBusSubscriber, listeners: &[url::Url]) -> Vec<url::Url> {
+    let deadline = Instant::now() + LISTENER_STARTUP_TIMEOUT;
+    let mut settled: Vec<url::Url> = Vec::new();
+    let mut in_use = Vec::new();
+    while settled.len() < listeners.len() && Instant::now() < deadline {
+        let (url, failed_in_use) = match events.try_recv() {
+            Ok(GlobalCtxEvent::ListenerAdded(url)) => (url, false),
+            Ok(GlobalCtxEvent::ListenerAddFailed(url, msg)) => {
+                let failed_in_use = is_addr_in_use(&msg);
+                (url, failed_in_use)
+            }
+            Ok(_) | Err(TryRecvError::Lagged(_)) => continue,
+            Err(TryRecvError::Empty) => {
+                std::thread::sleep(Duration::from_millis(20));
+                continue;
+            }
+            Err(TryRecvError::Closed) => break,
+        };
+        let Some(listener) = listeners.iter().find(|l| same_listener(l, &url)) else {
+            continue;
+        };
+        if settled.iter().any(|l| same_listener(l, listener)) {
+            continue;
+        }
+        settled.push(listener.clone());
+        if failed_in_use {
+            in_use.push(listener.clone());
+        }
+    }
+    in_use
+}
+
+/// Start an instance, moving listeners whose port is in use to the next port
+/// up to `port_retries` times. Returns the instance and its bound listeners.
+///
+/// EasyTier binds the listeners itself, so an address-in-use failure is only
+/// known from its events once the instance runs; the instance is then stopped
+/// and started again with the taken ports incremented. With 0 retries the
+/// instance is returned right away and EasyTier keeps retrying taken ports.
+fn start_instance_with_port_retry(
+    cfg: &TomlConfigLoader,
+    port_retries: u16,
+) -> Result<(NetworkInstance, Vec<url::Url>), String> {
+    let mut listeners = cfg.get_listeners().unwrap_or_default();
+    let mut attempt = 0;
+    loop {
+        cfg.set_listeners(listeners.clone());
+        let attempt_cfg = TomlConfigLoader::new_from_str(&cfg.dump())
+            .map_err(|e| format!("invalid config: {}", e))?;
+        let mut instance = NetworkInstance::new(attempt_cfg, ConfigSource::FFI);
+        let mut events = instance.start().map_err(|e| e.to_string())?;
+        if port_retries == 0 {
+            return Ok((instance, listeners));
+        }
+
+        let in_use = listeners_in_use(&mut events, &listeners);
+        if in_use.is_empty() {
+            return Ok((instance, listeners));
+        }
+        // Stops the instance and releases the listeners it did bind
+        drop(instance);
+        if attempt == port_retries {
+            return Err(format!(
+                "address in use for listener {} after {} retries",
+                in_use[0], port_retries
+            ));
+        }
+        attempt += 1;
+
+        for listener in listeners
+            .iter_mut()
+            .filter(|l| in_use.iter().any(|u| same_listener(u, l)))
+        {
+            let next_port = listener
+                .port()
+                .and_then(|port| port.checked_add(1))
+                .ok_or_else(|| format!("no port left to retry listener {}", listener))?;
+            let previous = listener.clone();
+            // Scheme and host are valid already, so setting the port cannot fail
+            let _ = listener.set_port(Some(next_port));
+            warn!("Listener {} is in use, retrying on {}", previous, listener);
+        }
+    }
+}
+
+/// Find a listener that would bind the RPC portal's port.
+///
+/// The portal listens on TCP on all addresses, so any TCP-based listener on
+/// `rpc_port` collides whatever its host; UDP-based listeners may share the
+/// number. Port 0 (pick a free port) never collides.
+fn find_rpc_port_collision(rpc_port: c_int, listeners: &[url::Url]) -> Option<&url::Url> {
+    if rpc_port <= 0 {
+        return None;
+    }
+    listeners.iter().find(|url| {
+        matches!(url.scheme(), "tcp" | "ws" | "wss")
+            && url.port_or_known_default().map(c_int::from) == Some(rpc_port)
+    })
 }
 
 /// Create and start an EasyTier core instance using Builder API
-/// Returns 0 on success, -1 on error
+/// Returns 0 on success, 1 (`EASYTIER_CORE_ALREADY_RUNNING`) if an instance
+/// with the same name is already running (it is left untouched), -1 on error
+///
+/// On failure `cortex_get_last_error_code` reports the error category.
 ///
 /// # Safety
 ///
@@ -66,7 +330,7 @@ pub struct EasyTierCoreConfig {
 pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreConfig) -> c_int {
     if core_config.is_null() {
         error!("start_easytier_core: core_config is null");
-        set_error_msg("core_config is null");
+        set_error(CortexErrorCode::NullPointer, "core_config is null");
         return -1;
     }
 
@@ -74,17 +338,10 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
     info!("start_easytier_core: Starting gateway with builder API");
 
     // Parse required parameters
-    let instance_name = match c_str_to_string(config.instance_name) {
-        Ok(name) => {
-            info!("Instance name: '{}'", name);
-            name
-        }
-        Err(e) => {
-            error!("Invalid instance_name: {}", e);
-            set_error_msg(&format!("invalid instance_name: {}", e));
-            return -1;
-        }
+    let Some(instance_name) = parse_instance_name(config.instance_name) else {
+        return -1;
     };
+    info!("Instance name: '{}'", instance_name);
 
     let network_name = match c_str_to_string(config.network_name) {
         Ok(name) => {
@@ -93,7 +350,10 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
         }
         Err(e) => {
             error!("Invalid network_name: {}", e);
-            set_error_msg(&format!("invalid network_name: {}", e));
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid network_name: {}", e),
+            );
             return -1;
         }
     };
@@ -105,7 +365,10 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
         }
         Err(e) => {
             error!("Invalid network_secret: {}", e);
-            set_error_msg(&format!("invalid network_secret: {}", e));
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid network_secret: {}", e),
+            );
             return -1;
         }
     };
@@ -118,36 +381,93 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
         c_str_to_string(config.default_protocol).unwrap_or_else(|_| "tcp".to_string());
     let foreign_network_whitelist =
         c_str_to_string(config.foreign_network_whitelist).unwrap_or_else(|_| "*".to_string());
+    let foreign_network_whitelist = match normalize_network_whitelist(&foreign_network_whitelist) {
+        Ok(whitelist) => whitelist,
+        Err(e) => {
+            error!("Invalid foreign_network_whitelist: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("invalid foreign_network_whitelist: {}", e),
+            );
+            return -1;
+        }
+    };
 
     // Parse arrays
-    let listener_urls = match parse_string_array(config.listener_urls, config.listener_urls_count) {
-        Ok(urls) => {
-            if urls.is_empty() {
-                error!("No listener URLs provided");
-                set_error_msg("no listener URLs provided");
+    let mut listener_urls =
+        match parse_string_array(config.listener_urls, config.listener_urls_count) {
+            Ok(urls) => urls,
+            Err(e) => {
+                error!("Failed to parse listener URLs: {}", e);
+                set_error(
+                    CortexErrorCode::from_c_str_error(e),
+                    &format!("failed to parse listener URLs: {}", e),
+                );
                 return -1;
             }
-            info!("Parsed {} listener URLs", urls.len());
-            urls
-        }
-        Err(e) => {
-            error!("Failed to parse listener URLs: {}", e);
-            set_error_msg(&format!("failed to parse listener URLs: {}", e));
+        };
+    match read_url_file(config.listener_urls_file) {
+        Some(Ok(urls)) => listener_urls.extend(urls),
+        Some(Err(e)) => {
+            error!("Failed to read listener URL file: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("failed to read listener URL file {}", e),
+            );
             return -1;
         }
-    };
+        None => {}
+    }
+    if listener_urls.is_empty() {
+        error!("No listener URLs provided");
+        set_error(
+            CortexErrorCode::InvalidArgument,
+            "no listener URLs provided",
+        );
+        return -1;
+    }
+    info!("Parsed {} listener URLs", listener_urls.len());
 
-    let peer_urls = match parse_string_array(config.peer_urls, config.peer_urls_count) {
-        Ok(urls) => {
-            info!("Parsed {} peer URLs", urls.len());
-            urls
-        }
+    let mut peer_urls = match parse_string_array(config.peer_urls, config.peer_urls_count) {
+        Ok(urls) => urls,
         Err(e) => {
             error!("Failed to parse peer URLs: {}", e);
-            set_error_msg(&format!("failed to parse peer URLs: {}", e));
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("failed to parse peer URLs: {}", e),
+            );
             return -1;
         }
     };
+    match read_url_file(config.peer_urls_file) {
+        Some(Ok(urls)) => peer_urls.extend(urls),
+        Some(Err(e)) => {
+            error!("Failed to read peer URL file: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("failed to read peer URL file {}", e),
+            );
+            return -1;
+        }
+        None => {}
+    }
+    info!("Parsed {} peer URLs", peer_urls.len());
+
+    let proxy_networks =
+        match parse_string_array(config.proxy_networks, config.proxy_networks_count) {
+            Ok(networks) => {
+                info!("Parsed {} proxy networks", networks.len());
+                networks
+            }
+            Err(e) => {
+                error!("Failed to parse proxy networks: {}", e);
+                set_error(
+                    CortexErrorCode::from_c_str_error(e),
+                    &format!("failed to parse proxy networks: {}", e),
+                );
+                return -1;
+            }
+        };
 
     // Determine operation mode
     let private_mode = config.private_mode != 0;
@@ -185,7 +505,10 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
             }
             Err(e) => {
                 error!("Invalid IPv4 address '{}': {}", ipv4_str, e);
-                set_error_msg(&format!("invalid IPv4 address: {}", e));
+                set_error(
+                    CortexErrorCode::InvalidArgument,
+                    &format!("invalid IPv4 address: {}", e),
+                );
                 return -1;
             }
         }
@@ -200,7 +523,10 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
             }
             Err(e) => {
                 error!("Invalid IPv6 address '{}': {}", ipv6_str, e);
-                set_error_msg(&format!("invalid IPv6 address: {}", e));
+                set_error(
+                    CortexErrorCode::InvalidArgument,
+                    &format!("invalid IPv6 address: {}", e),
+                );
                 return -1;
             }
         }
@@ -210,12 +536,26 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
     let listeners: Result<Vec<url::Url>, _> = listener_urls.iter().map(|s| s.parse()).collect();
     match listeners {
         Ok(urls) => {
+            if let Some(url) = find_rpc_port_collision(config.rpc_port, &urls) {
+                error!(
+                    "Listener {} collides with RPC port {}",
+                    url, config.rpc_port
+                );
+                set_error(
+                    CortexErrorCode::InvalidArgument,
+                    &format!("listener {} uses the RPC port {}", url, config.rpc_port),
+                );
+                return -1;
+            }
             cfg.set_listeners(urls);
             info!("Set {} listeners", listener_urls.len());
         }
         Err(e) => {
             error!("Invalid listener URL: {}", e);
-            set_error_msg(&format!("invalid listener URL: {}", e));
+            set_error(
+                CortexErrorCode::InvalidUrl,
+                &format!("invalid listener URL: {}", e),
+            );
             return -1;
         }
     }
@@ -234,12 +574,39 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
             }
             Err(e) => {
                 error!("Invalid peer URL: {}", e);
-                set_error_msg(&format!("invalid peer URL: {}", e));
+                set_error(
+                    CortexErrorCode::InvalidUrl,
+                    &format!("invalid peer URL: {}", e),
+                );
                 return -1;
             }
         }
     }
 
+    // Set subnet proxy networks
+    for network in &proxy_networks {
+        let cidr = match network.parse() {
+            Ok(cidr) => cidr,
+            Err(e) => {
+                error!("Invalid proxy network CIDR '{}': {}", network, e);
+                set_error(
+                    CortexErrorCode::InvalidArgument,
+                    &format!("invalid proxy network CIDR '{}': {}", network, e),
+                );
+                return -1;
+            }
+        };
+        if let Err(e) = cfg.add_proxy_cidr(cidr, None) {
+            error!("Failed to add proxy network '{}': {}", network, e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("failed to add proxy network '{}': {}", network, e),
+            );
+            return -1;
+        }
+        info!("Added proxy network: {}", network);
+    }
+
     // Set RPC portal
     match format!("0.0.0.0:{}", config.rpc_port).parse() {
         Ok(addr) => {
@@ -248,7 +615,10 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
         }
         Err(e) => {
             error!("Invalid RPC port {}: {}", config.rpc_port, e);
-            set_error_msg(&format!("invalid RPC port: {}", e));
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("invalid RPC port: {}", e),
+            );
             return -1;
         }
     }
@@ -286,25 +656,58 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
         if config.mtu <= 0 { 1380 } else { config.mtu }
     );
 
-    // Create and start the NetworkInstance
-    let mut instance = NetworkInstance::new(cfg, ConfigSource::FFI);
+    // 0 keeps the previous behaviour of letting EasyTier retry a taken port
+    let port_retries = config.port_retry_count.clamp(0, u16::MAX as c_int) as u16;
+    start_and_register_instance(instance_name, cfg, port_retries)
+}
 
-    match instance.start() {
-        Ok(_event_subscriber) => {
+/// Start a `NetworkInstance` from a fully built config and register it
+/// in `GATEWAY_INSTANCES` under `instance_name`.
+///
+/// The lock is held across the start so two concurrent calls with the same
+/// name cannot both launch an instance. An existing instance is left running
+/// and `EASYTIER_CORE_ALREADY_RUNNING` is returned.
+fn start_and_register_instance(
+    instance_name: String,
+    cfg: TomlConfigLoader,
+    port_retries: u16,
+) -> c_int {
+    let mut instances = match GATEWAY_INSTANCES.lock() {
+        Ok(instances) => instances,
+        Err(_) => {
+            error!("Failed to acquire GATEWAY_INSTANCES lock");
+            set_error_msg("failed to acquire lock");
+            return -1;
+        }
+    };
+
+    if instances.contains_key(&instance_name) {
+        warn!("Gateway instance '{}' is already running", instance_name);
+        set_error(
+            CortexErrorCode::AlreadyExists,
+            &format!("instance '{}' is already running", instance_name),
+        );
+        return EASYTIER_CORE_ALREADY_RUNNING;
+    }
+
+    match start_instance_with_port_retry(&cfg, port_retries) {
+        Ok((instance, listeners)) => {
             info!("Network instance started successfully");
 
             // Store the running instance
-            if let Ok(mut instances) = GATEWAY_INSTANCES.lock() {
-                instances.insert(instance_name.clone(), instance);
-                info!(
-                    "Gateway instance '{}' registered successfully",
-                    instance_name
-                );
-            } else {
-                error!("Failed to acquire GATEWAY_INSTANCES lock");
-                set_error_msg("failed to acquire lock");
-                return -1;
-            }
+            instances.insert(
+                instance_name.clone(),
+                GatewayInstance {
+                    instance,
+                    listeners,
+                },
+            );
+            ACTIVE_GATEWAY_INSTANCES.set(instances.len() as i64);
+            register_shutdown_hook("network_gateway", stop_all_for_shutdown);
+            info!(
+                "Gateway instance '{}' registered successfully",
+                instance_name
+            );
 
             0
         }
@@ -316,33 +719,95 @@ pub unsafe extern "C" fn start_easytier_core(core_config: *const EasyTierCoreCon
     }
 }
 
-/// Stop an EasyTier core instance
-/// Returns 0 on success, -1 on error
+/// Create and start an EasyTier core instance from a JSON-serialized
+/// EasyTier `NetworkConfig`, bypassing the `EasyTierCoreConfig` struct.
+/// The instance is registered under `instance_name`, which overrides any
+/// instance name in the config.
+/// Returns 0 on success, 1 (`EASYTIER_CORE_ALREADY_RUNNING`) if an instance
+/// with the same name is already running (it is left untouched), -1 on error
 ///
 /// # Safety
 ///
-/// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string.
+/// The caller must ensure that `instance_name` and `config_json` are valid pointers
+/// to null-terminated C strings.
 #[no_mangle]
-pub unsafe extern "C" fn stop_easytier_core(instance_name: *const c_char) -> c_int {
-    let name = match c_str_to_string(instance_name) {
-        Ok(name) => {
-            info!("Stopping gateway instance: {}", name);
-            name
+pub unsafe extern "C" fn start_easytier_core_from_config(
+    instance_name: *const c_char,
+    config_json: *const c_char,
+) -> c_int {
+    let Some(instance_name) = parse_instance_name(instance_name) else {
+        return -1;
+    };
+
+    let config_json = match c_str_to_string(config_json) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Invalid config_json: {}", e);
+            set_error(
+                CortexErrorCode::from_c_str_error(e),
+                &format!("invalid config_json: {}", e),
+            );
+            return -1;
         }
+    };
+
+    let network_config: NetworkConfig = match serde_json::from_str(&config_json) {
+        Ok(config) => config,
         Err(e) => {
-            error!("Invalid instance_name: {}", e);
-            set_error_msg(&format!("invalid instance_name: {}", e));
+            error!("Failed to parse config_json: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("failed to parse config_json: {}", e),
+            );
             return -1;
         }
     };
 
+    let cfg = match network_config.gen_config() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            error!("Invalid network config: {}", e);
+            set_error(
+                CortexErrorCode::InvalidArgument,
+                &format!("invalid network config: {}", e),
+            );
+            return -1;
+        }
+    };
+    cfg.set_inst_name(instance_name.clone());
+
+    info!(
+        "start_easytier_core_from_config: Starting gateway '{}' from config",
+        instance_name
+    );
+
+    start_and_register_instance(instance_name, cfg, 0)
+}
+
+/// Stop an EasyTier core instance
+/// Returns 0 on success, -1 on error
+///
+/// # Safety
+///
+/// The caller must ensure that `instance_name` is a valid pointer to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn stop_easytier_core(instance_name: *const c_char) -> c_int {
+    let Some(name) = parse_instance_name(instance_name) else {
+        return -1;
+    };
+    info!("Stopping gateway instance: {}", name);
+
     if let Ok(mut instances) = GATEWAY_INSTANCES.lock() {
         if instances.remove(&name).is_some() {
+            ACTIVE_GATEWAY_INSTANCES.set(instances.len() as i64);
             info!("Gateway instance '{}' stopped successfully", name);
             0
         } else {
             warn!("Gateway instance '{}' not found", name);
-            set_error_msg(&format!("instance '{}' not found", name));
+            set_error(
+                CortexErrorCode::NotFound,
+                &format!("instance '{}' not found", name),
+            );
             -1
         }
     } else {
@@ -352,6 +817,42 @@ pub unsafe extern "C" fn stop_easytier_core(instance_name: *const c_char) -> c_i
     }
 }
 
+/// Stop every running EasyTier core instance
+/// Returns the number of instances stopped (0 if none were running),
+/// or -1 if the instance registry lock is poisoned
+#[no_mangle]
+pub extern "C" fn stop_all_easytier_cores() -> c_int {
+    let drained: Vec<(String, GatewayInstance)> = match GATEWAY_INSTANCES.lock() {
+        Ok(mut instances) => {
+            let drained = instances.drain().collect();
+            ACTIVE_GATEWAY_INSTANCES.set(0);
+            drained
+        }
+        Err(_) => {
+            error!("Failed to acquire GATEWAY_INSTANCES lock");
+            set_error_msg("failed to acquire lock");
+            return -1;
+        }
+    };
+
+    let count = drained.len();
+    for (name, instance) in drained {
+        drop(instance);
+        info!("Gateway instance '{}' stopped successfully", name);
+    }
+
+    info!("Stopped {} gateway instance(s)", count);
+    count as c_int
+}
+
+/// Shutdown hook stopping every gateway instance at process exit
+fn stop_all_for_shutdown() -> Result<(), String> {
+    if stop_all_easytier_cores() < 0 {
+        return Err("failed to stop gateway instances".to_string());
+    }
+    Ok(())
+}
+
 /// Get gateway instance status (optional extension)
 ///
 /// # Safety
@@ -363,13 +864,8 @@ pub unsafe extern "C" fn get_easytier_core_status(
     instance_name: *const c_char,
     status_json_out: *mut *mut c_char,
 ) -> c_int {
-    let name = match c_str_to_string(instance_name) {
-        Ok(name) => name,
-        Err(e) => {
-            error!("Invalid instance_name: {}", e);
-            set_error_msg(&format!("invalid instance_name: {}", e));
-            return -1;
-        }
+    let Some(name) = parse_instance_name(instance_name) else {
+        return -1;
     };
 
     if status_json_out.is_null() {
@@ -379,12 +875,22 @@ pub unsafe extern "C" fn get_easytier_core_status(
     }
 
     let instances = GATEWAY_INSTANCES.lock().unwrap();
-    let exists = instances.contains_key(&name);
-
-    // Create simple status JSON
+    let listeners: Vec<String> = instances
+        .get(&name)
+        .map(|gateway| {
+            gateway
+                .listeners
+                .iter()
+                .map(|url| url.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Create simple status JSON, listeners carry the actually bound ports
     let status = serde_json::json!({
         "instance_name": name,
-        "running": exists,
+        "running": instances.contains_key(&name),
+        "listeners": listeners,
     });
 
     match serde_json::to_string(&status) {
@@ -417,4 +923,55 @@ mod tests {
         let size = std::mem::size_of::<EasyTierCoreConfig>();
         assert!(size > 0, "EasyTierCoreConfig should have non-zero size");
     }
+
+    #[test]
+    fn test_normalize_instance_name() {
+        assert_eq!(normalize_instance_name("  gw-1\t").unwrap(), "gw-1");
+        assert_eq!(
+            normalize_instance_name(&"n".repeat(MAX_INSTANCE_NAME_LEN)).unwrap(),
+            "n".repeat(MAX_INSTANCE_NAME_LEN)
+        );
+
+        assert!(normalize_instance_name("   ").is_err());
+        assert!(normalize_instance_name(&"n".repeat(MAX_INSTANCE_NAME_LEN + 1)).is_err());
+        assert!(normalize_instance_name("gw\u{7}1").is_err());
+    }
+
+    #[test]
+    fn test_read_url_file_skips_comments() {
+        let path = std::env::temp_dir().join(format!("gateway-peers-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "# relay peers\n\ntcp://10.0.0.1:11010\n  udp://10.0.0.2:11010  \n   # disabled\n",
+        )
+        .unwrap();
+        let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let urls = unsafe { read_url_file(c_path.as_ptr()) }.unwrap().unwrap();
+        assert_eq!(urls, vec!["tcp://10.0.0.1:11010", "udp://10.0.0.2:11010"]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(unsafe { read_url_file(c_path.as_ptr()) }.unwrap().is_err());
+        assert!(unsafe { read_url_file(std::ptr::null()) }.is_none());
+    }
+
+    #[test]
+    fn test_normalize_network_whitelist() {
+        assert_eq!(normalize_network_whitelist("*").unwrap(), "*");
+        assert_eq!(
+            normalize_network_whitelist("  net-a,net-b   lab-*\t").unwrap(),
+            "net-a net-b lab-*"
+        );
+        assert_eq!(
+            normalize_network_whitelist("net-a , net-b").unwrap(),
+            "net-a net-b"
+        );
+        assert_eq!(normalize_network_whitelist("   ").unwrap(), "");
+
+        assert!(normalize_network_whitelist("net-a,,net-b").is_err());
+        assert!(normalize_network_whitelist("net-a, ,net-b").is_err());
+        assert!(normalize_network_whitelist(",net-a").is_err());
+        assert!(normalize_network_whitelist("net-a,").is_err());
+        assert!(normalize_network_whitelist("net\u{7}a").is_err());
+    }
 }
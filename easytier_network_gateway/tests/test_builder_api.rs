@@ -35,6 +35,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -49,6 +51,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -95,6 +100,8 @@ mod builder_api_validation_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -109,6 +116,9 @@ mod builder_api_validation_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -155,6 +165,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -169,6 +181,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -214,6 +229,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -228,6 +245,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -276,6 +296,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: unsafe { (*peers_ptr).as_ptr() },
             peer_urls_count: 2,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -290,6 +312,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 0, // P2P mode
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -330,6 +355,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -344,6 +371,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 1,
             disable_udp_hole_punching: 1,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -393,6 +423,8 @@ mod builder_api_validation_tests {
                 rpc_port: *rpc_port,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -407,6 +439,9 @@ mod builder_api_validation_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -454,6 +489,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -468,6 +505,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -511,6 +551,8 @@ mod builder_api_validation_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -525,6 +567,9 @@ mod builder_api_validation_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -580,6 +625,8 @@ mod configuration_parsing_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -594,6 +641,9 @@ mod configuration_parsing_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -646,6 +696,8 @@ mod configuration_parsing_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -660,6 +712,9 @@ mod configuration_parsing_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -715,6 +770,8 @@ mod configuration_parsing_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -729,6 +786,9 @@ mod configuration_parsing_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -786,6 +846,8 @@ mod configuration_parsing_tests {
                 rpc_port: 15888,
                 peer_urls: unsafe { (*peers_ptr).as_ptr() },
                 peer_urls_count: 1,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -800,6 +862,9 @@ mod configuration_parsing_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 0, // P2P mode
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -850,6 +915,8 @@ mod configuration_parsing_tests {
             rpc_port: 15888,
             peer_urls: unsafe { (*peers_ptr).as_ptr() },
             peer_urls_count: 1,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -864,6 +931,9 @@ mod configuration_parsing_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 0,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -875,6 +945,129 @@ mod configuration_parsing_tests {
             let _ = Box::from_raw(peers_ptr);
         }
     }
+
+    #[test]
+    fn test_builder_api_proxy_network() {
+        // Test advertising a valid subnet proxy CIDR
+        let instance_name = CString::new("proxy-network-test").unwrap();
+        let network_name = CString::new("test-network").unwrap();
+        let network_secret = CString::new("test-secret").unwrap();
+        let listener = CString::new("tcp://0.0.0.0:13060").unwrap();
+        let proxy_network = CString::new("192.168.1.0/24").unwrap();
+
+        let listeners = vec![listener.as_ptr()];
+        let listeners_box = listeners.into_boxed_slice();
+        let listeners_ptr = Box::into_raw(listeners_box);
+
+        let proxy_networks = vec![proxy_network.as_ptr()];
+        let proxy_networks_box = proxy_networks.into_boxed_slice();
+        let proxy_networks_ptr = Box::into_raw(proxy_networks_box);
+
+        let config = EasyTierCoreConfig {
+            instance_name: instance_name.as_ptr(),
+            network_name: network_name.as_ptr(),
+            network_secret: network_secret.as_ptr(),
+            dhcp: 1,
+            ipv4: ptr::null(),
+            ipv6: ptr::null(),
+            listener_urls: unsafe { (*listeners_ptr).as_ptr() },
+            listener_urls_count: 1,
+            rpc_port: 15888,
+            peer_urls: ptr::null(),
+            peer_urls_count: 0,
+            proxy_networks: unsafe { (*proxy_networks_ptr).as_ptr() },
+            proxy_networks_count: 1,
+            default_protocol: ptr::null(),
+            dev_name: ptr::null(),
+            enable_encryption: 1,
+            enable_ipv6: 0,
+            mtu: 1380,
+            latency_first: 0,
+            enable_exit_node: 0,
+            no_tun: 1,
+            use_smoltcp: 0,
+            foreign_network_whitelist: ptr::null(),
+            disable_p2p: 0,
+            relay_all_peer_rpc: 0,
+            disable_udp_hole_punching: 0,
+            private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
+        };
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, 0, "Should start with a valid proxy network CIDR");
+
+            let stop_result = stop_easytier_core(instance_name.as_ptr());
+            assert_eq!(stop_result, 0, "Stop should succeed after start");
+
+            // Clean up
+            let _ = Box::from_raw(listeners_ptr);
+            let _ = Box::from_raw(proxy_networks_ptr);
+        }
+    }
+
+    #[test]
+    fn test_invalid_proxy_network() {
+        // Test with an invalid subnet proxy CIDR
+        let instance_name = CString::new("invalid-proxy-network").unwrap();
+        let network_name = CString::new("test-network").unwrap();
+        let network_secret = CString::new("test-secret").unwrap();
+        let listener = CString::new("tcp://0.0.0.0:13061").unwrap();
+        let proxy_network = CString::new("192.168.1.0/33").unwrap();
+
+        let listeners = vec![listener.as_ptr()];
+        let listeners_box = listeners.into_boxed_slice();
+        let listeners_ptr = Box::into_raw(listeners_box);
+
+        let proxy_networks = vec![proxy_network.as_ptr()];
+        let proxy_networks_box = proxy_networks.into_boxed_slice();
+        let proxy_networks_ptr = Box::into_raw(proxy_networks_box);
+
+        let config = EasyTierCoreConfig {
+            instance_name: instance_name.as_ptr(),
+            network_name: network_name.as_ptr(),
+            network_secret: network_secret.as_ptr(),
+            dhcp: 1,
+            ipv4: ptr::null(),
+            ipv6: ptr::null(),
+            listener_urls: unsafe { (*listeners_ptr).as_ptr() },
+            listener_urls_count: 1,
+            rpc_port: 15888,
+            peer_urls: ptr::null(),
+            peer_urls_count: 0,
+            proxy_networks: unsafe { (*proxy_networks_ptr).as_ptr() },
+            proxy_networks_count: 1,
+            default_protocol: ptr::null(),
+            dev_name: ptr::null(),
+            enable_encryption: 1,
+            enable_ipv6: 0,
+            mtu: 1380,
+            latency_first: 0,
+            enable_exit_node: 0,
+            no_tun: 1,
+            use_smoltcp: 0,
+            foreign_network_whitelist: ptr::null(),
+            disable_p2p: 0,
+            relay_all_peer_rpc: 0,
+            disable_udp_hole_punching: 0,
+            private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
+        };
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, -1, "Should fail with invalid proxy network CIDR");
+
+            // Clean up
+            let _ = Box::from_raw(listeners_ptr);
+            let _ = Box::from_raw(proxy_networks_ptr);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -946,6 +1139,8 @@ mod memory_safety_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -960,6 +1155,9 @@ mod memory_safety_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -0,0 +1,135 @@
+//! Tests for the error codes reported by the gateway FFI
+//!
+//! The last error code is process-wide, so every failure category is checked
+//! sequentially in a single test inside a dedicated test binary.
+
+use std::ffi::{c_int, CStr, CString};
+use std::ptr;
+
+#[cfg(test)]
+mod error_code_tests {
+    use super::*;
+    use easytier_common::{
+        cortex_get_last_error_code, easytier_common_get_error_msg, CortexErrorCode,
+    };
+    use easytier_network_gateway::{
+        start_easytier_core, start_easytier_core_from_config, stop_easytier_core,
+        EasyTierCoreConfig, EASYTIER_CORE_ALREADY_RUNNING,
+    };
+
+    /// Config with a single listener; all other options are defaults
+    fn base_config(
+        instance_name: &CString,
+        network: &CString,
+        listeners: &[*const std::ffi::c_char],
+    ) -> EasyTierCoreConfig {
+        EasyTierCoreConfig {
+            instance_name: instance_name.as_ptr(),
+            network_name: network.as_ptr(),
+            network_secret: network.as_ptr(),
+            dhcp: 1,
+            ipv4: ptr::null(),
+            ipv6: ptr::null(),
+            listener_urls: listeners.as_ptr(),
+            listener_urls_count: listeners.len() as c_int,
+            rpc_port: 0,
+            peer_urls: ptr::null(),
+            peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
+            default_protocol: ptr::null(),
+            dev_name: ptr::null(),
+            enable_encryption: 1,
+            enable_ipv6: 0,
+            mtu: 1380,
+            latency_first: 0,
+            enable_exit_node: 0,
+            no_tun: 1,
+            use_smoltcp: 0,
+            foreign_network_whitelist: ptr::null(),
+            disable_p2p: 0,
+            relay_all_peer_rpc: 0,
+            disable_udp_hole_punching: 0,
+            private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
+        }
+    }
+
+    fn assert_last_error(expected: CortexErrorCode) {
+        assert_eq!(
+            CortexErrorCode::from_c_int(cortex_get_last_error_code()),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_failure_categories_map_to_error_codes() {
+        let instance_name = CString::new("error-code-gateway").unwrap();
+        let network = CString::new("error-code-network").unwrap();
+        let listener = CString::new("tcp://0.0.0.0:11092").unwrap();
+        let listeners = [listener.as_ptr()];
+
+        unsafe {
+            // Null config
+            assert_eq!(start_easytier_core(ptr::null()), -1);
+            assert_last_error(CortexErrorCode::NullPointer);
+
+            // Null required string
+            let mut config = base_config(&instance_name, &network, &listeners);
+            config.network_name = ptr::null();
+            assert_eq!(start_easytier_core(&config), -1);
+            assert_last_error(CortexErrorCode::NullPointer);
+
+            // Non UTF-8 instance name
+            let invalid_utf8 = [0xffu8, 0xfe, 0];
+            let mut config = base_config(&instance_name, &network, &listeners);
+            config.instance_name = invalid_utf8.as_ptr() as *const std::ffi::c_char;
+            assert_eq!(start_easytier_core(&config), -1);
+            assert_last_error(CortexErrorCode::InvalidUtf8);
+
+            // Unparseable listener URL
+            let bad_listener = CString::new("not a url").unwrap();
+            let bad_listeners = [bad_listener.as_ptr()];
+            let config = base_config(&instance_name, &network, &bad_listeners);
+            assert_eq!(start_easytier_core(&config), -1);
+            assert_last_error(CortexErrorCode::InvalidUrl);
+
+            // Invalid option value
+            let bad_ipv4 = CString::new("999.999.999.999").unwrap();
+            let mut config = base_config(&instance_name, &network, &listeners);
+            config.ipv4 = bad_ipv4.as_ptr();
+            assert_eq!(start_easytier_core(&config), -1);
+            assert_last_error(CortexErrorCode::InvalidArgument);
+
+            let bad_json = CString::new("{not json").unwrap();
+            assert_eq!(
+                start_easytier_core_from_config(instance_name.as_ptr(), bad_json.as_ptr()),
+                -1
+            );
+            assert_last_error(CortexErrorCode::InvalidArgument);
+
+            // TCP listener on the RPC port, rejected before EasyTier starts
+            let mut config = base_config(&instance_name, &network, &listeners);
+            config.rpc_port = 11092;
+            assert_eq!(start_easytier_core(&config), -1);
+            assert_last_error(CortexErrorCode::InvalidArgument);
+            let msg = CStr::from_ptr(easytier_common_get_error_msg()).to_string_lossy();
+            assert!(msg.contains("RPC port 11092"), "unexpected error: {}", msg);
+
+            // Unknown instance
+            let unknown = CString::new("error-code-unknown").unwrap();
+            assert_eq!(stop_easytier_core(unknown.as_ptr()), -1);
+            assert_last_error(CortexErrorCode::NotFound);
+
+            // Duplicate instance
+            let config = base_config(&instance_name, &network, &listeners);
+            assert_eq!(start_easytier_core(&config), 0, "Start should succeed");
+            assert_eq!(start_easytier_core(&config), EASYTIER_CORE_ALREADY_RUNNING);
+            assert_last_error(CortexErrorCode::AlreadyExists);
+
+            assert_eq!(stop_easytier_core(instance_name.as_ptr()), 0);
+        }
+    }
+}
@@ -3,6 +3,7 @@
 //! This module tests the FFI interface for gateway operations including:
 //! - start_easytier_core (with Builder API)
 //! - stop_easytier_core
+//! - start_easytier_core_from_config
 //! - get_easytier_core_status
 //! - Configuration validation
 
@@ -49,6 +50,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -63,6 +66,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         (config, c_strings)
@@ -100,6 +106,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -114,6 +122,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -148,6 +159,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -162,6 +175,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -196,6 +212,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -210,6 +228,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -240,6 +261,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -254,6 +277,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -286,6 +312,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -300,6 +328,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -336,6 +367,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -350,6 +383,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -386,6 +422,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -400,6 +438,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -444,6 +485,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -458,6 +501,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -489,6 +535,204 @@ mod gateway_ffi_tests {
         }
     }
 
+    #[test]
+    fn test_start_gateway_valid_whitelist() {
+        // Comma- and space-separated globs are accepted
+        let (mut config, _c_strings) = create_test_config("test-valid-whitelist");
+        let whitelist = CString::new(" net-a, net-b  lab-* ").unwrap();
+        config.foreign_network_whitelist = whitelist.as_ptr();
+        config.no_tun = 1;
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, 0, "Should accept a multi-entry whitelist");
+
+            let instance_name = CString::new("test-valid-whitelist").unwrap();
+            let _ = stop_easytier_core(instance_name.as_ptr());
+        }
+    }
+
+    #[test]
+    fn test_start_gateway_malformed_whitelist() {
+        // Empty token between separators must be rejected
+        let (mut config, _c_strings) = create_test_config("test-malformed-whitelist");
+        let whitelist = CString::new("net-a,,net-b").unwrap();
+        config.foreign_network_whitelist = whitelist.as_ptr();
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, -1, "Should fail with malformed whitelist");
+        }
+    }
+
+    #[test]
+    fn test_start_gateway_peer_urls_file() {
+        let path = std::env::temp_dir().join("test-gateway-peers-file.txt");
+        std::fs::write(
+            &path,
+            "# relays\ntcp://peer1.example.com:11010\n\n# tcp://disabled.example.com:11010\n",
+        )
+        .unwrap();
+        let peers_file = CString::new(path.to_str().unwrap()).unwrap();
+
+        let (mut config, _c_strings) = create_test_config("test-peer-urls-file");
+        config.private_mode = 0;
+        config.peer_urls_file = peers_file.as_ptr();
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert!(result == 0 || result == -1, "Should handle a peers file");
+            if result == 0 {
+                let instance_name = CString::new("test-peer-urls-file").unwrap();
+                let _ = stop_easytier_core(instance_name.as_ptr());
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_start_gateway_missing_peer_urls_file() {
+        let peers_file = CString::new("/nonexistent/gateway-peers.txt").unwrap();
+        let (mut config, _c_strings) = create_test_config("test-missing-peer-urls-file");
+        config.peer_urls_file = peers_file.as_ptr();
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, -1, "Should fail when the peers file is missing");
+        }
+    }
+
+    #[test]
+    fn test_instance_name_with_trailing_whitespace() {
+        let raw_name = "test-trailing-space  ";
+        let (config, _c_strings) = create_test_config(raw_name);
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, 0, "Should start with a padded name");
+
+            let instance_name = CString::new(raw_name).unwrap();
+            assert_eq!(
+                stop_easytier_core(instance_name.as_ptr()),
+                0,
+                "Stop with the same raw name should find the instance"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_instance_names_rejected() {
+        for name in ["   ", "bad\u{1}name", &"n".repeat(65)] {
+            let (config, _c_strings) = create_test_config(name);
+            unsafe {
+                assert_eq!(
+                    start_easytier_core(&config),
+                    -1,
+                    "{:?} should be rejected",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_listener_port_retry_binds_next_port() {
+        // Keep the configured port busy
+        let _occupied = std::net::TcpListener::bind("0.0.0.0:11100").unwrap();
+
+        let listener = CString::new("tcp://0.0.0.0:11100").unwrap();
+        let listeners = [listener.as_ptr()];
+        let (mut config, _c_strings) = create_test_config("test-port-retry");
+        config.listener_urls = listeners.as_ptr();
+        config.listener_urls_count = 1;
+        config.port_retry_count = 3;
+
+        unsafe {
+            let result = start_easytier_core(&config);
+            assert_eq!(result, 0, "Should start on a nearby port");
+
+            let instance_name = CString::new("test-port-retry").unwrap();
+            let mut status_json: *mut std::ffi::c_char = ptr::null_mut();
+            assert_eq!(
+                get_easytier_core_status(instance_name.as_ptr(), &mut status_json),
+                0
+            );
+            let status: serde_json::Value =
+                serde_json::from_str(CString::from_raw(status_json).to_str().unwrap()).unwrap();
+            let bound: url::Url = status["listeners"][0].as_str().unwrap().parse().unwrap();
+            let port = bound.port().unwrap();
+            assert!(
+                (11101..=11103).contains(&port),
+                "Expected a port after 11100, got {}",
+                port
+            );
+
+            assert_eq!(stop_easytier_core(instance_name.as_ptr()), 0);
+        }
+    }
+
+    #[test]
+    fn test_listener_port_retry_exhausted() {
+        let listener = CString::new("tcp://0.0.0.0:11104").unwrap();
+        let listeners = [listener.as_ptr()];
+        let (mut config, _c_strings) = create_test_config("test-port-retry-exhausted");
+        config.listener_urls = listeners.as_ptr();
+        config.listener_urls_count = 1;
+        config.port_retry_count = 1;
+
+        // Both the port and its only retry are taken
+        let _first = std::net::TcpListener::bind("0.0.0.0:11104").unwrap();
+        let _second = std::net::TcpListener::bind("0.0.0.0:11105").unwrap();
+
+        unsafe {
+            assert_eq!(
+                start_easytier_core(&config),
+                -1,
+                "All candidate ports are busy"
+            );
+        }
+    }
+
+    #[test]
+    fn test_listener_port_retry_on_ipv6() {
+        // Skip where the host has no IPv6 loopback
+        let Ok(_occupied) = std::net::TcpListener::bind("[::1]:11106") else {
+            return;
+        };
+
+        let listener = CString::new("tcp://[::1]:11106").unwrap();
+        let listeners = [listener.as_ptr()];
+        let (mut config, _c_strings) = create_test_config("test-port-retry-ipv6");
+        config.listener_urls = listeners.as_ptr();
+        config.listener_urls_count = 1;
+        config.port_retry_count = 2;
+
+        unsafe {
+            assert_eq!(
+                start_easytier_core(&config),
+                0,
+                "Should start on a nearby port"
+            );
+
+            let instance_name = CString::new("test-port-retry-ipv6").unwrap();
+            let mut status_json: *mut std::ffi::c_char = ptr::null_mut();
+            assert_eq!(
+                get_easytier_core_status(instance_name.as_ptr(), &mut status_json),
+                0
+            );
+            let status: serde_json::Value =
+                serde_json::from_str(CString::from_raw(status_json).to_str().unwrap()).unwrap();
+            let bound: url::Url = status["listeners"][0].as_str().unwrap().parse().unwrap();
+            assert!(
+                (11107..=11108).contains(&bound.port().unwrap()),
+                "Expected a port after 11106, got {}",
+                bound
+            );
+
+            assert_eq!(stop_easytier_core(instance_name.as_ptr()), 0);
+        }
+    }
+
     #[test]
     fn test_start_gateway_with_peers() {
         // Test P2P mode with peer URLs
@@ -519,6 +763,8 @@ mod gateway_ffi_tests {
             rpc_port: 15888,
             peer_urls: unsafe { (*peers_ptr).as_ptr() },
             peer_urls_count: 2,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -533,6 +779,9 @@ mod gateway_ffi_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 0, // P2P mode
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -582,6 +831,8 @@ mod gateway_ffi_tests {
                 rpc_port,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -596,6 +847,9 @@ mod gateway_ffi_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -706,6 +960,8 @@ mod gateway_ffi_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -720,6 +976,9 @@ mod gateway_ffi_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -776,6 +1035,8 @@ mod gateway_ffi_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -790,6 +1051,9 @@ mod gateway_ffi_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -844,6 +1108,8 @@ mod gateway_ffi_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: *enc,
@@ -858,6 +1124,9 @@ mod gateway_ffi_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -905,6 +1174,8 @@ mod gateway_ffi_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -919,6 +1190,9 @@ mod gateway_ffi_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: *private_mode,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -990,6 +1264,8 @@ mod builder_api_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -1004,6 +1280,9 @@ mod builder_api_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -1056,6 +1335,8 @@ mod builder_api_tests {
                 rpc_port: 15888,
                 peer_urls: ptr::null(),
                 peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
                 default_protocol: ptr::null(),
                 dev_name: ptr::null(),
                 enable_encryption: 1,
@@ -1070,6 +1351,9 @@ mod builder_api_tests {
                 relay_all_peer_rpc: 0,
                 disable_udp_hole_punching: 0,
                 private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
             };
 
             unsafe {
@@ -1121,6 +1405,8 @@ mod gateway_lifecycle_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -1135,6 +1421,9 @@ mod gateway_lifecycle_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -1176,6 +1465,8 @@ mod gateway_lifecycle_tests {
             rpc_port: 15888,
             peer_urls: ptr::null(),
             peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
             default_protocol: ptr::null(),
             dev_name: ptr::null(),
             enable_encryption: 1,
@@ -1190,6 +1481,9 @@ mod gateway_lifecycle_tests {
             relay_all_peer_rpc: 0,
             disable_udp_hole_punching: 0,
             private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
         };
 
         unsafe {
@@ -1209,4 +1503,139 @@ mod gateway_lifecycle_tests {
             let _ = Box::from_raw(listeners_ptr);
         }
     }
+
+    #[test]
+    fn test_start_same_instance_twice() {
+        use easytier_network_gateway::{get_easytier_core_status, EASYTIER_CORE_ALREADY_RUNNING};
+
+        let instance_name = CString::new("already-running-test").unwrap();
+        let network_name = CString::new("test-network").unwrap();
+        let network_secret = CString::new("test-secret").unwrap();
+        let listener = CString::new("tcp://0.0.0.0:11083").unwrap();
+
+        let listeners = vec![listener.as_ptr()];
+        let listeners_box = listeners.into_boxed_slice();
+        let listeners_ptr = Box::into_raw(listeners_box);
+
+        let config = EasyTierCoreConfig {
+            instance_name: instance_name.as_ptr(),
+            network_name: network_name.as_ptr(),
+            network_secret: network_secret.as_ptr(),
+            dhcp: 1,
+            ipv4: ptr::null(),
+            ipv6: ptr::null(),
+            listener_urls: unsafe { (*listeners_ptr).as_ptr() },
+            listener_urls_count: 1,
+            rpc_port: 15888,
+            peer_urls: ptr::null(),
+            peer_urls_count: 0,
+            proxy_networks: ptr::null(),
+            proxy_networks_count: 0,
+            default_protocol: ptr::null(),
+            dev_name: ptr::null(),
+            enable_encryption: 1,
+            enable_ipv6: 0,
+            mtu: 1380,
+            latency_first: 0,
+            enable_exit_node: 0,
+            no_tun: 1,
+            use_smoltcp: 0,
+            foreign_network_whitelist: ptr::null(),
+            disable_p2p: 0,
+            relay_all_peer_rpc: 0,
+            disable_udp_hole_punching: 0,
+            private_mode: 1,
+            listener_urls_file: ptr::null(),
+            peer_urls_file: ptr::null(),
+            port_retry_count: 0,
+        };
+
+        unsafe {
+            let first = start_easytier_core(&config);
+            assert_eq!(first, 0, "First start should succeed");
+
+            let second = start_easytier_core(&config);
+            assert_eq!(
+                second, EASYTIER_CORE_ALREADY_RUNNING,
+                "Second start should report already running"
+            );
+
+            // The first instance must still be registered
+            let mut status_json: *mut i8 = ptr::null_mut();
+            let result = get_easytier_core_status(instance_name.as_ptr(), &mut status_json);
+            assert_eq!(result, 0);
+            let status_str = std::ffi::CStr::from_ptr(status_json).to_str().unwrap();
+            assert!(
+                status_str.contains("\"running\":true"),
+                "First instance should keep running"
+            );
+            easytier_common::easytier_common_free_string(status_json);
+
+            let stop_result = stop_easytier_core(instance_name.as_ptr());
+            assert_eq!(stop_result, 0, "Stop should succeed");
+
+            // Clean up
+            let _ = Box::from_raw(listeners_ptr);
+        }
+    }
+
+    #[test]
+    fn test_start_from_config_round_trip() {
+        use easytier::launcher::NetworkConfig;
+        use easytier_network_gateway::start_easytier_core_from_config;
+
+        // Serialize a minimal config and feed it back through the FFI
+        let network_config = NetworkConfig {
+            network_name: Some("from-config-network".to_string()),
+            network_secret: Some("from-config-secret".to_string()),
+            dhcp: Some(true),
+            listener_urls: vec!["tcp://0.0.0.0:11082".to_string()],
+            no_tun: Some(true),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&network_config).unwrap();
+        let parsed: NetworkConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.network_name, network_config.network_name);
+
+        let instance_name = CString::new("from-config-test").unwrap();
+        let config_json = CString::new(json).unwrap();
+
+        unsafe {
+            let start_result =
+                start_easytier_core_from_config(instance_name.as_ptr(), config_json.as_ptr());
+            assert_eq!(start_result, 0, "Start from config should succeed");
+
+            let stop_result = stop_easytier_core(instance_name.as_ptr());
+            assert_eq!(
+                stop_result, 0,
+                "Stop should succeed after start from config"
+            );
+        }
+    }
+
+    #[test]
+    fn test_start_from_config_invalid_input() {
+        use easytier_network_gateway::start_easytier_core_from_config;
+
+        let instance_name = CString::new("from-config-invalid").unwrap();
+        let bad_json = CString::new("{not valid json").unwrap();
+
+        unsafe {
+            assert_eq!(
+                start_easytier_core_from_config(ptr::null(), bad_json.as_ptr()),
+                -1,
+                "Should fail with null instance name"
+            );
+            assert_eq!(
+                start_easytier_core_from_config(instance_name.as_ptr(), ptr::null()),
+                -1,
+                "Should fail with null config"
+            );
+            assert_eq!(
+                start_easytier_core_from_config(instance_name.as_ptr(), bad_json.as_ptr()),
+                -1,
+                "Should fail with malformed JSON"
+            );
+        }
+    }
 }
@@ -0,0 +1,95 @@
+//! Tests for stop_all_easytier_cores
+//!
+//! Kept in a dedicated test binary because stopping every instance would
+//! interfere with tests running in parallel in the other test files.
+
+use std::ffi::CString;
+use std::ptr;
+
+#[cfg(test)]
+mod stop_all_tests {
+    use super::*;
+    use easytier_network_gateway::{
+        get_easytier_core_status, start_easytier_core, stop_all_easytier_cores, EasyTierCoreConfig,
+    };
+
+    fn is_running(instance_name: &CString) -> bool {
+        let mut status_json: *mut i8 = ptr::null_mut();
+        unsafe {
+            let result = get_easytier_core_status(instance_name.as_ptr(), &mut status_json);
+            assert_eq!(result, 0, "Status query should succeed");
+            let status_str = std::ffi::CStr::from_ptr(status_json).to_str().unwrap();
+            let running = status_str.contains("\"running\":true");
+            easytier_common::easytier_common_free_string(status_json);
+            running
+        }
+    }
+
+    #[test]
+    fn test_stop_all_cores() {
+        // Nothing running yet
+        assert_eq!(stop_all_easytier_cores(), 0, "Should stop nothing");
+
+        let network_name = CString::new("stop-all-network").unwrap();
+        let network_secret = CString::new("stop-all-secret").unwrap();
+        let names = [
+            CString::new("stop-all-1").unwrap(),
+            CString::new("stop-all-2").unwrap(),
+        ];
+        let listener_strings = [
+            CString::new("tcp://0.0.0.0:11090").unwrap(),
+            CString::new("tcp://0.0.0.0:11091").unwrap(),
+        ];
+
+        for (instance_name, listener) in names.iter().zip(listener_strings.iter()) {
+            let listeners = [listener.as_ptr()];
+
+            let config = EasyTierCoreConfig {
+                instance_name: instance_name.as_ptr(),
+                network_name: network_name.as_ptr(),
+                network_secret: network_secret.as_ptr(),
+                dhcp: 1,
+                ipv4: ptr::null(),
+                ipv6: ptr::null(),
+                listener_urls: listeners.as_ptr(),
+                listener_urls_count: 1,
+                rpc_port: 0,
+                peer_urls: ptr::null(),
+                peer_urls_count: 0,
+                proxy_networks: ptr::null(),
+                proxy_networks_count: 0,
+                default_protocol: ptr::null(),
+                dev_name: ptr::null(),
+                enable_encryption: 1,
+                enable_ipv6: 0,
+                mtu: 1380,
+                latency_first: 0,
+                enable_exit_node: 0,
+                no_tun: 1,
+                use_smoltcp: 0,
+                foreign_network_whitelist: ptr::null(),
+                disable_p2p: 0,
+                relay_all_peer_rpc: 0,
+                disable_udp_hole_punching: 0,
+                private_mode: 1,
+                listener_urls_file: ptr::null(),
+                peer_urls_file: ptr::null(),
+                port_retry_count: 0,
+            };
+
+            unsafe {
+                assert_eq!(start_easytier_core(&config), 0, "Start should succeed");
+            }
+            assert!(is_running(instance_name));
+        }
+
+        assert_eq!(stop_all_easytier_cores(), 2, "Should stop both instances");
+
+        for instance_name in &names {
+            assert!(!is_running(instance_name), "Instance should be stopped");
+        }
+
+        // Calling again is a no-op
+        assert_eq!(stop_all_easytier_cores(), 0);
+    }
+}
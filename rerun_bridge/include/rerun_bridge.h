@@ -3,9 +3,81 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * `rerun_encoder_read_into` copied every pending byte
+ */
+#define RERUN_READ_DONE 0
+
+/**
+ * `rerun_encoder_read_into` filled the buffer and more bytes are pending
+ */
+#define RERUN_READ_MORE 1
+
+/**
+ * Default size of the already-emitted buffer prefix that triggers compaction
+ */
+#define RERUN_DEFAULT_COMPACT_THRESHOLD (16 * 1024 * 1024)
+
+/**
+ * `rerun_encoder_set_timepoint` value that keeps the per-message times (default)
+ */
+#define RERUN_TIMEPOINT_NONE INT64_MIN
+
+/**
+ * `rerun_bridge_get_error_code`: no error recorded yet
+ */
+#define RERUN_ERROR_NONE 0
+
+/**
+ * `RerunBridgeError::RecordingCreation`
+ */
+#define RERUN_ERROR_RECORDING_CREATION 1
+
+/**
+ * `RerunBridgeError::LoggingFailed`
+ */
+#define RERUN_ERROR_LOGGING_FAILED 2
+
+/**
+ * `RerunBridgeError::ConversionFailed`
+ */
+#define RERUN_ERROR_CONVERSION_FAILED 3
+
+/**
+ * `RerunBridgeError::SerializationFailed`
+ */
+#define RERUN_ERROR_SERIALIZATION_FAILED 4
+
+/**
+ * `RerunBridgeError::InvalidData`
+ */
+#define RERUN_ERROR_INVALID_DATA 5
+
+/**
+ * `RerunBridgeError::MCAPError`
+ */
+#define RERUN_ERROR_MCAP 6
+
+/**
+ * Null pointer or otherwise invalid FFI argument
+ */
+#define RERUN_ERROR_INVALID_ARGUMENT 7
+
+/**
+ * Error reported through `set_error_msg` without a category
+ */
+#define RERUN_ERROR_OTHER 99
+
+/**
+ * Stateful converter following a growing MCAP file
+ */
+typedef struct RerunMcapTailer RerunMcapTailer;
+
 /**
  * Streaming encoder for generating proper RRD format from MCAP data
  * This uses `re_log_encoding::Encoder` which generates valid RRD files with `RRF2` headers
+ * All FFI calls lock the encoder state, so one handle can be shared between
+ * threads; concurrent calls on the same handle run one after the other.
  */
 typedef struct RerunStreamingEncoder RerunStreamingEncoder;
 
@@ -14,6 +86,11 @@ typedef struct RerunStreamingEncoder RerunStreamingEncoder;
  */
 const char *rerun_bridge_get_error(void);
 
+/**
+ * Get the `RERUN_ERROR_*` code of the last error, `RERUN_ERROR_NONE` if none was reported
+ */
+int rerun_bridge_get_error_code(void);
+
 /**
  * Free a C string allocated by Rust
  */
@@ -40,6 +117,66 @@ int32_t rerun_encoder_process_mcap_chunk(struct RerunStreamingEncoder *handle,
                                          uint8_t **out_data,
                                          uintptr_t *out_len);
 
+/**
+ * Enable or disable lossy UTF-8 handling for ROS 2 message strings
+ * When enabled, invalid UTF-8 is replaced with U+FFFD before the MCAP is decoded (default: disabled)
+ */
+int32_t rerun_encoder_set_lossy_strings(struct RerunStreamingEncoder *handle, bool enabled);
+
+/**
+ * Only convert messages whose timestamp lies in [start_ns, end_ns]
+ * Pass `i64::MIN` / `i64::MAX` for an unbounded start / end; the RRD header is always emitted
+ */
+int32_t rerun_encoder_set_time_range(struct RerunStreamingEncoder *handle,
+                                     int64_t start_ns,
+                                     int64_t end_ns);
+
+/**
+ * Discard already-emitted RRD bytes once more than `threshold` of them are buffered
+ * Bounds the memory of long-lived streams (default: `RERUN_DEFAULT_COMPACT_THRESHOLD`).
+ * Pass 0 to keep the whole stream, which `rerun_encoder_reset_position` needs for replay.
+ */
+int32_t rerun_encoder_set_compact_threshold(struct RerunStreamingEncoder *handle,
+                                            uintptr_t threshold);
+
+/**
+ * Namespace the entities of subsequently converted MCAP data under `prefix`, e.g. `robot_1`
+ * Null or an empty prefix converts without a prefix (default)
+ */
+int32_t rerun_encoder_set_entity_prefix(struct RerunStreamingEncoder *handle, const char *prefix);
+
+/**
+ * Log all subsequently converted messages at `time_ns`, e.g. for static snapshots
+ * of MCAP files with unreliable timestamps. `RERUN_TIMEPOINT_NONE` disables the override
+ * (default).
+ */
+int32_t rerun_encoder_set_timepoint(struct RerunStreamingEncoder *handle, int64_t time_ns);
+
+/**
+ * Get the number of chunks skipped because they could not be converted
+ * Returns 0 for a null handle
+ */
+uint64_t rerun_encoder_get_skipped_chunks(const struct RerunStreamingEncoder *handle);
+
+/**
+ * Convert MCAP data and keep the RRD bytes buffered in the encoder
+ * Use `rerun_encoder_read_into` to drain them into a caller-owned buffer
+ */
+int32_t rerun_encoder_feed_mcap_chunk(struct RerunStreamingEncoder *handle,
+                                      const uint8_t *mcap_data,
+                                      uintptr_t mcap_len);
+
+/**
+ * Copy up to `buf_len` pending RRD bytes into a caller-owned buffer
+ * Advances the read position by exactly `*out_written` bytes.
+ * Returns `RERUN_READ_DONE` when nothing is left, `RERUN_READ_MORE` when more
+ * bytes are pending, or -1 on error
+ */
+int32_t rerun_encoder_read_into(struct RerunStreamingEncoder *handle,
+                                uint8_t *buf,
+                                uintptr_t buf_len,
+                                uintptr_t *out_written);
+
 /**
  * Get initial RRD header chunk (call immediately after creation)
  * This returns the RRF2 header + metadata before any data is logged
@@ -48,6 +185,15 @@ int32_t rerun_encoder_get_initial_chunk(struct RerunStreamingEncoder *handle,
                                         uint8_t **out_data,
                                         uintptr_t *out_len);
 
+/**
+ * Rewind the read position so the full RRD stream can be replayed
+ * The next `rerun_encoder_get_initial_chunk` call returns everything encoded so far.
+ * The buffer is not cleared and no MCAP data is re-processed.
+ * Fails once compaction discarded emitted bytes; disable it with
+ * `rerun_encoder_set_compact_threshold(handle, 0)` before streaming to keep replay possible.
+ */
+int32_t rerun_encoder_reset_position(struct RerunStreamingEncoder *handle);
+
 /**
  * Finalize encoder and get final chunk (call before destroy)
  * This extracts any remaining data written by encoder.finish()
@@ -58,5 +204,59 @@ int32_t rerun_encoder_finalize(struct RerunStreamingEncoder *handle,
 
 /**
  * Destroy streaming encoder
+ * No other call on the same handle may still be running.
  */
 void rerun_encoder_destroy(struct RerunStreamingEncoder *handle);
+
+/**
+ * Get the number of encoder handles created and not destroyed yet
+ * Meant for leak checks: it returns to 0 once every encoder is destroyed.
+ */
+uintptr_t rerun_encoder_active_count(void);
+
+/**
+ * Limit how many MCAP conversions may run at once across all encoders
+ * Callers beyond the limit block until a running conversion finishes.
+ * Default: the number of CPUs. Returns -1 if `max` is 0
+ */
+int32_t rerun_set_max_concurrent_conversions(uintptr_t max);
+
+/**
+ * Summarize the channels, schemas and message counts of an MCAP file as JSON
+ * No RRD data is produced. Free the result with `rerun_bridge_free_string`.
+ * Returns -1 if the data is not a readable MCAP file
+ */
+int32_t rerun_mcap_summary(const uint8_t *mcap_data, uintptr_t mcap_len, char **out_json);
+
+/**
+ * Convert several MCAP chunks, e.g. from the files of a split recording, as
+ * one recording in timestamp order
+ * `chunks[i]` holds `chunk_lens[i]` bytes. Returns only new RRD data, like
+ * `rerun_encoder_process_mcap_chunk`.
+ */
+int32_t rerun_encoder_merge_mcap_chunks(struct RerunStreamingEncoder *handle,
+                                        const uint8_t *const *chunks,
+                                        const uintptr_t *chunk_lens,
+                                        uintptr_t chunk_count,
+                                        uint8_t **out_data,
+                                        uintptr_t *out_len);
+
+/**
+ * Create a tailer converting the MCAP file at `path` as it grows
+ * Returns null on error. Destroy it with `rerun_mcap_tailer_destroy`.
+ */
+struct RerunMcapTailer *rerun_mcap_tailer_create(const char *path, const char *application_id);
+
+/**
+ * Convert the complete MCAP records appended since the last poll
+ * `out_data` is null and `out_len` 0 when nothing new is complete yet.
+ * Free the result with `rerun_bridge_free_rrd_data`.
+ */
+int32_t rerun_mcap_tailer_poll(struct RerunMcapTailer *handle,
+                               uint8_t **out_data,
+                               uintptr_t *out_len);
+
+/**
+ * Destroy a tailer created by `rerun_mcap_tailer_create`
+ */
+void rerun_mcap_tailer_destroy(struct RerunMcapTailer *handle);
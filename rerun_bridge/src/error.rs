@@ -1,7 +1,27 @@
 //! Error types for rerun_bridge
 
+use std::ffi::c_int;
 use thiserror::Error;
 
+/// `rerun_bridge_get_error_code`: no error recorded yet
+pub const RERUN_ERROR_NONE: c_int = 0;
+/// `RerunBridgeError::RecordingCreation`
+pub const RERUN_ERROR_RECORDING_CREATION: c_int = 1;
+/// `RerunBridgeError::LoggingFailed`
+pub const RERUN_ERROR_LOGGING_FAILED: c_int = 2;
+/// `RerunBridgeError::ConversionFailed`
+pub const RERUN_ERROR_CONVERSION_FAILED: c_int = 3;
+/// `RerunBridgeError::SerializationFailed`
+pub const RERUN_ERROR_SERIALIZATION_FAILED: c_int = 4;
+/// `RerunBridgeError::InvalidData`
+pub const RERUN_ERROR_INVALID_DATA: c_int = 5;
+/// `RerunBridgeError::MCAPError`
+pub const RERUN_ERROR_MCAP: c_int = 6;
+/// Null pointer or otherwise invalid FFI argument
+pub const RERUN_ERROR_INVALID_ARGUMENT: c_int = 7;
+/// Error reported through `set_error_msg` without a category
+pub const RERUN_ERROR_OTHER: c_int = 99;
+
 #[derive(Error, Debug)]
 pub enum RerunBridgeError {
     #[error("Failed to create recording: {0}")]
@@ -23,4 +43,18 @@ pub enum RerunBridgeError {
     MCAPError(String),
 }
 
+impl RerunBridgeError {
+    /// Stable numeric code of the variant, returned by `rerun_bridge_get_error_code`
+    pub fn code(&self) -> c_int {
+        match self {
+            RerunBridgeError::RecordingCreation(_) => RERUN_ERROR_RECORDING_CREATION,
+            RerunBridgeError::LoggingFailed(_) => RERUN_ERROR_LOGGING_FAILED,
+            RerunBridgeError::ConversionFailed(_) => RERUN_ERROR_CONVERSION_FAILED,
+            RerunBridgeError::SerializationFailed(_) => RERUN_ERROR_SERIALIZATION_FAILED,
+            RerunBridgeError::InvalidData(_) => RERUN_ERROR_INVALID_DATA,
+            RerunBridgeError::MCAPError(_) => RERUN_ERROR_MCAP,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, RerunBridgeError>;
@@ -3,15 +3,23 @@
 //! FFI bridge for Rerun SDK to enable ROS data visualization from Go cortex_server.
 //! This crate converts MCAP messages to Rerun RRD format.
 
-use std::ffi::{c_char, CString};
+use std::ffi::{c_char, c_int, CString};
 use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Mutex;
 
 mod error;
+mod merge;
 mod recording;
+mod strings;
+mod summary;
+mod tailer;
 
 pub use error::*;
+pub use merge::*;
 pub use recording::*;
+pub use summary::*;
+pub use tailer::*;
 
 // Re-export logging macros from easytier_common (avoid name conflict with error module)
 pub use easytier_common::error as log_error;
@@ -21,8 +29,21 @@ pub use easytier_common::{debug, info, trace, warn};
 static ERROR_MSG: once_cell::sync::Lazy<Mutex<Vec<u8>>> =
     once_cell::sync::Lazy::new(|| Mutex::new(Vec::new()));
 
-/// Set error message for FFI error reporting
+static ERROR_CODE: AtomicI32 = AtomicI32::new(RERUN_ERROR_NONE);
+
+/// Set error message for FFI error reporting, with code `RERUN_ERROR_OTHER`
 pub fn set_error_msg(msg: &str) {
+    set_error(RERUN_ERROR_OTHER, msg);
+}
+
+/// Report a `RerunBridgeError` with its message and code
+pub fn set_bridge_error(err: &RerunBridgeError) {
+    set_error(err.code(), &err.to_string());
+}
+
+/// Set error code and message for FFI error reporting
+pub fn set_error(code: c_int, msg: &str) {
+    ERROR_CODE.store(code, Ordering::Relaxed);
     if let Ok(mut error_msg) = ERROR_MSG.lock() {
         error_msg.clear();
         error_msg.extend_from_slice(msg.as_bytes());
@@ -41,6 +62,12 @@ pub extern "C" fn rerun_bridge_get_error() -> *const c_char {
     ptr::null()
 }
 
+/// Get the `RERUN_ERROR_*` code of the last error, `RERUN_ERROR_NONE` if none was reported
+#[no_mangle]
+pub extern "C" fn rerun_bridge_get_error_code() -> c_int {
+    ERROR_CODE.load(Ordering::Relaxed)
+}
+
 /// Free a C string allocated by Rust
 #[no_mangle]
 pub extern "C" fn rerun_bridge_free_string(s: *const c_char) {
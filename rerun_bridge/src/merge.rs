@@ -0,0 +1,290 @@
+//! Merging MCAP chunks from several files into one recording
+//!
+//! A recording split across files is converted as a single MCAP: the
+//! messages of every chunk are ordered by log time and messages present in
+//! more than one file are written once, so the encoder emits one recording in
+//! timestamp order.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::ptr;
+
+use crate::recording::encoder_process_mcap_chunk_internal;
+use crate::{
+    set_bridge_error, set_error, RerunBridgeError, RerunStreamingEncoder, Result,
+    RERUN_ERROR_INVALID_ARGUMENT,
+};
+
+fn mcap_error(e: mcap::McapError) -> RerunBridgeError {
+    RerunBridgeError::MCAPError(e.to_string())
+}
+
+/// Write `messages` into a standalone MCAP, returns it with the message count
+///
+/// Schemas and channels are re-registered by content, so channel ids that
+/// clash between source files do not matter.
+pub(crate) fn write_messages<'a>(
+    messages: impl IntoIterator<Item = &'a mcap::Message<'a>>,
+) -> Result<(Vec<u8>, usize)> {
+    let mut schema_ids: HashMap<(&str, &str, &[u8]), u16> = HashMap::new();
+    let mut channel_ids: HashMap<(&str, &str, u16), u16> = HashMap::new();
+    let mut out = Cursor::new(Vec::new());
+    let mut count = 0;
+    {
+        let mut writer = mcap::Writer::new(&mut out).map_err(mcap_error)?;
+        for message in messages {
+            let channel = &message.channel;
+            let schema_id = match &channel.schema {
+                Some(schema) => {
+                    let key = (
+                        schema.name.as_str(),
+                        schema.encoding.as_str(),
+                        schema.data.as_ref(),
+                    );
+                    match schema_ids.get(&key) {
+                        Some(&id) => id,
+                        None => {
+                            let id = writer.add_schema(key.0, key.1, key.2).map_err(mcap_error)?;
+                            schema_ids.insert(key, id);
+                            id
+                        }
+                    }
+                }
+                // 0 is the "no schema" id
+                None => 0,
+            };
+
+            let key = (
+                channel.topic.as_str(),
+                channel.message_encoding.as_str(),
+                schema_id,
+            );
+            let channel_id = match channel_ids.get(&key) {
+                Some(&id) => id,
+                None => {
+                    let id = writer
+                        .add_channel(schema_id, key.0, key.1, &channel.metadata)
+                        .map_err(mcap_error)?;
+                    channel_ids.insert(key, id);
+                    id
+                }
+            };
+
+            writer
+                .write_to_known_channel(
+                    &mcap::records::MessageHeader {
+                        channel_id,
+                        sequence: message.sequence,
+                        log_time: message.log_time,
+                        publish_time: message.publish_time,
+                    },
+                    &message.data,
+                )
+                .map_err(mcap_error)?;
+            count += 1;
+        }
+        writer.finish().map_err(mcap_error)?;
+    }
+
+    Ok((out.into_inner(), count))
+}
+
+/// Combine the messages of several MCAP files into one MCAP ordered by log time
+///
+/// Messages with the same topic, log time, sequence and payload are only kept
+/// once. Returns the merged MCAP with its message count.
+pub fn merge_mcap_chunks(chunks: &[&[u8]]) -> Result<(Vec<u8>, usize)> {
+    let mut messages = Vec::new();
+    for chunk in chunks {
+        for message in mcap::MessageStream::new(chunk).map_err(mcap_error)? {
+            messages.push(message.map_err(mcap_error)?);
+        }
+    }
+    // Stable, so messages with equal timestamps keep their input order
+    messages.sort_by_key(|message| message.log_time);
+
+    let mut seen = HashSet::new();
+    write_messages(messages.iter().filter(|message| {
+        seen.insert((
+            message.channel.topic.as_str(),
+            message.log_time,
+            message.sequence,
+            message.data.as_ref(),
+        ))
+    }))
+}
+
+/// Convert several MCAP chunks, e.g. from the files of a split recording, as
+/// one recording in timestamp order
+/// `chunks[i]` holds `chunk_lens[i]` bytes. Returns only new RRD data, like
+/// `rerun_encoder_process_mcap_chunk`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_merge_mcap_chunks(
+    handle: *mut RerunStreamingEncoder,
+    chunks: *const *const u8,
+    chunk_lens: *const usize,
+    chunk_count: usize,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if handle.is_null()
+        || out_data.is_null()
+        || out_len.is_null()
+        || (chunk_count > 0 && (chunks.is_null() || chunk_lens.is_null()))
+    {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_merge_mcap_chunks",
+        );
+        return -1;
+    }
+
+    let (chunk_ptrs, lens) = if chunk_count == 0 {
+        (&[][..], &[][..])
+    } else {
+        unsafe {
+            (
+                std::slice::from_raw_parts(chunks, chunk_count),
+                std::slice::from_raw_parts(chunk_lens, chunk_count),
+            )
+        }
+    };
+    if chunk_ptrs.iter().any(|chunk| chunk.is_null()) {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null chunk passed to rerun_encoder_merge_mcap_chunks",
+        );
+        return -1;
+    }
+    let chunks: Vec<&[u8]> = chunk_ptrs
+        .iter()
+        .zip(lens)
+        .map(|(&chunk, &len)| unsafe { std::slice::from_raw_parts(chunk, len) })
+        .collect();
+
+    let result = merge_mcap_chunks(&chunks).and_then(|(mcap, _)| {
+        let mut encoder = unsafe { &*handle }.lock();
+        encoder_process_mcap_chunk_internal(&mut encoder, &mcap)
+    });
+
+    match result {
+        Ok(rrd) => {
+            let len = rrd.len();
+            let data = if len == 0 {
+                ptr::null_mut()
+            } else {
+                let mut rrd = rrd.into_boxed_slice();
+                let data = rrd.as_mut_ptr();
+                std::mem::forget(rrd);
+                data
+            };
+            unsafe {
+                *out_data = data;
+                *out_len = len;
+            }
+            0
+        }
+        Err(e) => {
+            set_bridge_error(&e);
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn read_test_mcap() -> Option<Vec<u8>> {
+        let mcap_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resource/ros2_bag/rosbag_2025_09_05-10_08_00_0.mcap"
+        );
+        match std::fs::read(mcap_path) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                println!(
+                    "⚠️ Skipping test: Could not read MCAP file at {}: {}",
+                    mcap_path, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Split a recording into two MCAP files holding alternate messages
+    fn split_alternating(mcap_data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let messages: Vec<_> = mcap::MessageStream::new(mcap_data)
+            .unwrap()
+            .map(|message| message.unwrap())
+            .collect();
+        let even = write_messages(messages.iter().step_by(2)).unwrap().0;
+        let odd = write_messages(messages.iter().skip(1).step_by(2))
+            .unwrap()
+            .0;
+        (even, odd)
+    }
+
+    #[test]
+    fn test_merge_orders_and_deduplicates() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+        let total = crate::summarize_mcap(&mcap_data).unwrap().message_count as usize;
+        let (even, odd) = split_alternating(&mcap_data);
+
+        // Feeding the first file twice must not duplicate its messages
+        let (merged, count) = merge_mcap_chunks(&[&odd, &even, &even]).unwrap();
+        assert_eq!(count, total);
+
+        let log_times: Vec<u64> = mcap::MessageStream::new(&merged)
+            .unwrap()
+            .map(|message| message.unwrap().log_time)
+            .collect();
+        assert!(log_times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_merged_chunks_share_one_recording_id() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+        let (even, odd) = split_alternating(&mcap_data);
+
+        let app_id = CString::new("merge_test").unwrap();
+        let handle = crate::rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+
+        let chunks = [even.as_ptr(), odd.as_ptr()];
+        let lens = [even.len(), odd.len()];
+        let mut out_data: *mut u8 = ptr::null_mut();
+        let mut out_len: usize = 0;
+        let result = rerun_encoder_merge_mcap_chunks(
+            handle,
+            chunks.as_ptr(),
+            lens.as_ptr(),
+            chunks.len(),
+            &mut out_data,
+            &mut out_len,
+        );
+        assert_eq!(result, 0);
+        assert!(out_len > 0);
+
+        let rrd = unsafe { std::slice::from_raw_parts(out_data, out_len) }.to_vec();
+        crate::rerun_bridge_free_rrd_data(out_data, out_len);
+        crate::rerun_encoder_destroy(handle);
+
+        let decoder =
+            re_log_encoding::decoder::Decoder::new(re_log_encoding::VersionPolicy::Warn, &rrd[..])
+                .unwrap();
+        let store_ids: HashSet<_> = decoder.map(|msg| msg.unwrap().store_id().clone()).collect();
+        assert_eq!(
+            store_ids.len(),
+            1,
+            "expected one recording, got {:?}",
+            store_ids
+        );
+    }
+}
@@ -34,19 +34,114 @@
 use std::ffi::{c_char, CStr};
 use std::io::Write;
 use std::ptr;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
 
+use re_chunk::external::arrow::array::BooleanArray;
+use re_chunk::{Chunk, TimeColumn};
 use re_data_loader::{loader_mcap::load_mcap, DataLoaderSettings, LoadedData};
 use re_log_encoding::{Encoder, EncodingOptions};
-use re_log_types::ApplicationId;
+use re_log_types::{ApplicationId, EntityPath, LogMsg, TimePoint, TimeType, Timeline};
 use std::sync::mpsc::channel;
 
-use crate::{set_error_msg, RerunBridgeError, Result};
+use crate::strings::lossy_utf8_mcap;
+use crate::{
+    set_bridge_error, set_error, RerunBridgeError, Result, RERUN_ERROR_INVALID_ARGUMENT,
+    RERUN_ERROR_SERIALIZATION_FAILED,
+};
 
 // ============================================================================
 // Encoder-Based Streaming (CORRECT IMPLEMENTATION) ✅
 // ============================================================================
 
+/// `rerun_encoder_read_into` copied every pending byte
+pub const RERUN_READ_DONE: i32 = 0;
+/// `rerun_encoder_read_into` filled the buffer and more bytes are pending
+pub const RERUN_READ_MORE: i32 = 1;
+
+/// Default size of the already-emitted buffer prefix that triggers compaction
+pub const RERUN_DEFAULT_COMPACT_THRESHOLD: usize = 16 * 1024 * 1024;
+
+/// `rerun_encoder_set_timepoint` value that keeps the per-message times (default)
+pub const RERUN_TIMEPOINT_NONE: i64 = i64::MIN;
+
+/// Timeline the timepoint override is logged on
+const TIMEPOINT_TIMELINE: &str = "log_time";
+
+/// Encoder handles created through FFI and not destroyed yet
+static ACTIVE_ENCODERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Limits how many MCAP conversions run at once across every encoder
+static CONVERSION_LIMITER: ConversionLimiter = ConversionLimiter::new();
+
+#[derive(Debug)]
+struct ConversionSlots {
+    /// Configured limit, `None` until set (use the number of CPUs)
+    max: Option<usize>,
+    running: usize,
+    /// Highest `running` seen since the process started
+    peak: usize,
+}
+
+impl ConversionSlots {
+    fn max(&self) -> usize {
+        self.max.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+    }
+}
+
+/// Counting semaphore for conversions; excess callers block until a slot frees up
+struct ConversionLimiter {
+    slots: Mutex<ConversionSlots>,
+    freed: Condvar,
+}
+
+/// A running conversion, releases its slot when dropped
+struct ConversionPermit<'a>(&'a ConversionLimiter);
+
+impl ConversionLimiter {
+    const fn new() -> Self {
+        Self {
+            slots: Mutex::new(ConversionSlots {
+                max: None,
+                running: 0,
+                peak: 0,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    fn slots(&self) -> MutexGuard<'_, ConversionSlots> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn acquire(&self) -> ConversionPermit<'_> {
+        let mut slots = self.slots();
+        while slots.running >= slots.max() {
+            slots = self.freed.wait(slots).unwrap_or_else(|e| e.into_inner());
+        }
+        slots.running += 1;
+        slots.peak = slots.peak.max(slots.running);
+        ConversionPermit(self)
+    }
+
+    fn set_max(&self, max: usize) {
+        self.slots().max = Some(max);
+        // A raised limit may admit waiting callers
+        self.freed.notify_all();
+    }
+}
+
+impl Drop for ConversionPermit<'_> {
+    fn drop(&mut self) {
+        self.0.slots().running -= 1;
+        self.0.freed.notify_one();
+    }
+}
+
 /// A shared buffer writer that allows reading the data without consuming it
 #[derive(Clone)]
 struct SharedBufferWriter {
@@ -67,6 +162,22 @@ impl SharedBufferWriter {
     fn len(&self) -> usize {
         self.buffer.lock().unwrap().len()
     }
+
+    /// Copy bytes starting at `start` into `dst`, returns how many were copied
+    fn copy_from(&self, start: usize, dst: &mut [u8]) -> usize {
+        let buffer = self.buffer.lock().unwrap();
+        let pending = buffer.get(start..).unwrap_or_default();
+        let n = pending.len().min(dst.len());
+        dst[..n].copy_from_slice(&pending[..n]);
+        n
+    }
+
+    /// Drop the first `n` bytes of the buffer
+    fn discard_front(&self, n: usize) {
+        let mut buffer = self.buffer.lock().unwrap();
+        let n = n.min(buffer.len());
+        buffer.drain(..n);
+    }
 }
 
 impl Write for SharedBufferWriter {
@@ -81,11 +192,150 @@ impl Write for SharedBufferWriter {
 
 /// Streaming encoder for generating proper RRD format from MCAP data
 /// This uses `re_log_encoding::Encoder` which generates valid RRD files with `RRF2` headers
+/// All FFI calls lock the encoder state, so one handle can be shared between
+/// threads; concurrent calls on the same handle run one after the other.
 pub struct RerunStreamingEncoder {
+    state: Mutex<EncoderState>,
+}
+
+/// Conversion state of a `RerunStreamingEncoder`
+pub(crate) struct EncoderState {
     encoder: Encoder<SharedBufferWriter>,
     buffer: SharedBufferWriter,
+    /// Read position inside `buffer`, bytes before it were already emitted
     last_position: usize,
+    /// Emitted bytes kept before they are discarded, 0 keeps everything
+    compact_threshold: usize,
+    /// Bytes discarded by compaction, replay is impossible once non-zero
+    discarded_bytes: u64,
     recording_id: String,
+    /// Repair invalid UTF-8 in ROS 2 message strings before conversion
+    lossy_strings: bool,
+    /// Chunks dropped because they could not be converted
+    skipped_chunks: u64,
+    /// Inclusive [start, end] window in nanoseconds, `i64::MIN`/`i64::MAX` mean unbounded
+    time_range: (i64, i64),
+    /// Prefix added to the entity path of every converted message
+    entity_prefix: Option<EntityPath>,
+    /// Fixed log time in nanoseconds for every converted message
+    timepoint: Option<i64>,
+}
+
+/// Timeline used for time-range filtering: the MCAP log time if present,
+/// otherwise the first timestamp timeline of the chunk
+fn filter_time_column(chunk: &Chunk) -> Option<&TimeColumn> {
+    let mut timestamps = chunk
+        .timelines()
+        .values()
+        .filter(|column| column.timeline().typ() == TimeType::TimestampNs);
+    let first = timestamps.next()?;
+    Some(
+        std::iter::once(first)
+            .chain(timestamps)
+            .find(|column| column.timeline().name().as_str().contains("log_time"))
+            .unwrap_or(first),
+    )
+}
+
+impl RerunStreamingEncoder {
+    fn new(state: EncoderState) -> Self {
+        Self {
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Lock the encoder state, a panic in another call does not make it unusable
+    pub(crate) fn lock(&self) -> MutexGuard<'_, EncoderState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl EncoderState {
+    /// Discard the emitted prefix of the buffer once it exceeds the threshold
+    fn compact(&mut self) {
+        if self.compact_threshold == 0 || self.last_position < self.compact_threshold {
+            return;
+        }
+
+        self.buffer.discard_front(self.last_position);
+        self.discarded_bytes += self.last_position as u64;
+        crate::trace!(
+            "Compacted {} emitted RRD bytes ({} discarded in total)",
+            self.last_position,
+            self.discarded_bytes
+        );
+        self.last_position = 0;
+    }
+
+    /// Encode one item produced by the MCAP loader, returns false if it was skipped
+    fn append_loaded_data(&mut self, loaded_data: LoadedData) -> Result<bool> {
+        // Time filtering works on chunks, so decode raw arrow messages first
+        let loaded_data = match loaded_data {
+            LoadedData::ArrowMsg(name, store_id, arrow_msg) if self.has_time_range() => {
+                match Chunk::from_arrow_msg(&arrow_msg) {
+                    Ok(chunk) => LoadedData::Chunk(name, store_id, chunk),
+                    Err(e) => {
+                        crate::warn!("Failed to decode arrow message into a chunk: {}", e);
+                        self.skipped_chunks += 1;
+                        return Ok(false);
+                    }
+                }
+            }
+            other => other,
+        };
+
+        let log_msg = match loaded_data {
+            LoadedData::LogMsg(_, msg) => msg,
+            LoadedData::Chunk(_, store_id, chunk) => {
+                let Some(chunk) = self.filter_time_range(chunk) else {
+                    return Ok(false);
+                };
+                match chunk.to_arrow_msg() {
+                    Ok(arrow_msg) => LogMsg::ArrowMsg(store_id, arrow_msg),
+                    Err(e) => {
+                        crate::warn!("Failed to convert chunk to arrow: {}", e);
+                        self.skipped_chunks += 1;
+                        return Ok(false);
+                    }
+                }
+            }
+            LoadedData::ArrowMsg(_, store_id, arrow_msg) => LogMsg::ArrowMsg(store_id, arrow_msg),
+        };
+
+        self.encoder.append(&log_msg).map_err(|e| {
+            RerunBridgeError::SerializationFailed(format!("Failed to encode message: {}", e))
+        })?;
+        Ok(true)
+    }
+
+    fn has_time_range(&self) -> bool {
+        self.time_range != (i64::MIN, i64::MAX)
+    }
+
+    /// Keep only the rows inside the time window
+    /// Chunks without a timestamp timeline (e.g. static data) are always kept
+    fn filter_time_range(&self, chunk: Chunk) -> Option<Chunk> {
+        if !self.has_time_range() {
+            return Some(chunk);
+        }
+        let Some(times) = filter_time_column(&chunk) else {
+            return Some(chunk);
+        };
+
+        let (start, end) = self.time_range;
+        let mask = BooleanArray::from(
+            times
+                .times_raw()
+                .iter()
+                .map(|time| (start..=end).contains(time))
+                .collect::<Vec<_>>(),
+        );
+        match mask.true_count() {
+            0 => None,
+            kept if kept == mask.len() => Some(chunk),
+            _ => chunk.filtered(&mask),
+        }
+    }
 }
 
 /// Create a new streaming encoder
@@ -96,7 +346,7 @@ pub extern "C" fn rerun_encoder_create(
     application_id: *const c_char,
 ) -> *mut RerunStreamingEncoder {
     if application_id.is_null() {
-        set_error_msg("application_id is null");
+        set_error(RERUN_ERROR_INVALID_ARGUMENT, "application_id is null");
         return ptr::null_mut();
     }
 
@@ -104,22 +354,28 @@ pub extern "C" fn rerun_encoder_create(
         match CStr::from_ptr(application_id).to_str() {
             Ok(s) => s,
             Err(e) => {
-                set_error_msg(&format!("Invalid UTF-8 in application_id: {}", e));
+                set_error(
+                    RERUN_ERROR_INVALID_ARGUMENT,
+                    &format!("Invalid UTF-8 in application_id: {}", e),
+                );
                 return ptr::null_mut();
             }
         }
     };
 
     match encoder_create_internal(app_id) {
-        Ok(encoder) => Box::into_raw(Box::new(encoder)),
+        Ok(state) => {
+            ACTIVE_ENCODERS.fetch_add(1, Ordering::SeqCst);
+            Box::into_raw(Box::new(RerunStreamingEncoder::new(state)))
+        }
         Err(e) => {
-            set_error_msg(&e.to_string());
+            set_bridge_error(&e);
             ptr::null_mut()
         }
     }
 }
 
-fn encoder_create_internal(app_id: &str) -> Result<RerunStreamingEncoder> {
+pub(crate) fn encoder_create_internal(app_id: &str) -> Result<EncoderState> {
     let options = EncodingOptions::PROTOBUF_COMPRESSED;
     let version = re_build_info::CrateVersion::LOCAL;
 
@@ -130,11 +386,18 @@ fn encoder_create_internal(app_id: &str) -> Result<RerunStreamingEncoder> {
 
     crate::debug!("🎬 Created RRD encoder with proper RRF2 format support");
 
-    Ok(RerunStreamingEncoder {
+    Ok(EncoderState {
         encoder,
         buffer,
         last_position: 0,
+        compact_threshold: RERUN_DEFAULT_COMPACT_THRESHOLD,
+        discarded_bytes: 0,
         recording_id: app_id.to_string(),
+        lossy_strings: false,
+        skipped_chunks: 0,
+        time_range: (i64::MIN, i64::MAX),
+        entity_prefix: None,
+        timepoint: None,
     })
 }
 
@@ -150,14 +413,17 @@ pub extern "C" fn rerun_encoder_process_mcap_chunk(
     out_len: *mut usize,
 ) -> i32 {
     if handle.is_null() || mcap_data.is_null() || out_data.is_null() || out_len.is_null() {
-        set_error_msg("Null pointer passed to rerun_encoder_process_mcap_chunk");
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_process_mcap_chunk",
+        );
         return -1;
     }
 
-    let encoder = unsafe { &mut *handle };
+    let mut encoder = unsafe { &*handle }.lock();
     let mcap_bytes = unsafe { std::slice::from_raw_parts(mcap_data, mcap_len) };
 
-    match encoder_process_mcap_chunk_internal(encoder, mcap_bytes) {
+    match encoder_process_mcap_chunk_internal(&mut encoder, mcap_bytes) {
         Ok(chunk_data) => {
             let len = chunk_data.len();
 
@@ -180,16 +446,21 @@ pub extern "C" fn rerun_encoder_process_mcap_chunk(
             0
         }
         Err(e) => {
-            set_error_msg(&e.to_string());
+            set_bridge_error(&e);
             -1
         }
     }
 }
 
-fn encoder_process_mcap_chunk_internal(
-    encoder_state: &mut RerunStreamingEncoder,
+/// Convert MCAP data and append it to the encoder buffer without extracting it
+/// Returns the number of encoded messages
+fn encoder_encode_mcap_internal(
+    encoder_state: &mut EncoderState,
     mcap_data: &[u8],
-) -> Result<Vec<u8>> {
+) -> Result<usize> {
+    // Held until every loaded chunk is appended, excess callers wait here
+    let _permit = CONVERSION_LIMITER.acquire();
+
     // Create channel for data loader
     let (tx, rx) = channel::<LoadedData>();
 
@@ -201,13 +472,25 @@ fn encoder_process_mcap_chunk_internal(
         recording_id: encoder_state.recording_id.as_str().into(),
         opened_store_id: None,
         force_store_info: false,
-        entity_path_prefix: None,
-        timepoint: None,
+        entity_path_prefix: encoder_state.entity_prefix.clone(),
+        timepoint: encoder_state.timepoint.map(|time_ns| {
+            TimePoint::default().with(Timeline::new_timestamp(TIMEPOINT_TIMELINE), time_ns)
+        }),
+    };
+
+    // The decoder rejects invalid UTF-8, so repair it up front in lossy mode
+    let repaired = if encoder_state.lossy_strings {
+        lossy_utf8_mcap(mcap_data).unwrap_or_else(|e| {
+            crate::warn!("Failed to repair invalid UTF-8 in MCAP strings: {}", e);
+            None
+        })
+    } else {
+        None
     };
 
     // Load MCAP chunk
     let result = load_mcap(
-        mcap_data,
+        repaired.as_deref().unwrap_or(mcap_data),
         &settings,
         &tx,
         &re_mcap::SelectedLayers::All,
@@ -223,33 +506,25 @@ fn encoder_process_mcap_chunk_internal(
         )));
     }
 
-    // Get current buffer position before encoding new data
-    let start_position = encoder_state.last_position;
-
     // Process all loaded data
     let mut message_count = 0;
     while let Ok(loaded_data) = rx.recv() {
-        let log_msg = match loaded_data {
-            LoadedData::LogMsg(_, msg) => msg,
-            LoadedData::Chunk(_, store_id, chunk) => match chunk.to_arrow_msg() {
-                Ok(arrow_msg) => re_log_types::LogMsg::ArrowMsg(store_id, arrow_msg),
-                Err(e) => {
-                    crate::warn!("Failed to convert chunk to arrow: {}", e);
-                    continue;
-                }
-            },
-            LoadedData::ArrowMsg(_, store_id, arrow_msg) => {
-                re_log_types::LogMsg::ArrowMsg(store_id, arrow_msg)
-            }
-        };
+        if encoder_state.append_loaded_data(loaded_data)? {
+            message_count += 1;
+        }
+    }
 
-        // Append to encoder
-        encoder_state.encoder.append(&log_msg).map_err(|e| {
-            RerunBridgeError::SerializationFailed(format!("Failed to encode message: {}", e))
-        })?;
+    Ok(message_count)
+}
 
-        message_count += 1;
-    }
+pub(crate) fn encoder_process_mcap_chunk_internal(
+    encoder_state: &mut EncoderState,
+    mcap_data: &[u8],
+) -> Result<Vec<u8>> {
+    // Get current buffer position before encoding new data
+    let start_position = encoder_state.last_position;
+
+    let message_count = encoder_encode_mcap_internal(encoder_state, mcap_data)?;
 
     // Note: The encoder writes directly to SharedBufferWriter via Write trait
     // Data is immediately available in the buffer after append() - no explicit flush needed
@@ -265,6 +540,7 @@ fn encoder_process_mcap_chunk_internal(
         let encoder_bytes = encoder_state.buffer.get_bytes();
         let new_bytes = &encoder_bytes[start_position..current_position];
         encoder_state.last_position = current_position;
+        encoder_state.compact();
 
         crate::debug!(
             " Encoded {} MCAP messages → {} new RRD bytes (total buffer: {} bytes)",
@@ -274,7 +550,7 @@ fn encoder_process_mcap_chunk_internal(
         );
 
         // Validate RRD header on first chunk
-        if start_position == 0 && new_bytes.len() >= 4 {
+        if start_position == 0 && encoder_state.discarded_bytes == 0 && new_bytes.len() >= 4 {
             crate::debug!(
                 "RRD header magic bytes: {:?} (expecting [82, 82, 70, 50] = 'RRF2')",
                 &new_bytes[0..4]
@@ -288,6 +564,227 @@ fn encoder_process_mcap_chunk_internal(
     }
 }
 
+/// Enable or disable lossy UTF-8 handling for ROS 2 message strings
+/// When enabled, invalid UTF-8 is replaced with U+FFFD before the MCAP is decoded (default: disabled)
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_set_lossy_strings(
+    handle: *mut RerunStreamingEncoder,
+    enabled: bool,
+) -> i32 {
+    if handle.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_set_lossy_strings",
+        );
+        return -1;
+    }
+
+    let mut encoder = unsafe { &*handle }.lock();
+    encoder.lossy_strings = enabled;
+    0
+}
+
+/// Only convert messages whose timestamp lies in [start_ns, end_ns]
+/// Pass `i64::MIN` / `i64::MAX` for an unbounded start / end; the RRD header is always emitted
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_set_time_range(
+    handle: *mut RerunStreamingEncoder,
+    start_ns: i64,
+    end_ns: i64,
+) -> i32 {
+    if handle.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_set_time_range",
+        );
+        return -1;
+    }
+    if start_ns > end_ns {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            &format!(
+                "Invalid time range: start {} is after end {}",
+                start_ns, end_ns
+            ),
+        );
+        return -1;
+    }
+
+    let mut encoder = unsafe { &*handle }.lock();
+    encoder.time_range = (start_ns, end_ns);
+    0
+}
+
+/// Discard already-emitted RRD bytes once more than `threshold` of them are buffered
+/// Bounds the memory of long-lived streams (default: `RERUN_DEFAULT_COMPACT_THRESHOLD`).
+/// Pass 0 to keep the whole stream, which `rerun_encoder_reset_position` needs for replay.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_set_compact_threshold(
+    handle: *mut RerunStreamingEncoder,
+    threshold: usize,
+) -> i32 {
+    if handle.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_set_compact_threshold",
+        );
+        return -1;
+    }
+
+    let mut encoder = unsafe { &*handle }.lock();
+    encoder.compact_threshold = threshold;
+    encoder.compact();
+    0
+}
+
+/// Namespace the entities of subsequently converted MCAP data under `prefix`, e.g. `robot_1`
+/// Null or an empty prefix converts without a prefix (default)
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_set_entity_prefix(
+    handle: *mut RerunStreamingEncoder,
+    prefix: *const c_char,
+) -> i32 {
+    if handle.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_set_entity_prefix",
+        );
+        return -1;
+    }
+
+    let prefix = if prefix.is_null() {
+        ""
+    } else {
+        match unsafe { CStr::from_ptr(prefix) }.to_str() {
+            Ok(prefix) => prefix.trim(),
+            Err(e) => {
+                set_error(
+                    RERUN_ERROR_INVALID_ARGUMENT,
+                    &format!("Invalid UTF-8 in entity prefix: {}", e),
+                );
+                return -1;
+            }
+        }
+    };
+
+    let entity_prefix = if prefix.is_empty() {
+        None
+    } else {
+        Some(EntityPath::parse_forgiving(prefix))
+    };
+    unsafe { &*handle }.lock().entity_prefix = entity_prefix;
+    0
+}
+
+/// Log all subsequently converted messages at `time_ns`, e.g. for static snapshots
+/// of MCAP files with unreliable timestamps. `RERUN_TIMEPOINT_NONE` disables the override
+/// (default).
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_set_timepoint(
+    handle: *mut RerunStreamingEncoder,
+    time_ns: i64,
+) -> i32 {
+    if handle.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_set_timepoint",
+        );
+        return -1;
+    }
+
+    unsafe { &*handle }.lock().timepoint = (time_ns != RERUN_TIMEPOINT_NONE).then_some(time_ns);
+    0
+}
+
+/// Get the number of chunks skipped because they could not be converted
+/// Returns 0 for a null handle
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_get_skipped_chunks(handle: *const RerunStreamingEncoder) -> u64 {
+    if handle.is_null() {
+        return 0;
+    }
+
+    unsafe { &*handle }.lock().skipped_chunks
+}
+
+/// Convert MCAP data and keep the RRD bytes buffered in the encoder
+/// Use `rerun_encoder_read_into` to drain them into a caller-owned buffer
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_feed_mcap_chunk(
+    handle: *mut RerunStreamingEncoder,
+    mcap_data: *const u8,
+    mcap_len: usize,
+) -> i32 {
+    if handle.is_null() || mcap_data.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_feed_mcap_chunk",
+        );
+        return -1;
+    }
+
+    let mut encoder = unsafe { &*handle }.lock();
+    let mcap_bytes = unsafe { std::slice::from_raw_parts(mcap_data, mcap_len) };
+
+    match encoder_encode_mcap_internal(&mut encoder, mcap_bytes) {
+        Ok(message_count) => {
+            crate::trace!("Buffered {} MCAP messages", message_count);
+            0
+        }
+        Err(e) => {
+            set_bridge_error(&e);
+            -1
+        }
+    }
+}
+
+/// Copy up to `buf_len` pending RRD bytes into a caller-owned buffer
+/// Advances the read position by exactly `*out_written` bytes.
+/// Returns `RERUN_READ_DONE` when nothing is left, `RERUN_READ_MORE` when more
+/// bytes are pending, or -1 on error
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_read_into(
+    handle: *mut RerunStreamingEncoder,
+    buf: *mut u8,
+    buf_len: usize,
+    out_written: *mut usize,
+) -> i32 {
+    if handle.is_null() || out_written.is_null() || (buf.is_null() && buf_len > 0) {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_read_into",
+        );
+        return -1;
+    }
+
+    let mut encoder = unsafe { &*handle }.lock();
+    let written = if buf_len == 0 {
+        0
+    } else {
+        let dst = unsafe { std::slice::from_raw_parts_mut(buf, buf_len) };
+        encoder.buffer.copy_from(encoder.last_position, dst)
+    };
+    encoder.last_position += written;
+    encoder.compact();
+    unsafe {
+        *out_written = written;
+    }
+
+    if encoder.buffer.len() > encoder.last_position {
+        RERUN_READ_MORE
+    } else {
+        RERUN_READ_DONE
+    }
+}
+
 /// Get initial RRD header chunk (call immediately after creation)
 /// This returns the RRF2 header + metadata before any data is logged
 #[no_mangle]
@@ -298,14 +795,17 @@ pub extern "C" fn rerun_encoder_get_initial_chunk(
     out_len: *mut usize,
 ) -> i32 {
     if handle.is_null() || out_data.is_null() || out_len.is_null() {
-        set_error_msg("Null pointer passed to rerun_encoder_get_initial_chunk");
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_get_initial_chunk",
+        );
         return -1;
     }
 
-    let encoder = unsafe { &mut *handle };
+    let mut encoder = unsafe { &*handle }.lock();
 
-    // On first call (last_position == 0), return the initial RRD header
-    if encoder.last_position == 0 {
+    // On first call (nothing emitted yet), return the initial RRD header
+    if encoder.last_position == 0 && encoder.discarded_bytes == 0 {
         let encoder_bytes = encoder.buffer.get_bytes();
         if !encoder_bytes.is_empty() {
             let header_chunk = encoder_bytes.to_vec();
@@ -314,6 +814,7 @@ pub extern "C" fn rerun_encoder_get_initial_chunk(
 
             std::mem::forget(header_chunk);
             encoder.last_position = len;
+            encoder.compact();
 
             crate::info!("Sending initial RRD header: {} bytes", len);
 
@@ -333,6 +834,42 @@ pub extern "C" fn rerun_encoder_get_initial_chunk(
     0
 }
 
+/// Rewind the read position so the full RRD stream can be replayed
+/// The next `rerun_encoder_get_initial_chunk` call returns everything encoded so far.
+/// The buffer is not cleared and no MCAP data is re-processed.
+/// Fails once compaction discarded emitted bytes; disable it with
+/// `rerun_encoder_set_compact_threshold(handle, 0)` before streaming to keep replay possible.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_encoder_reset_position(handle: *mut RerunStreamingEncoder) -> i32 {
+    if handle.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_reset_position",
+        );
+        return -1;
+    }
+
+    let mut encoder = unsafe { &*handle }.lock();
+    if encoder.discarded_bytes > 0 {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            &format!(
+                "Cannot replay: {} emitted bytes were discarded by compaction",
+                encoder.discarded_bytes
+            ),
+        );
+        return -1;
+    }
+    crate::debug!(
+        "Rewinding encoder from position {} to replay {} buffered bytes",
+        encoder.last_position,
+        encoder.buffer.len()
+    );
+    encoder.last_position = 0;
+    0
+}
+
 /// Finalize encoder and get final chunk (call before destroy)
 /// This extracts any remaining data written by encoder.finish()
 #[no_mangle]
@@ -343,15 +880,21 @@ pub extern "C" fn rerun_encoder_finalize(
     out_len: *mut usize,
 ) -> i32 {
     if handle.is_null() || out_data.is_null() || out_len.is_null() {
-        set_error_msg("Null pointer passed to rerun_encoder_finalize");
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_encoder_finalize",
+        );
         return -1;
     }
 
-    let encoder = unsafe { &mut *handle };
+    let mut encoder = unsafe { &*handle }.lock();
 
     // Finalize the encoder (writes end marker if needed)
     if let Err(e) = encoder.encoder.finish() {
-        set_error_msg(&format!("Failed to finalize encoder: {}", e));
+        set_error(
+            RERUN_ERROR_SERIALIZATION_FAILED,
+            &format!("Failed to finalize encoder: {}", e),
+        );
         return -1;
     }
 
@@ -368,6 +911,7 @@ pub extern "C" fn rerun_encoder_finalize(
             let ptr = final_chunk.as_ptr() as *mut u8;
             std::mem::forget(final_chunk);
             encoder.last_position = current_position;
+            encoder.compact();
 
             unsafe {
                 *out_data = ptr;
@@ -386,6 +930,7 @@ pub extern "C" fn rerun_encoder_finalize(
 }
 
 /// Destroy streaming encoder
+/// No other call on the same handle may still be running.
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn rerun_encoder_destroy(handle: *mut RerunStreamingEncoder) {
@@ -395,9 +940,45 @@ pub extern "C" fn rerun_encoder_destroy(handle: *mut RerunStreamingEncoder) {
             // Encoder is dropped here (finish() should have been called via finalize)
             crate::debug!("🗑️ Destroyed encoder handle");
         }
+        ACTIVE_ENCODERS.fetch_sub(1, Ordering::SeqCst);
     }
 }
 
+/// Get the number of encoder handles created and not destroyed yet
+/// Meant for leak checks: it returns to 0 once every encoder is destroyed.
+#[no_mangle]
+pub extern "C" fn rerun_encoder_active_count() -> usize {
+    ACTIVE_ENCODERS.load(Ordering::SeqCst)
+}
+
+/// Limit how many MCAP conversions may run at once across all encoders
+/// Callers beyond the limit block until a running conversion finishes.
+/// Default: the number of CPUs. Returns -1 if `max` is 0
+#[no_mangle]
+pub extern "C" fn rerun_set_max_concurrent_conversions(max: usize) -> i32 {
+    if max == 0 {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Maximum concurrent conversions must be at least 1",
+        );
+        return -1;
+    }
+
+    CONVERSION_LIMITER.set_max(max);
+    crate::debug!("Limited concurrent MCAP conversions to {}", max);
+    0
+}
+
+/// Current limit on concurrent MCAP conversions
+pub fn max_concurrent_conversions() -> usize {
+    CONVERSION_LIMITER.slots().max()
+}
+
+/// Highest number of MCAP conversions that ran at once since the process started
+pub fn peak_concurrent_conversions() -> usize {
+    CONVERSION_LIMITER.slots().peak
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -747,14 +1328,14 @@ mod tests {
         println!("Second initial chunk call returned 0 bytes");
 
         // Verify internal state was updated
-        let encoder = unsafe { &*handle };
+        let last_position = unsafe { &*handle }.lock().last_position;
         assert_eq!(
-            encoder.last_position, initial_size,
+            last_position, initial_size,
             "Buffer position should track initial chunk size"
         );
         println!(
             "Buffer position correctly tracks at {} bytes",
-            encoder.last_position
+            last_position
         );
 
         rerun_encoder_destroy(handle);
@@ -889,4 +1470,595 @@ mod tests {
         rerun_encoder_destroy(handle2);
         println!("Multiple encoders work independently");
     }
+
+    /// Build an MCAP with one `std_msgs/String` message holding `text`
+    fn string_message_mcap(text: &[u8]) -> Vec<u8> {
+        let mut payload = vec![0, 1, 0, 0];
+        payload.extend_from_slice(&(text.len() as u32 + 1).to_le_bytes());
+        payload.extend_from_slice(text);
+        payload.push(0);
+
+        let mut out = std::io::Cursor::new(Vec::new());
+        {
+            let mut writer = mcap::Writer::new(&mut out).unwrap();
+            let schema_id = writer
+                .add_schema("std_msgs/msg/String", "ros2msg", b"string data")
+                .unwrap();
+            let channel_id = writer
+                .add_channel(schema_id, "/chatter", "cdr", &Default::default())
+                .unwrap();
+            writer
+                .write_to_known_channel(
+                    &mcap::records::MessageHeader {
+                        channel_id,
+                        sequence: 0,
+                        log_time: 1_000,
+                        publish_time: 1_000,
+                    },
+                    &payload,
+                )
+                .unwrap();
+            writer.finish().unwrap();
+        }
+        out.into_inner()
+    }
+
+    /// Convert `mcap_data` on a fresh encoder, returns the emitted RRD bytes
+    fn encode_string_mcap(mcap_data: &[u8], lossy: bool) -> Result<Vec<u8>> {
+        let mut encoder = encoder_create_internal("test_lossy_strings").unwrap();
+        encoder.lossy_strings = lossy;
+        encoder_process_mcap_chunk_internal(&mut encoder, mcap_data)
+    }
+
+    #[test]
+    fn test_valid_strings_are_not_rewritten() {
+        let mcap_data = string_message_mcap(b"sensor name");
+        assert!(lossy_utf8_mcap(&mcap_data).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_utf8_message_repaired_in_lossy_mode() {
+        let mcap_data = string_message_mcap(b"sensor \xFF\xFE name");
+
+        let repaired = lossy_utf8_mcap(&mcap_data).unwrap().unwrap();
+        let message = mcap::MessageStream::new(&repaired)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let text = "sensor \u{FFFD}\u{FFFD} name";
+        assert_eq!(&message.data[4..8], &(text.len() as u32 + 1).to_le_bytes());
+        assert_eq!(&message.data[8..8 + text.len()], text.as_bytes());
+
+        let lossy = encode_string_mcap(&mcap_data, true).expect("Lossy conversion should succeed");
+        // Without lossy mode the decoder rejects the string
+        match encode_string_mcap(&mcap_data, false) {
+            Ok(strict) => assert!(
+                lossy.len() > strict.len(),
+                "Repaired message should be emitted only in lossy mode"
+            ),
+            Err(e) => println!("Strict conversion failed as expected: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_set_lossy_strings_null_handle() {
+        assert_eq!(rerun_encoder_set_lossy_strings(ptr::null_mut(), true), -1);
+        assert_eq!(rerun_encoder_get_skipped_chunks(ptr::null()), 0);
+    }
+
+    fn read_test_mcap() -> Option<Vec<u8>> {
+        let mcap_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resource/ros2_bag/rosbag_2025_09_05-10_08_00_0.mcap"
+        );
+        match std::fs::read(mcap_path) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                println!(
+                    "⚠️ Skipping test: Could not read MCAP file at {}: {}",
+                    mcap_path, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Earliest and latest filter timestamps across all chunks of an MCAP file
+    fn mcap_time_bounds(mcap_data: &[u8]) -> (i64, i64) {
+        let (tx, rx) = channel::<LoadedData>();
+        let settings = DataLoaderSettings {
+            application_id: Some(ApplicationId::from("time_bounds")),
+            recording_id: "time_bounds".into(),
+            opened_store_id: None,
+            force_store_info: false,
+            entity_path_prefix: None,
+            timepoint: None,
+        };
+        load_mcap(
+            mcap_data,
+            &settings,
+            &tx,
+            &re_mcap::SelectedLayers::All,
+            true,
+        )
+        .unwrap();
+        drop(tx);
+
+        rx.iter()
+            .filter_map(|loaded| match loaded {
+                LoadedData::Chunk(_, _, chunk) => filter_time_column(&chunk).and_then(|times| {
+                    Some((
+                        *times.times_raw().iter().min()?,
+                        *times.times_raw().iter().max()?,
+                    ))
+                }),
+                _ => None,
+            })
+            .fold((i64::MAX, i64::MIN), |(lo, hi), (min, max)| {
+                (lo.min(min), hi.max(max))
+            })
+    }
+
+    /// Encode the whole MCAP with an optional time window, returns (header, data) byte counts
+    fn encode_with_time_range(mcap_data: &[u8], range: Option<(i64, i64)>) -> (usize, usize) {
+        let app_id = CString::new("test_time_range").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        if let Some((start, end)) = range {
+            assert_eq!(rerun_encoder_set_time_range(handle, start, end), 0);
+        }
+
+        let mut header_data: *mut u8 = ptr::null_mut();
+        let mut header_len: usize = 0;
+        assert_eq!(
+            rerun_encoder_get_initial_chunk(handle, &mut header_data, &mut header_len),
+            0
+        );
+        if header_len >= 4 {
+            let magic_bytes = unsafe { std::slice::from_raw_parts(header_data, 4) };
+            assert_eq!(magic_bytes, &[82, 82, 70, 50], "Should have RRF2 magic");
+        }
+        if !header_data.is_null() && header_len > 0 {
+            crate::rerun_bridge_free_rrd_data(header_data, header_len);
+        }
+
+        let mut rrd_data: *mut u8 = ptr::null_mut();
+        let mut rrd_len: usize = 0;
+        let result = rerun_encoder_process_mcap_chunk(
+            handle,
+            mcap_data.as_ptr(),
+            mcap_data.len(),
+            &mut rrd_data,
+            &mut rrd_len,
+        );
+        assert_eq!(result, 0, "MCAP processing should succeed");
+        if !rrd_data.is_null() && rrd_len > 0 {
+            crate::rerun_bridge_free_rrd_data(rrd_data, rrd_len);
+        }
+
+        rerun_encoder_destroy(handle);
+        (header_len, rrd_len)
+    }
+
+    #[test]
+    fn test_time_range_sub_window_emits_less() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        let (first, last) = mcap_time_bounds(&mcap_data);
+        assert!(first < last, "Test bag should span a time range");
+        let span = last - first;
+        let window = (first + span / 4, first + span / 2);
+
+        let (_, full_len) = encode_with_time_range(&mcap_data, None);
+        let (_, window_len) = encode_with_time_range(&mcap_data, Some(window));
+        println!(
+            "Full recording: {} bytes, window {:?}: {} bytes",
+            full_len, window, window_len
+        );
+
+        assert!(window_len > 0, "Window should still contain messages");
+        assert!(
+            window_len < full_len,
+            "Sub-window should emit fewer messages than the full recording"
+        );
+    }
+
+    #[test]
+    fn test_time_range_excluding_everything_keeps_header() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        let (_, last) = mcap_time_bounds(&mcap_data);
+        let (header_len, _) = encode_with_time_range(&mcap_data, Some((last + 1, i64::MAX)));
+        assert!(header_len >= 4, "RRD header should still be emitted");
+    }
+
+    #[test]
+    fn test_set_time_range_rejects_inverted_window() {
+        let app_id = CString::new("test_inverted_range").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+
+        assert_eq!(rerun_encoder_set_time_range(handle, 10, 5), -1);
+
+        assert_eq!(rerun_encoder_set_time_range(handle, i64::MIN, i64::MAX), 0);
+        assert_eq!(rerun_encoder_set_time_range(ptr::null_mut(), 0, 1), -1);
+
+        rerun_encoder_destroy(handle);
+    }
+
+    /// Copy an FFI-returned buffer into a Vec and free it
+    fn take_rrd_data(data: *mut u8, len: usize) -> Vec<u8> {
+        if data.is_null() || len == 0 {
+            return Vec::new();
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(data, len) }.to_vec();
+        crate::rerun_bridge_free_rrd_data(data, len);
+        bytes
+    }
+
+    #[test]
+    fn test_reset_position_replays_same_bytes() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        let app_id = CString::new("test_replay").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!(rerun_encoder_set_compact_threshold(handle, 0), 0);
+
+        let mut out_data: *mut u8 = ptr::null_mut();
+        let mut out_len: usize = 0;
+        assert_eq!(
+            rerun_encoder_get_initial_chunk(handle, &mut out_data, &mut out_len),
+            0
+        );
+        let mut first_pass = take_rrd_data(out_data, out_len);
+
+        let mut rrd_data: *mut u8 = ptr::null_mut();
+        let mut rrd_len: usize = 0;
+        let result = rerun_encoder_process_mcap_chunk(
+            handle,
+            mcap_data.as_ptr(),
+            mcap_data.len(),
+            &mut rrd_data,
+            &mut rrd_len,
+        );
+        assert_eq!(result, 0, "MCAP processing should succeed");
+        first_pass.extend(take_rrd_data(rrd_data, rrd_len));
+        assert!(!first_pass.is_empty());
+
+        // Without a reset the header is not sent again
+        assert_eq!(
+            rerun_encoder_get_initial_chunk(handle, &mut out_data, &mut out_len),
+            0
+        );
+        assert_eq!(out_len, 0);
+
+        assert_eq!(rerun_encoder_reset_position(handle), 0);
+        assert_eq!(
+            rerun_encoder_get_initial_chunk(handle, &mut out_data, &mut out_len),
+            0
+        );
+        let replay = take_rrd_data(out_data, out_len);
+        assert_eq!(replay, first_pass, "Replay should re-emit the same bytes");
+
+        rerun_encoder_destroy(handle);
+    }
+
+    #[test]
+    fn test_reset_position_null_handle() {
+        assert_eq!(rerun_encoder_reset_position(ptr::null_mut()), -1);
+    }
+
+    #[test]
+    fn test_read_into_drains_in_fixed_size_reads() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        let app_id = CString::new("test_read_into").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+
+        assert_eq!(
+            rerun_encoder_feed_mcap_chunk(handle, mcap_data.as_ptr(), mcap_data.len()),
+            0,
+            "Feeding MCAP should succeed"
+        );
+        let expected = unsafe { &*handle }.lock().buffer.get_bytes();
+        assert!(!expected.is_empty());
+
+        let mut buf = [0u8; 4096];
+        let mut drained = Vec::new();
+        loop {
+            let mut written = 0usize;
+            let result = rerun_encoder_read_into(handle, buf.as_mut_ptr(), buf.len(), &mut written);
+            assert!(result == RERUN_READ_DONE || result == RERUN_READ_MORE);
+            drained.extend_from_slice(&buf[..written]);
+            if result == RERUN_READ_DONE {
+                break;
+            }
+            assert_eq!(written, buf.len(), "Partial reads only happen at the end");
+        }
+        assert_eq!(
+            drained, expected,
+            "Drained bytes should match the RRD stream"
+        );
+
+        // Nothing pending anymore
+        let mut written = usize::MAX;
+        assert_eq!(
+            rerun_encoder_read_into(handle, buf.as_mut_ptr(), buf.len(), &mut written),
+            RERUN_READ_DONE
+        );
+        assert_eq!(written, 0);
+
+        rerun_encoder_destroy(handle);
+    }
+
+    #[test]
+    fn test_read_into_null_pointers() {
+        let mut written = 0usize;
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            rerun_encoder_read_into(ptr::null_mut(), buf.as_mut_ptr(), buf.len(), &mut written),
+            -1
+        );
+
+        let app_id = CString::new("test_read_into_null").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!(
+            rerun_encoder_read_into(handle, ptr::null_mut(), buf.len(), &mut written),
+            -1
+        );
+        assert_eq!(
+            rerun_encoder_read_into(handle, buf.as_mut_ptr(), buf.len(), ptr::null_mut()),
+            -1
+        );
+        assert_eq!(rerun_encoder_feed_mcap_chunk(handle, ptr::null(), 0), -1);
+        rerun_encoder_destroy(handle);
+    }
+
+    #[test]
+    fn test_compaction_bounds_buffer_memory() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        // Re-split the recording into many small MCAP chunks
+        let messages: Vec<_> = mcap::MessageStream::new(&mcap_data)
+            .unwrap()
+            .map(|message| message.unwrap())
+            .collect();
+        let chunks: Vec<Vec<u8>> = messages
+            .chunks(messages.len().div_ceil(32))
+            .map(|chunk| crate::merge::write_messages(chunk).unwrap().0)
+            .collect();
+        assert!(chunks.len() > 1);
+
+        let threshold = 64 * 1024;
+        let app_id = CString::new("test_compaction").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!(rerun_encoder_set_compact_threshold(handle, threshold), 0);
+
+        let mut emitted = 0;
+        for chunk in &chunks {
+            let mut rrd_data: *mut u8 = ptr::null_mut();
+            let mut rrd_len: usize = 0;
+            let result = rerun_encoder_process_mcap_chunk(
+                handle,
+                chunk.as_ptr(),
+                chunk.len(),
+                &mut rrd_data,
+                &mut rrd_len,
+            );
+            assert_eq!(result, 0, "MCAP processing should succeed");
+            emitted += take_rrd_data(rrd_data, rrd_len).len();
+
+            // Only the emitted bytes below the threshold may stay buffered
+            let buffered = unsafe { &*handle }.lock().buffer.len();
+            assert!(buffered < threshold, "buffer holds {} bytes", buffered);
+        }
+
+        assert!(emitted > threshold, "test data should exceed the threshold");
+        {
+            let encoder = unsafe { &*handle }.lock();
+            assert_eq!(
+                encoder.discarded_bytes as usize + encoder.buffer.len(),
+                emitted
+            );
+        }
+
+        // The discarded bytes can no longer be replayed
+        assert_eq!(rerun_encoder_reset_position(handle), -1);
+
+        rerun_encoder_destroy(handle);
+    }
+
+    #[test]
+    fn test_shared_handle_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RerunStreamingEncoder>();
+
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+        let messages: Vec<_> = mcap::MessageStream::new(&mcap_data)
+            .unwrap()
+            .map(|message| message.unwrap())
+            .collect();
+        let chunks: Vec<Vec<u8>> = messages
+            .chunks(messages.len().div_ceil(16))
+            .map(|chunk| crate::merge::write_messages(chunk).unwrap().0)
+            .collect();
+
+        let app_id = CString::new("test_threads").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!(rerun_encoder_set_compact_threshold(handle, 0), 0);
+
+        // Raw pointers are not Send, pass the address instead
+        let address = handle as usize;
+        let emitted: usize = std::thread::scope(|scope| {
+            let workers: Vec<_> = chunks
+                .chunks(chunks.len().div_ceil(4))
+                .map(|worker_chunks| {
+                    scope.spawn(move || {
+                        let handle = address as *mut RerunStreamingEncoder;
+                        let mut emitted = 0;
+                        for chunk in worker_chunks {
+                            let mut rrd_data: *mut u8 = ptr::null_mut();
+                            let mut rrd_len: usize = 0;
+                            let result = rerun_encoder_process_mcap_chunk(
+                                handle,
+                                chunk.as_ptr(),
+                                chunk.len(),
+                                &mut rrd_data,
+                                &mut rrd_len,
+                            );
+                            assert_eq!(result, 0, "MCAP processing should succeed");
+                            emitted += take_rrd_data(rrd_data, rrd_len).len();
+                            rerun_encoder_get_skipped_chunks(handle);
+                        }
+                        emitted
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum()
+        });
+
+        // Every encoded byte was handed out exactly once
+        let buffered = unsafe { &*handle }.lock().buffer.len();
+        assert!(emitted > 0);
+        assert_eq!(emitted, buffered);
+
+        rerun_encoder_destroy(handle);
+    }
+
+    /// Decode an RRD stream into its log messages
+    fn decode_rrd(rrd: &[u8]) -> Vec<LogMsg> {
+        re_log_encoding::decoder::Decoder::new(re_log_encoding::VersionPolicy::Warn, rrd)
+            .unwrap()
+            .map(|msg| msg.unwrap())
+            .collect()
+    }
+
+    /// Entity paths of the data chunks in an RRD stream
+    fn rrd_entity_paths(rrd: &[u8]) -> Vec<EntityPath> {
+        decode_rrd(rrd)
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::ArrowMsg(_, arrow_msg) => Some(
+                    Chunk::from_arrow_msg(arrow_msg)
+                        .unwrap()
+                        .entity_path()
+                        .clone(),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_entity_prefix_namespaces_entities() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        let app_id = CString::new("test_entity_prefix").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        let prefix = CString::new("robot_1").unwrap();
+        assert_eq!(rerun_encoder_set_entity_prefix(handle, prefix.as_ptr()), 0);
+
+        assert_eq!(
+            rerun_encoder_feed_mcap_chunk(handle, mcap_data.as_ptr(), mcap_data.len()),
+            0
+        );
+        let rrd = unsafe { &*handle }.lock().buffer.get_bytes();
+        rerun_encoder_destroy(handle);
+
+        let paths = rrd_entity_paths(&rrd);
+        assert!(!paths.is_empty());
+        let prefix = EntityPath::from("robot_1");
+        for path in &paths {
+            assert!(path.starts_with(&prefix), "{} is not namespaced", path);
+        }
+    }
+
+    #[test]
+    fn test_empty_entity_prefix_clears_prefix() {
+        let app_id = CString::new("test_entity_prefix_clear").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+
+        let prefix = CString::new("robot_1").unwrap();
+        assert_eq!(rerun_encoder_set_entity_prefix(handle, prefix.as_ptr()), 0);
+        assert!(unsafe { &*handle }.lock().entity_prefix.is_some());
+
+        let empty = CString::new("").unwrap();
+        assert_eq!(rerun_encoder_set_entity_prefix(handle, empty.as_ptr()), 0);
+        assert!(unsafe { &*handle }.lock().entity_prefix.is_none());
+
+        assert_eq!(rerun_encoder_set_entity_prefix(handle, ptr::null()), 0);
+        assert_eq!(
+            rerun_encoder_set_entity_prefix(ptr::null_mut(), prefix.as_ptr()),
+            -1
+        );
+
+        rerun_encoder_destroy(handle);
+    }
+
+    #[test]
+    fn test_timepoint_override_still_converts() {
+        let Some(mcap_data) = read_test_mcap() else {
+            return;
+        };
+
+        let app_id = CString::new("test_timepoint").unwrap();
+        let handle = rerun_encoder_create(app_id.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!(
+            rerun_encoder_set_timepoint(handle, 1_700_000_000_000_000_000),
+            0
+        );
+        assert_eq!(
+            unsafe { &*handle }.lock().timepoint,
+            Some(1_700_000_000_000_000_000)
+        );
+
+        let mut rrd_data: *mut u8 = ptr::null_mut();
+        let mut rrd_len: usize = 0;
+        let result = rerun_encoder_process_mcap_chunk(
+            handle,
+            mcap_data.as_ptr(),
+            mcap_data.len(),
+            &mut rrd_data,
+            &mut rrd_len,
+        );
+        assert_eq!(result, 0, "MCAP processing should succeed");
+        let rrd = take_rrd_data(rrd_data, rrd_len);
+        assert!(
+            !rrd_entity_paths(&rrd).is_empty(),
+            "Data should be produced"
+        );
+
+        // The sentinel restores the per-message times
+        assert_eq!(rerun_encoder_set_timepoint(handle, RERUN_TIMEPOINT_NONE), 0);
+        assert_eq!(unsafe { &*handle }.lock().timepoint, None);
+        assert_eq!(rerun_encoder_set_timepoint(ptr::null_mut(), 0), -1);
+
+        rerun_encoder_destroy(handle);
+    }
 }
@@ -0,0 +1,401 @@
+//! Lossy repair of invalid UTF-8 in ROS 2 message strings
+//!
+//! Some rosbags carry non-UTF-8 bytes in string fields, which the MCAP
+//! decoder rejects. In lossy mode the CDR payloads of `ros2msg` channels are
+//! walked with their schema and rewritten with such bytes replaced by U+FFFD
+//! before conversion. An MCAP without invalid strings is converted unchanged.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::merge::write_messages;
+use crate::{RerunBridgeError, Result};
+
+/// Encapsulation header in front of every CDR payload, alignment starts after it
+const CDR_HEADER_LEN: usize = 4;
+
+/// Nesting limit, protects against schemas that include themselves
+const MAX_DEPTH: usize = 32;
+
+enum FieldType {
+    /// Fixed-size primitive of the given byte size
+    Primitive(usize),
+    String,
+    /// Nested message, by normalized name
+    Message(String),
+}
+
+enum Arity {
+    Single,
+    Fixed(usize),
+    /// Unbounded or bounded sequence, both prefixed with their length
+    Sequence,
+}
+
+struct Field {
+    ty: FieldType,
+    arity: Arity,
+}
+
+/// Field layout of a `ros2msg` schema and the messages it depends on
+struct MessageSchema {
+    root: String,
+    messages: HashMap<String, Vec<Field>>,
+}
+
+fn primitive_size(name: &str) -> Option<usize> {
+    Some(match name {
+        "bool" | "byte" | "char" | "int8" | "uint8" => 1,
+        "int16" | "uint16" => 2,
+        "int32" | "uint32" | "float32" => 4,
+        "int64" | "uint64" | "float64" => 8,
+        _ => return None,
+    })
+}
+
+/// `pkg/msg/Name` and `pkg/Name` both become `pkg/Name`
+fn normalize(name: &str) -> String {
+    match name.split('/').collect::<Vec<_>>().as_slice() {
+        [package, "msg", ty] => format!("{}/{}", package, ty),
+        _ => name.to_string(),
+    }
+}
+
+impl MessageSchema {
+    /// Parse the schema text, None if it uses a type that cannot be walked
+    fn parse(name: &str, text: &str) -> Option<Self> {
+        let root = normalize(name);
+        let mut definitions = vec![(root.clone(), Vec::new())];
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with("==") {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("MSG:") {
+                definitions.push((normalize(name.trim()), Vec::new()));
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let (ty, field) = (tokens.next()?, tokens.next()?);
+            // Constants take no space in the payload
+            if field.contains('=') || tokens.next().is_some_and(|t| t.starts_with('=')) {
+                continue;
+            }
+            definitions.last_mut()?.1.push(ty.to_string());
+        }
+
+        let names: Vec<String> = definitions.iter().map(|(name, _)| name.clone()).collect();
+        let mut messages = HashMap::new();
+        for (name, types) in definitions {
+            let package = name.split('/').next().unwrap_or_default();
+            let fields = types
+                .iter()
+                .map(|ty| Self::parse_field(ty, package, &names))
+                .collect::<Option<Vec<_>>>()?;
+            messages.insert(name, fields);
+        }
+        Some(Self { root, messages })
+    }
+
+    fn parse_field(ty: &str, package: &str, names: &[String]) -> Option<Field> {
+        let (base, arity) = match ty.split_once('[') {
+            Some((base, bound)) => {
+                let bound = bound.strip_suffix(']')?;
+                let arity = if bound.is_empty() || bound.starts_with("<=") {
+                    Arity::Sequence
+                } else {
+                    Arity::Fixed(bound.parse().ok()?)
+                };
+                (base, arity)
+            }
+            None => (ty, Arity::Single),
+        };
+
+        let ty = if let Some(size) = primitive_size(base) {
+            FieldType::Primitive(size)
+        } else if base == "string" || base.starts_with("string<=") {
+            FieldType::String
+        } else if base.starts_with("wstring") {
+            // Wide string layout differs between middlewares
+            return None;
+        } else if base.contains('/') {
+            FieldType::Message(normalize(base))
+        } else {
+            // Same package first, then any dependency with that name
+            let local = format!("{}/{}", package, base);
+            let suffix = format!("/{}", base);
+            let name = names
+                .iter()
+                .find(|name| **name == local)
+                .or_else(|| names.iter().find(|name| name.ends_with(&suffix)))?;
+            FieldType::Message(name.clone())
+        };
+        Some(Field { ty, arity })
+    }
+}
+
+/// Copies a CDR payload field by field, repairing strings on the way
+///
+/// Padding is recomputed for the output, since a repaired string changes the
+/// offsets of everything after it.
+struct CdrRewriter<'a> {
+    input: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    little_endian: bool,
+    changed: bool,
+}
+
+impl<'a> CdrRewriter<'a> {
+    fn align(&mut self, size: usize) -> Option<()> {
+        self.pos += (size - (self.pos - CDR_HEADER_LEN) % size) % size;
+        let out_padding = (size - (self.out.len() - CDR_HEADER_LEN) % size) % size;
+        self.out.resize(self.out.len() + out_padding, 0);
+        (self.pos <= self.input.len()).then_some(())
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.input.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn copy(&mut self, len: usize) -> Option<()> {
+        let bytes = self.input.get(self.pos..self.pos.checked_add(len)?)?;
+        self.out.extend_from_slice(bytes);
+        self.pos += len;
+        Some(())
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        self.align(4)?;
+        let bytes: [u8; 4] = self.take(4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Write a u32 right after `read_u32`, both sides are aligned already
+    fn write_u32(&mut self, value: u32) {
+        let bytes = if self.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        };
+        self.out.extend_from_slice(&bytes);
+    }
+
+    fn string(&mut self) -> Option<()> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.take(len)?;
+        // The length counts the terminating NUL
+        let text = String::from_utf8_lossy(bytes.strip_suffix(&[0]).unwrap_or(bytes));
+        self.changed |= matches!(text, Cow::Owned(_));
+        self.write_u32(u32::try_from(text.len() + 1).ok()?);
+        self.out.extend_from_slice(text.as_bytes());
+        self.out.push(0);
+        Some(())
+    }
+
+    fn value(&mut self, schema: &MessageSchema, ty: &FieldType, depth: usize) -> Option<()> {
+        match ty {
+            FieldType::Primitive(size) => {
+                self.align(*size)?;
+                self.copy(*size)
+            }
+            FieldType::String => self.string(),
+            FieldType::Message(name) => self.message(schema, name, depth + 1),
+        }
+    }
+
+    fn message(&mut self, schema: &MessageSchema, name: &str, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let fields = schema.messages.get(name)?;
+        // An empty message is serialized as a single placeholder byte
+        if fields.is_empty() {
+            return self.copy(1);
+        }
+
+        for field in fields {
+            let count = match field.arity {
+                Arity::Single => {
+                    self.value(schema, &field.ty, depth)?;
+                    continue;
+                }
+                Arity::Fixed(count) => count,
+                Arity::Sequence => {
+                    let count = self.read_u32()?;
+                    self.write_u32(count);
+                    count as usize
+                }
+            };
+
+            match &field.ty {
+                // Primitive elements are contiguous, copy them in one go
+                FieldType::Primitive(size) if count > 0 => {
+                    self.align(*size)?;
+                    self.copy(size.checked_mul(count)?)?;
+                }
+                FieldType::Primitive(_) => {}
+                ty => {
+                    for _ in 0..count {
+                        self.value(schema, ty, depth)?;
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Rewrite one CDR payload, None if it needs no repair or cannot be walked
+fn lossy_cdr_payload(schema: &MessageSchema, payload: &[u8]) -> Option<Vec<u8>> {
+    // Only plain CDR, big or little endian
+    let little_endian = match payload.get(..2)? {
+        [0, 0] => false,
+        [0, 1] => true,
+        _ => return None,
+    };
+    let mut rewriter = CdrRewriter {
+        input: payload,
+        pos: CDR_HEADER_LEN,
+        out: payload.get(..CDR_HEADER_LEN)?.to_vec(),
+        little_endian,
+        changed: false,
+    };
+    rewriter.message(schema, &schema.root, 0)?;
+    if !rewriter.changed {
+        return None;
+    }
+
+    // Keep trailing padding as it was
+    rewriter
+        .out
+        .extend_from_slice(&payload[rewriter.pos.min(payload.len())..]);
+    Some(rewriter.out)
+}
+
+/// Replace invalid UTF-8 in the string fields of every ROS 2 message
+///
+/// Returns the rewritten MCAP, or None when no message holds invalid UTF-8.
+pub(crate) fn lossy_utf8_mcap(mcap_data: &[u8]) -> Result<Option<Vec<u8>>> {
+    let mcap_error = |e: mcap::McapError| RerunBridgeError::MCAPError(e.to_string());
+
+    let mut schemas: HashMap<u16, Option<MessageSchema>> = HashMap::new();
+    let mut messages = Vec::new();
+    let mut repaired = 0;
+    for message in mcap::MessageStream::new(mcap_data).map_err(mcap_error)? {
+        let mut message = message.map_err(mcap_error)?;
+        let schema = match &message.channel.schema {
+            Some(schema)
+                if schema.encoding == "ros2msg" && message.channel.message_encoding == "cdr" =>
+            {
+                schemas
+                    .entry(schema.id)
+                    .or_insert_with(|| {
+                        std::str::from_utf8(&schema.data)
+                            .ok()
+                            .and_then(|text| MessageSchema::parse(&schema.name, text))
+                    })
+                    .as_ref()
+            }
+            _ => None,
+        };
+        if let Some(data) = schema.and_then(|schema| lossy_cdr_payload(schema, &message.data)) {
+            message.data = Cow::Owned(data);
+            repaired += 1;
+        }
+        messages.push(message);
+    }
+
+    if repaired == 0 {
+        return Ok(None);
+    }
+    crate::debug!("Replaced invalid UTF-8 in {} ROS 2 messages", repaired);
+    write_messages(&messages).map(|(mcap, _)| Some(mcap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG_SCHEMA: &str = "\
+# Diagnostic message
+uint8 DEBUG=10
+builtin_interfaces/Time stamp
+string name
+string[] values
+uint8[] data
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+";
+
+    /// Little-endian CDR for a LOG_SCHEMA message
+    fn log_payload(name: &[u8], values: &[&[u8]], data: &[u8]) -> Vec<u8> {
+        fn align(out: &mut Vec<u8>, size: usize) {
+            while (out.len() - CDR_HEADER_LEN) % size != 0 {
+                out.push(0);
+            }
+        }
+        fn string(out: &mut Vec<u8>, value: &[u8]) {
+            align(out, 4);
+            out.extend_from_slice(&(value.len() as u32 + 1).to_le_bytes());
+            out.extend_from_slice(value);
+            out.push(0);
+        }
+
+        let mut out = vec![0, 1, 0, 0];
+        out.extend_from_slice(&7i32.to_le_bytes());
+        out.extend_from_slice(&9u32.to_le_bytes());
+        string(&mut out, name);
+        align(&mut out, 4);
+        out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        for value in values {
+            string(&mut out, value);
+        }
+        align(&mut out, 4);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn log_schema() -> MessageSchema {
+        MessageSchema::parse("diagnostic_msgs/msg/Log", LOG_SCHEMA).unwrap()
+    }
+
+    #[test]
+    fn test_valid_payload_is_left_alone() {
+        let payload = log_payload(b"sensor", &[b"ok".as_slice()], &[1, 2, 3]);
+        assert!(lossy_cdr_payload(&log_schema(), &payload).is_none());
+    }
+
+    #[test]
+    fn test_invalid_strings_are_replaced() {
+        let payload = log_payload(
+            b"sensor \xFF\xFE name",
+            &[b"a".as_slice(), b"\xC3"],
+            &[1, 2, 3],
+        );
+        let repaired = lossy_cdr_payload(&log_schema(), &payload).unwrap();
+        assert_eq!(
+            repaired,
+            log_payload(
+                "sensor \u{FFFD}\u{FFFD} name".as_bytes(),
+                &[b"a".as_slice(), "\u{FFFD}".as_bytes()],
+                &[1, 2, 3]
+            )
+        );
+    }
+
+    #[test]
+    fn test_wide_strings_are_not_walked() {
+        assert!(MessageSchema::parse("pkg/msg/Wide", "wstring text").is_none());
+    }
+}
@@ -0,0 +1,248 @@
+//! MCAP summary extraction without RRD conversion
+//!
+//! Lists the channels of an MCAP file with their schema and message count so
+//! a UI can show what a recording contains before streaming it.
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CString};
+use std::ptr;
+
+use serde::Serialize;
+
+use crate::{
+    set_bridge_error, set_error, RerunBridgeError, Result, RERUN_ERROR_INVALID_ARGUMENT,
+    RERUN_ERROR_SERIALIZATION_FAILED,
+};
+
+#[derive(Debug, Serialize)]
+pub struct McapSchemaSummary {
+    pub name: String,
+    pub encoding: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McapChannelSummary {
+    pub id: u16,
+    pub topic: String,
+    pub message_encoding: String,
+    pub schema: Option<McapSchemaSummary>,
+    pub message_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McapSummary {
+    pub message_count: u64,
+    /// Log time of the first message in nanoseconds, None if there are no messages
+    pub start_time_ns: Option<u64>,
+    /// Log time of the last message in nanoseconds, None if there are no messages
+    pub end_time_ns: Option<u64>,
+    pub channels: Vec<McapChannelSummary>,
+}
+
+/// Summarize an MCAP file
+/// Uses the summary section when present and falls back to scanning all messages
+pub fn summarize_mcap(mcap_data: &[u8]) -> Result<McapSummary> {
+    let summary = mcap::Summary::read(mcap_data)
+        .map_err(|e| RerunBridgeError::MCAPError(format!("Failed to read MCAP summary: {}", e)))?;
+
+    match summary {
+        Some(summary) if summary.stats.is_some() => Ok(from_summary_section(&summary)),
+        _ => scan_messages(mcap_data),
+    }
+}
+
+fn channel_summary(channel: &mcap::Channel, message_count: u64) -> McapChannelSummary {
+    McapChannelSummary {
+        id: channel.id,
+        topic: channel.topic.clone(),
+        message_encoding: channel.message_encoding.clone(),
+        schema: channel.schema.as_ref().map(|schema| McapSchemaSummary {
+            name: schema.name.clone(),
+            encoding: schema.encoding.clone(),
+        }),
+        message_count,
+    }
+}
+
+fn from_summary_section(summary: &mcap::Summary) -> McapSummary {
+    let stats = summary.stats.as_ref();
+    let counts = stats.map(|stats| &stats.channel_message_counts);
+
+    let mut channels: Vec<_> = summary
+        .channels
+        .values()
+        .map(|channel| {
+            let count = counts
+                .and_then(|counts| counts.get(&channel.id))
+                .copied()
+                .unwrap_or(0);
+            channel_summary(channel, count)
+        })
+        .collect();
+    channels.sort_by_key(|channel| channel.id);
+
+    let message_count = stats.map(|stats| stats.message_count).unwrap_or(0);
+    McapSummary {
+        message_count,
+        start_time_ns: stats
+            .filter(|_| message_count > 0)
+            .map(|stats| stats.message_start_time),
+        end_time_ns: stats
+            .filter(|_| message_count > 0)
+            .map(|stats| stats.message_end_time),
+        channels,
+    }
+}
+
+fn scan_messages(mcap_data: &[u8]) -> Result<McapSummary> {
+    let stream = mcap::MessageStream::new(mcap_data)
+        .map_err(|e| RerunBridgeError::MCAPError(format!("Failed to read MCAP: {}", e)))?;
+
+    let mut channels: BTreeMap<u16, McapChannelSummary> = BTreeMap::new();
+    let mut message_count = 0u64;
+    let mut start_time_ns: Option<u64> = None;
+    let mut end_time_ns: Option<u64> = None;
+
+    for message in stream {
+        let message = message
+            .map_err(|e| RerunBridgeError::MCAPError(format!("Failed to read message: {}", e)))?;
+
+        channels
+            .entry(message.channel.id)
+            .or_insert_with(|| channel_summary(&message.channel, 0))
+            .message_count += 1;
+        message_count += 1;
+        start_time_ns = Some(start_time_ns.map_or(message.log_time, |t| t.min(message.log_time)));
+        end_time_ns = Some(end_time_ns.map_or(message.log_time, |t| t.max(message.log_time)));
+    }
+
+    Ok(McapSummary {
+        message_count,
+        start_time_ns,
+        end_time_ns,
+        channels: channels.into_values().collect(),
+    })
+}
+
+/// Summarize the channels, schemas and message counts of an MCAP file as JSON
+/// No RRD data is produced. Free the result with `rerun_bridge_free_string`.
+/// Returns -1 if the data is not a readable MCAP file
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_mcap_summary(
+    mcap_data: *const u8,
+    mcap_len: usize,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    if mcap_data.is_null() || out_json.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_mcap_summary",
+        );
+        return -1;
+    }
+
+    let mcap_bytes = unsafe { std::slice::from_raw_parts(mcap_data, mcap_len) };
+    let json = summarize_mcap(mcap_bytes).and_then(|summary| {
+        serde_json::to_string(&summary)
+            .map_err(|e| RerunBridgeError::SerializationFailed(e.to_string()))
+    });
+
+    match json.map(CString::new) {
+        Ok(Ok(json)) => {
+            unsafe {
+                *out_json = json.into_raw();
+            }
+            0
+        }
+        Ok(Err(e)) => {
+            set_error(
+                RERUN_ERROR_SERIALIZATION_FAILED,
+                &format!("Summary contains a NUL byte: {}", e),
+            );
+            unsafe {
+                *out_json = ptr::null_mut();
+            }
+            -1
+        }
+        Err(e) => {
+            set_bridge_error(&e);
+            unsafe {
+                *out_json = ptr::null_mut();
+            }
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_mcap_summary_lists_topics() {
+        let mcap_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resource/ros2_bag/rosbag_2025_09_05-10_08_00_0.mcap"
+        );
+        let mcap_data = match std::fs::read(mcap_path) {
+            Ok(data) => data,
+            Err(e) => {
+                println!(
+                    "⚠️ Skipping test: Could not read MCAP file at {}: {}",
+                    mcap_path, e
+                );
+                return;
+            }
+        };
+
+        let mut out_json: *mut c_char = ptr::null_mut();
+        let result = rerun_mcap_summary(mcap_data.as_ptr(), mcap_data.len(), &mut out_json);
+        assert_eq!(result, 0, "Summary should succeed");
+        assert!(!out_json.is_null());
+
+        let json = unsafe { CStr::from_ptr(out_json).to_str().unwrap().to_owned() };
+        crate::rerun_bridge_free_string(out_json);
+        println!("MCAP summary: {}", json);
+
+        let summary: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let channels = summary["channels"].as_array().unwrap();
+        assert!(!channels.is_empty(), "At least one topic should be listed");
+        assert!(channels
+            .iter()
+            .all(|channel| channel["topic"].as_str().is_some_and(|t| !t.is_empty())));
+        assert!(summary["message_count"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_mcap_summary_rejects_invalid_data() {
+        let invalid_mcap = [0xDE, 0xAD, 0xBE, 0xEF, 0xCA, 0xFE, 0xBA, 0xBE];
+        let mut out_json: *mut c_char = ptr::null_mut();
+
+        let result = rerun_mcap_summary(invalid_mcap.as_ptr(), invalid_mcap.len(), &mut out_json);
+        assert_eq!(result, -1);
+        assert!(out_json.is_null());
+
+        assert_eq!(rerun_mcap_summary(ptr::null(), 0, &mut out_json), -1);
+    }
+
+    #[test]
+    fn test_mcap_error_sets_error_code() {
+        let invalid_mcap = [0xDE, 0xAD, 0xBE, 0xEF, 0xCA, 0xFE, 0xBA, 0xBE];
+        let mut out_json: *mut c_char = ptr::null_mut();
+
+        let result = rerun_mcap_summary(invalid_mcap.as_ptr(), invalid_mcap.len(), &mut out_json);
+        assert_eq!(result, -1);
+        assert_eq!(
+            crate::rerun_bridge_get_error_code(),
+            crate::RERUN_ERROR_MCAP
+        );
+
+        assert_eq!(rerun_mcap_summary(ptr::null(), 0, &mut out_json), -1);
+        assert_eq!(
+            crate::rerun_bridge_get_error_code(),
+            crate::RERUN_ERROR_INVALID_ARGUMENT
+        );
+    }
+}
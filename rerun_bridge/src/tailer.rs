@@ -0,0 +1,395 @@
+//! Incremental conversion of an MCAP file that is still being written
+//!
+//! The tailer remembers how far the file has been read and only reads the
+//! bytes appended since the previous poll. The schemas and channels seen so
+//! far are kept, so the new messages can be written as a standalone MCAP. A
+//! record cut off at the end of the file is left for the next poll, once the
+//! writer has completed it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Arc;
+
+use mcap::records::Record;
+
+use crate::merge::write_messages;
+use crate::recording::{
+    encoder_create_internal, encoder_process_mcap_chunk_internal, EncoderState,
+};
+use crate::{set_bridge_error, set_error, RerunBridgeError, Result, RERUN_ERROR_INVALID_ARGUMENT};
+
+/// MCAP record opcode of the footer, followed only by the closing magic
+const FOOTER_OPCODE: u8 = 0x02;
+
+/// Size of a record header: opcode and little-endian content length
+const RECORD_HEADER_LEN: usize = 9;
+
+/// Stateful converter following a growing MCAP file
+pub struct RerunMcapTailer {
+    path: PathBuf,
+    encoder: EncoderState,
+    /// Number of bytes of the file read so far
+    offset: u64,
+    /// Bytes read but not converted yet, starting at a record boundary
+    pending: Vec<u8>,
+    /// Whether the leading magic has been read
+    started: bool,
+    /// Whether the footer has been read, nothing follows it
+    finished: bool,
+    /// Schemas seen so far, by id
+    schemas: HashMap<u16, Arc<mcap::Schema<'static>>>,
+    /// Channels seen so far, by id
+    channels: HashMap<u16, Arc<mcap::Channel<'static>>>,
+}
+
+fn mcap_error(e: mcap::McapError) -> RerunBridgeError {
+    RerunBridgeError::MCAPError(e.to_string())
+}
+
+/// Length of the prefix of `data` made of complete top-level records, and
+/// whether the footer follows it
+///
+/// `data` starts at a record boundary. The footer itself is not included.
+fn complete_records_len(data: &[u8]) -> (usize, bool) {
+    let mut pos = 0;
+    while let Some(header) = data.get(pos..pos + RECORD_HEADER_LEN) {
+        let opcode = header[0];
+        if opcode == FOOTER_OPCODE {
+            return (pos, true);
+        }
+
+        let len = u64::from_le_bytes(header[1..].try_into().unwrap());
+        let Some(end) = usize::try_from(len)
+            .ok()
+            .and_then(|len| (pos + RECORD_HEADER_LEN).checked_add(len))
+            .filter(|&end| end <= data.len())
+        else {
+            break;
+        };
+        pos = end;
+    }
+    (pos, false)
+}
+
+impl RerunMcapTailer {
+    fn new(path: PathBuf, application_id: &str) -> Result<Self> {
+        Ok(Self {
+            path,
+            encoder: encoder_create_internal(application_id)?,
+            offset: 0,
+            pending: Vec::new(),
+            started: false,
+            finished: false,
+            schemas: HashMap::new(),
+            channels: HashMap::new(),
+        })
+    }
+
+    /// Convert the messages appended since the last poll, returns the new RRD
+    /// bytes and the number of new messages
+    fn poll(&mut self) -> Result<(Vec<u8>, usize)> {
+        if self.finished {
+            return Ok((Vec::new(), 0));
+        }
+        self.read_appended()?;
+
+        if !self.started {
+            if self.pending.len() < mcap::MAGIC.len() {
+                return Ok((Vec::new(), 0));
+            }
+            if !self.pending.starts_with(mcap::MAGIC) {
+                return Err(RerunBridgeError::InvalidData(format!(
+                    "{} is not an MCAP file",
+                    self.path.display()
+                )));
+            }
+            self.pending.drain(..mcap::MAGIC.len());
+            self.started = true;
+        }
+
+        let (end, footer) = complete_records_len(&self.pending);
+        let records: Vec<u8> = self.pending.drain(..end).collect();
+        if footer {
+            self.finished = true;
+            self.pending = Vec::new();
+        }
+
+        let messages = self.read_messages(&records)?;
+        if messages.is_empty() {
+            return Ok((Vec::new(), 0));
+        }
+
+        let (mcap, new_messages) = write_messages(&messages)?;
+        let rrd = encoder_process_mcap_chunk_internal(&mut self.encoder, &mcap)?;
+        Ok((rrd, new_messages))
+    }
+
+    /// Append the bytes written to the file since the last read to `pending`
+    fn read_appended(&mut self) -> Result<()> {
+        let io_error = |e: std::io::Error| {
+            RerunBridgeError::InvalidData(format!("Failed to read {}: {}", self.path.display(), e))
+        };
+
+        let mut file = File::open(&self.path).map_err(io_error)?;
+        file.seek(SeekFrom::Start(self.offset)).map_err(io_error)?;
+        let read = file.read_to_end(&mut self.pending).map_err(io_error)?;
+        self.offset += read as u64;
+        Ok(())
+    }
+
+    /// Read the messages of complete top-level `records`, remembering the
+    /// schemas and channels they define for later polls
+    fn read_messages(&mut self, records: &[u8]) -> Result<Vec<mcap::Message<'static>>> {
+        let mut messages = Vec::new();
+        for record in mcap::read::LinearReader::sans_magic(records) {
+            match record.map_err(mcap_error)? {
+                Record::Chunk { header, data } => {
+                    for record in mcap::read::ChunkReader::new(header, &data).map_err(mcap_error)? {
+                        self.read_record(record.map_err(mcap_error)?, &mut messages)?;
+                    }
+                }
+                record => self.read_record(record, &mut messages)?,
+            }
+        }
+        Ok(messages)
+    }
+
+    fn read_record(
+        &mut self,
+        record: Record<'_>,
+        messages: &mut Vec<mcap::Message<'static>>,
+    ) -> Result<()> {
+        match record {
+            Record::Schema { header, data } => {
+                let schema = mcap::Schema {
+                    id: header.id,
+                    name: header.name,
+                    encoding: header.encoding,
+                    data: Cow::Owned(data.to_vec()),
+                };
+                self.schemas.insert(header.id, Arc::new(schema));
+            }
+            Record::Channel(channel) => {
+                // 0 is the "no schema" id
+                let schema = match channel.schema_id {
+                    0 => None,
+                    id => Some(self.schemas.get(&id).cloned().ok_or_else(|| {
+                        RerunBridgeError::MCAPError(format!(
+                            "Channel {} references unknown schema {}",
+                            channel.id, id
+                        ))
+                    })?),
+                };
+                let channel = mcap::Channel {
+                    id: channel.id,
+                    topic: channel.topic,
+                    schema,
+                    message_encoding: channel.message_encoding,
+                    metadata: channel.metadata,
+                };
+                self.channels.insert(channel.id, Arc::new(channel));
+            }
+            Record::Message { header, data } => {
+                let channel = self
+                    .channels
+                    .get(&header.channel_id)
+                    .cloned()
+                    .ok_or_else(|| {
+                        RerunBridgeError::MCAPError(format!(
+                            "Message references unknown channel {}",
+                            header.channel_id
+                        ))
+                    })?;
+                messages.push(mcap::Message {
+                    channel,
+                    sequence: header.sequence,
+                    log_time: header.log_time,
+                    publish_time: header.publish_time,
+                    data: Cow::Owned(data.to_vec()),
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Create a tailer converting the MCAP file at `path` as it grows
+/// Returns null on error. Destroy it with `rerun_mcap_tailer_destroy`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_mcap_tailer_create(
+    path: *const c_char,
+    application_id: *const c_char,
+) -> *mut RerunMcapTailer {
+    if path.is_null() || application_id.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_mcap_tailer_create",
+        );
+        return ptr::null_mut();
+    }
+
+    let (path, app_id) = unsafe {
+        match (
+            CStr::from_ptr(path).to_str(),
+            CStr::from_ptr(application_id).to_str(),
+        ) {
+            (Ok(path), Ok(app_id)) => (path, app_id),
+            _ => {
+                set_error(
+                    RERUN_ERROR_INVALID_ARGUMENT,
+                    "Invalid UTF-8 passed to rerun_mcap_tailer_create",
+                );
+                return ptr::null_mut();
+            }
+        }
+    };
+
+    match RerunMcapTailer::new(PathBuf::from(path), app_id) {
+        Ok(tailer) => Box::into_raw(Box::new(tailer)),
+        Err(e) => {
+            set_bridge_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Convert the complete MCAP records appended since the last poll
+/// `out_data` is null and `out_len` 0 when nothing new is complete yet.
+/// Free the result with `rerun_bridge_free_rrd_data`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_mcap_tailer_poll(
+    handle: *mut RerunMcapTailer,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if handle.is_null() || out_data.is_null() || out_len.is_null() {
+        set_error(
+            RERUN_ERROR_INVALID_ARGUMENT,
+            "Null pointer passed to rerun_mcap_tailer_poll",
+        );
+        return -1;
+    }
+
+    let tailer = unsafe { &mut *handle };
+    match tailer.poll() {
+        Ok((rrd, _)) => {
+            let len = rrd.len();
+            let data = if len == 0 {
+                ptr::null_mut()
+            } else {
+                let mut rrd = rrd.into_boxed_slice();
+                let data = rrd.as_mut_ptr();
+                std::mem::forget(rrd);
+                data
+            };
+            unsafe {
+                *out_data = data;
+                *out_len = len;
+            }
+            0
+        }
+        Err(e) => {
+            set_bridge_error(&e);
+            -1
+        }
+    }
+}
+
+/// Destroy a tailer created by `rerun_mcap_tailer_create`
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn rerun_mcap_tailer_destroy(handle: *mut RerunMcapTailer) {
+    if !handle.is_null() {
+        unsafe {
+            let _ = Box::from_raw(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_complete_records_wait_for_torn_records() {
+        assert_eq!(complete_records_len(&[]), (0, false));
+
+        let mut data = vec![0x01];
+        data.extend_from_slice(&3u64.to_le_bytes());
+        data.extend_from_slice(b"abc");
+        let complete = data.len();
+        data.push(0x05);
+        data.extend_from_slice(&10u64.to_le_bytes());
+        data.extend_from_slice(b"partial");
+        assert_eq!(complete_records_len(&data), (complete, false));
+
+        data.truncate(complete);
+        data.push(FOOTER_OPCODE);
+        data.extend_from_slice(&20u64.to_le_bytes());
+        assert_eq!(complete_records_len(&data), (complete, true));
+    }
+
+    #[test]
+    fn test_second_poll_converts_only_appended_messages() {
+        let mcap_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resource/ros2_bag/rosbag_2025_09_05-10_08_00_0.mcap"
+        );
+        let mcap_data = match std::fs::read(mcap_path) {
+            Ok(data) => data,
+            Err(e) => {
+                println!(
+                    "⚠️ Skipping test: Could not read MCAP file at {}: {}",
+                    mcap_path, e
+                );
+                return;
+            }
+        };
+        let total = crate::summarize_mcap(&mcap_data).unwrap().message_count as usize;
+
+        let path = std::env::temp_dir().join(format!("rerun-tailer-{}.mcap", std::process::id()));
+        // The split usually falls inside a record, which must wait for the rest
+        let split = mcap_data.len() / 2;
+        std::fs::write(&path, &mcap_data[..split]).unwrap();
+
+        let mut tailer = RerunMcapTailer::new(path.clone(), "tailer_test").unwrap();
+        let (first_rrd, first) = tailer.poll().unwrap();
+        assert!(first > 0 && first < total, "first poll converted {}", first);
+        assert!(!first_rrd.is_empty());
+
+        // Nothing new until the writer appends more
+        assert_eq!(tailer.poll().unwrap().1, 0);
+
+        // Bytes already read are never read again
+        std::fs::write(&path, vec![0u8; split]).unwrap();
+
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&mcap_data[split..])
+            .unwrap();
+        let (second_rrd, second) = tailer.poll().unwrap();
+        assert_eq!(
+            second,
+            total - first,
+            "second poll should only convert new messages"
+        );
+        assert!(!second_rrd.is_empty());
+        assert!(
+            !second_rrd.starts_with(b"RRF2"),
+            "the RRD header is only emitted once"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,32 @@
+//! Encoder handle leak detection
+//!
+//! The active count is process-wide, so this test lives in its own binary
+//! where no other test creates encoders concurrently.
+
+use std::ffi::CString;
+
+use rerun_bridge::{rerun_encoder_active_count, rerun_encoder_create, rerun_encoder_destroy};
+
+#[test]
+fn test_active_count_tracks_create_and_destroy() {
+    assert_eq!(rerun_encoder_active_count(), 0);
+
+    let app_id = CString::new("active_count_test").unwrap();
+    let handles: Vec<_> = (0..3)
+        .map(|_| rerun_encoder_create(app_id.as_ptr()))
+        .collect();
+    assert!(handles.iter().all(|handle| !handle.is_null()));
+    assert_eq!(rerun_encoder_active_count(), 3);
+
+    rerun_encoder_destroy(handles[0]);
+    rerun_encoder_destroy(handles[1]);
+    assert_eq!(rerun_encoder_active_count(), 1);
+
+    // Failed creation and destroying null do not change the count
+    assert!(rerun_encoder_create(std::ptr::null()).is_null());
+    rerun_encoder_destroy(std::ptr::null_mut());
+    assert_eq!(rerun_encoder_active_count(), 1);
+
+    rerun_encoder_destroy(handles[2]);
+    assert_eq!(rerun_encoder_active_count(), 0);
+}
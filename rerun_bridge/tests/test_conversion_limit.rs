@@ -0,0 +1,81 @@
+//! Concurrent MCAP conversion limit
+//!
+//! The limit is process-wide, so this test lives in its own binary where no
+//! other test converts MCAP data concurrently.
+
+use std::ffi::CString;
+use std::sync::{Arc, Barrier};
+
+use rerun_bridge::{
+    max_concurrent_conversions, peak_concurrent_conversions, rerun_bridge_free_rrd_data,
+    rerun_encoder_create, rerun_encoder_destroy, rerun_encoder_process_mcap_chunk,
+    rerun_set_max_concurrent_conversions,
+};
+
+fn read_test_mcap() -> Option<Vec<u8>> {
+    let mcap_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/resource/ros2_bag/rosbag_2025_09_05-10_08_00_0.mcap"
+    );
+    match std::fs::read(mcap_path) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            println!(
+                "⚠️ Skipping test: Could not read MCAP file at {}: {}",
+                mcap_path, e
+            );
+            None
+        }
+    }
+}
+
+/// Convert `mcap` with a fresh encoder, returns the number of RRD bytes produced
+fn convert(mcap: &[u8]) -> usize {
+    let app_id = CString::new("conversion_limit_test").unwrap();
+    let handle = rerun_encoder_create(app_id.as_ptr());
+    assert!(!handle.is_null());
+
+    let mut out_data = std::ptr::null_mut();
+    let mut out_len = 0;
+    let ret = rerun_encoder_process_mcap_chunk(
+        handle,
+        mcap.as_ptr(),
+        mcap.len(),
+        &mut out_data,
+        &mut out_len,
+    );
+    assert_eq!(ret, 0, "Conversion should succeed");
+    rerun_bridge_free_rrd_data(out_data, out_len);
+    rerun_encoder_destroy(handle);
+    out_len
+}
+
+#[test]
+fn test_conversions_serialize_at_limit_of_one() {
+    assert!(max_concurrent_conversions() >= 1);
+    assert_eq!(rerun_set_max_concurrent_conversions(0), -1);
+
+    let Some(mcap) = read_test_mcap() else {
+        return;
+    };
+    let mcap = Arc::new(mcap);
+
+    assert_eq!(rerun_set_max_concurrent_conversions(1), 0);
+    assert_eq!(max_concurrent_conversions(), 1);
+
+    let start = Arc::new(Barrier::new(2));
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            let mcap = mcap.clone();
+            let start = start.clone();
+            std::thread::spawn(move || {
+                start.wait();
+                convert(&mcap)
+            })
+        })
+        .collect();
+    let sizes: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+
+    assert!(sizes.iter().all(|&len| len > 0));
+    assert_eq!(peak_concurrent_conversions(), 1);
+}